@@ -4,8 +4,8 @@
 
 extern crate lalrpop;
 
-use std::path::Path;
-use std::{fs, io};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
 
 /// Copy all files in a folder from `src` to `dst`.
 fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()> {
@@ -45,6 +45,8 @@ fn main() {
     let git_hash = String::from_utf8(output.stdout).unwrap();
     println!("cargo:rustc-env=GIT_HASH={git_hash}");
 
+    compile_remark_handler();
+
     // Static link LLVM libs
     #[cfg(feature = "release")]
     static_link_llvm();
@@ -52,22 +54,31 @@ fn main() {
     lalrpop::process_root().unwrap();
 }
 
-#[allow(dead_code)]
-fn static_link_llvm() {
+/// Runs the llvm-config of the LLVM inkwell links, found like llvm-sys does:
+/// in `$LLVM_SYS_160_PREFIX/bin` when it is set, on the PATH otherwise
+fn llvm_config(arg: &str) -> String {
     use std::process::Command;
 
-    println!("Use Static Link");
-    // compile our linker
-    let cxxflags = Command::new("llvm-config")
-        .args(["--cxxflags"])
+    println!("cargo:rerun-if-env-changed=LLVM_SYS_160_PREFIX");
+    let llvm_config = match env::var_os("LLVM_SYS_160_PREFIX") {
+        Some(prefix) => Path::new(&prefix).join("bin").join("llvm-config"),
+        None => PathBuf::from("llvm-config"),
+    };
+    let output = Command::new(&llvm_config)
+        .arg(arg)
         .output()
-        .expect("could not execute llvm-config");
+        .unwrap_or_else(|err| panic!("could not execute {}: {err}", llvm_config.display()));
 
-    let cxxflags = String::from_utf8(cxxflags.stdout).unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Compiles a C++ file against the LLVM headers into a static library
+fn compile_llvm_cpp(file: &str, lib: &str) {
+    let cxxflags = llvm_config("--cxxflags");
 
     let mut build = cc::Build::new();
 
-    build.file("src/yul2ir/linker.cpp").cpp(true);
+    build.file(file).cpp(true);
 
     if !cfg!(target_os = "windows") {
         build.flag("-Wno-unused-parameter");
@@ -77,14 +88,22 @@ fn static_link_llvm() {
         build.flag(flag);
     }
 
-    build.compile("liblinker.a");
+    build.compile(lib);
+}
+
+/// The C++ diagnostic handler enabling the LLVM remarks of one context
+fn compile_remark_handler() {
+    compile_llvm_cpp("src/yul2ir/remarks.cpp", "libremarks.a");
+}
+
+#[allow(dead_code)]
+fn static_link_llvm() {
+    println!("Use Static Link");
+    // compile our linker
+    compile_llvm_cpp("src/yul2ir/linker.cpp", "liblinker.a");
 
     // add the llvm linker
-    let libdir = Command::new("llvm-config")
-        .args(["--libdir"])
-        .output()
-        .unwrap();
-    let libdir = String::from_utf8(libdir.stdout).unwrap();
+    let libdir = llvm_config("--libdir");
 
     println!("cargo:libdir={libdir}");
     for lib in &["lldELF", "lldCommon", "lldWasm"] {
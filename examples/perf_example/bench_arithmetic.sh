@@ -0,0 +1,77 @@
+#!/bin/bash
+set -e
+
+# Compare div, mod and exp with a constant operand, which strength reduction
+# lowers to shifts, multiply-highs and multiply chains, against the same
+# operations with the operand from the calldata, which call the stdlib.
+# arithmetic_bench.yul is built with --enable-all-optimizers and runs each
+# operation 1000 times per call, the table holds the mean in-process time of
+# CALLS calls. It is written to bench_results/arithmetic.txt, commit it with
+# the change it measures.
+
+source ../scripts/common.sh
+source ./bench_common.sh
+
+BUILD_MODE=${1:-release}
+CALLS=${CALLS:-20}
+RESULTS=$BENCH_RESULTS_DIR/arithmetic.txt
+WASM=$BENCH_DIR/arithmetic_bench.wasm
+
+if [ "$BUILD_MODE" == "release" ]; then
+    YUL2WASM_PATH="../../target/release/yul2wasm"
+else
+    YUL2WASM_PATH="../../target/debug/yul2wasm"
+fi
+
+# "<name> <constant op> <variable op> <x> <operand>"
+OPERATIONS=(
+    "div_256 0 1 123456789 256"
+    "div_1000003 2 3 123456789123456789 1000003"
+    "mod_1000003 4 5 123456789123456789 1000003"
+    "exp_3 6 7 987654321 3"
+)
+
+function calldata() {
+    printf "0x%064x%064x%064x" $1 $2 $3
+}
+
+# calls the operation CALLS times, prints the result of the last call
+function bench_operation() {
+    local log=$1
+    local data=$2
+    rm -f test.db
+    /opt/chain_mockcli -f $WASM --action deploy -i 0x > /dev/null
+    for _ in $(seq $CALLS); do
+        /opt/chain_mockcli -f $WASM --action call --print-time -i $data >> $log
+    done
+    /opt/chain_mockcli -f $WASM --action call -i $data | grep "evm finish with result hex"
+}
+
+rm -rf $BENCH_DIR
+mkdir -p $BENCH_DIR $BENCH_RESULTS_DIR
+
+$YUL2WASM_PATH --input arithmetic_bench.yul --output $WASM --enable-all-optimizers
+
+for operation in "${OPERATIONS[@]}"; do
+    read -r name constant_op variable_op x operand <<< "$operation"
+    echo "running $name"
+    constant_result=$(bench_operation $BENCH_DIR/${name}_constant.log $(calldata $constant_op $x $operand))
+    variable_result=$(bench_operation $BENCH_DIR/${name}_variable.log $(calldata $variable_op $x $operand))
+    if [ "$constant_result" != "$variable_result" ]; then
+        echo "$name: the constant operand gives \"$constant_result\", the variable one \"$variable_result\"" >&2
+        exit 1
+    fi
+    check_call_times $BENCH_DIR/${name}_constant.log
+    check_call_times $BENCH_DIR/${name}_variable.log
+done
+
+{
+    echo "# $BUILD_MODE build with --enable-all-optimizers, mean in-process time of $CALLS calls of 1000 operations"
+    printf "%-14s %12s %12s\n" operation constant_us variable_us
+    for operation in "${OPERATIONS[@]}"; do
+        read -r name _ <<< "$operation"
+        printf "%-14s %12s %12s\n" $name \
+            $(mean_call_time $BENCH_DIR/${name}_constant.log) \
+            $(mean_call_time $BENCH_DIR/${name}_variable.log)
+    done
+} | tee $RESULTS
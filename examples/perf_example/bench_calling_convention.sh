@@ -0,0 +1,38 @@
+#!/bin/bash
+set -e
+
+# Compare the value and pointer internal calling conventions on the token
+# contracts: wasm size of each contract and mean in-process time of the
+# calls of its test script. The table is written to
+# bench_results/calling_convention.txt, commit it with the change it measures.
+
+source ./bench_common.sh
+
+BUILD_MODE=${1:-release}
+RESULTS=$BENCH_RESULTS_DIR/calling_convention.txt
+
+rm -rf $BENCH_DIR
+mkdir -p $BENCH_DIR $BENCH_RESULTS_DIR
+
+for convention in value pointer; do
+    echo "building with --calling-convention $convention"
+    YUL2WASM_CALLING_CONVENTION=$convention ./build.sh $BUILD_MODE > $BENCH_DIR/build_$convention.log
+    for contract in $BENCH_CONTRACTS; do
+        cp out/$contract.wasm $BENCH_DIR/${contract}_$convention.wasm
+        $(test_script $contract) > $BENCH_DIR/${contract}_$convention.log
+        check_call_times $BENCH_DIR/${contract}_$convention.log
+    done
+done
+
+{
+    echo "# $BUILD_MODE build, mean in-process time of the calls of each test script"
+    printf "%-10s %12s %12s %12s %12s %6s\n" contract value_bytes pointer_bytes value_us pointer_us calls
+    for contract in $BENCH_CONTRACTS; do
+        printf "%-10s %12s %12s %12s %12s %6s\n" $contract \
+            $(wc -c < $BENCH_DIR/${contract}_value.wasm) \
+            $(wc -c < $BENCH_DIR/${contract}_pointer.wasm) \
+            $(mean_call_time $BENCH_DIR/${contract}_value.log) \
+            $(mean_call_time $BENCH_DIR/${contract}_pointer.log) \
+            $(call_times $BENCH_DIR/${contract}_value.log | cut -d' ' -f1)
+    done
+} | tee $RESULTS
@@ -0,0 +1,67 @@
+#!/bin/bash
+
+# Shared helpers of the bench_*.sh scripts. Times are the in-process ones
+# printed by chain_mockcli --print-time, so process startup and the database
+# setup of the test scripts are not part of them.
+
+BENCH_CONTRACTS="GLDToken MyERC721 MyERC1155"
+BENCH_DIR=out/bench
+BENCH_RESULTS_DIR=bench_results
+
+function test_script() {
+    case $1 in
+        GLDToken) echo ./test_gldtoken.sh ;;
+        MyERC721) echo ./test_erc721.sh ;;
+        MyERC1155) echo ./test_erc1155.sh ;;
+    esac
+}
+
+# prints "<calls> <total us>" of the --print-time lines of a log. A line is
+# read as one call if it mentions "time" and holds a number with a unit, the
+# first one is taken, e.g. "call time: 12.5 us" or "time cost 3ms". The
+# format of chain_mockcli isn't specified anywhere, check_call_times stops the
+# benchmark when it changes instead of writing a table without times
+function call_times() {
+    awk '
+        tolower($0) ~ /time/ && match($0, /[0-9]+(\.[0-9]+)? *(ns|us|ms|s)([^a-zA-Z]|$)/) {
+            token = substr($0, RSTART, RLENGTH)
+            value = token + 0
+            if (token ~ /ns/) value /= 1000
+            else if (token ~ /us/) value *= 1
+            else if (token ~ /ms/) value *= 1000
+            else value *= 1000000
+            calls++
+            total += value
+        }
+        END { printf "%d %.0f\n", calls, total }
+    ' "$1"
+}
+
+# prints the mean in-process time in us of the calls of a log
+function mean_call_time() {
+    call_times "$1" | awk '{ if ($1 > 0) printf "%.1f\n", $2 / $1; else print "-" }'
+}
+
+# fails if no call time can be read from the log, showing the lines that
+# mention a time so the parser can be fixed
+function check_call_times() {
+    local calls=$(call_times "$1" | cut -d' ' -f1)
+    if [ "$calls" -eq 0 ]; then
+        echo "no --print-time call time read from $1, its time lines are:" >&2
+        grep -i time "$1" | head -5 >&2
+        return 1
+    fi
+}
+
+# the parser reads the sample formats of the call_times comment
+function check_call_times_parser() {
+    local sample=$(mktemp)
+    printf "call time: 12.5 us\ntime cost 3ms\nevm finish with result hex: 00\n" > $sample
+    local parsed=$(call_times $sample)
+    rm -f $sample
+    if [ "$parsed" != "2 3012" ]; then
+        echo "call_times parsed the samples as \"$parsed\" instead of \"2 3012\"" >&2
+        return 1
+    fi
+}
+check_call_times_parser
@@ -0,0 +1,47 @@
+#!/bin/bash
+set -e
+
+# Compare LLVM pass pipelines on the token contracts: wasm size of each
+# contract and mean in-process time of the calls of its test script. The
+# default pipeline is compared with default<O3> and default<Oz> by default,
+# pass others as arguments after the build mode, e.g.
+#   ./bench_llvm_passes.sh release default "default<O2>,mergefunc"
+# The table is written to bench_results/llvm_passes.txt, commit it with the
+# change it measures.
+
+source ./bench_common.sh
+
+BUILD_MODE=${1:-release}
+shift || true
+PIPELINES=("$@")
+if [ ${#PIPELINES[@]} -eq 0 ]; then
+    PIPELINES=(default "default<O3>" "default<Oz>")
+fi
+RESULTS=$BENCH_RESULTS_DIR/llvm_passes.txt
+
+rm -rf $BENCH_DIR
+mkdir -p $BENCH_DIR $BENCH_RESULTS_DIR
+
+for index in "${!PIPELINES[@]}"; do
+    pipeline=${PIPELINES[$index]}
+    echo "building with --llvm-passes \"$pipeline\""
+    YUL2WASM_LLVM_PASSES=$pipeline ./build.sh $BUILD_MODE > $BENCH_DIR/build_$index.log
+    for contract in $BENCH_CONTRACTS; do
+        cp out/$contract.wasm $BENCH_DIR/${contract}_$index.wasm
+        $(test_script $contract) > $BENCH_DIR/${contract}_$index.log
+        check_call_times $BENCH_DIR/${contract}_$index.log
+    done
+done
+
+{
+    echo "# $BUILD_MODE build, mean in-process time of the calls of each test script"
+    printf "%-10s %-40s %10s %10s %6s\n" contract pipeline bytes us calls
+    for contract in $BENCH_CONTRACTS; do
+        for index in "${!PIPELINES[@]}"; do
+            printf "%-10s %-40s %10s %10s %6s\n" $contract "${PIPELINES[$index]}" \
+                $(wc -c < $BENCH_DIR/${contract}_$index.wasm) \
+                $(mean_call_time $BENCH_DIR/${contract}_$index.log) \
+                $(call_times $BENCH_DIR/${contract}_$index.log | cut -d' ' -f1)
+        done
+    done
+} | tee $RESULTS
@@ -0,0 +1,80 @@
+#!/bin/bash
+set -e
+
+# Compare the lowerings of the selector dispatcher: wasm size of each
+# contract, mean in-process time of the calls of the token test scripts and
+# of CALLS calls of the first, a middle and the last function of
+# DispatchBench, which has 64 external functions. Lowerings are all three by
+# default, pass others as arguments after the build mode, e.g.
+#   CALLS=500 ./bench_selector_dispatch.sh release switch perfect-hash
+# The table is written to bench_results/selector_dispatch.txt, commit it with
+# the change it measures.
+
+source ../scripts/common.sh
+source ./bench_common.sh
+
+ABI_ENCODE="../scripts/abi_encode.py"
+
+BUILD_MODE=${1:-release}
+shift || true
+DISPATCHES=("$@")
+if [ ${#DISPATCHES[@]} -eq 0 ]; then
+    DISPATCHES=(switch binary-search perfect-hash)
+fi
+CALLS=${CALLS:-100}
+DISPATCH_FUNCTIONS="fn00 fn31 fn63"
+RESULTS=$BENCH_RESULTS_DIR/selector_dispatch.txt
+
+# calls the function of DispatchBench CALLS times, checks the result of fnNN(1)
+function bench_dispatch() {
+    local function=$1
+    local log=$2
+    local calldata=$($ABI_ENCODE "$function(uint256)" "1")
+    local expected=$(printf "%064x" $(( 10#${function#fn} + 1 )))
+    rm -f test.db
+    /opt/chain_mockcli -f out/dispatch_bench.wasm --action deploy -i 0x > /dev/null
+    for _ in $(seq $CALLS); do
+        /opt/chain_mockcli -f out/dispatch_bench.wasm --action call --print-time -i $calldata >> $log
+    done
+    output=$(/opt/chain_mockcli -f out/dispatch_bench.wasm --action call -i $calldata)
+    run_cmd_and_grep "$output" "evm finish with result hex: $expected" > /dev/null
+}
+
+rm -rf $BENCH_DIR
+mkdir -p $BENCH_DIR $BENCH_RESULTS_DIR
+
+for dispatch in "${DISPATCHES[@]}"; do
+    echo "building with --selector-dispatch $dispatch"
+    YUL2WASM_SELECTOR_DISPATCH=$dispatch ./build.sh $BUILD_MODE > $BENCH_DIR/build_$dispatch.log
+    cp out/dispatch_bench.wasm $BENCH_DIR/DispatchBench_$dispatch.wasm
+    for contract in $BENCH_CONTRACTS; do
+        cp out/$contract.wasm $BENCH_DIR/${contract}_$dispatch.wasm
+        $(test_script $contract) > $BENCH_DIR/${contract}_$dispatch.log
+        check_call_times $BENCH_DIR/${contract}_$dispatch.log
+    done
+    for function in $DISPATCH_FUNCTIONS; do
+        bench_dispatch $function $BENCH_DIR/DispatchBench_${function}_$dispatch.log
+        check_call_times $BENCH_DIR/DispatchBench_${function}_$dispatch.log
+    done
+done
+
+{
+    echo "# $BUILD_MODE build, mean in-process time of the calls of each test script and of $CALLS calls of each DispatchBench function"
+    printf "%-20s %-14s %10s %10s %6s\n" contract dispatch bytes us calls
+    for contract in $BENCH_CONTRACTS; do
+        for dispatch in "${DISPATCHES[@]}"; do
+            printf "%-20s %-14s %10s %10s %6s\n" $contract $dispatch \
+                $(wc -c < $BENCH_DIR/${contract}_$dispatch.wasm) \
+                $(mean_call_time $BENCH_DIR/${contract}_$dispatch.log) \
+                $(call_times $BENCH_DIR/${contract}_$dispatch.log | cut -d' ' -f1)
+        done
+    done
+    for function in $DISPATCH_FUNCTIONS; do
+        for dispatch in "${DISPATCHES[@]}"; do
+            printf "%-20s %-14s %10s %10s %6s\n" DispatchBench.$function $dispatch \
+                $(wc -c < $BENCH_DIR/DispatchBench_$dispatch.wasm) \
+                $(mean_call_time $BENCH_DIR/DispatchBench_${function}_$dispatch.log) \
+                $(call_times $BENCH_DIR/DispatchBench_${function}_$dispatch.log | cut -d' ' -f1)
+        done
+    done
+} | tee $RESULTS
@@ -0,0 +1,84 @@
+#!/bin/bash
+set -e
+
+# Compare the bool_tests and byte_tests workloads of simd128_bench.yul built
+# without and with --simd128, which lowers the bytes32 operations and the big
+# endian conversions to v128 instructions instead of stdlib calls. Both builds
+# use --enable-all-optimizers and run each workload 1000 times per call, the
+# table holds the mean in-process time of CALLS calls. It is written to
+# bench_results/simd128.txt, commit it with the change it measures. The
+# runtime must support the simd128 proposal.
+
+source ../scripts/common.sh
+source ./bench_common.sh
+
+BUILD_MODE=${1:-release}
+CALLS=${CALLS:-20}
+RESULTS=$BENCH_RESULTS_DIR/simd128.txt
+
+if [ "$BUILD_MODE" == "release" ]; then
+    YUL2WASM_PATH="../../target/release/yul2wasm"
+else
+    YUL2WASM_PATH="../../target/debug/yul2wasm"
+fi
+
+# "<name> <op>"
+WORKLOADS=(
+    "bool 0"
+    "byte 1"
+)
+MODES=(scalar simd128)
+
+# 0x0102...1f20 and 0xff00 repeated
+A=0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20
+B=ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00
+
+function calldata() {
+    printf "0x%064x%s%s" $1 $A $B
+}
+
+# calls the workload CALLS times, prints the result of the last call
+function bench_workload() {
+    local wasm=$1
+    local log=$2
+    local data=$3
+    rm -f test.db
+    /opt/chain_mockcli -f $wasm --action deploy -i 0x > /dev/null
+    for _ in $(seq $CALLS); do
+        /opt/chain_mockcli -f $wasm --action call --print-time -i $data >> $log
+    done
+    /opt/chain_mockcli -f $wasm --action call -i $data | grep "evm finish with result hex"
+}
+
+rm -rf $BENCH_DIR
+mkdir -p $BENCH_DIR $BENCH_RESULTS_DIR
+
+$YUL2WASM_PATH --input simd128_bench.yul --output $BENCH_DIR/simd128_bench_scalar.wasm --enable-all-optimizers
+$YUL2WASM_PATH --input simd128_bench.yul --output $BENCH_DIR/simd128_bench_simd128.wasm --enable-all-optimizers --simd128
+
+for workload in "${WORKLOADS[@]}"; do
+    read -r name op <<< "$workload"
+    echo "running $name"
+    for mode in "${MODES[@]}"; do
+        bench_workload $BENCH_DIR/simd128_bench_$mode.wasm $BENCH_DIR/${name}_$mode.log $(calldata $op) \
+            > $BENCH_DIR/${name}_$mode.result
+        check_call_times $BENCH_DIR/${name}_$mode.log
+    done
+    if ! cmp -s $BENCH_DIR/${name}_scalar.result $BENCH_DIR/${name}_simd128.result; then
+        echo "$name: the scalar and simd128 builds give different results" >&2
+        exit 1
+    fi
+done
+
+{
+    echo "# $BUILD_MODE build with --enable-all-optimizers, mean in-process time of $CALLS calls of 1000 iterations"
+    printf "%-10s %12s %12s %10s %10s\n" workload scalar_us simd128_us scalar_b simd128_b
+    for workload in "${WORKLOADS[@]}"; do
+        read -r name _ <<< "$workload"
+        printf "%-10s %12s %12s %10s %10s\n" $name \
+            $(mean_call_time $BENCH_DIR/${name}_scalar.log) \
+            $(mean_call_time $BENCH_DIR/${name}_simd128.log) \
+            $(wc -c < $BENCH_DIR/simd128_bench_scalar.wasm) \
+            $(wc -c < $BENCH_DIR/simd128_bench_simd128.wasm)
+    done
+} | tee $RESULTS
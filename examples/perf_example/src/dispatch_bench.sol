@@ -0,0 +1,261 @@
+pragma solidity ^0.8.0;
+
+// 64 external functions, to compare the lowerings of the selector dispatcher
+// with bench_selector_dispatch.sh
+contract DispatchBench {
+    function fn00(uint256 x) external pure returns (uint256) {
+        return x + 0;
+    }
+
+    function fn01(uint256 x) external pure returns (uint256) {
+        return x + 1;
+    }
+
+    function fn02(uint256 x) external pure returns (uint256) {
+        return x + 2;
+    }
+
+    function fn03(uint256 x) external pure returns (uint256) {
+        return x + 3;
+    }
+
+    function fn04(uint256 x) external pure returns (uint256) {
+        return x + 4;
+    }
+
+    function fn05(uint256 x) external pure returns (uint256) {
+        return x + 5;
+    }
+
+    function fn06(uint256 x) external pure returns (uint256) {
+        return x + 6;
+    }
+
+    function fn07(uint256 x) external pure returns (uint256) {
+        return x + 7;
+    }
+
+    function fn08(uint256 x) external pure returns (uint256) {
+        return x + 8;
+    }
+
+    function fn09(uint256 x) external pure returns (uint256) {
+        return x + 9;
+    }
+
+    function fn10(uint256 x) external pure returns (uint256) {
+        return x + 10;
+    }
+
+    function fn11(uint256 x) external pure returns (uint256) {
+        return x + 11;
+    }
+
+    function fn12(uint256 x) external pure returns (uint256) {
+        return x + 12;
+    }
+
+    function fn13(uint256 x) external pure returns (uint256) {
+        return x + 13;
+    }
+
+    function fn14(uint256 x) external pure returns (uint256) {
+        return x + 14;
+    }
+
+    function fn15(uint256 x) external pure returns (uint256) {
+        return x + 15;
+    }
+
+    function fn16(uint256 x) external pure returns (uint256) {
+        return x + 16;
+    }
+
+    function fn17(uint256 x) external pure returns (uint256) {
+        return x + 17;
+    }
+
+    function fn18(uint256 x) external pure returns (uint256) {
+        return x + 18;
+    }
+
+    function fn19(uint256 x) external pure returns (uint256) {
+        return x + 19;
+    }
+
+    function fn20(uint256 x) external pure returns (uint256) {
+        return x + 20;
+    }
+
+    function fn21(uint256 x) external pure returns (uint256) {
+        return x + 21;
+    }
+
+    function fn22(uint256 x) external pure returns (uint256) {
+        return x + 22;
+    }
+
+    function fn23(uint256 x) external pure returns (uint256) {
+        return x + 23;
+    }
+
+    function fn24(uint256 x) external pure returns (uint256) {
+        return x + 24;
+    }
+
+    function fn25(uint256 x) external pure returns (uint256) {
+        return x + 25;
+    }
+
+    function fn26(uint256 x) external pure returns (uint256) {
+        return x + 26;
+    }
+
+    function fn27(uint256 x) external pure returns (uint256) {
+        return x + 27;
+    }
+
+    function fn28(uint256 x) external pure returns (uint256) {
+        return x + 28;
+    }
+
+    function fn29(uint256 x) external pure returns (uint256) {
+        return x + 29;
+    }
+
+    function fn30(uint256 x) external pure returns (uint256) {
+        return x + 30;
+    }
+
+    function fn31(uint256 x) external pure returns (uint256) {
+        return x + 31;
+    }
+
+    function fn32(uint256 x) external pure returns (uint256) {
+        return x + 32;
+    }
+
+    function fn33(uint256 x) external pure returns (uint256) {
+        return x + 33;
+    }
+
+    function fn34(uint256 x) external pure returns (uint256) {
+        return x + 34;
+    }
+
+    function fn35(uint256 x) external pure returns (uint256) {
+        return x + 35;
+    }
+
+    function fn36(uint256 x) external pure returns (uint256) {
+        return x + 36;
+    }
+
+    function fn37(uint256 x) external pure returns (uint256) {
+        return x + 37;
+    }
+
+    function fn38(uint256 x) external pure returns (uint256) {
+        return x + 38;
+    }
+
+    function fn39(uint256 x) external pure returns (uint256) {
+        return x + 39;
+    }
+
+    function fn40(uint256 x) external pure returns (uint256) {
+        return x + 40;
+    }
+
+    function fn41(uint256 x) external pure returns (uint256) {
+        return x + 41;
+    }
+
+    function fn42(uint256 x) external pure returns (uint256) {
+        return x + 42;
+    }
+
+    function fn43(uint256 x) external pure returns (uint256) {
+        return x + 43;
+    }
+
+    function fn44(uint256 x) external pure returns (uint256) {
+        return x + 44;
+    }
+
+    function fn45(uint256 x) external pure returns (uint256) {
+        return x + 45;
+    }
+
+    function fn46(uint256 x) external pure returns (uint256) {
+        return x + 46;
+    }
+
+    function fn47(uint256 x) external pure returns (uint256) {
+        return x + 47;
+    }
+
+    function fn48(uint256 x) external pure returns (uint256) {
+        return x + 48;
+    }
+
+    function fn49(uint256 x) external pure returns (uint256) {
+        return x + 49;
+    }
+
+    function fn50(uint256 x) external pure returns (uint256) {
+        return x + 50;
+    }
+
+    function fn51(uint256 x) external pure returns (uint256) {
+        return x + 51;
+    }
+
+    function fn52(uint256 x) external pure returns (uint256) {
+        return x + 52;
+    }
+
+    function fn53(uint256 x) external pure returns (uint256) {
+        return x + 53;
+    }
+
+    function fn54(uint256 x) external pure returns (uint256) {
+        return x + 54;
+    }
+
+    function fn55(uint256 x) external pure returns (uint256) {
+        return x + 55;
+    }
+
+    function fn56(uint256 x) external pure returns (uint256) {
+        return x + 56;
+    }
+
+    function fn57(uint256 x) external pure returns (uint256) {
+        return x + 57;
+    }
+
+    function fn58(uint256 x) external pure returns (uint256) {
+        return x + 58;
+    }
+
+    function fn59(uint256 x) external pure returns (uint256) {
+        return x + 59;
+    }
+
+    function fn60(uint256 x) external pure returns (uint256) {
+        return x + 60;
+    }
+
+    function fn61(uint256 x) external pure returns (uint256) {
+        return x + 61;
+    }
+
+    function fn62(uint256 x) external pure returns (uint256) {
+        return x + 62;
+    }
+
+    function fn63(uint256 x) external pure returns (uint256) {
+        return x + 63;
+    }
+}
@@ -78,6 +78,130 @@ struct Args {
         default_value = "false"
     )]
     enable_little_endian_storage_load_store: bool,
+    // Shadow stack size in bytes, defaults to 64KiB (32KiB with --enable-all-optimizers)
+    #[arg(long = "stack-size", help = "Stack size in bytes")]
+    stack_size: Option<u32>,
+    #[arg(
+        long = "stack-guard",
+        help = "Revert on stack overflow instead of overwriting memory",
+        default_value = "false"
+    )]
+    stack_guard: bool,
+    // For hosts that don't enforce staticcall or the call depth: export
+    // call_static, set_call_depth and is_static_context, halt on state
+    // modifications when the contract is entered through call_static and fail
+    // calls at the depth limit
+    #[arg(
+        long = "static-context-check",
+        help = "Check static context at runtime",
+        default_value = "false"
+    )]
+    static_context_check: bool,
+    // config::LlvmPipeline, overrides the passes picked from --opt-level
+    #[arg(
+        long = "llvm-passes",
+        help = "LLVM pass pipeline: default or a new pass manager pipeline",
+        default_value = "default"
+    )]
+    llvm_passes: String,
+    // config::CallingConvention
+    #[arg(
+        long = "calling-convention",
+        help = "Internal calling convention of Yul functions: value, pointer",
+        default_value = "value"
+    )]
+    calling_convention: String,
+    // config::SelectorDispatch, switch stays the default until the lowerings
+    // are measured with bench_selector_dispatch.sh
+    #[arg(
+        long = "selector-dispatch",
+        help = "Lowering of the external dispatcher switch: switch, binary-search, perfect-hash",
+        default_value = "switch"
+    )]
+    selector_dispatch: String,
+    // Big-number math in the stdlib instead of the host addmod/mulmod and
+    // the generic i256 lowering
+    #[arg(
+        long = "limb-u256-math",
+        help = "Use the limb-based u256 math runtime",
+        default_value = "false"
+    )]
+    limb_u256_math: bool,
+    // The runtime must support the wasm simd128 proposal
+    #[arg(
+        long = "simd128",
+        help = "Use wasm simd128 for bytes32 operations",
+        default_value = "false"
+    )]
+    simd128: bool,
+    // config::ChainProfile
+    #[arg(
+        long = "chain-profile",
+        help = "Chain runtime the contract is compiled for: dtvm, mvp",
+        default_value = "dtvm"
+    )]
+    chain_profile: String,
+    // Comma separated, the default features of the chain profile if not given
+    #[arg(
+        long = "wasm-features",
+        help = "Wasm features to enable: bulk-memory, multi-value, sign-ext, mutable-globals, tail-call"
+    )]
+    wasm_features: Option<String>,
+    // Out-of-range accesses revert with Panic(0x41) instead of wrapping
+    // their offsets to 32 bits
+    #[arg(
+        long = "safe-memory",
+        help = "Bounds-check every EVM memory access",
+        default_value = "false"
+    )]
+    safe_memory: bool,
+    // Only used with --safe-memory, offsets from 2GiB up address the data
+    // segments so the memory can't grow beyond them
+    #[arg(
+        long = "max-memory-size",
+        help = "Largest EVM memory size in bytes with --safe-memory",
+        default_value_t = yul2ir::config::DEFAULT_MAX_MEMORY_SIZE,
+        value_parser = clap::value_parser!(u32).range(1..=0x7fff_ffff)
+    )]
+    max_memory_size: u32,
+    // For hosts that don't meter the EVM memory themselves, needs the useGas
+    // host api. Implied by --gas-metering evm
+    #[arg(
+        long = "memory-expansion-gas",
+        help = "Charge EVM memory expansion gas through useGas",
+        default_value = "false"
+    )]
+    memory_expansion_gas: bool,
+    // config::GasMetering, for chains without engine-level metering, needs
+    // the useGas host api
+    #[arg(
+        long = "gas-metering",
+        help = "Charge gas at basic block boundaries: evm, wasm"
+    )]
+    gas_metering: Option<String>,
+    // Lines of name = cost replacing the default costs of --gas-metering
+    #[arg(long = "gas-cost-table", help = "Gas cost table file path")]
+    gas_cost_table: Option<String>,
+    // Written to <output dir>/<main contract>_<output file>.cost.txt
+    #[arg(
+        long = "cost-report",
+        help = "Write a static cost report per ABI function",
+        default_value = "false"
+    )]
+    cost_report: bool,
+    // Written to <output dir>/<main contract>_<output file>.size.txt
+    #[arg(
+        long = "size-report",
+        help = "Write the wasm size of each function and data object",
+        default_value = "false"
+    )]
+    size_report: bool,
+    // Written to <output dir>/<main contract>_<output file>.remarks.txt or .json
+    #[arg(
+        long = "remarks",
+        help = "Write the rewrites applied or missed and the LLVM remarks: text, json"
+    )]
+    remarks: Option<String>,
     #[arg(
         long = "default_ret_type",
         help = "Default return type",
@@ -115,9 +239,128 @@ fn main() {
         no_binaryen_optimize: args.no_binaryen_optimize,
         enable_all_optimizers: args.enable_all_optimizers,
         enable_storage_load_store_little_endian: args.enable_little_endian_storage_load_store,
+        stack_size: args.stack_size,
+        enable_stack_guard: args.stack_guard,
+        enable_static_context_check: args.static_context_check,
+        enable_limb_u256_math: args.limb_u256_math,
+        enable_simd128: args.simd128,
+        enable_safe_memory: args.safe_memory,
+        max_memory_size: args.max_memory_size,
+        enable_memory_expansion_gas: args.memory_expansion_gas,
+        enable_cost_report: args.cost_report,
+        enable_size_report: args.size_report,
         ..Default::default()
     };
 
+    opts.llvm_pipeline = match args.llvm_passes.parse() {
+        Ok(llvm_pipeline) => llvm_pipeline,
+        Err(err) => {
+            eprintln!(
+                "{}. Expected: default or a pipeline like \"default<O2>\"",
+                err
+            );
+            process::exit(1);
+        }
+    };
+
+    opts.calling_convention = match args.calling_convention.parse() {
+        Ok(calling_convention) => calling_convention,
+        Err(err) => {
+            eprintln!("{}. Expected: value, pointer", err);
+            process::exit(1);
+        }
+    };
+
+    opts.selector_dispatch = match args.selector_dispatch.parse() {
+        Ok(selector_dispatch) => selector_dispatch,
+        Err(err) => {
+            eprintln!("{}. Expected: switch, binary-search, perfect-hash", err);
+            process::exit(1);
+        }
+    };
+
+    opts.chain_profile = match args.chain_profile.parse() {
+        Ok(chain_profile) => chain_profile,
+        Err(err) => {
+            eprintln!("{}. Expected: dtvm, mvp", err);
+            process::exit(1);
+        }
+    };
+    opts.wasm_features = match &args.wasm_features {
+        Some(wasm_features) => match yul2ir::config::parse_wasm_features(wasm_features) {
+            Ok(wasm_features) => wasm_features,
+            Err(err) => {
+                eprintln!(
+                    "{}. Expected: bulk-memory, multi-value, sign-ext, mutable-globals, tail-call",
+                    err
+                );
+                process::exit(1);
+            }
+        },
+        None => opts.chain_profile.default_wasm_features(),
+    };
+    if let Err(err) = opts.validate_wasm_features() {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+
+    if let Some(gas_metering) = &args.gas_metering {
+        opts.gas_metering = match gas_metering.parse() {
+            Ok(gas_metering) => Some(gas_metering),
+            Err(err) => {
+                eprintln!("{}. Expected: evm, wasm", err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(gas_cost_table) = &args.gas_cost_table {
+        let table = match fs::read_to_string(gas_cost_table) {
+            Ok(content) => content,
+            Err(err) => {
+                eprintln!("Error reading file {}: {}", gas_cost_table, err);
+                process::exit(1);
+            }
+        };
+        opts.gas_cost_overrides = match yul2ir::config::parse_gas_cost_table(&table) {
+            Ok(gas_cost_overrides) => gas_cost_overrides,
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Err(err) = opts.validate_gas_cost_table() {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+    if let Some(remarks) = &args.remarks {
+        opts.remarks = match remarks.parse() {
+            Ok(remarks) => Some(remarks),
+            Err(err) => {
+                eprintln!("{}. Expected: text, json", err);
+                process::exit(1);
+            }
+        };
+    }
+
+    if let Some(stack_size) = args.stack_size {
+        if stack_size == 0 || stack_size % 16 != 0 {
+            eprintln!(
+                "Invalid stack size: {}. Expected a positive multiple of 16",
+                stack_size
+            );
+            process::exit(1);
+        }
+        if args.stack_guard && stack_size <= yul2ir::stack_usage::STACK_GUARD_RESERVE {
+            eprintln!(
+                "Invalid stack size: {}. --stack-guard needs more than {} bytes of stack",
+                stack_size,
+                yul2ir::stack_usage::STACK_GUARD_RESERVE
+            );
+            process::exit(1);
+        }
+    }
+
     for sym in &args.symbol {
         let parts: Vec<&str> = sym.split('=').collect();
         if parts.len() == 2 {
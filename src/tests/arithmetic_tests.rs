@@ -8,6 +8,12 @@ use super::test_helper::TestRuntime;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+    use crate::yul2ir::config::Yul2IROptions;
+    use crate::yul2ir::context::Yul2IRContext;
+    use crate::yul2ir::yul;
+    use ethabi::ParamType;
+    use inkwell::context::Context;
 
     #[test]
     fn test_yul_add_overflow_u32() {
@@ -815,4 +821,177 @@ mod tests {
             .unwrap();
         runtime.assert_result("e8e77626586f73b955364c7b4bbf0bb7f7685ebd40e852b164633a4acbd3244c");
     }
+
+    #[test]
+    fn test_yul_keccak256_constant_memory() {
+        let mut runtime = TestRuntime::new(
+            "KeccakConstantMemoryTest",
+            "target/test_yul_keccak256_constant_memory",
+        );
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+                object "KeccakConstantMemoryTest" {
+                    code {
+                    }
+                    object "KeccakConstantMemoryTest_deployed" {
+                        code {
+                            // hashed at compile time
+                            mstore(0, "example.main")
+                            mstore(0x80, keccak256(0, 12))
+                            // the string stored in the branch isn't known after it
+                            if calldatasize() {
+                                mstore(0, "other")
+                            }
+                            mstore(0xa0, keccak256(0, 12))
+                            return(0x80, 0x40)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime.call(&solidity_selector("test()"), &[]).unwrap();
+        runtime.assert_result(concat!(
+            "b0ae1bb16fc1b16f3e2fb344bef6b08581bbec9f0228f7dd5fd38a8c141b3425",
+            "27d314fb3f4f043cf805c777c6d5fb522bf9dbc77473cf79b136a6590ddef2bc",
+        ));
+    }
+
+    #[test]
+    fn test_yul_strength_reduction() {
+        let mut runtime = TestRuntime::new(
+            "StrengthReductionTest",
+            "target/test_yul_strength_reduction",
+        );
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+                object "StrengthReductionTest" {
+                    code {
+                    }
+                    object "StrengthReductionTest_deployed" {
+                        code {
+                            let x := calldataload(4)
+                            let n := calldataload(36)
+                            let zero := calldataload(68)
+                            mstore(0x80, mul(x, 8))
+                            mstore(0xa0, mul(32, x))
+                            mstore(0xc0, div(x, 16))
+                            mstore(0xe0, div(x, 10))
+                            mstore(0x100, div(x, 3))
+                            mstore(0x120, mod(x, 256))
+                            mstore(0x140, div(x, 0))
+                            mstore(0x160, mod(x, 0))
+                            mstore(0x180, sdiv(x, 4))
+                            mstore(0x1a0, exp(2, n))
+                            mstore(0x1c0, exp(256, div(n, 8)))
+                            mstore(0x1e0, exp(x, 3))
+                            mstore(0x200, exp(x, 0))
+                            mstore(0x220, exp(4, n))
+                            // divided in the stdlib
+                            mstore(0x240, div(x, zero))
+                            mstore(0x260, mod(x, zero))
+                            mstore(0x280, div(n, sub(n, 193)))
+                            mstore(0x2a0, mod(n, sub(n, 193)))
+                            return(0x80, 0x240)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(
+                &solidity_selector("test(uint256,uint256,uint256)"),
+                &encode_abi_parameters(&[
+                    (
+                        // 0xfedcba9876543210 repeated
+                        "115277457729594790117272911370839532189043261309930451181949783328023217713680"
+                            .to_string(),
+                        ParamType::Uint(256),
+                    ),
+                    ("200".to_string(), ParamType::Uint(256)),
+                    ("0".to_string(), ParamType::Uint(256)),
+                ]),
+            )
+            .unwrap();
+        runtime.assert_result(concat!(
+            "f6e5d4c3b2a19087f6e5d4c3b2a19087f6e5d4c3b2a19087f6e5d4c3b2a19080",
+            "db97530eca86421fdb97530eca86421fdb97530eca86421fdb97530eca864200",
+            "0fedcba9876543210fedcba9876543210fedcba9876543210fedcba987654321",
+            "197c790f3f086b68197c790f3f086b68197c790f3f086b68197c790f3f086b68",
+            "54f43e32d21c10b054f43e32d21c10b054f43e32d21c10b054f43e32d21c10b0",
+            "0000000000000000000000000000000000000000000000000000000000000010",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            // rounded towards zero
+            "ffb72ea61d950c843fb72ea61d950c843fb72ea61d950c843fb72ea61d950c84",
+            "0000000000000100000000000000000000000000000000000000000000000000",
+            "0000000000000100000000000000000000000000000000000000000000000000",
+            "aa832264686eb65ceaca2ed3d9a3b695e33b0553bf4629cc93d5a5e419561000",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "000000000000000000000000000000000000000000000000000000000000001c",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+        ));
+    }
+
+    // Lowers `expr` of the calldata words x and d and returns the IR
+    fn arithmetic_ir(expr: &str) -> String {
+        let object = yul::ObjectParser::new()
+            .parse(&format!(
+                r#"
+                object "ArithmeticIR" {{
+                    code {{
+                    }}
+                    object "ArithmeticIR_deployed" {{
+                        code {{
+                            let x := calldataload(4)
+                            let d := calldataload(36)
+                            mstore(0x80, {expr})
+                            return(0x80, 0x20)
+                        }}
+                    }}
+                }}
+                "#
+            ))
+            .unwrap();
+        let llvm_context = Context::create();
+        let opts = Yul2IROptions::test("ArithmeticIR");
+        let mut context = Yul2IRContext::new_with_object(&llvm_context, &opts, object);
+        context.emit("output").unwrap();
+        let module = context.llvm_module.borrow();
+        module.print_to_string().to_string()
+    }
+
+    #[test]
+    fn test_div_by_power_of_two_lowering() {
+        for expr in [
+            "div(x, 1)",
+            "div(x, 2)",
+            "div(x, 256)",
+            "div(x, 0x100000000000000000000000000000000)",
+            "div(x, 0x8000000000000000000000000000000000000000000000000000000000000000)",
+            "mod(x, 256)",
+        ] {
+            let ir = arithmetic_ir(expr);
+            assert!(
+                !ir.contains("call void @wrapper_u256_div("),
+                "{expr} calls wrapper_u256_div"
+            );
+            assert!(
+                !ir.contains("call void @wrapper_u256_mod("),
+                "{expr} calls wrapper_u256_mod"
+            );
+        }
+        // other divisors still go through the stdlib
+        assert!(arithmetic_ir("div(x, d)").contains("call void @wrapper_u256_div("));
+    }
 }
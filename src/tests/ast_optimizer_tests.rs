@@ -0,0 +1,212 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::ast::{Expression, Literal, Object, Statement};
+#[allow(unused)]
+use crate::yul2ir::ast_optimizer::optimize_object;
+#[allow(unused)]
+use crate::yul2ir::yul;
+
+#[allow(unused)]
+fn optimize_code(code: &str) -> Vec<Statement> {
+    let object: Object = yul::ObjectParser::new()
+        .parse(&format!(
+            "object \"AstOptimizerTest\" {{ code {{ {code} }} }}"
+        ))
+        .unwrap();
+    optimize_object(&object).code.statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::U256;
+
+    // the constant stored by the last statement of the optimized code if it
+    // is `sstore(0, constant)`
+    fn stored_constant(code: &str) -> Option<U256> {
+        match optimize_code(code).last() {
+            Some(Statement::FunctionCall(call)) if call.id.name == "sstore" => {
+                match &call.arguments[1] {
+                    Expression::Literal(Literal::DecimalNumberLiteral(dec, None)) => {
+                        Some(U256::from_dec_str(&dec.dec).unwrap())
+                    }
+                    _ => None,
+                }
+            }
+            _ => panic!("the code doesn't end with sstore"),
+        }
+    }
+
+    #[test]
+    fn test_ast_optimizer_folds_builtins() {
+        let folded = |expr: &str| stored_constant(&format!("sstore(0, {expr})"));
+        assert_eq!(folded("add(not(0), 2)"), Some(U256::one()));
+        assert_eq!(folded("sub(0, 1)"), Some(U256::MAX));
+        assert_eq!(folded("exp(2, 255)"), Some(U256::one() << 255));
+        assert_eq!(folded("exp(2, 256)"), Some(U256::zero()));
+        assert_eq!(folded("div(7, 0)"), Some(U256::zero()));
+        // -10 / 3 and -10 % 3
+        assert_eq!(folded("sdiv(not(9), 3)"), Some(!U256::from(2)));
+        assert_eq!(folded("smod(not(9), 3)"), Some(U256::MAX));
+        // the minimum divided by -1 overflows to itself
+        assert_eq!(
+            folded("sdiv(shl(255, 1), not(0))"),
+            Some(U256::one() << 255)
+        );
+        assert_eq!(folded("slt(not(0), 0)"), Some(U256::one()));
+        assert_eq!(folded("sgt(not(0), 0)"), Some(U256::zero()));
+        assert_eq!(folded("byte(30, 0x1234)"), Some(U256::from(0x12)));
+        assert_eq!(folded("byte(32, 0x1234)"), Some(U256::zero()));
+        assert_eq!(folded("shr(256, 1)"), Some(U256::zero()));
+        assert_eq!(folded("sar(4, shl(255, 1))"), Some(U256::from(0x1f) << 251));
+        assert_eq!(folded("sar(300, not(0))"), Some(U256::MAX));
+        assert_eq!(folded("signextend(0, 0xff)"), Some(U256::MAX));
+        assert_eq!(folded("signextend(0, 0x17f)"), Some(U256::from(0x7f)));
+        assert_eq!(folded("addmod(not(0), 2, 7)"), Some(U256::from(3)));
+        assert_eq!(folded("mulmod(not(0), not(0), 13)"), Some(U256::from(4)));
+        assert_eq!(folded("add(calldataload(0), 1)"), None);
+    }
+
+    #[test]
+    fn test_ast_optimizer_propagates_let_constants() {
+        assert_eq!(
+            stored_constant("let a := 1 let b := shl(a, 3) sstore(0, add(a, b))"),
+            Some(U256::from(7))
+        );
+        // assigned variables aren't constant
+        assert_eq!(
+            stored_constant("let i := 1 i := add(i, 1) sstore(0, i)"),
+            None
+        );
+        // typed variables keep their type
+        assert_eq!(stored_constant("let a:u32 := 1 sstore(0, a)"), None);
+    }
+
+    #[test]
+    fn test_ast_optimizer_folds_keccak256_of_constant_memory() {
+        let hash = U256::from_str_radix(
+            "e321d900f3fd366734e2d071e30949ded20c27fd638f1a059390091c643b62c5",
+            16,
+        )
+        .unwrap();
+        assert_eq!(
+            stored_constant("mstore(0, 0x1234) sstore(0, keccak256(0, 0x20))"),
+            Some(hash)
+        );
+        assert_eq!(
+            stored_constant("mstore(0, 0x1200) mstore8(31, 0x34) sstore(0, keccak256(0, 0x20))"),
+            Some(hash)
+        );
+        // part of the range isn't known
+        assert_eq!(
+            stored_constant("mstore8(31, 0x34) sstore(0, keccak256(0, 0x20))"),
+            None
+        );
+        assert_eq!(
+            stored_constant(
+                "mstore(0, 0x1234) mstore(calldataload(0), 1) sstore(0, keccak256(0, 0x20))"
+            ),
+            None
+        );
+        assert_eq!(
+            stored_constant(
+                "mstore(0, 0x1234) if calldataload(0) { mstore(0, 1) } sstore(0, keccak256(0, 0x20))"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ast_optimizer_removes_dead_code() {
+        let statements = optimize_code(
+            r#"
+            let a := 1
+            let b := add(a, 2)
+            if 0 { revert(0, 0) }
+            switch b
+            case 3 { sstore(0, b) }
+            default { sstore(0, 5) }
+            let unused := mul(b, 2)
+            let loaded := sload(0)
+            for { } 0 { } { sstore(1, 1) }
+            return(0, 0)
+            sstore(2, 2)
+            function f() { }
+            "#,
+        );
+        assert_eq!(statements.len(), 4);
+        match &statements[0] {
+            Statement::Block(block) => assert!(matches!(
+                &block.statements[..],
+                [Statement::FunctionCall(call)] if call.id.name == "sstore"
+            )),
+            _ => panic!("the switch isn't replaced by the selected case"),
+        }
+        // sload isn't pure
+        assert!(matches!(&statements[1], Statement::VariableDeclaration(_)));
+        assert!(
+            matches!(&statements[2], Statement::FunctionCall(call) if call.id.name == "return")
+        );
+        assert!(matches!(&statements[3], Statement::FunctionDefinition(_)));
+    }
+
+    #[test]
+    fn test_ast_optimizer_folded_contract() {
+        let mut runtime = TestRuntime::new(
+            "AstOptimizerTest",
+            "target/test_ast_optimizer_folded_contract",
+        );
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+                object "AstOptimizerTest" {
+                    code {
+                    }
+                    object "AstOptimizerTest_deployed" {
+                        code {
+                            mstore(0, 0x1234)
+                            let hash := keccak256(0, 0x20)
+                            let quotient := sdiv(not(9), 3)
+                            let remainder := smod(not(9), 3)
+                            let extended := signextend(0, 0xff)
+                            let shifted := sar(4, shl(255, 1))
+                            let selected := 0
+                            switch byte(30, 0x1234)
+                            case 0x12 { selected := 1 }
+                            default { selected := 2 }
+                            for { } lt(selected, 0) { } { revert(0, 0) }
+                            if 0 { revert(0, 0) }
+                            mstore(0x80, hash)
+                            mstore(0xa0, quotient)
+                            mstore(0xc0, remainder)
+                            mstore(0xe0, extended)
+                            mstore(0x100, shifted)
+                            mstore(0x120, selected)
+                            mstore(0x140, mulmod(not(0), not(0), 13))
+                            return(0x80, 0xe0)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime.call(&solidity_selector("test()"), &[]).unwrap();
+        runtime.assert_result(concat!(
+            "e321d900f3fd366734e2d071e30949ded20c27fd638f1a059390091c643b62c5",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffd",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "f800000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::config::CallingConvention;
+
+#[allow(unused)]
+const CALLING_CONVENTION_CONTRACT: &str = r#"
+object "CallingConventionTest" {
+    code {
+    }
+    object "CallingConventionTest_deployed" {
+        code {
+            let a, b := split(calldataload(4))
+            let total := 0
+            for { let i := 0 } lt(i, 3) { i := add(i, 1) } {
+                total := add(total, scaled(a, i))
+            }
+            mstore(0x80, b)
+            a, b := split(total)
+            mstore(0xa0, a)
+            mstore(0xc0, b)
+            mstore(0xe0, fib(10))
+            mstore(0x100, low(not(0), 7))
+            return(0x80, 0xa0)
+
+            function split(value) -> high, low_bits {
+                high := shr(128, value)
+                low_bits := and(value, sub(shl(128, 1), 1))
+            }
+            function scaled(value, factor) -> result {
+                if iszero(factor) {
+                    result := value
+                    leave
+                }
+                result := mul(value, factor)
+            }
+            function fib(n) -> r {
+                r := n
+                if gt(n, 1) {
+                    r := add(fib(sub(n, 1)), fib(sub(n, 2)))
+                }
+            }
+            function low(value, bits) -> result {
+                result := and(value, sub(shl(bits, 1), 1))
+            }
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+    use crate::yul2ir::config::Yul2IROptions;
+    use crate::yul2ir::context::Yul2IRContext;
+    use crate::yul2ir::yul;
+    use ethabi::ParamType;
+    use inkwell::context::Context;
+    use inkwell::types::BasicMetadataTypeEnum;
+
+    #[test]
+    fn test_pointer_calling_convention_signatures() {
+        let object = yul::ObjectParser::new()
+            .parse(CALLING_CONVENTION_CONTRACT)
+            .unwrap();
+        let llvm_context = Context::create();
+        let mut opts = Yul2IROptions::test("CallingConventionTest");
+        opts.calling_convention = CallingConvention::Pointer;
+        let mut context = Yul2IRContext::new_with_object(&llvm_context, &opts, object);
+        context.emit("output").unwrap();
+
+        let module = context.llvm_module.borrow();
+        let signature = |name: &str| {
+            let function = module
+                .get_functions()
+                .find(|function| {
+                    function
+                        .get_name()
+                        .to_str()
+                        .unwrap()
+                        .ends_with(&format!("_deployed.{name}"))
+                })
+                .unwrap();
+            let ty = function.get_type();
+            let params = ty
+                .get_param_types()
+                .iter()
+                .map(|param| match param {
+                    BasicMetadataTypeEnum::PointerType(_) => "ptr".to_string(),
+                    BasicMetadataTypeEnum::IntType(int) => format!("i{}", int.get_bit_width()),
+                    _ => panic!("unexpected parameter type {param:?}"),
+                })
+                .collect::<Vec<_>>();
+            (params, ty.get_return_type().is_none())
+        };
+        // the value and both out-pointers of the tuple
+        assert_eq!(signature("split"), (vec!["ptr".to_string(); 3], true));
+        assert_eq!(signature("fib"), (vec!["ptr".to_string(); 2], true));
+    }
+
+    #[test]
+    fn test_pointer_calling_convention_contract() {
+        // untyped and narrowed parameters
+        for enable_all_optimizers in [false, true] {
+            let mut runtime = TestRuntime::new(
+                "CallingConventionTest",
+                "target/test_pointer_calling_convention_contract",
+            );
+            runtime.clear_testdata();
+            runtime.set_calling_convention(CallingConvention::Pointer);
+            runtime.set_enable_all_optimizers(enable_all_optimizers);
+            runtime
+                .compile_test_yul(CALLING_CONVENTION_CONTRACT)
+                .unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime
+                .call(
+                    &solidity_selector("test(uint256)"),
+                    // 5 * 2^128 + 7
+                    &encode_abi_parameters(&[(
+                        "1701411834604692317316873037158841057287".to_string(),
+                        ParamType::Uint(256),
+                    )]),
+                )
+                .unwrap();
+            runtime.assert_result(concat!(
+                "0000000000000000000000000000000000000000000000000000000000000007",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000014",
+                "0000000000000000000000000000000000000000000000000000000000000037",
+                "000000000000000000000000000000000000000000000000000000000000007f",
+            ));
+        }
+    }
+}
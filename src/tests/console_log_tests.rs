@@ -0,0 +1,129 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::{encode_abi_parameters, solidity_selector, TestRuntime};
+#[allow(unused)]
+use ethabi::ParamType;
+
+// Compiles a contract that forwards its calldata (after the selector) to the
+// console.log address as forge-std does, then returns 0x2a
+#[allow(unused)]
+fn call_console_log(case_name: &str, payload: &[u8]) -> TestRuntime {
+    let mut runtime = TestRuntime::new(case_name, &format!("target/test_{case_name}"));
+    runtime.clear_testdata();
+    let yul_code = format!(
+        r#"
+        object "{case_name}" {{
+            code {{
+            }}
+            object "{case_name}_deployed" {{
+                code {{
+                    let size := sub(calldatasize(), 4)
+                    calldatacopy(0x00, 4, size)
+                    pop(staticcall(gas(), 0x000000000000000000636F6e736F6c652e6c6f67, 0x00, size, 0x00, 0x00))
+                    mstore(0x00, 0x2a)
+                    return(0x00, 0x20)
+                }}
+            }}
+        }}
+        "#
+    );
+    runtime.compile_test_yul(&yul_code).unwrap();
+    runtime.deploy(&[]).unwrap();
+    runtime
+        .call(&solidity_selector("test_console_log()"), payload)
+        .unwrap();
+    runtime
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_log_string_uint_bool() {
+        let payload = [
+            solidity_selector("log(string,uint256,bool)"),
+            encode_abi_parameters(&[
+                ("hello console".to_string(), ParamType::String),
+                (
+                    "1234567890123456789012345".to_string(),
+                    ParamType::Uint(256),
+                ),
+                ("true".to_string(), ParamType::Bool),
+            ]),
+        ]
+        .concat();
+        let runtime = call_console_log("console_log_string_uint_bool", &payload);
+        runtime.assert_output_contains("hello console 1234567890123456789012345 true");
+        runtime.assert_result("000000000000000000000000000000000000000000000000000000000000002a");
+    }
+
+    #[test]
+    fn test_console_log_int_and_address() {
+        // -42 in two's complement
+        let payload = [
+            solidity_selector("log(int256)"),
+            hex::decode("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffd6")
+                .unwrap(),
+        ]
+        .concat();
+        let runtime = call_console_log("console_log_int", &payload);
+        runtime.assert_output_contains("-42");
+
+        let payload = [
+            solidity_selector("log(address)"),
+            encode_abi_parameters(&[(
+                "0x1234567890123456789012345678901234567890".to_string(),
+                ParamType::Address,
+            )]),
+        ]
+        .concat();
+        let runtime = call_console_log("console_log_address", &payload);
+        runtime.assert_output_contains("0x1234567890123456789012345678901234567890");
+    }
+
+    #[test]
+    fn test_console_log_unsupported_selector() {
+        let payload = solidity_selector("log(uint256,uint256,uint256,uint256)");
+        let runtime = call_console_log("console_log_unsupported_selector", &payload);
+        runtime.assert_output_contains("console.log: unsupported selector 0x");
+        runtime.assert_result("000000000000000000000000000000000000000000000000000000000000002a");
+    }
+
+    #[test]
+    fn test_console_log_clears_return_data() {
+        let mut runtime = TestRuntime::new(
+            "ConsoleLogReturnDataTest",
+            "target/test_console_log_clears_return_data",
+        );
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+            object "ConsoleLogReturnDataTest" {
+                code {
+                }
+                object "ConsoleLogReturnDataTest_deployed" {
+                    code {
+                        // 0x40 bytes of return data from the identity precompile
+                        pop(staticcall(gas(), 0x04, 0x00, 0x40, 0x00, 0x00))
+                        // log(uint) of 0
+                        mstore(0x80, 0xf5b1bba900000000000000000000000000000000000000000000000000000000)
+                        pop(staticcall(gas(), 0x000000000000000000636F6e736F6c652e6c6f67, 0x80, 0x24, 0x00, 0x00))
+                        mstore(0x00, returndatasize())
+                        return(0x00, 0x20)
+                    }
+                }
+            }
+            "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(&solidity_selector("test_console_log()"), &[])
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000000");
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::cost_report::{CostReport, ExternalFunctionCost};
+
+#[allow(unused)]
+const COST_REPORT_CONTRACT: &str = r#"
+object "CostReportTest" {
+    code {
+        sstore(0, caller())
+        datacopy(0, dataoffset("CostReportTest_deployed"), datasize("CostReportTest_deployed"))
+        return(0, datasize("CostReportTest_deployed"))
+    }
+    object "CostReportTest_deployed" {
+        code {
+            function fun_get() {
+                mstore(0, sload(0))
+                return(0, 0x20)
+            }
+            function store_logged(slot, value) {
+                sstore(slot, value)
+                log1(0, 0, slot)
+            }
+            function fun_set(value) {
+                store_logged(1, value)
+                if gt(value, 10) {
+                    store_logged(2, value)
+                }
+            }
+            function fun_sum_fixed() {
+                let total := 0
+                for { let i := 0 } lt(i, 3) { i := add(i, 1) } {
+                    total := add(total, sload(i))
+                }
+                mstore(0, total)
+                return(0, 0x20)
+            }
+            function fun_sum(n) {
+                let total := 0
+                for { let i := 0 } lt(i, n) { i := add(i, 1) } {
+                    total := add(total, sload(i))
+                }
+                mstore(0, total)
+                return(0, 0x20)
+            }
+            function countdown(n) -> r {
+                if n {
+                    r := add(countdown(sub(n, 1)), 1)
+                }
+            }
+
+            if iszero(lt(calldatasize(), 4)) {
+                let selector := shr(224, calldataload(0))
+                switch selector
+                case 0x11111111 { fun_get() }
+                case 0x22222222 { fun_set(calldataload(4)) }
+                case 0x33333333 { fun_sum_fixed() }
+                case 0x44444444 { fun_sum(calldataload(4)) }
+                case 0x55555555 {
+                    mstore(0, countdown(calldataload(4)))
+                    return(0, 0x20)
+                }
+                default { revert(0, 0) }
+            }
+            revert(0, 0)
+        }
+    }
+}
+"#;
+
+/// Compiles the contract renamed, each test writes its own report file
+#[allow(unused)]
+fn compile_cost_report_contract(contract_name: &str, case_name: &str) -> TestRuntime {
+    let mut runtime = TestRuntime::new(contract_name, case_name);
+    runtime.set_enable_cost_report(true);
+    runtime
+        .compile_test_yul(&COST_REPORT_CONTRACT.replace("CostReportTest", contract_name))
+        .unwrap();
+    runtime
+}
+
+#[allow(unused)]
+fn find_function<'a>(report: &'a CostReport, selector: &str) -> &'a ExternalFunctionCost {
+    report
+        .functions
+        .iter()
+        .find(|function| function.selector == selector)
+        .unwrap_or_else(|| panic!("no {selector} in {report}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_report_dispatch_table() {
+        let runtime = compile_cost_report_contract(
+            "CostReportDispatchTableTest",
+            "target/test_cost_report_dispatch_table",
+        );
+        let report = runtime.cost_report();
+        let selectors: Vec<&str> = report
+            .functions
+            .iter()
+            .map(|function| function.selector.as_str())
+            .collect();
+        assert_eq!(
+            selectors,
+            vec![
+                "constructor",
+                "0x11111111",
+                "0x22222222",
+                "0x33333333",
+                "0x44444444",
+                "0x55555555",
+                "fallback"
+            ]
+        );
+        let constructor = find_function(report, "constructor");
+        assert_eq!(constructor.object, "CostReportDispatchTableTest");
+        assert_eq!(constructor.cost.host_calls["sstore"], 1);
+
+        let get = find_function(report, "0x11111111");
+        assert_eq!(get.object, "CostReportDispatchTableTest_deployed");
+        assert_eq!(get.entry.as_deref(), Some("fun_get"));
+        assert_eq!(get.cost.host_calls["sload"], 1);
+        assert!(get.cost.is_bounded());
+        assert!(get.cost.wasm_instructions > 0);
+
+        assert_eq!(find_function(report, "0x55555555").entry, None);
+    }
+
+    #[test]
+    fn test_cost_report_worst_case_host_calls() {
+        let runtime = compile_cost_report_contract(
+            "CostReportWorstCaseHostCallsTest",
+            "target/test_cost_report_worst_case_host_calls",
+        );
+        let report = runtime.cost_report();
+        // the worst case takes the branch calling store_logged again
+        let set = find_function(report, "0x22222222");
+        assert_eq!(set.cost.host_calls["sstore"], 2);
+        assert_eq!(set.cost.host_calls["log1"], 2);
+        assert!(set.cost.is_bounded());
+
+        let sum_fixed = find_function(report, "0x33333333");
+        assert_eq!(sum_fixed.cost.host_calls["sload"], 3);
+        assert!(sum_fixed.cost.is_bounded());
+        assert!(
+            sum_fixed.cost.wasm_instructions
+                > find_function(report, "0x11111111").cost.wasm_instructions
+        );
+    }
+
+    #[test]
+    fn test_cost_report_unbounded_code() {
+        let runtime = compile_cost_report_contract(
+            "CostReportUnboundedCodeTest",
+            "target/test_cost_report_unbounded_code",
+        );
+        let report = runtime.cost_report();
+        let sum = find_function(report, "0x44444444");
+        assert!(!sum.cost.is_bounded());
+        assert!(sum.cost.unbounded_loops.contains("fun_sum"));
+        // one iteration is counted
+        assert_eq!(sum.cost.host_calls["sload"], 1);
+
+        let countdown = find_function(report, "0x55555555");
+        assert!(countdown.cost.recursive_functions.contains("countdown"));
+        assert!(countdown.cost.unbounded_loops.is_empty());
+
+        let text = report.to_string();
+        assert!(text.contains("unbounded loops in fun_sum"), "{text}");
+        assert!(text.contains("unbounded recursion in countdown"), "{text}");
+    }
+
+    #[test]
+    fn test_cost_report_llvm_instructions() {
+        let runtime = compile_cost_report_contract(
+            "CostReportLlvmInstructionsTest",
+            "target/test_cost_report_llvm_instructions",
+        );
+        let report = runtime.cost_report();
+        let prefix =
+            "CostReportLlvmInstructionsTest_deployed.CostReportLlvmInstructionsTest_deployed";
+        assert!(report.llvm_instructions[&format!("{prefix}.fun_set")] > 0);
+        assert!(report.llvm_instructions["call"] > 0);
+        assert!(report.llvm_instructions["deploy"] > 0);
+        // stdlib functions aren't transformed from Yul
+        assert!(!report.llvm_instructions.contains_key("wrapper_sload_u256"));
+
+        let written = std::fs::read_to_string(concat!(
+            "./target/CostReportLlvmInstructionsTest/",
+            "CostReportLlvmInstructionsTest_output.cost.txt"
+        ))
+        .unwrap();
+        assert!(written.contains("0x22222222 (fun_set)"), "{written}");
+    }
+}
@@ -212,4 +212,209 @@ mod tests {
             .call(&solidity_selector("test_func()"), &[])
             .unwrap();
     }
+
+    #[test]
+    fn test_yul_opt_dedup_alpha_equivalent_functions() {
+        let mut runtime = TestRuntime::new("DedupTest1", "target/test_dedup1");
+        let emited_bc = runtime
+            .compile_test_yul(
+                r#"
+            object "DedupTest1" {
+                code {
+                }
+                object "DedupTest1_deployed" {
+                    code {
+                        function cleanup_a(value) -> cleaned {
+                            cleaned := and(value, 0xff)
+                        }
+                        function cleanup_b(x) -> y {
+                            y := and(x, 0xff)
+                        }
+                        function checked_add_a(x, y) -> sum {
+                            let x_1 := cleanup_a(x)
+                            sum := add(x_1, cleanup_a(y))
+                        }
+                        function checked_add_b(a, b) -> s {
+                            let tmp := cleanup_b(a)
+                            s := add(tmp, cleanup_b(b))
+                        }
+
+                        let r := add(checked_add_a(0x1ff, 2), checked_add_b(0x101, 3))
+                        mstore(0x00, r)
+                        return(0x00, 0x20)
+                    }
+                }
+            }
+            "#,
+            )
+            .unwrap();
+        std::fs::write("target/test_dedup1/test_dedup1.wasm", emited_bc).unwrap();
+        runtime.wasm2wat(
+            "target/test_dedup1/test_dedup1.wasm",
+            "target/test_dedup1/test_dedup1.wat",
+        );
+        let wat_path = "target/test_dedup1/test_dedup1.wat";
+        assert!(contains_string(wat_path, "cleanup_a").unwrap());
+        assert!(contains_string(wat_path, "checked_add_a").unwrap());
+        assert!(!contains_string(wat_path, "cleanup_b").unwrap());
+        // checked_add_b only becomes equal to checked_add_a once cleanup_b is merged
+        assert!(!contains_string(wat_path, "checked_add_b").unwrap());
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(&solidity_selector("test_func()"), &[])
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000105");
+    }
+
+    #[test]
+    fn test_yul_opt_dedup_keeps_different_functions() {
+        let mut runtime = TestRuntime::new("DedupTest2", "target/test_dedup2");
+        let emited_bc = runtime
+            .compile_test_yul(
+                r#"
+            object "DedupTest2" {
+                code {
+                }
+                object "DedupTest2_deployed" {
+                    code {
+                        function increment_by_one(x) -> r {
+                            r := add(x, 1)
+                        }
+                        function increment_by_two(x) -> r {
+                            r := add(x, 2)
+                        }
+                        function swapped_sub(x, y) -> r {
+                            r := sub(y, x)
+                        }
+                        function plain_sub(x, y) -> r {
+                            r := sub(x, y)
+                        }
+
+                        let r := add(increment_by_one(1), increment_by_two(1))
+                        r := add(r, add(swapped_sub(1, 10), plain_sub(10, 2)))
+                        mstore(0x00, r)
+                        return(0x00, 0x20)
+                    }
+                }
+            }
+            "#,
+            )
+            .unwrap();
+        std::fs::write("target/test_dedup2/test_dedup2.wasm", emited_bc).unwrap();
+        runtime.wasm2wat(
+            "target/test_dedup2/test_dedup2.wasm",
+            "target/test_dedup2/test_dedup2.wat",
+        );
+        let wat_path = "target/test_dedup2/test_dedup2.wat";
+        for name in [
+            "increment_by_one",
+            "increment_by_two",
+            "swapped_sub",
+            "plain_sub",
+        ] {
+            assert!(contains_string(wat_path, name).unwrap());
+        }
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(&solidity_selector("test_func()"), &[])
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000016");
+    }
+
+    #[test]
+    fn test_yul_opt_dedup_address_decoder_by_body() {
+        let mut runtime = TestRuntime::new("DedupTest3", "target/test_dedup3");
+        let emited_bc = runtime
+            .compile_test_yul(
+                r#"
+            object "DedupTest3" {
+                code {
+                }
+                object "DedupTest3_deployed" {
+                    code {
+                        function abi_decode_address_plus_one(value) -> r {
+                            r := add(value, 1)
+                        }
+                        function increment(x) -> y {
+                            y := add(x, 1)
+                        }
+                        function abi_decode_address_is_zero(value) -> r {
+                            r := iszero(value)
+                        }
+                        function check_zero(x) -> y {
+                            y := iszero(x)
+                        }
+
+                        mstore(0x00, add(abi_decode_address_plus_one(1), increment(2)))
+                        // iszero is lowered as false inside the address decoder
+                        mstore(0x20, abi_decode_address_is_zero(0))
+                        mstore(0x40, check_zero(0))
+                        return(0x00, 0x60)
+                    }
+                }
+            }
+            "#,
+            )
+            .unwrap();
+        std::fs::write("target/test_dedup3/test_dedup3.wasm", emited_bc).unwrap();
+        runtime.wasm2wat(
+            "target/test_dedup3/test_dedup3.wasm",
+            "target/test_dedup3/test_dedup3.wat",
+        );
+        let wat_path = "target/test_dedup3/test_dedup3.wat";
+        // the name only matters for bodies using iszero or and
+        assert!(contains_string(wat_path, "abi_decode_address_plus_one").unwrap());
+        assert!(!contains_string(wat_path, "increment").unwrap());
+        assert!(contains_string(wat_path, "abi_decode_address_is_zero").unwrap());
+        assert!(contains_string(wat_path, "check_zero").unwrap());
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(&solidity_selector("test_func()"), &[])
+            .unwrap();
+        runtime.assert_result(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        ));
+    }
+
+    #[test]
+    fn test_yul_opt_dedup_size_report() {
+        let mut runtime = TestRuntime::new("DedupTest4", "target/test_dedup4");
+        runtime.set_enable_size_report(true);
+        runtime
+            .compile_test_yul(
+                r#"
+            object "DedupTest4" {
+                code {
+                }
+                object "DedupTest4_deployed" {
+                    code {
+                        function cleanup_a(value) -> cleaned {
+                            cleaned := and(value, 0xff)
+                        }
+                        function cleanup_b(x) -> y {
+                            y := and(x, 0xff)
+                        }
+                        function cleanup_c(v) -> w {
+                            w := and(v, 0xff)
+                        }
+
+                        mstore(0x00, add(cleanup_a(1), add(cleanup_b(2), cleanup_c(3))))
+                        return(0x00, 0x20)
+                    }
+                }
+            }
+            "#,
+            )
+            .unwrap();
+        // recorded without verbose
+        let deduplication = runtime.size_report().deduplication.clone().unwrap();
+        assert_eq!(deduplication.merged, 2);
+        assert_eq!(deduplication.kept, 1);
+        assert!(runtime
+            .size_report()
+            .to_string()
+            .contains("function deduplication: merged 2 functions into 1"));
+    }
 }
@@ -0,0 +1,170 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::config::{parse_gas_cost_table, GasMetering, Yul2IROptions};
+#[allow(unused)]
+use crate::yul2ir::context::Yul2IRContext;
+#[allow(unused)]
+use crate::yul2ir::yul;
+#[allow(unused)]
+use inkwell::context::Context;
+
+#[allow(unused)]
+const GAS_METERING_CONTRACT: &str = r#"
+object "GasMeteringTest" {
+    code {
+    }
+    object "GasMeteringTest_deployed" {
+        code {
+            let total := 0
+            for { let i := 0 } lt(i, calldataload(4)) { i := add(i, 1) } {
+                total := add(total, i)
+            }
+            sstore(0, total)
+            mstore(0, total)
+            return(0, 0x20)
+        }
+    }
+}
+"#;
+
+/// IR of the gas metering contract with the given metering and costs
+#[allow(unused)]
+fn gas_metering_ir(gas_metering: GasMetering, gas_cost_overrides: &[(&str, u64)]) -> String {
+    let object = yul::ObjectParser::new()
+        .parse(GAS_METERING_CONTRACT)
+        .unwrap();
+    let llvm_context = Context::create();
+    let mut opts = Yul2IROptions::test("GasMeteringTest");
+    opts.gas_metering = Some(gas_metering);
+    opts.gas_cost_overrides = gas_cost_overrides
+        .iter()
+        .map(|(name, cost)| (name.to_string(), *cost))
+        .collect();
+    let mut context = Yul2IRContext::new_with_object(&llvm_context, &opts, object);
+    context.emit("output").unwrap();
+    let module = context.llvm_module.borrow();
+    module.print_to_string().to_string()
+}
+
+/// Costs charged by the useGas calls of the IR
+#[allow(unused)]
+fn gas_charges(ir: &str) -> Vec<u64> {
+    ir.match_indices("call void @useGas(i64 ")
+        .map(|(index, pattern)| {
+            let cost = &ir[index + pattern.len()..];
+            cost[..cost.find(')').unwrap()].parse().unwrap()
+        })
+        .collect()
+}
+
+/// Body of the function defined in the IR under the given name
+#[allow(unused)]
+fn function_body<'a>(ir: &'a str, name: &str) -> Option<&'a str> {
+    let signature = format!(" @{name}(");
+    let start = ir
+        .match_indices("\ndefine ")
+        .map(|(index, _)| &ir[index + 1..])
+        .find(|define| define[..define.find('\n').unwrap()].contains(&signature))?;
+    Some(&start[..start.find("\n}\n").unwrap()])
+}
+
+/// Functions defined in the IR and reachable from the given one
+#[allow(unused)]
+fn reachable_functions<'a>(ir: &'a str, entry: &str) -> Vec<(String, &'a str)> {
+    let mut reachable: Vec<(String, &str)> = vec![];
+    let mut pending = vec![entry.to_string()];
+    while let Some(name) = pending.pop() {
+        if reachable.iter().any(|(seen, _)| *seen == name) {
+            continue;
+        }
+        let Some(body) = function_body(ir, &name) else {
+            continue;
+        };
+        for line in body.lines() {
+            let Some(call) = line.find("call ").map(|index| &line[index..]) else {
+                continue;
+            };
+            if let Some(callee) = call.find(" @").map(|at| &call[at + 2..]) {
+                pending.push(callee[..callee.find('(').unwrap_or(callee.len())].to_string());
+            }
+        }
+        reachable.push((name, body));
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gas_cost_table() {
+        assert_eq!(
+            parse_gas_cost_table("# costs\nsstore = 5000\n\ncall=700 # warm\n").unwrap(),
+            vec![("sstore".to_string(), 5000), ("call".to_string(), 700)]
+        );
+        assert_eq!(
+            parse_gas_cost_table("sstore 5000").unwrap_err(),
+            "Invalid gas cost line: sstore 5000"
+        );
+        assert_eq!(
+            parse_gas_cost_table("sstore = -1").unwrap_err(),
+            "Invalid gas cost line: sstore = -1"
+        );
+    }
+
+    #[test]
+    fn test_gas_cost_table_rejected_by_gas_metering() {
+        let mut runtime = TestRuntime::new(
+            "GasMeteringTest",
+            "target/test_gas_cost_table_rejected_by_gas_metering",
+        );
+        runtime.set_gas_metering(Some(GasMetering::Wasm));
+        runtime.set_gas_cost_overrides(&[("sstore", 1)]);
+        let err = runtime.compile_test_yul(GAS_METERING_CONTRACT).unwrap_err();
+        assert_eq!(err, "unknown gas cost sstore for the wasm gas metering");
+    }
+
+    #[test]
+    fn test_evm_gas_metering() {
+        let ir = gas_metering_ir(GasMetering::Evm, &[("sstore", 1_000_000)]);
+        let charges = gas_charges(&ir);
+        // the sstore is charged together with the other builtins of its block
+        assert!(
+            charges
+                .iter()
+                .any(|cost| (1_000_000..1_001_000).contains(cost)),
+            "no sstore charge in {charges:?}"
+        );
+        // the loop condition is charged on every iteration
+        assert!(charges.len() >= 2, "charges: {charges:?}");
+    }
+
+    #[test]
+    fn test_wasm_gas_metering() {
+        let ir = gas_metering_ir(GasMetering::Wasm, &[("instruction", 7)]);
+        let charges = gas_charges(&ir);
+        assert!(!charges.is_empty());
+        // no block is cheaper than one instruction
+        assert!(
+            charges.iter().all(|cost| *cost >= 7),
+            "charges: {charges:?}"
+        );
+        // wizer runs _start and everything it calls at build time without
+        // the host api
+        let reachable = reachable_functions(&ir, "_start");
+        let names: Vec<&str> = reachable.iter().map(|(name, _)| name.as_str()).collect();
+        assert!(names.contains(&"__init_evm_heap"), "reachable: {names:?}");
+        for (name, body) in &reachable {
+            assert!(!body.contains("@useGas"), "{name} is metered");
+        }
+        if let Some(body) = function_body(&ir, "wasm_memory_end") {
+            assert!(names.contains(&"wasm_memory_end"), "reachable: {names:?}");
+            assert!(!body.contains("@useGas"));
+        }
+    }
+}
@@ -392,4 +392,108 @@ mod tests {
         runtime.call(&solidity_selector("test()"), &[]).unwrap();
         runtime.assert_revert("08c379a00000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000003c68656c6c6f776f726c642e20546869732069732061206c6f6e672072657665727420737472696e67206c6f6e676572207468616e203332627974657300000000");
     }
+
+    #[test]
+    fn test_yul_callcode() {
+        let mut runtime = TestRuntime::new("CallCodeTest", "target/test_yul_callcode");
+        runtime.clear_testdata();
+        let _emited_bc = runtime
+            .compile_test_yul(
+                r#"
+            object "CallCodeTest" {
+                code {
+                    let _datasize := datasize("CallCodeTest_deployed")
+                    datacopy(0x00, dataoffset("CallCodeTest_deployed"), _datasize)
+                    return(0x00, _datasize)
+                }
+
+                object "CallCodeTest_deployed" {
+                    code {
+                        datacopy(0x00, dataoffset("ContractA"), datasize("ContractA"))
+                        let addrContractA := create(0, 0x00, datasize("ContractA"))
+                        mstore(0x00, 0xb7cdb9f0)
+                        let success := callcode(gas(), addrContractA, 0, 0x00, 0x04, 0x00, 0x20)
+                        if iszero(success) { revert(0x00, 0x00) }
+
+                        // the callee ran in this contract's context, so its sstore is visible here
+                        mstore(0x20, sload(0x01))
+                        return(0x00, 0x40)
+                    }
+
+                    object "ContractA" {
+                        code {
+                            let _aDatasize := datasize("ContractA_deployed")
+                            datacopy(0x00, dataoffset("ContractA_deployed"), _aDatasize)
+                            return(0x00, _aDatasize)
+                        }
+
+                        object "ContractA_deployed" {
+                            code {
+                                sstore(0x01, 0x2a)
+                                mstore(0x00, 0x7b)
+                                return(0x00, 0x20)
+                            }
+                        }
+                    }
+                }
+            }
+            "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(&solidity_selector("test_callcode()"), &[])
+            .unwrap();
+        runtime.assert_result("000000000000000000000000000000000000000000000000000000000000007b000000000000000000000000000000000000000000000000000000000000002a");
+    }
+
+    #[test]
+    fn test_yul_callcode_revert() {
+        let mut runtime = TestRuntime::new("CallCodeRevertTest", "target/test_yul_callcode_revert");
+        runtime.clear_testdata();
+        let _emited_bc = runtime
+            .compile_test_yul(
+                r#"
+            object "CallCodeRevertTest" {
+                code {
+                    let _datasize := datasize("CallCodeRevertTest_deployed")
+                    datacopy(0x00, dataoffset("CallCodeRevertTest_deployed"), _datasize)
+                    return(0x00, _datasize)
+                }
+
+                object "CallCodeRevertTest_deployed" {
+                    code {
+                        datacopy(0x00, dataoffset("ContractA"), datasize("ContractA"))
+                        let addrContractA := create(0, 0x00, datasize("ContractA"))
+                        let success := callcode(gas(), addrContractA, 0, 0x00, 0x00, 0x00, 0x00)
+                        mstore(0x00, success)
+                        mstore(0x20, returndatasize())
+                        return(0x00, 0x40)
+                    }
+
+                    object "ContractA" {
+                        code {
+                            let _aDatasize := datasize("ContractA_deployed")
+                            datacopy(0x00, dataoffset("ContractA_deployed"), _aDatasize)
+                            return(0x00, _aDatasize)
+                        }
+
+                        object "ContractA_deployed" {
+                            code {
+                                mstore(0x00, 0x01)
+                                revert(0x00, 0x20)
+                            }
+                        }
+                    }
+                }
+            }
+            "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(&solidity_selector("test_callcode_revert()"), &[])
+            .unwrap();
+        runtime.assert_result("00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000020");
+    }
 }
@@ -0,0 +1,127 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+
+#[allow(unused)]
+const LIMB_U256_CONTRACT: &str = r#"
+object "LimbU256Test" {
+    code {
+    }
+    object "LimbU256Test_deployed" {
+        code {
+            let a := calldataload(4)
+            let b := calldataload(36)
+            let m := calldataload(68)
+            let zero := calldataload(100)
+            mstore(0x80, div(a, b))
+            mstore(0xa0, mod(a, b))
+            mstore(0xc0, sdiv(a, b))
+            mstore(0xe0, smod(a, b))
+            mstore(0x100, addmod(a, a, m))
+            mstore(0x120, mulmod(a, a, m))
+            mstore(0x140, exp(or(b, 1), 200))
+            mstore(0x160, exp(or(b, 1), m))
+            mstore(0x180, div(a, zero))
+            mstore(0x1a0, mod(a, zero))
+            mstore(0x1c0, sdiv(a, zero))
+            mstore(0x1e0, smod(a, zero))
+            mstore(0x200, addmod(a, b, zero))
+            mstore(0x220, mulmod(a, b, zero))
+            return(0x80, 0x1c0)
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+    use ethabi::ParamType;
+
+    // Runs the contract with and without the optimizers on (a, b, m, 0)
+    fn run_limb_u256_contract(a: &str, b: &str, m: &str, expected: &str) {
+        for enable_all_optimizers in [false, true] {
+            let mut runtime = TestRuntime::new("LimbU256Test", "target/test_limb_u256_contract");
+            runtime.clear_testdata();
+            runtime.set_enable_limb_u256_math(true);
+            runtime.set_enable_all_optimizers(enable_all_optimizers);
+            runtime.compile_test_yul(LIMB_U256_CONTRACT).unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime
+                .call(
+                    &solidity_selector("test(uint256,uint256,uint256,uint256)"),
+                    &encode_abi_parameters(&[
+                        (a.to_string(), ParamType::Uint(256)),
+                        (b.to_string(), ParamType::Uint(256)),
+                        (m.to_string(), ParamType::Uint(256)),
+                        ("0".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_result(expected);
+        }
+    }
+
+    #[test]
+    fn test_limb_u256_multi_limb_operands() {
+        run_limb_u256_contract(
+            // 0xfedcba9876543210 repeated in all four limbs, negative as int256
+            "115277457729594790117272911370839532189043261309930451181949783328023217713680",
+            // 0x123456789abcdef0fedcba98
+            "5634002667681019492877253272",
+            // 2^255 - 19
+            "57896044618658097711785492504343953926634992332820282019728792003956564819949",
+            concat!(
+                "00000000000000000000000e0000000000000c790000005b000b0f5a0000a1c3",
+                "000000000000000000000000000000000000000009ccb3b5cbb8d74d82d87848",
+                "fffffffffffffffffffffffff000000000000000cfffffff980000c76fffffaa",
+                "ffffffffffffffffffffffffffffffffffffffffeeeefb832d3a01c3947ae120",
+                "7db97530eca86421fdb97530eca86421fdb97530eca86421fdb97530eca86459",
+                "1e9a8badbc738f1d02c41cc8830813dde6edade3499c989ecb173efe103122a8",
+                "035b70b0e036462b1181cb4000ed87b83c53f8b2cbe05d871ee77b1ba30f7dc1",
+                "96aea63ab640839d262d36fc7e62f91e8e7f6a17de0182cad05928bb294dc639",
+                // division, remainder and modulo by zero
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        );
+    }
+
+    #[test]
+    fn test_limb_u256_int256_min_by_minus_one() {
+        run_limb_u256_contract(
+            // -2^255
+            "57896044618658097711785492504343953926634992332820282019728792003956564819968",
+            // -1, also the modulus
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+            concat!(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "8000000000000000000000000000000000000000000000000000000000000000",
+                // sdiv(-2^255, -1) overflows back to -2^255
+                "8000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                // the 257 bits sum and 512 bits product
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "4000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        );
+    }
+}
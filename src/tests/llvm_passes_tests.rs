@@ -0,0 +1,104 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::config::LlvmPipeline;
+
+#[allow(unused)]
+const LLVM_PASSES_CONTRACT: &str = r#"
+object "LlvmPassesTest" {
+    code {
+    }
+    object "LlvmPassesTest_deployed" {
+        code {
+            let total := 0
+            for { let i := 0 } lt(i, 4) { i := add(i, 1) } {
+                total := add(total, square(add(i, calldataload(4))))
+            }
+            mstore(0, total)
+            mstore(0x20, square(total))
+            return(0, 0x40)
+
+            function square(value) -> result {
+                result := mul(value, value)
+            }
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+    use ethabi::ParamType;
+
+    #[test]
+    fn test_llvm_pipeline_names() {
+        assert_eq!("default".parse(), Ok(LlvmPipeline::Default));
+        assert_eq!(
+            "default<O3>".parse(),
+            Ok(LlvmPipeline::Custom("default<O3>".to_string()))
+        );
+        assert_eq!(
+            " default<O2>,mergefunc ".parse(),
+            Ok(LlvmPipeline::Custom("default<O2>,mergefunc".to_string()))
+        );
+        assert_eq!(
+            "".parse::<LlvmPipeline>(),
+            Err("Empty LLVM pass pipeline".to_string())
+        );
+        assert_eq!(
+            LlvmPipeline::Custom("function(sroa)".to_string()).to_string(),
+            "function(sroa)"
+        );
+    }
+
+    #[test]
+    fn test_llvm_pipelines_agree() {
+        for pipeline in [
+            LlvmPipeline::Default,
+            LlvmPipeline::Custom("default<O3>".to_string()),
+            LlvmPipeline::Custom("default<Oz>".to_string()),
+            LlvmPipeline::Custom("function(sroa,instcombine),globaldce".to_string()),
+        ] {
+            let mut runtime =
+                TestRuntime::new("LlvmPassesTest", "target/test_llvm_pipelines_agree");
+            runtime.clear_testdata();
+            runtime.set_llvm_pipeline(pipeline);
+            runtime.compile_test_yul(LLVM_PASSES_CONTRACT).unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime
+                .call(
+                    &solidity_selector("test(uint256)"),
+                    &encode_abi_parameters(&[("1".to_string(), ParamType::Uint(256))]),
+                )
+                .unwrap();
+            // 1 + 4 + 9 + 16 and its square
+            runtime.assert_result(concat!(
+                "000000000000000000000000000000000000000000000000000000000000001e",
+                "0000000000000000000000000000000000000000000000000000000000000384",
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_llvm_pipeline() {
+        let mut runtime =
+            TestRuntime::new("LlvmPassesInvalidTest", "target/test_invalid_llvm_pipeline");
+        runtime.set_llvm_pipeline(LlvmPipeline::Custom("no-such-pass".to_string()));
+        let err = runtime
+            .compile_test_yul(
+                &LLVM_PASSES_CONTRACT.replace("LlvmPassesTest", "LlvmPassesInvalidTest"),
+            )
+            .unwrap_err();
+        assert!(
+            err.starts_with("Failed to run llvm passes no-such-pass:"),
+            "{err}"
+        );
+    }
+}
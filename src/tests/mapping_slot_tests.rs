@@ -0,0 +1,243 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::ast::Object;
+#[allow(unused)]
+use crate::yul2ir::yul;
+
+#[allow(unused)]
+fn parse_code(code: &str) -> Object {
+    yul::ObjectParser::new()
+        .parse(&format!(
+            "object \"MappingSlotIdiomTest\" {{ code {{ {code} }} }}"
+        ))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+    use crate::yul2ir::config::Yul2IROptions;
+    use crate::yul2ir::context::Yul2IRContext;
+    use ethabi::ParamType;
+    use inkwell::context::Context;
+
+    // Some(keep_scratch) when the code starts with the mapping slot idiom
+    fn idiom_keeps_scratch(code: &str) -> Option<bool> {
+        let object = parse_code(code);
+        let statements = object.code.statements.clone();
+        let llvm_context = Context::create();
+        let opts = Yul2IROptions::test("MappingSlotIdiomTest");
+        let context = Yul2IRContext::new_with_object(&llvm_context, &opts, object);
+        context
+            .matches_mapping_slot_idiom(&statements)
+            .map(|idiom| idiom.keep_scratch)
+    }
+
+    #[test]
+    fn test_mapping_slot_idiom_scratch_space_analysis() {
+        // overwritten by the next mapping access before it is read
+        assert_eq!(
+            idiom_keeps_scratch(
+                r#"
+                mstore(0, caller())
+                mstore(0x20, 0)
+                let balance := sload(keccak256(0, 0x40))
+                if lt(balance, 10) {
+                    let memPos := mload(64)
+                    mstore(memPos, balance)
+                    revert(memPos, 0x20)
+                }
+                mstore(0, caller())
+                mstore(0x20, 0)
+                sstore(keccak256(0, 0x40), sub(balance, 10))
+                "#
+            ),
+            Some(false)
+        );
+        // execution ends without reading it
+        assert_eq!(
+            idiom_keeps_scratch(
+                r#"
+                mstore(0x00, calldataload(4))
+                mstore(0x20, 1)
+                mstore(0x80, sload(keccak256(0x00, 0x40)))
+                return(0x80, 0x20)
+                "#
+            ),
+            Some(false)
+        );
+        // 0x20 is reused by the next hash
+        assert_eq!(
+            idiom_keeps_scratch(
+                r#"
+                mstore(0, calldataload(4))
+                mstore(0x20, 1)
+                let slot := keccak256(0, 0x40)
+                mstore(0, calldataload(36))
+                sstore(keccak256(0, 0x40), slot)
+                "#
+            ),
+            Some(true)
+        );
+        // the caller can read it
+        assert_eq!(
+            idiom_keeps_scratch(
+                r#"
+                mstore(0, calldataload(4))
+                mstore(0x20, 1)
+                sstore(keccak256(0, 0x40), 1)
+                "#
+            ),
+            Some(true)
+        );
+        // msize counts the scratch space words
+        assert_eq!(
+            idiom_keeps_scratch(
+                r#"
+                mstore(0, calldataload(4))
+                mstore(0x20, 1)
+                let value := sload(keccak256(0, 0x40))
+                mstore(0x100, 1)
+                pop(msize())
+                "#
+            ),
+            Some(true)
+        );
+        // the slot is evaluated after the key is stored and reads it
+        assert_eq!(
+            idiom_keeps_scratch(
+                r#"
+                mstore(0, calldataload(4))
+                mstore(0x20, mload(0))
+                sstore(keccak256(0, 0x40), 1)
+                "#
+            ),
+            None
+        );
+        // the hash is not the only memory access of the statement
+        assert_eq!(
+            idiom_keeps_scratch(
+                r#"
+                mstore(0, calldataload(4))
+                mstore(0x20, 1)
+                sstore(keccak256(0, 0x40), mload(0x80))
+                "#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_mapping_slot_read_write() {
+        let mut runtime =
+            TestRuntime::new("MappingSlotTest", "target/test_mapping_slot_read_write");
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+                object "MappingSlotTest" {
+                    code {
+                    }
+                    object "MappingSlotTest_deployed" {
+                        code {
+                            let key := calldataload(4)
+                            mstore(0, key)
+                            mstore(0x20, 1)
+                            sstore(keccak256(0, 0x40), 7)
+                            // 0x20 still holds the slot of the first mapping access
+                            mstore(0, add(key, 1))
+                            let next := keccak256(0, 0x40)
+                            sstore(next, 9)
+                            mstore(0, key)
+                            mstore(0x20, 1)
+                            let a := sload(keccak256(0, 0x40))
+                            mstore(0, add(key, 1))
+                            mstore(0x20, 1)
+                            let b := sload(keccak256(0, 0x40))
+                            mstore(0x80, add(mul(a, 0x100), b))
+                            return(0x80, 0x20)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(
+                &solidity_selector("test(uint256)"),
+                &encode_abi_parameters(&[("42".to_string(), ParamType::Uint(256))]),
+            )
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000709");
+    }
+
+    #[test]
+    fn test_mapping_slot_keeps_observed_scratch_space() {
+        let mut runtime = TestRuntime::new(
+            "MappingSlotTest",
+            "target/test_mapping_slot_keeps_observed_scratch_space",
+        );
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+                object "MappingSlotTest" {
+                    code {
+                    }
+                    object "MappingSlotTest_deployed" {
+                        code {
+                            mstore(0, 0x1234)
+                            mstore(0x20, 5)
+                            let value := sload(keccak256(0, 0x40))
+                            return(0, 0x40)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime.call(&solidity_selector("test()"), &[]).unwrap();
+        runtime.assert_result("00000000000000000000000000000000000000000000000000000000000012340000000000000000000000000000000000000000000000000000000000000005");
+    }
+
+    #[test]
+    fn test_mapping_slot_keeps_scratch_space_for_msize() {
+        let mut runtime = TestRuntime::new(
+            "MappingSlotTest",
+            "target/test_mapping_slot_keeps_scratch_space_for_msize",
+        );
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+                object "MappingSlotTest" {
+                    code {
+                    }
+                    object "MappingSlotTest_deployed" {
+                        code {
+                            mstore(0, 0x1234)
+                            mstore(0x20, 5)
+                            let value := sload(keccak256(0, 0x40))
+                            let size := msize()
+                            mstore(0x80, size)
+                            return(0x80, 0x20)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime.call(&solidity_selector("test()"), &[]).unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000040");
+    }
+}
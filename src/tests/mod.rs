@@ -2,20 +2,36 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod arithmetic_tests;
+mod ast_optimizer_tests;
 mod bool_tests;
 mod byte_tests;
+mod calling_convention_tests;
 mod chain_context_tests;
+mod console_log_tests;
+mod cost_report_tests;
 mod create_tests;
 mod function_optimize_tests;
+mod gas_metering_tests;
 mod hostapi_tests;
 mod int_cast_tests;
 mod int_constant_tests;
+mod limb_u256_tests;
 mod linkersymbol_tests;
+mod llvm_passes_tests;
+mod mapping_slot_tests;
 mod mod_arithmetic_tests;
 mod mstore_tests;
+mod precompile_tests;
+mod remarks_tests;
+mod safe_memory_tests;
+mod selector_dispatch_tests;
 mod shift_tests;
 mod signed_arithmetic_tests;
+mod simd_tests;
+mod size_report_tests;
 mod solidity_strings;
+mod stack_tests;
+mod static_context_tests;
 mod string_tests;
 mod syntax_tests;
 mod test;
@@ -24,3 +40,5 @@ mod transfer_tests;
 mod tstore_tload_tests;
 mod tuple_tests;
 mod var_redefine_tests;
+mod wasm_features_tests;
+mod width_inference_tests;
@@ -5,6 +5,145 @@
 use super::test_helper::solidity_selector;
 #[allow(unused)]
 use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::config::GasMetering;
+#[allow(unused)]
+use crate::yul2ir::yul_instruction::YulLowLevelValueType;
+
+#[allow(unused)]
+const MEMORY_GROWTH_CONTRACT: &str = r#"
+object "MemoryGrowthTest" {
+    code {
+    }
+    object "MemoryGrowthTest_deployed" {
+        code {
+            mstore(0x80, 0x1111)
+            // one word every 4 KiB up to 256 KiB, far past the first page
+            for { let i := 0 } lt(i, 64) { i := add(i, 1) } {
+                mstore(add(mul(i, 0x1000), 0x100), add(i, 1))
+            }
+            let sum := 0
+            for { let i := 0 } lt(i, 64) { i := add(i, 1) } {
+                sum := add(sum, mload(add(mul(i, 0x1000), 0x100)))
+            }
+            mcopy(0x40100, 0x100, 0x20)
+            datacopy(0x50000, dataoffset("Blob"), datasize("Blob"))
+            mstore(0x00, mload(0x80))
+            mstore(0x20, sum)
+            mstore(0x40, mload(0x3f100))
+            mstore(0x60, mload(0x40100))
+            mstore(0x80, mload(0x50000))
+            mstore(0xa0, mload(0x60000))
+            return(0x00, 0xc0)
+        }
+        data "Blob" hex"deadbeef"
+    }
+}
+"#;
+
+// With bytes32 returns every call of `one` allocates its return value with
+// memory_alloca_bytes32, past its static buffer it grows the wasm memory
+// after the EVM memory, which then has to move to grow
+#[allow(unused)]
+const MEMORY_RELOCATION_CONTRACT: &str = r#"
+object "MemoryRelocationTest" {
+    code {
+    }
+    object "MemoryRelocationTest_deployed" {
+        code {
+            mstore(0x80, 0x1111)
+            mstore(0xffe0, 0x2222)
+            let sum := 0
+            for { let i := 0 } lt(i, 400) { i := add(i, 1) } {
+                sum := add(sum, one())
+            }
+            mstore(0x40000, 0x3333)
+            mcopy(0x40020, 0x80, 0x20)
+            mstore(0x00, mload(0x80))
+            mstore(0x20, mload(0xffe0))
+            mstore(0x40, mload(0x40000))
+            mstore(0x60, sum)
+            mstore(0x80, mload(0x40020))
+            return(0x00, 0xa0)
+
+            function one() -> r {
+                r := 1
+            }
+        }
+    }
+}
+"#;
+
+#[allow(unused)]
+const MSIZE_CONTRACT: &str = r#"
+object "MsizeTest" {
+    code {
+    }
+    object "MsizeTest_deployed" {
+        code {
+            let empty := msize()
+            mstore8(0x40, 1)
+            let after_mstore8 := msize()
+            pop(mload(0x61))
+            let after_mload := msize()
+            // zero sized accesses don't expand the memory
+            pop(keccak256(0x200, 0))
+            calldatacopy(0x400, 0, 0)
+            let after_empty := msize()
+            calldatacopy(0x100, 0, 1)
+            let after_calldatacopy := msize()
+            // mcopy expands the memory for its source too
+            mcopy(0, 0x130, 0x10)
+            let after_mcopy := msize()
+            mstore(0x00, empty)
+            mstore(0x20, after_mstore8)
+            mstore(0x40, after_mload)
+            mstore(0x60, after_empty)
+            mstore(0x80, after_calldatacopy)
+            mstore(0xa0, after_mcopy)
+            return(0x00, 0xc0)
+        }
+    }
+}
+"#;
+
+// The free memory pointer is a word of the EVM memory too
+#[allow(unused)]
+const MEMPTR_MSIZE_CONTRACT: &str = r#"
+object "MemptrMsizeTest" {
+    code {
+    }
+    object "MemptrMsizeTest_deployed" {
+        code {
+            mstore(64, 0x80)
+            let after_mstore := msize()
+            pop(mload(64))
+            let after_mload := msize()
+            mstore(0x00, after_mstore)
+            mstore(0x20, after_mload)
+            return(0x00, 0x40)
+        }
+    }
+}
+"#;
+
+#[allow(unused)]
+const MEMORY_EXPANSION_GAS_CONTRACT: &str = r#"
+object "MemoryExpansionGasTest" {
+    code {
+    }
+    object "MemoryExpansionGasTest_deployed" {
+        code {
+            let before := gas()
+            mstore(0x3fe0, 1)
+            let after := gas()
+            mstore(0x00, sub(before, after))
+            return(0x00, 0x20)
+        }
+    }
+}
+"#;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +240,234 @@ mod tests {
             .unwrap();
         runtime.assert_result("ffab000000000000000000000000000000000000000000000000000000000000");
     }
+
+    #[test]
+    fn test_mstore_beyond_16k() {
+        // small contracts (no sub contract) keep the EVM memory after the
+        // static data with all optimizers, new pages otherwise
+        for enable_all_optimizers in [true, false] {
+            let mut runtime = TestRuntime::new(
+                "MemoryGrowthTest",
+                &format!("target/test_mstore_beyond_16k_{enable_all_optimizers}"),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_all_optimizers(enable_all_optimizers);
+            runtime.compile_test_yul(MEMORY_GROWTH_CONTRACT).unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime.call(&solidity_selector("test()"), &[]).unwrap();
+            runtime.assert_result(concat!(
+                "0000000000000000000000000000000000000000000000000000000000001111",
+                "0000000000000000000000000000000000000000000000000000000000000820",
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "00000004deadbeef000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ));
+        }
+    }
+
+    #[test]
+    fn test_memory_relocation_keeps_contents() {
+        for enable_all_optimizers in [true, false] {
+            let mut runtime = TestRuntime::new(
+                "MemoryRelocationTest",
+                &format!("target/test_memory_relocation_keeps_contents_{enable_all_optimizers}"),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_all_optimizers(enable_all_optimizers);
+            runtime.set_default_ret_type(YulLowLevelValueType::Bytes32Pointer);
+            runtime
+                .compile_test_yul(MEMORY_RELOCATION_CONTRACT)
+                .unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime.call(&solidity_selector("test()"), &[]).unwrap();
+            runtime.assert_result(concat!(
+                "0000000000000000000000000000000000000000000000000000000000001111",
+                "0000000000000000000000000000000000000000000000000000000000002222",
+                "0000000000000000000000000000000000000000000000000000000000003333",
+                "0000000000000000000000000000000000000000000000000000000000000190",
+                "0000000000000000000000000000000000000000000000000000000000001111",
+            ));
+        }
+    }
+
+    #[test]
+    fn test_mstore_stress_small_contract() {
+        // every word of the first 64 KiB, then a sparse write at 1 MiB
+        let mut runtime = TestRuntime::new(
+            "MemoryStressTest",
+            "target/test_mstore_stress_small_contract",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_all_optimizers(true);
+        runtime
+            .compile_test_yul(
+                r#"
+                object "MemoryStressTest" {
+                    code {
+                    }
+                    object "MemoryStressTest_deployed" {
+                        code {
+                            for { let p := 0 } lt(p, 0x10000) { p := add(p, 0x20) } {
+                                mstore(p, not(p))
+                            }
+                            mstore(0x100000, 0xabcd)
+                            let bad := 0
+                            for { let p := 0 } lt(p, 0x10000) { p := add(p, 0x20) } {
+                                if iszero(eq(mload(p), not(p))) {
+                                    bad := add(bad, 1)
+                                }
+                            }
+                            mstore(0x00, bad)
+                            mstore(0x20, mload(0x100000))
+                            mstore(0x40, keccak256(0x4000, 0x20))
+                            mstore(0x60, keccak256(0x80, 0x20))
+                            return(0x00, 0x80)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime.call(&solidity_selector("test()"), &[]).unwrap();
+        runtime.assert_result(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "000000000000000000000000000000000000000000000000000000000000abcd",
+            "d13614a6a90ea0cfe88130a5261ac3172cea7351e6e1d2d52e06252068fca066",
+            "d73a8b37d37505c2dd527dc2212a539ebcabebcb0784b9c34d12a8977695d77a",
+        ));
+    }
+
+    #[test]
+    fn test_mstore_above_data_offset_base() {
+        // 0x80000000 is EVM memory like any other offset, not the dataoffset of
+        // a data segment: its expansion runs out of gas or out of wasm memory
+        for enable_memory_expansion_gas in [false, true] {
+            let mut runtime = TestRuntime::new(
+                "MemoryHighOffsetTest",
+                &format!("target/test_mstore_above_data_offset_base_{enable_memory_expansion_gas}"),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_memory_expansion_gas(enable_memory_expansion_gas);
+            runtime
+                .compile_test_yul(
+                    r#"
+                    object "MemoryHighOffsetTest" {
+                        code {
+                        }
+                        object "MemoryHighOffsetTest_deployed" {
+                            code {
+                                mstore(0x80000000, 1)
+                                mstore(0x00, mload(0x80000000))
+                                return(0x00, 0x20)
+                            }
+                        }
+                    }
+                    "#,
+                )
+                .unwrap();
+            runtime.deploy(&[]).unwrap();
+            assert!(runtime.call(&solidity_selector("test()"), &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_msize_rounds_to_touched_words() {
+        // all optimizers keep the free memory pointer in a wasm global
+        for enable_all_optimizers in [false, true] {
+            let mut runtime = TestRuntime::new(
+                "MsizeTest",
+                &format!("target/test_msize_rounds_to_touched_words_{enable_all_optimizers}"),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_all_optimizers(enable_all_optimizers);
+            runtime.compile_test_yul(MSIZE_CONTRACT).unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime.call(&solidity_selector("test()"), &[]).unwrap();
+            runtime.assert_result(concat!(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "00000000000000000000000000000000000000000000000000000000000000a0",
+                "0000000000000000000000000000000000000000000000000000000000000120",
+                "0000000000000000000000000000000000000000000000000000000000000140",
+            ));
+
+            let mut runtime = TestRuntime::new(
+                "MemptrMsizeTest",
+                &format!("target/test_msize_rounds_to_touched_words_{enable_all_optimizers}"),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_all_optimizers(enable_all_optimizers);
+            runtime.compile_test_yul(MEMPTR_MSIZE_CONTRACT).unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime.call(&solidity_selector("test()"), &[]).unwrap();
+            runtime.assert_result(concat!(
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+            ));
+        }
+    }
+
+    #[test]
+    fn test_memory_expansion_gas_hook() {
+        let mut runtime = TestRuntime::new("MsizeTest", "target/test_memory_expansion_gas_hook");
+        let wasm_bytes = runtime.compile_test_yul(MSIZE_CONTRACT).unwrap();
+        let imports_use_gas = |wasm_bytes: &[u8]| {
+            wasm_bytes
+                .windows(b"useGas".len())
+                .any(|name| name == b"useGas")
+        };
+        assert!(!imports_use_gas(&wasm_bytes));
+        runtime.set_enable_memory_expansion_gas(true);
+        let wasm_bytes = runtime.compile_test_yul(MSIZE_CONTRACT).unwrap();
+        assert!(imports_use_gas(&wasm_bytes));
+    }
+
+    #[test]
+    fn test_memory_expansion_gas_charged() {
+        // 512 words: 3 * 512 + 512 * 512 / 512 = 2048, nothing without the hook.
+        // The host doesn't meter the wasm instructions, the gas() difference is
+        // only what useGas charged
+        for (enable_memory_expansion_gas, charged) in [(false, 0), (true, 2048)] {
+            let mut runtime = TestRuntime::new(
+                "MemoryExpansionGasTest",
+                &format!("target/test_memory_expansion_gas_charged_{enable_memory_expansion_gas}"),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_gas_meter(false);
+            runtime.set_enable_memory_expansion_gas(enable_memory_expansion_gas);
+            runtime
+                .compile_test_yul(MEMORY_EXPANSION_GAS_CONTRACT)
+                .unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime.call(&solidity_selector("test()"), &[]).unwrap();
+            runtime.assert_result(&format!("{charged:064x}"));
+        }
+    }
+
+    #[test]
+    fn test_memory_expansion_gas_charged_by_evm_gas_metering() {
+        // the evm gas metering charges the memory gas without
+        // --memory-expansion-gas, the straight line code is charged once on
+        // entry so the gas() difference is still only the expansion
+        for (gas_metering, charged) in [(GasMetering::Evm, 2048), (GasMetering::Wasm, 0)] {
+            let mut runtime = TestRuntime::new(
+                "MemoryExpansionGasTest",
+                &format!(
+                    "target/test_memory_expansion_gas_charged_by_evm_gas_metering_{gas_metering:?}"
+                ),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_gas_meter(false);
+            runtime.set_gas_metering(Some(gas_metering));
+            runtime
+                .compile_test_yul(MEMORY_EXPANSION_GAS_CONTRACT)
+                .unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime.call(&solidity_selector("test()"), &[]).unwrap();
+            runtime.assert_result(&format!("{charged:064x}"));
+        }
+    }
 }
@@ -0,0 +1,318 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+
+// Compiles a contract that forwards its calldata (after the selector) to the
+// precompile at `address`, and returns the precompile return data
+#[allow(unused)]
+fn call_precompile(case_name: &str, address: u8, input: &[u8]) -> TestRuntime {
+    let mut runtime = TestRuntime::new(case_name, &format!("target/test_{case_name}"));
+    runtime.clear_testdata();
+    let yul_code = format!(
+        r#"
+        object "{case_name}" {{
+            code {{
+            }}
+            object "{case_name}_deployed" {{
+                code {{
+                    let size := sub(calldatasize(), 4)
+                    calldatacopy(0x00, 4, size)
+                    let success := staticcall(gas(), {address:#04x}, 0x00, size, 0x1000, 0x40)
+                    if iszero(success) {{ revert(0x00, 0x00) }}
+                    returndatacopy(0x1000, 0x00, returndatasize())
+                    return(0x1000, returndatasize())
+                }}
+            }}
+        }}
+        "#
+    );
+    runtime.compile_test_yul(&yul_code).unwrap();
+    runtime.set_enable_gas_meter(false);
+    runtime.deploy(&[]).unwrap();
+    runtime
+        .call(&solidity_selector("test_precompile()"), input)
+        .unwrap();
+    runtime
+}
+
+// Calls the precompile at `address` with the calldata after the selector and
+// `gas_limit` gas, and returns the success flag and the gas the call used.
+// With the gas meter off, the difference of gas() is only what useGas charged
+#[allow(unused)]
+fn precompile_gas(case_name: &str, address: u8, gas_limit: &str, input: &[u8]) -> TestRuntime {
+    let mut runtime = TestRuntime::new(case_name, &format!("target/test_{case_name}"));
+    runtime.clear_testdata();
+    runtime.set_enable_gas_meter(false);
+    let yul_code = format!(
+        r#"
+        object "{case_name}" {{
+            code {{
+            }}
+            object "{case_name}_deployed" {{
+                code {{
+                    let size := sub(calldatasize(), 4)
+                    calldatacopy(0x00, 4, size)
+                    let before := gas()
+                    let success := staticcall({gas_limit}, {address:#04x}, 0x00, size, 0x1000, 0x40)
+                    let used := sub(before, gas())
+                    mstore(0x1000, success)
+                    mstore(0x1020, used)
+                    return(0x1000, 0x40)
+                }}
+            }}
+        }}
+        "#
+    );
+    runtime.compile_test_yul(&yul_code).unwrap();
+    runtime.deploy(&[]).unwrap();
+    runtime
+        .call(&solidity_selector("test_precompile()"), input)
+        .unwrap();
+    runtime
+}
+
+// EIP-152 test vector 4: blake2b("abc") with the given rounds
+#[allow(unused)]
+fn blake2f_input(rounds: &str) -> Vec<u8> {
+    hex::decode(
+        [
+            rounds,
+            "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5",
+            "d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b",
+            "6162630000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0300000000000000",
+            "0000000000000000",
+            "01",
+        ]
+        .concat(),
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precompile_ecrecover() {
+        let secret_key = libsecp256k1::SecretKey::parse(&[0x11u8; 32]).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let hash = keccak_hash::keccak(b"hello precompile").0;
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &secret_key);
+
+        let mut input = hash.to_vec();
+        input.extend_from_slice(&[0u8; 31]);
+        input.push(recovery_id.serialize() + 27);
+        input.extend_from_slice(&signature.serialize());
+
+        let expected_address = keccak_hash::keccak(&public_key.serialize()[1..]).0[12..].to_vec();
+        let runtime = call_precompile("precompile_ecrecover", 0x01, &input);
+        runtime.assert_result(&format!(
+            "000000000000000000000000{}",
+            hex::encode(expected_address)
+        ));
+    }
+
+    #[test]
+    fn test_precompile_ecrecover_invalid_v() {
+        let mut input = vec![0u8; 128];
+        input[63] = 29;
+        let runtime = call_precompile("precompile_ecrecover_invalid_v", 0x01, &input);
+        runtime.assert_result("");
+    }
+
+    #[test]
+    fn test_precompile_ecrecover_known_vector() {
+        let input = hex::decode(concat!(
+            "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+            "000000000000000000000000000000000000000000000000000000000000001b",
+            "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e",
+            "789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02",
+        ))
+        .unwrap();
+        let runtime = call_precompile("precompile_ecrecover_known_vector", 0x01, &input);
+        runtime.assert_result("000000000000000000000000ceaccac640adf55b2028469bd36ba501f28b699d");
+    }
+
+    #[test]
+    fn test_precompile_sha256() {
+        let runtime = call_precompile("precompile_sha256", 0x02, b"abc");
+        runtime.assert_result("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        // computed in wasm, the module can't reach the host precompiles
+        assert!(!runtime.wat().contains("\"callStatic\""));
+    }
+
+    #[test]
+    fn test_precompile_ripemd160() {
+        let runtime = call_precompile("precompile_ripemd160", 0x03, b"abc");
+        runtime.assert_result("0000000000000000000000008eb208f7e05d987a9b044a8e98c6b087f15a0bfc");
+    }
+
+    #[test]
+    fn test_precompile_identity() {
+        let runtime = call_precompile("precompile_identity", 0x04, b"hello identity");
+        runtime.assert_result(&hex::encode(b"hello identity"));
+    }
+
+    #[test]
+    fn test_precompile_modexp() {
+        // 3 ** (p - 2) mod p == 3 ** -1 mod p for the secp256k1 field prime p
+        let input = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "03",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2d",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        ))
+        .unwrap();
+        let runtime = call_precompile("precompile_modexp", 0x05, &input);
+        runtime.assert_result("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa9fffffd75");
+    }
+
+    #[test]
+    fn test_precompile_modexp_fermat() {
+        // EIP-198 example: 3 ** (p - 1) mod p == 1 for the secp256k1 field prime p
+        let input = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "03",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        ))
+        .unwrap();
+        let runtime = call_precompile("precompile_modexp_fermat", 0x05, &input);
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000001");
+    }
+
+    #[test]
+    fn test_precompile_modexp_zero_modulus_length() {
+        let input = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0302",
+        ))
+        .unwrap();
+        let runtime = call_precompile("precompile_modexp_zero_modulus_length", 0x05, &input);
+        runtime.assert_result("");
+    }
+
+    #[test]
+    fn test_precompile_bn256_add() {
+        // G1 + G1 == 2 * G1
+        let input = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+        ))
+        .unwrap();
+        let runtime = call_precompile("precompile_bn256_add", 0x06, &input);
+        runtime.assert_result(concat!(
+            "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3",
+            "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4",
+        ));
+    }
+
+    #[test]
+    fn test_precompile_bn256_scalar_mul() {
+        let input = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+        ))
+        .unwrap();
+        let runtime = call_precompile("precompile_bn256_scalar_mul", 0x07, &input);
+        runtime.assert_result(concat!(
+            "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3",
+            "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4",
+        ));
+    }
+
+    #[test]
+    fn test_precompile_bn256_pairing_empty_input() {
+        // the empty product of pairings is the identity, so the check succeeds
+        let runtime = call_precompile("precompile_bn256_pairing_empty_input", 0x08, &[]);
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000001");
+    }
+
+    #[test]
+    fn test_precompile_bn256_pairing() {
+        // e(G1, G2) * e(-G1, G2) == 1
+        let input = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+            "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+            "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+            "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45",
+            "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+            "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+            "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+            "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+        ))
+        .unwrap();
+        let runtime = call_precompile("precompile_bn256_pairing", 0x08, &input);
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000001");
+
+        // e(G1, G2) alone isn't the identity
+        let runtime = call_precompile("precompile_bn256_pairing_failed", 0x08, &input[..192]);
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000000");
+    }
+
+    #[test]
+    fn test_precompile_blake2f() {
+        let runtime = call_precompile("precompile_blake2f", 0x09, &blake2f_input("0000000c"));
+        runtime.assert_result(concat!(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1",
+            "7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+        ));
+        // computed in wasm, the module can't reach the host precompiles
+        assert!(!runtime.wat().contains("\"callStatic\""));
+    }
+
+    #[test]
+    fn test_precompile_gas() {
+        // 60 + 12 per word of input
+        let runtime = precompile_gas("precompile_gas_sha256", 0x02, "gas()", b"abc");
+        runtime.assert_result(&format!("{:064x}{:064x}", 1, 72));
+        // 15 + 3 per word of input
+        let runtime = precompile_gas("precompile_gas_identity", 0x04, "gas()", &[0x11; 40]);
+        runtime.assert_result(&format!("{:064x}{:064x}", 1, 21));
+        // one per round
+        let runtime = precompile_gas(
+            "precompile_gas_blake2f",
+            0x09,
+            "gas()",
+            &blake2f_input("0000000c"),
+        );
+        runtime.assert_result(&format!("{:064x}{:064x}", 1, 12));
+    }
+
+    #[test]
+    fn test_precompile_out_of_gas() {
+        // the rounds aren't run without the gas for them, the call fails and
+        // consumes its gas
+        let runtime = precompile_gas(
+            "precompile_out_of_gas_blake2f",
+            0x09,
+            "1000",
+            &blake2f_input("ffffffff"),
+        );
+        runtime.assert_result(&format!("{:064x}{:064x}", 0, 1000));
+        let runtime = precompile_gas("precompile_out_of_gas_sha256", 0x02, "71", b"abc");
+        runtime.assert_result(&format!("{:064x}{:064x}", 0, 71));
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::config::{LlvmPipeline, RemarksFormat};
+#[allow(unused)]
+use crate::yul2ir::remarks::{RemarkKind, RemarkReport};
+
+#[allow(unused)]
+const REMARKS_CONTRACT: &str = r#"
+object "RemarksTest" {
+    code {
+    }
+    object "RemarksTest_deployed" {
+        code {
+            function revert_error_zero() {
+                revert(0, 0)
+            }
+            function fun_transfer_5(to, value) {
+                sstore(to, value)
+            }
+            if slt(add(calldatasize(), not(3)), 32) {
+                revert_error_zero()
+            }
+            let selector := shr(224, calldataload(0))
+            let argument := shr(224, calldataload(4))
+            fun_transfer_5(argument, mload(64))
+            mstore(0, and(argument, sub(shl(160, 1), 1)))
+            mstore(0x20, selector)
+            return(0, 0x40)
+        }
+    }
+}
+"#;
+
+/// Compiles the contract renamed, each test writes its own remarks file
+#[allow(unused)]
+fn compile_remarks_contract(
+    contract_name: &str,
+    case_name: &str,
+    configure: impl FnOnce(&mut TestRuntime),
+) -> RemarkReport {
+    let mut runtime = TestRuntime::new(contract_name, case_name);
+    runtime.set_remarks(Some(RemarksFormat::Text));
+    configure(&mut runtime);
+    runtime
+        .compile_test_yul(&REMARKS_CONTRACT.replace("RemarksTest", contract_name))
+        .unwrap();
+    runtime.remark_report().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remarks_applied_rewrites() {
+        let report = compile_remarks_contract(
+            "RemarksAppliedTest",
+            "target/test_remarks_applied_rewrites",
+            |_| {},
+        );
+        let selector_loads = report.find("selector_load", RemarkKind::Applied);
+        assert_eq!(selector_loads.len(), 1, "{report}");
+        assert_eq!(selector_loads[0].location, "shr(224, calldataload(0))");
+        assert!(selector_loads[0]
+            .function
+            .contains("RemarksAppliedTest_deployed"));
+        assert_eq!(
+            report
+                .find("calldatasize_minus_4", RemarkKind::Applied)
+                .len(),
+            1,
+            "{report}"
+        );
+        let revert_zero = report.find("revert_zero", RemarkKind::Applied);
+        assert_eq!(revert_zero.len(), 1, "{report}");
+        assert!(revert_zero[0].function.ends_with(".revert_error_zero"));
+
+        let written = std::fs::read_to_string(
+            "./target/RemarksAppliedTest/RemarksAppliedTest_output.remarks.txt",
+        )
+        .unwrap();
+        assert!(
+            written.contains("applied selector_load at shr(224, calldataload(0))"),
+            "{written}"
+        );
+    }
+
+    #[test]
+    fn test_remarks_near_misses() {
+        let report = compile_remarks_contract(
+            "RemarksNearMissesTest",
+            "target/test_remarks_near_misses",
+            |_| {},
+        );
+        let selector_loads = report.find("selector_load", RemarkKind::Missed);
+        assert_eq!(selector_loads.len(), 1, "{report}");
+        assert_eq!(selector_loads[0].location, "shr(224, calldataload(4))");
+        assert!(selector_loads[0].message.contains("offset isn't 0"));
+        // the free memory pointer global needs --enable-all-optimizers
+        let memptr = report.find("memptr_global", RemarkKind::Missed);
+        assert_eq!(memptr.len(), 1, "{report}");
+        assert_eq!(memptr[0].location, "mload(64)");
+    }
+
+    #[test]
+    fn test_remarks_all_optimizers() {
+        let report = compile_remarks_contract(
+            "RemarksAllOptimizersTest",
+            "target/test_remarks_all_optimizers",
+            |runtime| runtime.set_enable_all_optimizers(true),
+        );
+        assert_eq!(
+            report.find("memptr_global", RemarkKind::Applied).len(),
+            1,
+            "{report}"
+        );
+        assert!(report.find("memptr_global", RemarkKind::Missed).is_empty());
+        let address_masks = report.find("address_mask", RemarkKind::Applied);
+        assert_eq!(address_masks.len(), 1, "{report}");
+        assert!(address_masks[0].message.contains("argument"));
+        let erc20 = report.find("erc20_intrinsic", RemarkKind::Missed);
+        assert_eq!(erc20.len(), 1, "{report}");
+        assert!(
+            erc20[0]
+                .message
+                .contains("fun_transfer_5 is named like an ERC20 function"),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn test_remarks_llvm_inlining() {
+        let report = compile_remarks_contract(
+            "RemarksLlvmInliningTest",
+            "target/test_remarks_llvm_inlining",
+            |runtime| {
+                runtime.set_llvm_pipeline(LlvmPipeline::Custom("default<O2>".to_string()));
+                runtime.set_remarks(Some(RemarksFormat::Json));
+            },
+        );
+        let inlined = report.find("inline", RemarkKind::Applied);
+        assert!(
+            inlined.iter().any(
+                |remark| remark.message.contains(".fun_transfer_5' inlined into '")
+                    && remark.function.contains("RemarksLlvmInliningTest_deployed")
+            ),
+            "{report}"
+        );
+
+        let written = std::fs::read_to_string(
+            "./target/RemarksLlvmInliningTest/RemarksLlvmInliningTest_output.remarks.json",
+        )
+        .unwrap();
+        assert!(written.starts_with("[\n"), "{written}");
+        assert!(
+            written.contains("\"pass\": \"inline\", \"kind\": \"applied\""),
+            "{written}"
+        );
+    }
+
+    #[test]
+    fn test_remarks_llvm_missed_per_function() {
+        let report = compile_remarks_contract(
+            "RemarksLlvmMissedTest",
+            "target/test_remarks_llvm_missed_per_function",
+            |runtime| runtime.set_llvm_pipeline(LlvmPipeline::Custom("default<O2>".to_string())),
+        );
+        let missed = report.find("llvm", RemarkKind::Missed);
+        assert!(!missed.is_empty(), "{report}");
+        for remark in missed {
+            assert!(!remark.function.is_empty(), "{remark:?}");
+        }
+        assert!(!report.to_string().contains("(module):"), "{report}");
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::encode_abi_parameters;
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use ethabi::ParamType;
+
+#[allow(unused)]
+const SAFE_MEMORY_CONTRACT: &str = r#"
+object "SafeMemoryTest" {
+    code {
+    }
+    object "SafeMemoryTest_deployed" {
+        code {
+            switch calldataload(4)
+            case 0 {
+                mstore(0x80, 0x1234)
+                mstore8(0x9f, 0x56)
+                mcopy(0xa0, 0x80, 0x20)
+                // zero sized accesses don't expand the memory at any offset
+                calldatacopy(shl(128, 1), 0, 0)
+                mstore(0xc0, keccak256(shl(128, 1), 0))
+                return(0xa0, 0x40)
+            }
+            case 1 {
+                // would alias 0x80 with 32 bits offsets
+                mstore(add(shl(64, 1), 0x80), 1)
+                return(0x80, 0x20)
+            }
+            case 2 {
+                mstore(0xffe0, 7)
+                return(0xffe0, 0x20)
+            }
+            case 3 {
+                mstore(0xffe1, 7)
+                return(0xffe0, 0x20)
+            }
+            case 4 {
+                // offset + size overflows 64 bits
+                mstore(sub(shl(64, 1), 0x20), 1)
+                return(0, 0x20)
+            }
+            default {
+                return(0, shl(40, 1))
+            }
+        }
+    }
+}
+"#;
+
+// Panic(0x41)
+#[allow(unused)]
+const MEMORY_LIMIT_PANIC: &str = concat!(
+    "4e487b71",
+    "0000000000000000000000000000000000000000000000000000000000000041"
+);
+
+#[allow(unused)]
+fn call_safe_memory_contract(case_name: &str, mode: u32) -> TestRuntime {
+    let mut runtime = TestRuntime::new("SafeMemoryTest", case_name);
+    runtime.clear_testdata();
+    runtime.set_enable_safe_memory(true);
+    runtime.set_max_memory_size(0x10000);
+    runtime.compile_test_yul(SAFE_MEMORY_CONTRACT).unwrap();
+    runtime.deploy(&[]).unwrap();
+    runtime
+        .call(
+            &solidity_selector("test(uint256)"),
+            &encode_abi_parameters(&[(mode.to_string(), ParamType::Uint(256))]),
+        )
+        .unwrap();
+    runtime
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_memory_in_range_accesses() {
+        let runtime = call_safe_memory_contract("target/test_safe_memory_in_range_accesses", 0);
+        runtime.assert_result(concat!(
+            "0000000000000000000000000000000000000000000000000000000000001256",
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        ));
+    }
+
+    #[test]
+    fn test_safe_memory_reverts_on_wrapping_offset() {
+        let runtime =
+            call_safe_memory_contract("target/test_safe_memory_reverts_on_wrapping_offset", 1);
+        runtime.assert_revert(MEMORY_LIMIT_PANIC);
+    }
+
+    #[test]
+    fn test_safe_memory_max_memory_size() {
+        let runtime = call_safe_memory_contract("target/test_safe_memory_max_memory_size", 2);
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000007");
+        let runtime =
+            call_safe_memory_contract("target/test_safe_memory_max_memory_size_exceeded", 3);
+        runtime.assert_revert(MEMORY_LIMIT_PANIC);
+    }
+
+    #[test]
+    fn test_safe_memory_reverts_on_overflowing_range() {
+        let runtime =
+            call_safe_memory_contract("target/test_safe_memory_reverts_on_overflowing_range", 4);
+        runtime.assert_revert(MEMORY_LIMIT_PANIC);
+        let runtime =
+            call_safe_memory_contract("target/test_safe_memory_reverts_on_huge_return", 5);
+        runtime.assert_revert(MEMORY_LIMIT_PANIC);
+    }
+}
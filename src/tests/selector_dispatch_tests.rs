@@ -0,0 +1,221 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::config::{RemarksFormat, SelectorDispatch};
+#[allow(unused)]
+use crate::yul2ir::remarks::RemarkKind;
+#[allow(unused)]
+use crate::yul2ir::selector_dispatch::{find_perfect_hash, perfect_hash_slot};
+
+#[allow(unused)]
+const DISPATCH_FUNCTIONS: usize = 12;
+
+/// Contract dispatching fn0() .. fn{count - 1}() like Solidity, each returns
+/// its index
+#[allow(unused)]
+fn dispatch_contract(contract_name: &str, count: usize) -> String {
+    let cases: Vec<String> = (0..count)
+        .map(|index| {
+            let selector = hex::encode(solidity_selector(&format!("fn{index}()")));
+            format!("case 0x{selector} {{ mstore(0, {index}) return(0, 32) }}")
+        })
+        .collect();
+    format!(
+        r#"
+object "{contract_name}" {{
+    code {{
+    }}
+    object "{contract_name}_deployed" {{
+        code {{
+            if iszero(lt(calldatasize(), 4)) {{
+                switch shr(224, calldataload(0))
+                {}
+                default {{ }}
+            }}
+            mstore(0, 0xdead)
+            revert(0, 32)
+        }}
+    }}
+}}
+"#,
+        cases.join("\n                ")
+    )
+}
+
+/// Calls every function and an unknown selector, which reverts with 0xdead
+#[allow(unused)]
+fn check_dispatch(contract_name: &str, case_name: &str, selector_dispatch: SelectorDispatch) {
+    let mut runtime = TestRuntime::new(contract_name, case_name);
+    runtime.clear_testdata();
+    runtime.set_selector_dispatch(selector_dispatch);
+    runtime
+        .compile_test_yul(&dispatch_contract(contract_name, DISPATCH_FUNCTIONS))
+        .unwrap();
+    runtime.deploy(&[]).unwrap();
+    for index in 0..DISPATCH_FUNCTIONS {
+        runtime
+            .call(&solidity_selector(&format!("fn{index}()")), &[])
+            .unwrap();
+        runtime.assert_result(&format!("{index:064x}"));
+    }
+    runtime.call(&solidity_selector("unknown()"), &[]).unwrap();
+    runtime.assert_revert(&format!("{:064x}", 0xdead));
+}
+
+/// The dispatch contract switching over a variable holding the selector,
+/// assigned again after its declaration with `reassigned`
+#[allow(unused)]
+fn selector_variable_contract(contract_name: &str, reassigned: bool) -> String {
+    let reassignment = if reassigned {
+        "selector := and(selector, 0xffffffff)\n                "
+    } else {
+        ""
+    };
+    dispatch_contract(contract_name, DISPATCH_FUNCTIONS).replace(
+        "switch shr(224, calldataload(0))",
+        &format!(
+            "let selector := shr(224, calldataload(0))\n                {reassignment}switch selector"
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_perfect_hash() {
+        let selectors: Vec<u32> = (0..64)
+            .map(|index| {
+                let selector = solidity_selector(&format!("fn{index:02}(uint256)"));
+                u32::from_be_bytes(selector.try_into().unwrap())
+            })
+            .collect();
+        let (multiplier, bits) = find_perfect_hash(&selectors).unwrap();
+        assert!((6..=10).contains(&bits), "{bits}");
+        let mut slots: Vec<u32> = selectors
+            .iter()
+            .map(|selector| perfect_hash_slot(*selector, multiplier, bits))
+            .collect();
+        slots.sort();
+        slots.dedup();
+        assert_eq!(slots.len(), selectors.len());
+        assert!(slots.iter().all(|slot| *slot < 1 << bits));
+        // the search is deterministic
+        assert_eq!(find_perfect_hash(&selectors), Some((multiplier, bits)));
+    }
+
+    #[test]
+    fn test_selector_dispatch_switch() {
+        check_dispatch(
+            "SelectorDispatchSwitchTest",
+            "target/test_selector_dispatch_switch",
+            SelectorDispatch::Switch,
+        );
+    }
+
+    #[test]
+    fn test_selector_dispatch_binary_search() {
+        check_dispatch(
+            "SelectorDispatchBinarySearchTest",
+            "target/test_selector_dispatch_binary_search",
+            SelectorDispatch::BinarySearch,
+        );
+    }
+
+    #[test]
+    fn test_selector_dispatch_perfect_hash() {
+        check_dispatch(
+            "SelectorDispatchPerfectHashTest",
+            "target/test_selector_dispatch_perfect_hash",
+            SelectorDispatch::PerfectHash,
+        );
+    }
+
+    #[test]
+    fn test_selector_dispatch_remarks() {
+        let mut runtime = TestRuntime::new(
+            "SelectorDispatchRemarksTest",
+            "target/test_selector_dispatch_remarks",
+        );
+        runtime.set_selector_dispatch(SelectorDispatch::PerfectHash);
+        runtime.set_remarks(Some(RemarksFormat::Text));
+        runtime
+            .compile_test_yul(&dispatch_contract(
+                "SelectorDispatchRemarksTest",
+                DISPATCH_FUNCTIONS,
+            ))
+            .unwrap();
+        let report = runtime.remark_report();
+        let dispatches = report.find("selector_dispatch", RemarkKind::Applied);
+        assert_eq!(dispatches.len(), 1, "{report}");
+        assert_eq!(dispatches[0].location, "switch shr(224, calldataload(0))");
+        assert!(
+            dispatches[0]
+                .message
+                .starts_with("12 selectors dispatched by a perfect hash"),
+            "{report}"
+        );
+
+        // a few cases keep the LLVM switch
+        runtime
+            .compile_test_yul(&dispatch_contract("SelectorDispatchRemarksTest", 4))
+            .unwrap();
+        let report = runtime.remark_report();
+        assert!(report
+            .find("selector_dispatch", RemarkKind::Applied)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_selector_dispatch_variable() {
+        for selector_dispatch in [
+            SelectorDispatch::BinarySearch,
+            SelectorDispatch::PerfectHash,
+        ] {
+            let mut runtime = TestRuntime::new(
+                "SelectorDispatchVariableTest",
+                "target/test_selector_dispatch_variable",
+            );
+            runtime.clear_testdata();
+            runtime.set_selector_dispatch(selector_dispatch);
+            runtime.set_remarks(Some(RemarksFormat::Text));
+            runtime
+                .compile_test_yul(&selector_variable_contract(
+                    "SelectorDispatchVariableTest",
+                    false,
+                ))
+                .unwrap();
+            let report = runtime.remark_report();
+            let dispatches = report.find("selector_dispatch", RemarkKind::Applied);
+            assert_eq!(dispatches.len(), 1, "{report}");
+            assert_eq!(dispatches[0].location, "switch selector");
+            runtime.deploy(&[]).unwrap();
+            for index in 0..DISPATCH_FUNCTIONS {
+                runtime
+                    .call(&solidity_selector(&format!("fn{index}()")), &[])
+                    .unwrap();
+                runtime.assert_result(&format!("{index:064x}"));
+            }
+            runtime.call(&solidity_selector("unknown()"), &[]).unwrap();
+            runtime.assert_revert(&format!("{:064x}", 0xdead));
+
+            // a variable assigned again may no longer hold the selector
+            runtime
+                .compile_test_yul(&selector_variable_contract(
+                    "SelectorDispatchVariableTest",
+                    true,
+                ))
+                .unwrap();
+            let report = runtime.remark_report();
+            assert!(report
+                .find("selector_dispatch", RemarkKind::Applied)
+                .is_empty());
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+
+#[allow(unused)]
+const BYTES32_OPERATIONS_CONTRACT: &str = r#"
+object "Simd128Test" {
+    code {
+    }
+    object "Simd128Test_deployed" {
+        code {
+            let a := calldataload(4)
+            let b := calldataload(36)
+            mstore(0x80, and(a, b))
+            mstore(0xa0, or(a, b))
+            mstore(0xc0, xor(a, b))
+            mstore(0xe0, not(a))
+            mstore(0x100, eq(a, b))
+            mstore(0x120, eq(a, a))
+            mstore(0x140, iszero(a))
+            mstore(0x160, iszero(xor(a, a)))
+            // big endian conversions of u256 values
+            mstore(0x180, add(a, 1))
+            mstore(0x1a0, shr(8, b))
+            return(0x80, 0x140)
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+    use crate::yul2ir::config::{ChainProfile, Yul2IROptions};
+    use crate::yul2ir::context::Yul2IRContext;
+    use crate::yul2ir::yul;
+    use ethabi::ParamType;
+    use ethereum_types::U256;
+    use inkwell::context::Context;
+
+    #[test]
+    fn test_simd128_bytes32_operations() {
+        for enable_simd128 in [false, true] {
+            let mut runtime =
+                TestRuntime::new("Simd128Test", "target/test_simd128_bytes32_operations");
+            runtime.clear_testdata();
+            runtime.set_enable_simd128(enable_simd128);
+            runtime
+                .compile_test_yul(BYTES32_OPERATIONS_CONTRACT)
+                .unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime
+                .call(
+                    &solidity_selector("test(uint256,uint256)"),
+                    &encode_abi_parameters(&[
+                        // 0x0102...1f20
+                        (
+                            "455867356320691211509944977504407603390036387149619137164185182714736811808"
+                                .to_string(),
+                            ParamType::Uint(256),
+                        ),
+                        // 0xff00 repeated
+                        (
+                            "115341536360906404779899502576747487978354537254490211650198994186870666100480"
+                                .to_string(),
+                            ParamType::Uint(256),
+                        ),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_result(concat!(
+                "010003000500070009000b000d000f00110013001500170019001b001d001f00",
+                "ff02ff04ff06ff08ff0aff0cff0eff10ff12ff14ff16ff18ff1aff1cff1eff20",
+                "fe02fc04fa06f808f60af40cf20ef010ee12ec14ea16e818e61ae41ce21ee020",
+                "fefdfcfbfaf9f8f7f6f5f4f3f2f1f0efeeedecebeae9e8e7e6e5e4e3e2e1e0df",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f21",
+                "00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff",
+            ));
+        }
+    }
+
+    #[test]
+    fn test_simd128_rejected_by_chain_profile() {
+        let mut runtime = TestRuntime::new(
+            "Simd128Test",
+            "target/test_simd128_rejected_by_chain_profile",
+        );
+        runtime.set_chain_profile(ChainProfile::Mvp);
+        runtime.set_enable_simd128(true);
+        let err = runtime
+            .compile_test_yul(BYTES32_OPERATIONS_CONTRACT)
+            .unwrap_err();
+        assert_eq!(err, "simd128 is not supported by the mvp chain profile");
+    }
+
+    #[test]
+    fn test_simd128_lowering() {
+        let object = yul::ObjectParser::new()
+            .parse(BYTES32_OPERATIONS_CONTRACT)
+            .unwrap();
+        let llvm_context = Context::create();
+        let mut opts = Yul2IROptions::test("Simd128Test");
+        opts.enable_simd128 = true;
+        let mut context = Yul2IRContext::new_with_object(&llvm_context, &opts, object);
+        context.emit("output").unwrap();
+
+        let ir = context.llvm_module.borrow().print_to_string().to_string();
+        assert!(ir.contains("@llvm.wasm.anytrue.v16i8"));
+        for wrapper in [
+            "wrapper_bytes32_and",
+            "wrapper_bytes32_or",
+            "wrapper_bytes32_xor",
+            "wrapper_bytes32_not",
+            "wrapper_bytes32_eq",
+            "wrapper_bytes32_iszero",
+        ] {
+            assert!(
+                !ir.contains(&format!("call void @{wrapper}(")),
+                "{wrapper} called"
+            );
+            assert!(
+                !ir.contains(&format!("call i32 @{wrapper}(")),
+                "{wrapper} called"
+            );
+        }
+    }
+
+    const WORKLOAD_ITERATIONS: u64 = 1000;
+
+    // Runs the workload over calldata (a, b), checks its result and returns
+    // the text format of the compiled contract
+    fn run_simd128_workload(
+        name: &str,
+        body: &str,
+        a: U256,
+        b: U256,
+        enable_simd128: bool,
+        expected: &str,
+    ) -> String {
+        let mut runtime = TestRuntime::new(
+            "Simd128Workload",
+            &format!("target/test_simd128_workloads_{name}_{enable_simd128}"),
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_simd128(enable_simd128);
+        runtime
+            .compile_test_yul(&format!(
+                r#"
+                object "Simd128Workload" {{
+                    code {{
+                    }}
+                    object "Simd128Workload_deployed" {{
+                        code {{
+                            let a := calldataload(4)
+                            let b := calldataload(36)
+                            let acc := 0
+                            for {{ let i := 0 }} lt(i, {WORKLOAD_ITERATIONS}) {{ i := add(i, 1) }} {{
+                                {body}
+                            }}
+                            mstore(0x80, a)
+                            mstore(0xa0, b)
+                            mstore(0xc0, acc)
+                            return(0x80, 0x60)
+                        }}
+                    }}
+                }}
+                "#
+            ))
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        let calldata = encode_abi_parameters(&[
+            (a.to_string(), ParamType::Uint(256)),
+            (b.to_string(), ParamType::Uint(256)),
+        ]);
+        runtime
+            .call(&solidity_selector("test(uint256,uint256)"), &calldata)
+            .unwrap();
+        runtime.assert_result(expected);
+        runtime.wat()
+    }
+
+    // The bitwise and comparison operations of bool_tests and the byte
+    // extraction and big endian stores of byte_tests, in loops
+    #[test]
+    fn test_simd128_workloads() {
+        let a = U256::from_big_endian(&(1..=32).collect::<Vec<u8>>());
+        let b = U256::from_big_endian(&[0xff, 0x00].repeat(16));
+
+        let (mut bool_a, mut bool_b, mut bool_acc) = (a, b, U256::zero());
+        for _ in 0..WORKLOAD_ITERATIONS {
+            bool_a = bool_a ^ !bool_b;
+            bool_b = (bool_a | bool_b) & !bool_a;
+            bool_acc = bool_acc
+                + U256::from((bool_a == bool_b) as u8)
+                + U256::from(bool_b.is_zero() as u8);
+        }
+        let mut byte_acc = U256::zero();
+        for i in 0..WORKLOAD_ITERATIONS {
+            let stored = a.overflowing_add(U256::from(i)).0;
+            // byte 0 of U256::byte is the least significant one
+            let byte = U256::from(a.byte(31 - (i as usize & 31)));
+            byte_acc = (byte_acc ^ stored).overflowing_add(byte).0;
+        }
+
+        let workloads = [
+            (
+                "bool",
+                r#"
+                a := xor(a, not(b))
+                b := and(or(a, b), not(a))
+                acc := add(acc, add(eq(a, b), iszero(b)))
+                "#,
+                format!("{bool_a:064x}{bool_b:064x}{bool_acc:064x}"),
+            ),
+            (
+                "byte",
+                r#"
+                mstore(0x100, add(a, i))
+                acc := add(xor(acc, mload(0x100)), byte(and(i, 31), a))
+                "#,
+                format!("{a:064x}{b:064x}{byte_acc:064x}"),
+            ),
+        ];
+        for (name, body, expected) in workloads {
+            let scalar = run_simd128_workload(name, body, a, b, false, &expected);
+            assert!(!scalar.contains("v128"), "{name}: v128 without simd128");
+            let simd = run_simd128_workload(name, body, a, b, true, &expected);
+            assert!(simd.contains("v128.load"), "{name}: no v128.load");
+            assert!(simd.contains("v128.store"), "{name}: no v128.store");
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+
+#[allow(unused)]
+const SIZE_REPORT_CONTRACT: &str = r#"
+object "SizeReportTest" {
+    code {
+    }
+    object "SizeReportTest_deployed" {
+        code {
+            function copy_blob() -> size {
+                size := datasize("Blob")
+                datacopy(0, dataoffset("Blob"), size)
+            }
+            function copy_child() -> size {
+                size := datasize("Child")
+                datacopy(0x40, dataoffset("Child"), size)
+            }
+            let size := add(copy_blob(), copy_child())
+            mstore(0, size)
+            return(0, 0x20)
+        }
+        data "Blob" hex"00112233445566778899"
+        object "Child" {
+            code {
+            }
+            object "Child_deployed" {
+                code {
+                    mstore(0, 1)
+                    return(0, 0x20)
+                }
+            }
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_report() {
+        let mut runtime = TestRuntime::new("SizeReportTest", "target/test_size_report");
+        runtime.set_enable_size_report(true);
+        let wasm = runtime.compile_test_yul(SIZE_REPORT_CONTRACT).unwrap();
+        let report = runtime.size_report();
+
+        let steps: Vec<&str> = report.steps.iter().map(|(step, _)| step.as_str()).collect();
+        assert_eq!(steps, vec!["wasm-ld", "wasm-opt", "wizer"]);
+        let linked = report.steps[0].1.as_ref().unwrap();
+        assert!(linked.section("code") > 0);
+        assert_eq!(report.output_sizes().unwrap().total, wasm.len());
+
+        // the exported entry points are named with or without a name section
+        let call = report
+            .functions
+            .iter()
+            .find(|function| function.name == "call")
+            .unwrap_or_else(|| panic!("no call function in {report}"));
+        assert!(call.is_yul);
+        assert!(call.size > 0);
+        assert!(report
+            .functions
+            .windows(2)
+            .all(|pair| pair[0].size >= pair[1].size));
+
+        // 4 bytes length prefix and the bytes
+        assert_eq!(report.data_objects["SizeReportTest_deployed.Blob"], 14);
+        assert!(report.data_objects["SizeReportTest_deployed.Child"] > 4);
+
+        let written =
+            std::fs::read_to_string("./target/SizeReportTest/SizeReportTest_output.size.txt")
+                .unwrap();
+        assert!(
+            written.contains("SizeReportTest_deployed.Blob: 14 bytes"),
+            "{written}"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use ethabi::ParamType;
+
+#[allow(unused)]
+const COUNTDOWN_YUL: &str = r#"
+    object "StackTest" {
+        code {
+        }
+        object "StackTest_deployed" {
+            code {
+                function countdown(n) -> r {
+                    r := 0
+                    if n {
+                        r := add(countdown(sub(n, 1)), 1)
+                    }
+                }
+
+                mstore(0x00, countdown(calldataload(4)))
+                return(0x00, 0x20)
+            }
+        }
+    }
+    "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+
+    #[test]
+    fn test_stack_usage_report() {
+        let mut runtime =
+            TestRuntime::new("StackUsageReportTest", "target/test_stack_usage_report");
+        runtime.clear_testdata();
+        runtime
+            .compile_test_yul(
+                r#"
+                object "StackUsageReportTest" {
+                    code {
+                    }
+                    object "StackUsageReportTest_deployed" {
+                        code {
+                            function inner(a) -> r {
+                                mstore(0x00, a)
+                                r := keccak256(0x00, 0x20)
+                            }
+                            function middle(a) -> r {
+                                r := add(inner(a), 1)
+                            }
+                            function outer(a) -> r {
+                                r := mul(middle(a), 2)
+                            }
+                            function ping(n) -> r {
+                                if n { r := pong(sub(n, 1)) }
+                            }
+                            function pong(n) -> r {
+                                if n { r := ping(sub(n, 1)) }
+                            }
+
+                            mstore(0x20, add(outer(calldataload(4)), ping(3)))
+                            return(0x20, 0x20)
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+
+        let prefix = "StackUsageReportTest_deployed.StackUsageReportTest_deployed";
+        let report = runtime.stack_usage_report();
+        assert!(report
+            .recursive_functions
+            .contains(&format!("{prefix}.ping")));
+        assert!(report
+            .recursive_functions
+            .contains(&format!("{prefix}.pong")));
+        assert!(!report
+            .recursive_functions
+            .contains(&format!("{prefix}.outer")));
+        for name in ["outer", "middle", "inner"] {
+            assert!(report.frame_sizes.contains_key(&format!("{prefix}.{name}")));
+        }
+        let path_depth: u64 = report
+            .worst_case_path
+            .iter()
+            .map(|name| report.frame_sizes[name])
+            .sum();
+        assert_eq!(path_depth, report.worst_case_depth);
+        assert!(report
+            .frame_sizes
+            .values()
+            .all(|size| *size <= report.worst_case_depth));
+    }
+
+    #[test]
+    fn test_stack_guard_shallow_recursion() {
+        let mut runtime =
+            TestRuntime::new("StackTest", "target/test_stack_guard_shallow_recursion");
+        runtime.clear_testdata();
+        runtime.set_enable_stack_guard(true);
+        runtime.compile_test_yul(COUNTDOWN_YUL).unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(
+                &solidity_selector("countdown(uint256)"),
+                &encode_abi_parameters(&[("10".to_string(), ParamType::Uint(256))]),
+            )
+            .unwrap();
+        runtime.assert_result("000000000000000000000000000000000000000000000000000000000000000a");
+    }
+
+    #[test]
+    fn test_stack_guard_reverts_on_overflow() {
+        let mut runtime =
+            TestRuntime::new("StackTest", "target/test_stack_guard_reverts_on_overflow");
+        runtime.clear_testdata();
+        runtime.set_enable_gas_meter(false);
+        runtime.set_enable_stack_guard(true);
+        runtime.compile_test_yul(COUNTDOWN_YUL).unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(
+                &solidity_selector("countdown(uint256)"),
+                &encode_abi_parameters(&[("100000".to_string(), ParamType::Uint(256))]),
+            )
+            .unwrap();
+        // Error("stack overflow")
+        runtime.assert_revert("08c379a00000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000000e737461636b206f766572666c6f77000000000000000000000000000000000000");
+    }
+
+    #[test]
+    fn test_stack_size_option() {
+        let mut runtime = TestRuntime::new("StackTest", "target/test_stack_size_option");
+        runtime.clear_testdata();
+        runtime.set_stack_size(Some(0x20000));
+        runtime.set_enable_stack_guard(true);
+        runtime.compile_test_yul(COUNTDOWN_YUL).unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(
+                &solidity_selector("countdown(uint256)"),
+                &encode_abi_parameters(&[("100".to_string(), ParamType::Uint(256))]),
+            )
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000064");
+    }
+}
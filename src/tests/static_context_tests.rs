@@ -0,0 +1,424 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use parity_wasm::elements::{
+    BlockType, ExportEntry, External, Func, FuncBody, ImportCountType, Instruction, Instructions,
+    Internal, Module,
+};
+
+#[allow(unused)]
+const STORE_AND_LOG_YUL: &str = r#"
+    object "StaticContextTest" {
+        code {
+        }
+        object "StaticContextTest_deployed" {
+            code {
+                sstore(0x01, 0x2a)
+                tstore(0x02, 0x2b)
+                mstore(0x00, add(sload(0x01), tload(0x02)))
+                log1(0x00, 0x20, 0x1234)
+                let success := call(gas(), 0x1234, 0, 0x00, 0x00, 0x00, 0x00)
+                return(0x00, 0x20)
+            }
+        }
+    }
+    "#;
+
+// Deployed code that runs `operation` then returns 0x55
+#[allow(unused)]
+fn state_modifying_yul(contract_name: &str, operation: &str) -> String {
+    format!(
+        r#"
+    object "{contract_name}" {{
+        code {{
+        }}
+        object "{contract_name}_deployed" {{
+            code {{
+                {operation}
+                mstore(0x00, 0x55)
+                return(0x00, 0x20)
+            }}
+        }}
+    }}
+    "#
+    )
+}
+
+#[allow(unused)]
+const IDENTITY_AT_DEPTH_YUL: &str = r#"
+    object "CallDepthTest" {
+        code {
+        }
+        object "CallDepthTest_deployed" {
+            code {
+                mstore(0x00, 0x2a)
+                mstore(0x80, staticcall(gas(), 0x04, 0x00, 0x20, 0x40, 0x20))
+                mstore(0xa0, returndatasize())
+                return(0x80, 0x40)
+            }
+        }
+    }
+    "#;
+
+#[allow(unused)]
+const CALLS_AT_DEPTH_YUL: &str = r#"
+    object "CallDepthTest" {
+        code {
+        }
+        object "CallDepthTest_deployed" {
+            code {
+                mstore(0x00, call(gas(), 0x1234, 0, 0x00, 0x00, 0x00, 0x00))
+                mstore(0x20, callcode(gas(), 0x1234, 0, 0x00, 0x00, 0x00, 0x00))
+                mstore(0x40, delegatecall(gas(), 0x1234, 0x00, 0x00, 0x00, 0x00))
+                mstore(0x60, staticcall(gas(), 0x1234, 0x00, 0x00, 0x00, 0x00))
+                mstore(0x80, create(0, 0x00, 0x00))
+                mstore(0xa0, create2(0, 0x00, 0x00, 0x01))
+                mstore(0xc0, returndatasize())
+                return(0x00, 0xe0)
+            }
+        }
+    }
+    "#;
+
+#[allow(unused)]
+fn exported_function(module: &Module, name: &str) -> u32 {
+    module
+        .export_section()
+        .unwrap()
+        .entries()
+        .iter()
+        .find(|entry| entry.field() == name)
+        .map(|entry| match entry.internal() {
+            Internal::Function(index) => *index,
+            internal => panic!("{name} exports {internal:?}"),
+        })
+        .unwrap_or_else(|| panic!("no {name} export"))
+}
+
+/// Rewrites the `call` export, which chain_mockcli runs, to a function that
+/// calls `set_call_depth(call_depth)` if given, then the `entry` export.
+/// Stands in for a host calling `call_static` and `set_call_depth`
+#[allow(unused)]
+fn with_call_entry(wasm_bytes: &[u8], entry: &str, call_depth: Option<i32>) -> Vec<u8> {
+    let mut module: Module = parity_wasm::deserialize_buffer(wasm_bytes).unwrap();
+    let entry_index = exported_function(&module, entry);
+    let mut body = vec![];
+    if let Some(call_depth) = call_depth {
+        body.push(Instruction::I32Const(call_depth));
+        body.push(Instruction::Call(exported_function(
+            &module,
+            "set_call_depth",
+        )));
+    }
+    body.push(Instruction::Call(entry_index));
+    body.push(Instruction::End);
+
+    let imported_functions = module.import_count(ImportCountType::Function) as u32;
+    let functions = module.function_section_mut().unwrap().entries_mut();
+    let entry_type = functions[(entry_index - imported_functions) as usize].type_ref();
+    functions.push(Func::new(entry_type));
+    let new_index = imported_functions + functions.len() as u32 - 1;
+    module
+        .code_section_mut()
+        .unwrap()
+        .bodies_mut()
+        .push(FuncBody::new(vec![], Instructions::new(body)));
+    let exports = module.export_section_mut().unwrap().entries_mut();
+    exports.retain(|entry| entry.field() != "call");
+    exports.push(ExportEntry::new(
+        "call".to_string(),
+        Internal::Function(new_index),
+    ));
+    parity_wasm::serialize(module).unwrap()
+}
+
+/// Rewrites every call to the host `finish` to first trap unless the
+/// `is_static_context` export returns `expected`. Stands in for a host asking
+/// the caller before it runs a delegatecall or callcode
+#[allow(unused)]
+fn with_is_static_context_check(wasm_bytes: &[u8], expected: i32) -> Vec<u8> {
+    let mut module: Module = parity_wasm::deserialize_buffer(wasm_bytes).unwrap();
+    let is_static_index = exported_function(&module, "is_static_context");
+    let (finish_index, finish_type) = module
+        .import_section()
+        .unwrap()
+        .entries()
+        .iter()
+        .filter_map(|entry| match entry.external() {
+            External::Function(type_ref) => Some((entry.field(), *type_ref)),
+            _ => None,
+        })
+        .enumerate()
+        .find(|(_, (field, _))| *field == "finish")
+        .map(|(index, (_, type_ref))| (index as u32, type_ref))
+        .expect("no finish import");
+
+    let imported_functions = module.import_count(ImportCountType::Function) as u32;
+    let functions = module.function_section_mut().unwrap().entries_mut();
+    functions.push(Func::new(finish_type));
+    let checked_finish_index = imported_functions + functions.len() as u32 - 1;
+    for body in module.code_section_mut().unwrap().bodies_mut() {
+        for instruction in body.code_mut().elements_mut() {
+            if *instruction == Instruction::Call(finish_index) {
+                *instruction = Instruction::Call(checked_finish_index);
+            }
+        }
+    }
+    module
+        .code_section_mut()
+        .unwrap()
+        .bodies_mut()
+        .push(FuncBody::new(
+            vec![],
+            Instructions::new(vec![
+                Instruction::Call(is_static_index),
+                Instruction::I32Const(expected),
+                Instruction::I32Ne,
+                Instruction::If(BlockType::NoResult),
+                Instruction::Unreachable,
+                Instruction::End,
+                Instruction::GetLocal(0),
+                Instruction::GetLocal(1),
+                Instruction::Call(finish_index),
+                Instruction::End,
+            ]),
+        ));
+    parity_wasm::serialize(module).unwrap()
+}
+
+/// Compiles with the static context check, deploys, then calls the contract
+/// through `entry` at `call_depth`
+#[allow(unused)]
+fn call_through_entry(
+    contract_name: &str,
+    case_name: &str,
+    yul_code: &str,
+    entry: &str,
+    call_depth: Option<i32>,
+) -> (TestRuntime, Result<i32, String>) {
+    let mut runtime = TestRuntime::new(contract_name, case_name);
+    runtime.clear_testdata();
+    runtime.set_enable_static_context_check(true);
+    let wasm_bytes = runtime.compile_test_yul(yul_code).unwrap();
+    runtime.deploy(&[]).unwrap();
+    runtime.set_wasm_bytecode(with_call_entry(&wasm_bytes, entry, call_depth));
+    let result = runtime.call(&solidity_selector("test()"), &[]);
+    (runtime, result)
+}
+
+#[allow(unused)]
+fn exported_names(wasm_bytes: &[u8]) -> Vec<String> {
+    let module: parity_wasm::elements::Module =
+        parity_wasm::deserialize_buffer(wasm_bytes).unwrap();
+    module
+        .export_section()
+        .map(|section| {
+            section
+                .entries()
+                .iter()
+                .map(|entry| entry.field().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_context_check_exports_call_static() {
+        let mut runtime = TestRuntime::new(
+            "StaticContextTest",
+            "target/test_static_context_check_exports_call_static",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_static_context_check(true);
+        let wasm_bytes = runtime.compile_test_yul(STORE_AND_LOG_YUL).unwrap();
+        let exports = exported_names(&wasm_bytes);
+        assert!(exports.contains(&"call".to_string()));
+        assert!(exports.contains(&"call_static".to_string()));
+    }
+
+    #[test]
+    fn test_no_call_static_without_static_context_check() {
+        let mut runtime = TestRuntime::new(
+            "StaticContextTest",
+            "target/test_no_call_static_without_static_context_check",
+        );
+        runtime.clear_testdata();
+        let wasm_bytes = runtime.compile_test_yul(STORE_AND_LOG_YUL).unwrap();
+        let exports = exported_names(&wasm_bytes);
+        assert!(exports.contains(&"call".to_string()));
+        assert!(!exports.contains(&"call_static".to_string()));
+    }
+
+    #[test]
+    fn test_static_context_check_allows_state_changes_in_call() {
+        let mut runtime = TestRuntime::new(
+            "StaticContextTest",
+            "target/test_static_context_check_allows_state_changes_in_call",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_static_context_check(true);
+        runtime.compile_test_yul(STORE_AND_LOG_YUL).unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime.call(&solidity_selector("test()"), &[]).unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000055");
+    }
+
+    #[test]
+    fn test_static_context_check_exports_set_call_depth() {
+        let mut runtime = TestRuntime::new(
+            "StaticContextTest",
+            "target/test_static_context_check_exports_set_call_depth",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_static_context_check(true);
+        let wasm_bytes = runtime.compile_test_yul(STORE_AND_LOG_YUL).unwrap();
+        let exports = exported_names(&wasm_bytes);
+        assert_eq!(
+            exports
+                .iter()
+                .filter(|name| name.as_str() == "set_call_depth")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_call_static_allows_reads() {
+        let (runtime, result) = call_through_entry(
+            "StaticContextReadTest",
+            "target/test_call_static_allows_reads",
+            &state_modifying_yul(
+                "StaticContextReadTest",
+                "pop(sload(0x01)) pop(tload(0x02)) pop(staticcall(gas(), 0x04, 0, 0, 0, 0))",
+            ),
+            "call_static",
+            None,
+        );
+        result.unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000055");
+    }
+
+    #[test]
+    fn test_call_static_traps_state_modifications() {
+        let operations = [
+            ("Sstore", "sstore(0x01, 0x2a)"),
+            ("Tstore", "tstore(0x02, 0x2b)"),
+            ("Log0", "log0(0x00, 0x20)"),
+            ("Log4", "log4(0x00, 0x20, 1, 2, 3, 4)"),
+            ("Create", "pop(create(0, 0x00, 0x00))"),
+            ("Create2", "pop(create2(0, 0x00, 0x00, 0x01))"),
+            ("SelfDestruct", "selfdestruct(0x1234)"),
+            (
+                "ValueCall",
+                "pop(call(gas(), 0x1234, 1, 0x00, 0x00, 0x00, 0x00))",
+            ),
+        ];
+        for (name, operation) in operations {
+            let contract_name = format!("StaticContext{name}Test");
+            let yul_code = state_modifying_yul(&contract_name, operation);
+            let (_, result) = call_through_entry(
+                &contract_name,
+                &format!("target/test_call_static_traps_{}", name.to_lowercase()),
+                &yul_code,
+                "call_static",
+                None,
+            );
+            assert!(result.is_err(), "{operation} didn't trap in call_static");
+        }
+
+        // the same store runs through call
+        let (runtime, result) = call_through_entry(
+            "StaticContextSstoreTest",
+            "target/test_call_static_traps_sstore_in_call",
+            &state_modifying_yul("StaticContextSstoreTest", "sstore(0x01, 0x2a)"),
+            "call",
+            None,
+        );
+        result.unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000055");
+    }
+
+    #[test]
+    fn test_is_static_context_for_delegatecall_and_callcode() {
+        let yul_code = state_modifying_yul(
+            "StaticContextDelegateTest",
+            concat!(
+                "pop(delegatecall(gas(), 0x1234, 0x00, 0x00, 0x00, 0x00)) ",
+                "pop(callcode(gas(), 0x1234, 0, 0x00, 0x00, 0x00, 0x00))",
+            ),
+        );
+        for (entry, expected) in [("call_static", 1), ("call", 0)] {
+            let mut runtime = TestRuntime::new(
+                "StaticContextDelegateTest",
+                &format!("target/test_is_static_context_in_{entry}"),
+            );
+            runtime.clear_testdata();
+            runtime.set_enable_static_context_check(true);
+            let wasm_bytes = runtime.compile_test_yul(&yul_code).unwrap();
+            runtime.deploy(&[]).unwrap();
+
+            // the host asks the caller when it handles callDelegate or
+            // callCode, the frame answers 1 only when entered through
+            // call_static
+            let checked = with_is_static_context_check(&wasm_bytes, expected);
+            runtime.set_wasm_bytecode(with_call_entry(&checked, entry, None));
+            runtime.call(&solidity_selector("test()"), &[]).unwrap();
+            runtime
+                .assert_result("0000000000000000000000000000000000000000000000000000000000000055");
+
+            let wrong = with_is_static_context_check(&wasm_bytes, 1 - expected);
+            runtime.set_wasm_bytecode(with_call_entry(&wrong, entry, None));
+            assert!(runtime.call(&solidity_selector("test()"), &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_call_depth_below_limit() {
+        let (runtime, result) = call_through_entry(
+            "CallDepthTest",
+            "target/test_call_depth_below_limit",
+            IDENTITY_AT_DEPTH_YUL,
+            "call",
+            Some(1023),
+        );
+        result.unwrap();
+        runtime.assert_result(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+        ));
+    }
+
+    #[test]
+    fn test_call_depth_limit() {
+        // the precompile call fails and leaves no return data
+        let (runtime, result) = call_through_entry(
+            "CallDepthTest",
+            "target/test_call_depth_limit",
+            IDENTITY_AT_DEPTH_YUL,
+            "call",
+            Some(1024),
+        );
+        result.unwrap();
+        runtime.assert_result(&"0".repeat(128));
+
+        // calls and creates fail without reaching the host, the frame goes on
+        let (runtime, result) = call_through_entry(
+            "CallDepthTest",
+            "target/test_call_depth_limit_calls",
+            CALLS_AT_DEPTH_YUL,
+            "call",
+            Some(1024),
+        );
+        result.unwrap();
+        runtime.assert_result(&"0".repeat(448));
+    }
+}
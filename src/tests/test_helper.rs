@@ -2,11 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 
 #[allow(unused)]
-use crate::yul2ir::config::Yul2IROptions;
+use crate::yul2ir::config::{
+    CallingConvention, ChainProfile, GasMetering, LlvmPipeline, RemarksFormat, SelectorDispatch,
+    WasmFeature, Yul2IROptions, DEFAULT_MAX_MEMORY_SIZE,
+};
 #[allow(unused)]
 use crate::yul2ir::context::Yul2IRContext;
 #[allow(unused)]
+use crate::yul2ir::cost_report::CostReport;
+#[allow(unused)]
+use crate::yul2ir::remarks::RemarkReport;
+#[allow(unused)]
+use crate::yul2ir::size_report::SizeReport;
+#[allow(unused)]
+use crate::yul2ir::stack_usage::StackUsageReport;
+#[allow(unused)]
 use crate::yul2ir::yul;
+#[allow(unused)]
+use crate::yul2ir::yul_instruction::YulLowLevelValueType;
 use ethabi::{encode, ParamType, Token};
 use ethereum_types::H160;
 #[allow(unused)]
@@ -39,6 +52,57 @@ pub struct TestRuntime {
     enable_gas_meter: bool,
     #[allow(unused)]
     enable_all_optimizers: bool,
+    #[allow(unused)]
+    stack_size: Option<u32>,
+    #[allow(unused)]
+    enable_stack_guard: bool,
+    #[allow(unused)]
+    enable_static_context_check: bool,
+    #[allow(unused)]
+    calling_convention: CallingConvention,
+    #[allow(unused)]
+    selector_dispatch: SelectorDispatch,
+    #[allow(unused)]
+    llvm_pipeline: LlvmPipeline,
+    #[allow(unused)]
+    default_ret_type: YulLowLevelValueType,
+    #[allow(unused)]
+    enable_limb_u256_math: bool,
+    #[allow(unused)]
+    enable_simd128: bool,
+    #[allow(unused)]
+    chain_profile: ChainProfile,
+    /// The default features of the chain profile if None
+    #[allow(unused)]
+    wasm_features: Option<Vec<WasmFeature>>,
+    #[allow(unused)]
+    enable_safe_memory: bool,
+    #[allow(unused)]
+    max_memory_size: u32,
+    #[allow(unused)]
+    enable_memory_expansion_gas: bool,
+    #[allow(unused)]
+    gas_metering: Option<GasMetering>,
+    #[allow(unused)]
+    gas_cost_overrides: Vec<(String, u64)>,
+    /// Stack usage of the last compiled contract
+    #[allow(unused)]
+    stack_usage_report: Option<StackUsageReport>,
+    #[allow(unused)]
+    enable_cost_report: bool,
+    /// Cost report of the last compiled contract with enable_cost_report
+    #[allow(unused)]
+    cost_report: Option<CostReport>,
+    #[allow(unused)]
+    enable_size_report: bool,
+    /// Size report of the last compiled contract with enable_size_report
+    #[allow(unused)]
+    size_report: Option<SizeReport>,
+    #[allow(unused)]
+    remarks: Option<RemarksFormat>,
+    /// Remarks of the last compiled contract with remarks
+    #[allow(unused)]
+    remark_report: Option<RemarkReport>,
 }
 
 // Helper function to calculate solidity selector from function signature
@@ -99,6 +163,29 @@ impl TestRuntime {
             output_dir: output_basepath.to_string(),
             enable_gas_meter: true,
             enable_all_optimizers: false,
+            stack_size: None,
+            enable_stack_guard: false,
+            enable_static_context_check: false,
+            calling_convention: CallingConvention::Value,
+            selector_dispatch: SelectorDispatch::Switch,
+            llvm_pipeline: LlvmPipeline::Default,
+            default_ret_type: YulLowLevelValueType::U256,
+            enable_limb_u256_math: false,
+            enable_simd128: false,
+            chain_profile: ChainProfile::Dtvm,
+            wasm_features: None,
+            enable_safe_memory: false,
+            max_memory_size: DEFAULT_MAX_MEMORY_SIZE,
+            enable_memory_expansion_gas: false,
+            gas_metering: None,
+            gas_cost_overrides: vec![],
+            stack_usage_report: None,
+            enable_cost_report: false,
+            cost_report: None,
+            enable_size_report: false,
+            size_report: None,
+            remarks: None,
+            remark_report: None,
         }
     }
 
@@ -124,8 +211,36 @@ impl TestRuntime {
         if self.enable_all_optimizers {
             opts.enable_all_optimizers = true;
         }
+        opts.stack_size = self.stack_size;
+        opts.enable_stack_guard = self.enable_stack_guard;
+        opts.enable_static_context_check = self.enable_static_context_check;
+        opts.calling_convention = self.calling_convention;
+        opts.selector_dispatch = self.selector_dispatch;
+        opts.llvm_pipeline = self.llvm_pipeline.clone();
+        opts.enable_limb_u256_math = self.enable_limb_u256_math;
+        opts.enable_simd128 = self.enable_simd128;
+        opts.chain_profile = self.chain_profile;
+        opts.wasm_features = self
+            .wasm_features
+            .clone()
+            .unwrap_or_else(|| self.chain_profile.default_wasm_features());
+        opts.enable_safe_memory = self.enable_safe_memory;
+        opts.max_memory_size = self.max_memory_size;
+        opts.enable_memory_expansion_gas = self.enable_memory_expansion_gas;
+        opts.gas_metering = self.gas_metering;
+        opts.gas_cost_overrides = self.gas_cost_overrides.clone();
+        opts.enable_cost_report = self.enable_cost_report;
+        opts.enable_size_report = self.enable_size_report;
+        opts.remarks = self.remarks;
         let mut context = Yul2IRContext::new_with_object(&llvm_context, &opts, expr);
+        context.default_ret_type = self.default_ret_type;
         let emited_bc = context.emit("output");
+        self.stack_usage_report = context.stack_usage_report.borrow().clone();
+        self.cost_report = context.cost_report.borrow().clone();
+        self.size_report = context.size_report.borrow().clone();
+        self.remark_report = self.remarks.map(|_| RemarkReport {
+            remarks: context.remarks.borrow().clone(),
+        });
         match emited_bc {
             Ok(emited_bc) => {
                 self.wasm_bytecode = emited_bc.clone();
@@ -200,6 +315,139 @@ impl TestRuntime {
         self.enable_all_optimizers = enable_all_optimizers;
     }
 
+    #[allow(unused)]
+    pub fn set_stack_size(&mut self, stack_size: Option<u32>) {
+        self.stack_size = stack_size;
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_stack_guard(&mut self, enable_stack_guard: bool) {
+        self.enable_stack_guard = enable_stack_guard;
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_static_context_check(&mut self, enable_static_context_check: bool) {
+        self.enable_static_context_check = enable_static_context_check;
+    }
+
+    /// Replaces the compiled wasm, e.g. with a module whose entries were rewritten
+    #[allow(unused)]
+    pub fn set_wasm_bytecode(&mut self, wasm_bytecode: Vec<u8>) {
+        self.wasm_bytecode = wasm_bytecode;
+    }
+
+    #[allow(unused)]
+    pub fn set_calling_convention(&mut self, calling_convention: CallingConvention) {
+        self.calling_convention = calling_convention;
+    }
+
+    #[allow(unused)]
+    pub fn set_selector_dispatch(&mut self, selector_dispatch: SelectorDispatch) {
+        self.selector_dispatch = selector_dispatch;
+    }
+
+    #[allow(unused)]
+    pub fn set_llvm_pipeline(&mut self, llvm_pipeline: LlvmPipeline) {
+        self.llvm_pipeline = llvm_pipeline;
+    }
+
+    /// Return type of untyped Yul functions, like --default_ret_type
+    #[allow(unused)]
+    pub fn set_default_ret_type(&mut self, default_ret_type: YulLowLevelValueType) {
+        self.default_ret_type = default_ret_type;
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_limb_u256_math(&mut self, enable_limb_u256_math: bool) {
+        self.enable_limb_u256_math = enable_limb_u256_math;
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_simd128(&mut self, enable_simd128: bool) {
+        self.enable_simd128 = enable_simd128;
+    }
+
+    #[allow(unused)]
+    pub fn set_chain_profile(&mut self, chain_profile: ChainProfile) {
+        self.chain_profile = chain_profile;
+    }
+
+    #[allow(unused)]
+    pub fn set_wasm_features(&mut self, wasm_features: &[WasmFeature]) {
+        self.wasm_features = Some(wasm_features.to_vec());
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_safe_memory(&mut self, enable_safe_memory: bool) {
+        self.enable_safe_memory = enable_safe_memory;
+    }
+
+    #[allow(unused)]
+    pub fn set_max_memory_size(&mut self, max_memory_size: u32) {
+        self.max_memory_size = max_memory_size;
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_memory_expansion_gas(&mut self, enable_memory_expansion_gas: bool) {
+        self.enable_memory_expansion_gas = enable_memory_expansion_gas;
+    }
+
+    #[allow(unused)]
+    pub fn set_gas_metering(&mut self, gas_metering: Option<GasMetering>) {
+        self.gas_metering = gas_metering;
+    }
+
+    #[allow(unused)]
+    pub fn set_gas_cost_overrides(&mut self, gas_cost_overrides: &[(&str, u64)]) {
+        self.gas_cost_overrides = gas_cost_overrides
+            .iter()
+            .map(|(name, cost)| (name.to_string(), *cost))
+            .collect();
+    }
+
+    #[allow(unused)]
+    pub fn stack_usage_report(&self) -> &StackUsageReport {
+        self.stack_usage_report
+            .as_ref()
+            .expect("no contract compiled yet")
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_cost_report(&mut self, enable_cost_report: bool) {
+        self.enable_cost_report = enable_cost_report;
+    }
+
+    #[allow(unused)]
+    pub fn cost_report(&self) -> &CostReport {
+        self.cost_report
+            .as_ref()
+            .expect("no contract compiled with the cost report yet")
+    }
+
+    #[allow(unused)]
+    pub fn set_enable_size_report(&mut self, enable_size_report: bool) {
+        self.enable_size_report = enable_size_report;
+    }
+
+    #[allow(unused)]
+    pub fn size_report(&self) -> &SizeReport {
+        self.size_report
+            .as_ref()
+            .expect("no contract compiled with the size report yet")
+    }
+
+    #[allow(unused)]
+    pub fn set_remarks(&mut self, remarks: Option<RemarksFormat>) {
+        self.remarks = remarks;
+    }
+
+    #[allow(unused)]
+    pub fn remark_report(&self) -> &RemarkReport {
+        self.remark_report
+            .as_ref()
+            .expect("no contract compiled with remarks yet")
+    }
+
     // Deploy contract and return exit code
     #[allow(unused)]
     pub fn deploy(&mut self, calldata: &[u8]) -> Result<(), String> {
@@ -340,6 +588,18 @@ impl TestRuntime {
         self.assert_last_status_line(revert_prefix, revert_hex, "revert");
     }
 
+    // Asserts that the last run printed the expected text, e.g. through the debug host api
+    #[allow(unused)]
+    pub fn assert_output_contains(&self, expected: &str) {
+        assert!(
+            self.last_output.contains(expected) || self.last_error_output.contains(expected),
+            "Expected output to contain '{}'. Full output:\n{}\n{}",
+            expected,
+            self.last_output,
+            self.last_error_output
+        );
+    }
+
     #[allow(unused)]
     pub fn wasm2wat(&self, wasm_file_path: &str, wat_file_path: &str) {
         let _ = std::process::Command::new("wasm2wat")
@@ -349,4 +609,24 @@ impl TestRuntime {
             .output()
             .unwrap();
     }
+
+    // Text format of the last compiled wasm, with all wasm features enabled
+    // so that every opcode the compiler may emit is printed
+    #[allow(unused)]
+    pub fn wat(&self) -> String {
+        let tmp_dir = tempdir().unwrap();
+        let wasm_file_path = tmp_dir.path().join(format!("{}.wasm", self.case_name));
+        std::fs::write(&wasm_file_path, &self.wasm_bytecode).unwrap();
+        let output = std::process::Command::new("wasm2wat")
+            .arg("--enable-all")
+            .arg(&wasm_file_path)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "wasm2wat failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
 }
@@ -5,11 +5,184 @@
 use super::test_helper::solidity_selector;
 #[allow(unused)]
 use super::test_helper::TestRuntime;
+
+// Standard ERC20 functions with the names and variable numbering changed,
+// balances are at slot 0, allowances at slot 1 and totalSupply at slot 2
+#[allow(unused)]
+const RENAMED_ERC20_YUL: &str = r#"
+    object "PatternRulesTest" {
+        code {
+        }
+        object "PatternRulesTest_deployed" {
+            code {
+                function fun_move_balance(var_a, var_b, var_amount)
+                {
+                    let _11 := sub(shl(160, 1), 1)
+                    let _12 := and(var_a, _11)
+                    if iszero(_12)
+                    {
+                        let _13 := mload(64)
+                        mstore(_13, shl(225, 0x4b637e8f))
+                        mstore(add(_13, 4), 0x00)
+                        revert(_13, 36)
+                    }
+                    let _14 := and(var_b, _11)
+                    if iszero(_14)
+                    {
+                        let _15 := mload(64)
+                        mstore(_15, shl(224, 0xec442f05))
+                        mstore(add(_15, 4), 0x00)
+                        revert(_15, 36)
+                    }
+                    mstore(0, _12)
+                    mstore(0x20, 0)
+                    let _16 := sload(keccak256(0, 0x40))
+                    if lt(_16, var_amount)
+                    {
+                        mstore(0, shl(226, 0x391434e3))
+                        mstore(4, _12)
+                        mstore(36, _16)
+                        mstore(68, var_amount)
+                        revert(0, 100)
+                    }
+                    mstore(0, _12)
+                    mstore(0x20, 0)
+                    sstore(keccak256(0, 0x40), sub(_16, var_amount))
+                    mstore(0, _14)
+                    let _17 := keccak256(0, 0x40)
+                    sstore(_17, add(sload(_17), var_amount))
+                    let _18 := mload(0x40)
+                    mstore(_18, var_amount)
+                    log3(_18, 0x20, 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef, _12, _14)
+                }
+
+                function fun_transferFrom_42(var_sender, var_recipient, var_amount) -> var_ok
+                {
+                    mstore(0, and(var_sender, sub(shl(160, 1), 1)))
+                    mstore(0x20, 1)
+                    let _21 := keccak256(0, 0x40)
+                    mstore(0, and(caller(), sub(shl(160, 1), 1)))
+                    mstore(0x20, _21)
+                    let _22 := keccak256(0, 0x40)
+                    let _23 := sload(_22)
+                    if iszero(eq(_23, not(0)))
+                    {
+                        if lt(_23, var_amount)
+                        {
+                            mstore(0, shl(224, 0xfb8f41b2))
+                            mstore(4, caller())
+                            mstore(36, _23)
+                            mstore(68, var_amount)
+                            revert(0, 100)
+                        }
+                        sstore(_22, sub(_23, var_amount))
+                    }
+                    fun_move_balance(var_sender, var_recipient, var_amount)
+                    var_ok := 1
+                }
+
+                function fun_approve_7(var_holder, var_operator, var_amount)
+                {
+                    let _31 := and(var_holder, sub(shl(160, 1), 1))
+                    if iszero(_31)
+                    {
+                        mstore(0, shl(224, 0xe602df05))
+                        mstore(4, 0)
+                        revert(0, 36)
+                    }
+                    let _32 := and(var_operator, sub(shl(160, 1), 1))
+                    if iszero(_32)
+                    {
+                        mstore(0, shl(224, 0x94280d62))
+                        mstore(4, 0)
+                        revert(0, 36)
+                    }
+                    mstore(0, _31)
+                    mstore(0x20, 1)
+                    let _33 := keccak256(0, 0x40)
+                    mstore(0, _32)
+                    mstore(0x20, _33)
+                    sstore(keccak256(0, 0x40), var_amount)
+                    let _34 := mload(0x40)
+                    mstore(_34, var_amount)
+                    log3(_34, 0x20, 0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925, _31, _32)
+                }
+
+                function fun_issue(var_to, var_amount)
+                {
+                    let _41 := and(var_to, sub(shl(160, 1), 1))
+                    if iszero(_41)
+                    {
+                        mstore(0, shl(224, 0xec442f05))
+                        mstore(4, 0)
+                        revert(0, 36)
+                    }
+                    let _42 := sload(2)
+                    let _43 := add(_42, var_amount)
+                    if gt(_42, _43)
+                    {
+                        mstore(0, shl(224, 0x4e487b71))
+                        mstore(4, 0x11)
+                        revert(0, 36)
+                    }
+                    sstore(2, _43)
+                    mstore(0, _41)
+                    mstore(0x20, 0)
+                    let _44 := keccak256(0, 0x40)
+                    sstore(_44, add(sload(_44), var_amount))
+                    let _45 := mload(0x40)
+                    mstore(_45, var_amount)
+                    log3(_45, 0x20, 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef, 0, _41)
+                }
+
+                switch shr(224, calldataload(0))
+                case 0x40c10f19 {
+                    // mint(address,uint256)
+                    fun_issue(calldataload(4), calldataload(36))
+                    mstore(0, 1)
+                }
+                case 0xe1f21c67 {
+                    // approve(address,address,uint256)
+                    fun_approve_7(calldataload(4), calldataload(36), calldataload(68))
+                    mstore(0, 1)
+                }
+                case 0x23b872dd {
+                    // transferFrom(address,address,uint256)
+                    mstore(0, fun_transferFrom_42(calldataload(4), calldataload(36), calldataload(68)))
+                }
+                case 0x70a08231 {
+                    // balanceOf(address)
+                    mstore(0, and(calldataload(4), sub(shl(160, 1), 1)))
+                    mstore(0x20, 0)
+                    mstore(0, add(sload(keccak256(0, 0x40)), 0))
+                }
+                case 0x18160ddd {
+                    // totalSupply()
+                    mstore(0, add(sload(2), 0))
+                }
+                default {
+                    revert(0, 0)
+                }
+                return(0, 0x20)
+            }
+        }
+    }
+    "#;
+
+// OpenZeppelin v5 ERC20 compiled by solc in the tests
+#[allow(unused)]
+const OPEN_ZEPPELIN_ERC20_SOL_CODE: &str = include_str!("openzeppelin_erc20_full.sol");
+
 #[cfg(test)]
 mod tests {
     use ethabi::ParamType;
 
     use crate::tests::test_helper::encode_abi_parameters;
+    use crate::yul2ir::config::RemarksFormat;
+    use crate::yul2ir::pattern::{match_pattern_rules, RuleMatchArg, ERC20_RULES};
+    use crate::yul2ir::remarks::RemarkKind;
+    use crate::yul2ir::utils::remove_comments;
+    use crate::yul2ir::yul;
 
     use super::*;
 
@@ -194,4 +367,510 @@ mod tests {
         // Expect success (1)
         runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000001");
     }
+
+    #[test]
+    fn test_pattern_rules_match_renamed_erc20_functions() {
+        let object = yul::ObjectParser::new()
+            .parse(&remove_comments(RENAMED_ERC20_YUL))
+            .unwrap();
+        let crate::yul2ir::ast::InnerSegment::Object(deployed) = &object.inner_segments[0] else {
+            panic!("no deployed object");
+        };
+        let matches = match_pattern_rules(deployed, ERC20_RULES);
+        assert_eq!(matches.len(), 4);
+        assert_eq!(matches["fun_move_balance"].rule, "erc20_transfer");
+        assert_eq!(matches["fun_transferFrom_42"].rule, "erc20_transfer_from");
+        assert_eq!(matches["fun_approve_7"].rule, "erc20_approve");
+        assert_eq!(matches["fun_issue"].rule, "erc20_mint");
+        assert!(matches["fun_transferFrom_42"].has_return);
+
+        // transferFrom gets the allowances slot and the balances slot of the
+        // transfer function it calls
+        let slots: Vec<String> = matches["fun_transferFrom_42"]
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                RuleMatchArg::Constant(crate::yul2ir::ast::Expression::Literal(
+                    crate::yul2ir::ast::Literal::DecimalNumberLiteral(dec, _),
+                )) => Some(dec.dec.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(slots, vec!["1".to_string(), "0".to_string()]);
+    }
+
+    #[test]
+    fn test_pattern_rules_reject_changed_functions() {
+        // the Transfer event topic is changed and the balance is updated twice
+        let changed = RENAMED_ERC20_YUL
+            .replace("log3(_18, 0x20, 0xddf252ad", "log3(_18, 0x20, 0xadf252ad")
+            .replace(
+                "sstore(2, _43)",
+                "sstore(2, _43)\n                    sstore(3, _43)",
+            );
+        let object = yul::ObjectParser::new()
+            .parse(&remove_comments(&changed))
+            .unwrap();
+        let crate::yul2ir::ast::InnerSegment::Object(deployed) = &object.inner_segments[0] else {
+            panic!("no deployed object");
+        };
+        let matches = match_pattern_rules(deployed, ERC20_RULES);
+        assert!(!matches.contains_key("fun_move_balance"));
+        assert!(!matches.contains_key("fun_issue"));
+        // it calls a function that isn't a standard transfer anymore
+        assert!(!matches.contains_key("fun_transferFrom_42"));
+        assert_eq!(matches["fun_approve_7"].rule, "erc20_approve");
+    }
+
+    #[test]
+    fn test_pattern_rules_reject_different_reverts() {
+        // the intrinsic would revert with ERC20InsufficientBalance instead
+        let empty_revert = RENAMED_ERC20_YUL.replace(
+            "mstore(0, shl(226, 0x391434e3))\n                        mstore(4, _12)\n                        mstore(36, _16)\n                        mstore(68, var_amount)\n                        revert(0, 100)",
+            "revert(0, 0)",
+        );
+        assert_ne!(empty_revert, RENAMED_ERC20_YUL);
+        // ERC20InvalidSpender with the spender instead of address(0)
+        let other_argument = RENAMED_ERC20_YUL.replace(
+            "mstore(0, shl(224, 0x94280d62))\n                        mstore(4, 0)",
+            "mstore(0, shl(224, 0x94280d62))\n                        mstore(4, _32)",
+        );
+        assert_ne!(other_argument, RENAMED_ERC20_YUL);
+        // an OpenZeppelin v4 string error
+        let string_error = RENAMED_ERC20_YUL.replace(
+            "mstore(0, shl(224, 0x4e487b71))\n                        mstore(4, 0x11)\n                        revert(0, 36)",
+            "mstore(0, shl(229, 4594637))\n                        mstore(4, 32)\n                        mstore(36, 31)\n                        mstore(68, \"ERC20: mint to the zero address\")\n                        revert(0, 100)",
+        );
+        assert_ne!(string_error, RENAMED_ERC20_YUL);
+
+        let match_deployed = |yul_code: &str| {
+            let object = yul::ObjectParser::new()
+                .parse(&remove_comments(yul_code))
+                .unwrap();
+            let crate::yul2ir::ast::InnerSegment::Object(deployed) = &object.inner_segments[0]
+            else {
+                panic!("no deployed object");
+            };
+            match_pattern_rules(deployed, ERC20_RULES)
+        };
+        let matches = match_deployed(&empty_revert);
+        assert!(!matches.contains_key("fun_move_balance"));
+        assert!(!matches.contains_key("fun_transferFrom_42"));
+        assert_eq!(matches["fun_issue"].rule, "erc20_mint");
+        let matches = match_deployed(&other_argument);
+        assert!(!matches.contains_key("fun_approve_7"));
+        assert_eq!(matches["fun_move_balance"].rule, "erc20_transfer");
+        let matches = match_deployed(&string_error);
+        assert!(!matches.contains_key("fun_issue"));
+        assert_eq!(matches["fun_approve_7"].rule, "erc20_approve");
+    }
+
+    #[test]
+    fn test_token_iropt_keeps_different_revert() {
+        let mut runtime = TestRuntime::new(
+            "PatternRulesTest",
+            "target/test_token_iropt_keeps_different_revert",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_all_optimizers(true);
+        runtime
+            .compile_test_yul(&RENAMED_ERC20_YUL.replace(
+                "mstore(0, shl(226, 0x391434e3))\n                        mstore(4, _12)\n                        mstore(36, _16)\n                        mstore(68, var_amount)\n                        revert(0, 100)",
+                "revert(0, 0)",
+            ))
+            .unwrap();
+        runtime.set_sender(Some(
+            "0x3333333333333333333333333333333333333333".to_string(),
+        ));
+        runtime.deploy(&[]).unwrap();
+
+        let owner = "0x1111111111111111111111111111111111111111".to_string();
+        let recipient = "0x2222222222222222222222222222222222222222".to_string();
+        let spender = "0x3333333333333333333333333333333333333333".to_string();
+        runtime
+            .call(
+                &solidity_selector("approve(address,address,uint256)"),
+                &encode_abi_parameters(&[
+                    (owner.clone(), ParamType::Address),
+                    (spender.clone(), ParamType::Address),
+                    ("300".to_string(), ParamType::Uint(256)),
+                ]),
+            )
+            .unwrap();
+        // the owner has no balance, the function reverts with its own empty data
+        runtime
+            .call(
+                &solidity_selector("transferFrom(address,address,uint256)"),
+                &encode_abi_parameters(&[
+                    (owner.clone(), ParamType::Address),
+                    (recipient.clone(), ParamType::Address),
+                    ("200".to_string(), ParamType::Uint(256)),
+                ]),
+            )
+            .unwrap();
+        runtime.assert_revert("");
+    }
+
+    #[test]
+    fn test_token_iropt_evaluates_arguments_right_to_left() {
+        let mut runtime = TestRuntime::new(
+            "PatternRulesTest",
+            "target/test_token_iropt_evaluates_arguments_right_to_left",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_all_optimizers(true);
+        runtime.set_remarks(Some(RemarksFormat::Text));
+        // record appends its digit to the number at 0x200
+        let yul_code = RENAMED_ERC20_YUL
+            .replace(
+                "                function fun_issue(var_to, var_amount)",
+                concat!(
+                    "                function record(value, digit) -> r\n",
+                    "                {\n",
+                    "                    mstore(0x200, add(mul(mload(0x200), 10), digit))\n",
+                    "                    r := value\n",
+                    "                }\n\n",
+                    "                function fun_issue(var_to, var_amount)",
+                ),
+            )
+            .replace(
+                "fun_issue(calldataload(4), calldataload(36))\n                    mstore(0, 1)",
+                "fun_issue(record(calldataload(4), 1), record(calldataload(36), 2))\n                    mstore(0, mload(0x200))",
+            );
+        assert_ne!(yul_code, RENAMED_ERC20_YUL);
+        runtime.compile_test_yul(&yul_code).unwrap();
+        let report = runtime.remark_report();
+        assert!(
+            report
+                .find("erc20_intrinsic", RemarkKind::Applied)
+                .iter()
+                .any(|remark| remark.message.starts_with("fun_issue matched erc20_mint")),
+            "{report}"
+        );
+        runtime.deploy(&[]).unwrap();
+
+        let owner = "0x1111111111111111111111111111111111111111".to_string();
+        runtime
+            .call(
+                &solidity_selector("mint(address,uint256)"),
+                &encode_abi_parameters(&[
+                    (owner.clone(), ParamType::Address),
+                    ("1000".to_string(), ParamType::Uint(256)),
+                ]),
+            )
+            .unwrap();
+        // the amount is evaluated before the address
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000015");
+        runtime
+            .call(
+                &solidity_selector("balanceOf(address)"),
+                &encode_abi_parameters(&[(owner.clone(), ParamType::Address)]),
+            )
+            .unwrap();
+        runtime.assert_result("00000000000000000000000000000000000000000000000000000000000003e8");
+    }
+
+    #[test]
+    fn test_token_iropt_solc_openzeppelin_erc20() {
+        let solidity_code = format!(
+            r#"
+        pragma solidity ^0.8.20;
+        {OPEN_ZEPPELIN_ERC20_SOL_CODE}
+
+        contract OpenZeppelinToken is ERC20 {{
+            constructor() ERC20("Token", "TKN") {{}}
+
+            function mint(address to, uint256 value) public {{
+                _mint(to, value);
+            }}
+        }}
+        "#
+        );
+        let owner = "0x1111111111111111111111111111111111111111".to_string();
+        let recipient = "0x2222222222222222222222222222222222222222".to_string();
+        let spender = "0x3333333333333333333333333333333333333333".to_string();
+        let word = |value: &str| format!("{value:0>64}");
+
+        // the same calls give the same results and revert data whether or not
+        // the intrinsics replaced the functions solc emitted
+        for enable_all_optimizers in [false, true] {
+            let mut runtime = TestRuntime::new(
+                "OpenZeppelinToken",
+                &format!("target/test_token_iropt_solc_openzeppelin_erc20_{enable_all_optimizers}"),
+            );
+            runtime.clear_testdata();
+            let yul_code = runtime
+                .compile_solidity_to_yul(&solidity_code, "OpenZeppelinToken")
+                .unwrap();
+            runtime.set_enable_all_optimizers(enable_all_optimizers);
+            runtime.set_remarks(Some(RemarksFormat::Text));
+            runtime.compile_test_yul(&yul_code).unwrap();
+            if enable_all_optimizers {
+                // every transfer function solc emitted is either replaced or
+                // reported with the reason it isn't
+                let report = runtime.remark_report();
+                let erc20_remarks: Vec<_> = [RemarkKind::Applied, RemarkKind::Missed]
+                    .into_iter()
+                    .flat_map(|kind| report.find("erc20_intrinsic", kind))
+                    .collect();
+                assert!(
+                    erc20_remarks
+                        .iter()
+                        .any(|remark| remark.message.starts_with("fun__transfer")),
+                    "{report}"
+                );
+            }
+            runtime.set_sender(Some(owner.clone()));
+            runtime.deploy(&[]).unwrap();
+
+            runtime
+                .call(
+                    &solidity_selector("mint(address,uint256)"),
+                    &encode_abi_parameters(&[
+                        (owner.clone(), ParamType::Address),
+                        ("1000".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime
+                .call(
+                    &solidity_selector("transfer(address,uint256)"),
+                    &encode_abi_parameters(&[
+                        (recipient.clone(), ParamType::Address),
+                        ("200".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_result(&word("1"));
+            runtime
+                .call(
+                    &solidity_selector("balanceOf(address)"),
+                    &encode_abi_parameters(&[(owner.clone(), ParamType::Address)]),
+                )
+                .unwrap();
+            runtime.assert_result(&word("320"));
+
+            // ERC20InsufficientBalance(owner, 800, 5000)
+            runtime
+                .call(
+                    &solidity_selector("transfer(address,uint256)"),
+                    &encode_abi_parameters(&[
+                        (recipient.clone(), ParamType::Address),
+                        ("5000".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_revert(&format!(
+                "e450d38c{}{}{}",
+                word(&owner[2..]),
+                word("320"),
+                word("1388")
+            ));
+            // ERC20InvalidReceiver(address(0))
+            runtime
+                .call(
+                    &solidity_selector("transfer(address,uint256)"),
+                    &encode_abi_parameters(&[
+                        (
+                            "0x0000000000000000000000000000000000000000".to_string(),
+                            ParamType::Address,
+                        ),
+                        ("1".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_revert(&format!("ec442f05{}", word("0")));
+
+            runtime
+                .call(
+                    &solidity_selector("approve(address,uint256)"),
+                    &encode_abi_parameters(&[
+                        (spender.clone(), ParamType::Address),
+                        ("300".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_result(&word("1"));
+
+            runtime.set_sender(Some(spender.clone()));
+            // ERC20InsufficientAllowance(spender, 300, 500)
+            runtime
+                .call(
+                    &solidity_selector("transferFrom(address,address,uint256)"),
+                    &encode_abi_parameters(&[
+                        (owner.clone(), ParamType::Address),
+                        (recipient.clone(), ParamType::Address),
+                        ("500".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_revert(&format!(
+                "fb8f41b2{}{}{}",
+                word(&spender[2..]),
+                word("12c"),
+                word("1f4")
+            ));
+            runtime
+                .call(
+                    &solidity_selector("transferFrom(address,address,uint256)"),
+                    &encode_abi_parameters(&[
+                        (owner.clone(), ParamType::Address),
+                        (recipient.clone(), ParamType::Address),
+                        ("100".to_string(), ParamType::Uint(256)),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_result(&word("1"));
+            runtime
+                .call(
+                    &solidity_selector("balanceOf(address)"),
+                    &encode_abi_parameters(&[(recipient.clone(), ParamType::Address)]),
+                )
+                .unwrap();
+            runtime.assert_result(&word("12c"));
+            runtime
+                .call(
+                    &solidity_selector("allowance(address,address)"),
+                    &encode_abi_parameters(&[
+                        (owner.clone(), ParamType::Address),
+                        (spender.clone(), ParamType::Address),
+                    ]),
+                )
+                .unwrap();
+            runtime.assert_result(&word("c8"));
+            runtime
+                .call(&solidity_selector("totalSupply()"), &[])
+                .unwrap();
+            runtime.assert_result(&word("3e8"));
+        }
+    }
+
+    #[test]
+    fn test_token_iropt_renamed_erc20_functions() {
+        let mut runtime = TestRuntime::new(
+            "PatternRulesTest",
+            "target/test_token_iropt_renamed_erc20_functions",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_all_optimizers(true);
+        runtime.compile_test_yul(RENAMED_ERC20_YUL).unwrap();
+        runtime.set_sender(Some(
+            "0x3333333333333333333333333333333333333333".to_string(),
+        ));
+        runtime.deploy(&[]).unwrap();
+
+        let owner = "0x1111111111111111111111111111111111111111".to_string();
+        let recipient = "0x2222222222222222222222222222222222222222".to_string();
+        let spender = "0x3333333333333333333333333333333333333333".to_string();
+        runtime
+            .call(
+                &solidity_selector("mint(address,uint256)"),
+                &encode_abi_parameters(&[
+                    (owner.clone(), ParamType::Address),
+                    ("1000".to_string(), ParamType::Uint(256)),
+                ]),
+            )
+            .unwrap();
+        runtime
+            .call(
+                &solidity_selector("approve(address,address,uint256)"),
+                &encode_abi_parameters(&[
+                    (owner.clone(), ParamType::Address),
+                    (spender.clone(), ParamType::Address),
+                    ("300".to_string(), ParamType::Uint(256)),
+                ]),
+            )
+            .unwrap();
+        let transfer_from_params = encode_abi_parameters(&[
+            (owner.clone(), ParamType::Address),
+            (recipient.clone(), ParamType::Address),
+            ("200".to_string(), ParamType::Uint(256)),
+        ]);
+        runtime
+            .call(
+                &solidity_selector("transferFrom(address,address,uint256)"),
+                &transfer_from_params,
+            )
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000001");
+
+        runtime
+            .call(
+                &solidity_selector("balanceOf(address)"),
+                &encode_abi_parameters(&[(recipient.clone(), ParamType::Address)]),
+            )
+            .unwrap();
+        runtime.assert_result("00000000000000000000000000000000000000000000000000000000000000c8");
+        runtime
+            .call(
+                &solidity_selector("balanceOf(address)"),
+                &encode_abi_parameters(&[(owner.clone(), ParamType::Address)]),
+            )
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000320");
+        runtime
+            .call(&solidity_selector("totalSupply()"), &[])
+            .unwrap();
+        runtime.assert_result("00000000000000000000000000000000000000000000000000000000000003e8");
+
+        // only 100 of the allowance is left
+        runtime
+            .call(
+                &solidity_selector("transferFrom(address,address,uint256)"),
+                &transfer_from_params,
+            )
+            .unwrap();
+        // ERC20InsufficientAllowance(spender, 100, 200)
+        runtime.assert_revert("fb8f41b20000000000000000000000003333333333333333333333333333333333333333000000000000000000000000000000000000000000000000000000000000006400000000000000000000000000000000000000000000000000000000000000c8");
+
+        // the high bits of the addresses are masked like in the Yul functions
+        let dirty_owner = format!("ffffffffffffffffffffffff{}", &owner[2..]);
+        let dirty_recipient = format!("ffffffffffffffffffffffff{}", &recipient[2..]);
+        let mut calldata = hex::decode(&dirty_owner).unwrap();
+        calldata.extend(hex::decode(&dirty_recipient).unwrap());
+        calldata.extend(hex::decode(format!("{:064x}", 1000)).unwrap());
+        runtime
+            .call(
+                &solidity_selector("transferFrom(address,address,uint256)"),
+                &calldata,
+            )
+            .unwrap();
+        // ERC20InsufficientAllowance(spender, 100, 1000), the allowance is checked first
+        runtime.assert_revert("fb8f41b20000000000000000000000003333333333333333333333333333333333333333000000000000000000000000000000000000000000000000000000000000006400000000000000000000000000000000000000000000000000000000000003e8");
+        runtime
+            .call(
+                &solidity_selector("approve(address,address,uint256)"),
+                &encode_abi_parameters(&[
+                    (owner.clone(), ParamType::Address),
+                    (spender.clone(), ParamType::Address),
+                    ("5000".to_string(), ParamType::Uint(256)),
+                ]),
+            )
+            .unwrap();
+        runtime
+            .call(
+                &solidity_selector("transferFrom(address,address,uint256)"),
+                &calldata,
+            )
+            .unwrap();
+        // ERC20InsufficientBalance(owner, 800, 1000) with the masked owner
+        runtime.assert_revert("e450d38c0000000000000000000000001111111111111111111111111111111111111111000000000000000000000000000000000000000000000000000000000000032000000000000000000000000000000000000000000000000000000000000003e8");
+        let mut calldata = hex::decode(&dirty_owner).unwrap();
+        calldata.extend(hex::decode(&dirty_recipient).unwrap());
+        calldata.extend(hex::decode(format!("{:064x}", 100)).unwrap());
+        runtime
+            .call(
+                &solidity_selector("transferFrom(address,address,uint256)"),
+                &calldata,
+            )
+            .unwrap();
+        runtime.assert_result("0000000000000000000000000000000000000000000000000000000000000001");
+        runtime
+            .call(
+                &solidity_selector("balanceOf(address)"),
+                &encode_abi_parameters(&[(recipient.clone(), ParamType::Address)]),
+            )
+            .unwrap();
+        runtime.assert_result("000000000000000000000000000000000000000000000000000000000000012c");
+    }
 }
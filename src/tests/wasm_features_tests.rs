@@ -0,0 +1,345 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::encode_abi_parameters;
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::config::{parse_wasm_features, ChainProfile, LlvmPipeline, WasmFeature};
+#[allow(unused)]
+use ethabi::ParamType;
+
+#[allow(unused)]
+const WASM_FEATURES_CONTRACT: &str = r#"
+object "WasmFeaturesTest" {
+    code {
+    }
+    object "WasmFeaturesTest_deployed" {
+        code {
+            // overlapping copies in both directions
+            mstore(0x80, 0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f)
+            mcopy(0x81, 0x80, 0x20)
+            let first := mload(0x80)
+            mstore(0x100, 0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f)
+            mcopy(0x100, 0x103, 0x1d)
+            let second := mload(0x100)
+            calldatacopy(0x180, 4, 0x20)
+            let q, r := divmod(calldataload(4), 7)
+            mstore(0x80, first)
+            mstore(0xa0, second)
+            mstore(0xc0, mload(0x180))
+            mstore(0xe0, q)
+            mstore(0x100, r)
+            mstore(0x120, signextend(0, 0xff))
+            mstore(0x140, signextend(1, 0x8001))
+            mstore(0x160, sum(100, 0))
+            return(0x80, 0x100)
+
+            function divmod(a, b) -> quotient, remainder {
+                quotient := div(a, b)
+                remainder := mod(a, b)
+            }
+            function sum(n, acc) -> result {
+                if iszero(n) {
+                    result := acc
+                    leave
+                }
+                result := sum(sub(n, 1), add(acc, n))
+            }
+        }
+    }
+}
+"#;
+
+#[allow(unused)]
+const WASM_FEATURES_RESULT: &str = concat!(
+    "00000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e",
+    "030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f1d1e1f",
+    "00000000000000000000000000000000000000000000000000000000000003e8",
+    "000000000000000000000000000000000000000000000000000000000000008e",
+    "0000000000000000000000000000000000000000000000000000000000000006",
+    "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+    "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff8001",
+    "00000000000000000000000000000000000000000000000000000000000013ba",
+);
+
+// Tuples returned from a recursion wasm-opt can't inline and calls in tail
+// position of functions calling each other
+#[allow(unused)]
+const WASM_FEATURE_OPCODES_CONTRACT: &str = r#"
+object "WasmFeatureOpcodesTest" {
+    code {
+    }
+    object "WasmFeatureOpcodesTest_deployed" {
+        code {
+            let q, r := divmod_steps(calldataload(4), 7)
+            mstore(0x80, q)
+            mstore(0xa0, r)
+            mstore(0xc0, is_even(calldataload(4)))
+            mstore(0xe0, signextend(0, calldataload(4)))
+            return(0x80, 0x80)
+
+            function divmod_steps(a, b) -> quotient, remainder {
+                if lt(a, b) {
+                    remainder := a
+                    leave
+                }
+                quotient, remainder := divmod_steps(sub(a, b), b)
+                quotient := add(quotient, 1)
+            }
+            function is_even(n) -> even {
+                if iszero(n) {
+                    even := 1
+                    leave
+                }
+                even := is_odd(sub(n, 1))
+            }
+            function is_odd(n) -> odd {
+                if iszero(n) {
+                    leave
+                }
+                odd := is_even(sub(n, 1))
+            }
+        }
+    }
+}
+"#;
+
+#[allow(unused)]
+const WASM_FEATURE_OPCODES_RESULT: &str = concat!(
+    "000000000000000000000000000000000000000000000000000000000000008e",
+    "0000000000000000000000000000000000000000000000000000000000000006",
+    "0000000000000000000000000000000000000000000000000000000000000001",
+    "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe8",
+);
+
+/// Instructions of the text format that need a wasm feature
+#[allow(unused)]
+const FEATURE_INSTRUCTIONS: [(WasmFeature, &[&str]); 3] = [
+    (WasmFeature::BulkMemory, &["memory.copy", "memory.fill"]),
+    (
+        WasmFeature::SignExt,
+        &["extend8_s", "extend16_s", "i64.extend32_s"],
+    ),
+    (WasmFeature::TailCall, &["return_call"]),
+];
+
+/// Whether a function or block type of the text format has several results
+#[allow(unused)]
+fn has_multi_value_results(wat: &str) -> bool {
+    wat.match_indices("(result ").any(|(index, pattern)| {
+        let results = &wat[index + pattern.len()..];
+        results[..results.find(')').unwrap()]
+            .split_whitespace()
+            .count()
+            > 1
+    })
+}
+
+/// Asserts the text format has no instruction of the disabled features
+#[allow(unused)]
+fn assert_no_disabled_feature_opcodes(wat: &str, enabled: &[WasmFeature]) {
+    for (feature, instructions) in FEATURE_INSTRUCTIONS {
+        if enabled.contains(&feature) {
+            continue;
+        }
+        for instruction in instructions {
+            assert!(
+                !wat.contains(instruction),
+                "{instruction} without {feature} in {enabled:?}"
+            );
+        }
+    }
+    if !enabled.contains(&WasmFeature::MultiValue) {
+        assert!(
+            !has_multi_value_results(wat),
+            "multiple results without multi-value in {enabled:?}"
+        );
+    }
+}
+
+/// Runs the contract with the features and returns its text format
+#[allow(unused)]
+fn run_wasm_features_contract(
+    case_name: &str,
+    contract: &str,
+    expected: &str,
+    chain_profile: ChainProfile,
+    wasm_features: &[WasmFeature],
+) -> String {
+    let mut runtime = TestRuntime::new("WasmFeaturesTest", case_name);
+    runtime.clear_testdata();
+    runtime.set_chain_profile(chain_profile);
+    runtime.set_wasm_features(wasm_features);
+    runtime.compile_test_yul(contract).unwrap();
+    runtime.deploy(&[]).unwrap();
+    runtime
+        .call(
+            &solidity_selector("test(uint256)"),
+            &encode_abi_parameters(&[("1000".to_string(), ParamType::Uint(256))]),
+        )
+        .unwrap();
+    runtime.assert_result(expected);
+    runtime.wat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wasm_features() {
+        assert_eq!(
+            parse_wasm_features("bulk-memory, tail-call,,bulk-memory").unwrap(),
+            vec![WasmFeature::BulkMemory, WasmFeature::TailCall]
+        );
+        assert_eq!(parse_wasm_features("").unwrap(), vec![]);
+        assert_eq!(
+            parse_wasm_features("multi-value,simd").unwrap_err(),
+            "Unknown wasm feature: simd"
+        );
+        assert_eq!("mvp".parse::<ChainProfile>().unwrap(), ChainProfile::Mvp);
+    }
+
+    #[test]
+    fn test_wasm_features_rejected_by_chain_profile() {
+        let mut runtime = TestRuntime::new(
+            "WasmFeaturesTest",
+            "target/test_wasm_features_rejected_by_chain_profile",
+        );
+        runtime.set_chain_profile(ChainProfile::Mvp);
+        runtime.set_wasm_features(&[WasmFeature::SignExt, WasmFeature::BulkMemory]);
+        let err = runtime
+            .compile_test_yul(WASM_FEATURES_CONTRACT)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "wasm feature sign-ext is not supported by the mvp chain profile"
+        );
+    }
+
+    #[test]
+    fn test_wasm_features_mvp_profile() {
+        for (contract, expected) in [
+            (WASM_FEATURES_CONTRACT, WASM_FEATURES_RESULT),
+            (WASM_FEATURE_OPCODES_CONTRACT, WASM_FEATURE_OPCODES_RESULT),
+        ] {
+            let wat = run_wasm_features_contract(
+                "target/test_wasm_features_mvp_profile",
+                contract,
+                expected,
+                ChainProfile::Mvp,
+                &[],
+            );
+            assert_no_disabled_feature_opcodes(&wat, &[]);
+            for instruction in ["v128", "trunc_sat"] {
+                assert!(!wat.contains(instruction), "{instruction} in mvp profile");
+            }
+        }
+    }
+
+    #[test]
+    fn test_wasm_feature_combinations() {
+        let features = [
+            WasmFeature::BulkMemory,
+            WasmFeature::MultiValue,
+            WasmFeature::SignExt,
+            WasmFeature::TailCall,
+        ];
+        for mask in 0..(1 << features.len()) {
+            let mut enabled = vec![WasmFeature::MutableGlobals];
+            enabled.extend(
+                features
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| mask & (1 << index) != 0)
+                    .map(|(_, feature)| *feature),
+            );
+            let wat = run_wasm_features_contract(
+                &format!("target/test_wasm_feature_combinations_{mask}"),
+                WASM_FEATURES_CONTRACT,
+                WASM_FEATURES_RESULT,
+                ChainProfile::Dtvm,
+                &enabled,
+            );
+            assert_no_disabled_feature_opcodes(&wat, &enabled);
+            if enabled.contains(&WasmFeature::BulkMemory) {
+                assert!(
+                    wat.contains("memory.copy"),
+                    "no memory.copy with {enabled:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_wasm_feature_opcodes() {
+        let multi_value = [WasmFeature::MutableGlobals, WasmFeature::MultiValue];
+        let wat = run_wasm_features_contract(
+            "target/test_wasm_feature_opcodes_multi_value",
+            WASM_FEATURE_OPCODES_CONTRACT,
+            WASM_FEATURE_OPCODES_RESULT,
+            ChainProfile::Dtvm,
+            &multi_value,
+        );
+        assert_no_disabled_feature_opcodes(&wat, &multi_value);
+        assert!(has_multi_value_results(&wat), "no multiple results");
+
+        // u256 results are tail called as multiple results
+        let tail_call = [
+            WasmFeature::MutableGlobals,
+            WasmFeature::MultiValue,
+            WasmFeature::TailCall,
+        ];
+        let wat = run_wasm_features_contract(
+            "target/test_wasm_feature_opcodes_tail_call",
+            WASM_FEATURE_OPCODES_CONTRACT,
+            WASM_FEATURE_OPCODES_RESULT,
+            ChainProfile::Dtvm,
+            &tail_call,
+        );
+        assert_no_disabled_feature_opcodes(&wat, &tail_call);
+        assert!(wat.contains("return_call"), "no return_call");
+    }
+
+    // Calls only become return_call once tailcallelim marked them tail, which
+    // is why the pipeline without an optimization level runs it
+    #[test]
+    fn test_wasm_feature_tail_call_needs_tailcallelim() {
+        for (pipeline, expect_return_call) in [
+            ("function(sroa,instcombine,simplifycfg)", false),
+            ("function(sroa,instcombine,simplifycfg,tailcallelim)", true),
+        ] {
+            let mut runtime = TestRuntime::new(
+                "WasmFeaturesTest",
+                "target/test_wasm_feature_tail_call_needs_tailcallelim",
+            );
+            runtime.clear_testdata();
+            runtime.set_wasm_features(&[
+                WasmFeature::MutableGlobals,
+                WasmFeature::MultiValue,
+                WasmFeature::TailCall,
+            ]);
+            runtime.set_llvm_pipeline(LlvmPipeline::Custom(pipeline.to_string()));
+            runtime
+                .compile_test_yul(WASM_FEATURE_OPCODES_CONTRACT)
+                .unwrap();
+            runtime.deploy(&[]).unwrap();
+            runtime
+                .call(
+                    &solidity_selector("test(uint256)"),
+                    &encode_abi_parameters(&[("1000".to_string(), ParamType::Uint(256))]),
+                )
+                .unwrap();
+            runtime.assert_result(WASM_FEATURE_OPCODES_RESULT);
+            assert_eq!(
+                runtime.wat().contains("return_call"),
+                expect_return_call,
+                "{pipeline}"
+            );
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+#[allow(unused)]
+use super::test_helper::solidity_selector;
+#[allow(unused)]
+use super::test_helper::TestRuntime;
+#[allow(unused)]
+use crate::yul2ir::ast::Object;
+#[allow(unused)]
+use crate::yul2ir::yul;
+
+#[allow(unused)]
+fn parse_code(code: &str) -> Object {
+    yul::ObjectParser::new()
+        .parse(&format!(
+            "object \"WidthInferenceTest\" {{ code {{ {code} }} }}"
+        ))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_helper::encode_abi_parameters;
+    use crate::yul2ir::config::Yul2IROptions;
+    use crate::yul2ir::context::Yul2IRContext;
+    use crate::yul2ir::width_inference::{FunctionWidths, ValueWidth};
+    use ethabi::ParamType;
+    use inkwell::context::Context;
+    use std::collections::HashMap;
+
+    // function name => inferred widths of the functions lowered with them
+    fn infer_widths(code: &str) -> HashMap<String, FunctionWidths> {
+        let object = parse_code(code);
+        let llvm_context = Context::create();
+        let opts = Yul2IROptions::test("WidthInferenceTest");
+        let context = Yul2IRContext::new_with_object(&llvm_context, &opts, object.clone());
+        context.infer_integer_widths(&object);
+        let integer_widths = context.integer_widths.borrow();
+        [
+            "fill",
+            "is_small",
+            "to_address",
+            "is_negative",
+            "twice",
+            "typed",
+            "abi_decode_t_address",
+        ]
+        .into_iter()
+        .filter_map(|name| {
+            let widths = integer_widths.get(&context.get_func_decl_qualifier_name_by_str(name));
+            Some((name.to_string(), widths?.clone()))
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_width_inference_over_call_graph() {
+        let widths = infer_widths(
+            r#"
+            let size := calldatasize()
+            sstore(0, fill(0x80, size))
+            sstore(1, is_small(calldataload(0)))
+            sstore(2, to_address(caller()))
+            sstore(3, is_negative(size))
+            sstore(4, add(twice(1), twice(size)))
+            sstore(5, typed(1))
+            sstore(6, abi_decode_t_address(4))
+            function fill(offset, count) -> end {
+                for { let i := 0 } lt(i, count) { i := add(i, 1) } {
+                    mstore(add(offset, shl(5, i)), i)
+                }
+                end := add(offset, count)
+            }
+            function is_small(value) -> result {
+                result := lt(value, 10)
+            }
+            function to_address(value) -> addr {
+                addr := and(value, sub(shl(160, 1), 1))
+            }
+            function is_negative(value) -> negative {
+                negative := slt(value, 0)
+            }
+            function twice(x) -> y {
+                y := add(x, x)
+            }
+            function typed(x:u32) -> y {
+                y := x
+            }
+            function abi_decode_t_address(offset) -> value {
+                value := calldataload(offset)
+                if iszero(eq(value, and(value, sub(shl(160, 1), 1)))) { revert(0, 0) }
+            }
+            "#,
+        );
+
+        let fill = &widths["fill"];
+        assert_eq!(fill.params, vec![ValueWidth::I32, ValueWidth::I32]);
+        assert_eq!(fill.returns, vec![ValueWidth::I64]);
+        // bounded by the loop condition
+        assert_eq!(fill.variables["i"], ValueWidth::I32);
+        assert_eq!(fill.address_params, vec![false, false]);
+
+        let is_small = &widths["is_small"];
+        assert_eq!(is_small.params, vec![ValueWidth::U256]);
+        assert_eq!(is_small.returns, vec![ValueWidth::Bool]);
+        assert_eq!(is_small.address_params, vec![false]);
+
+        // only read masked to an address
+        assert_eq!(widths["to_address"].address_params, vec![true]);
+
+        // signed comparisons widen their operands when lowered
+        assert_eq!(widths["is_negative"].params, vec![ValueWidth::I32]);
+
+        // named like a solc helper, inferred like any other function
+        let abi_decode = &widths["abi_decode_t_address"];
+        assert_eq!(abi_decode.params, vec![ValueWidth::I32]);
+        assert_eq!(abi_decode.returns, vec![ValueWidth::U256]);
+
+        // the arguments of both calls
+        let twice = &widths["twice"];
+        assert_eq!(twice.params, vec![ValueWidth::I32]);
+        assert_eq!(twice.returns, vec![ValueWidth::I64]);
+
+        // explicitly typed functions keep their types
+        assert!(!widths.contains_key("typed"));
+    }
+
+    #[test]
+    fn test_width_inference_loop_counter_assigned_in_body() {
+        let widths = infer_widths(
+            r#"
+            sstore(0, fill(0x80, calldatasize()))
+            function fill(offset, count) -> end {
+                for { let i := 0 } lt(i, count) { i := add(i, 1) } {
+                    i := add(i, calldataload(0))
+                }
+                end := offset
+            }
+            "#,
+        );
+        assert_eq!(widths["fill"].variables["i"], ValueWidth::U256);
+        assert_eq!(widths["fill"].returns, vec![ValueWidth::I32]);
+    }
+
+    #[test]
+    fn test_width_inference_narrowed_contract() {
+        let mut runtime = TestRuntime::new(
+            "WidthInferenceTest",
+            "target/test_width_inference_narrowed_contract",
+        );
+        runtime.clear_testdata();
+        runtime.set_enable_all_optimizers(true);
+        runtime
+            .compile_test_yul(
+                r#"
+                object "WidthInferenceTest" {
+                    code {
+                    }
+                    object "WidthInferenceTest_deployed" {
+                        code {
+                            let size := calldatasize()
+                            mstore(0x80, fill(0x200, 3))
+                            mstore(0xa0, add(mload(0x220), mload(0x240)))
+                            mstore(0xc0, widen(calldataload(4)))
+                            mstore(0xe0, widen(size))
+                            mstore(0x100, is_negative(0x80000000))
+                            mstore(0x120, eq(to_address(caller()), caller()))
+                            mstore(0x140, low_word_is_negative(0x80000000))
+                            mstore(0x160, slt(byte(31, size), 0))
+                            mstore(0x180, abi_decode_address(4))
+                            return(0x80, 0x120)
+
+                            function fill(offset, count) -> end {
+                                for { let i := 0 } lt(i, count) { i := add(i, 1) } {
+                                    mstore(add(offset, shl(5, i)), i)
+                                }
+                                end := add(offset, count)
+                            }
+                            function widen(x) -> y {
+                                y := add(x, 1)
+                            }
+                            function is_negative(value) -> negative {
+                                negative := slt(value, 0)
+                            }
+                            function to_address(value) -> addr {
+                                addr := and(value, sub(shl(160, 1), 1))
+                            }
+                            function low_word_is_negative(value) -> negative {
+                                negative := sgt(0, and(value, 0xffffffff))
+                            }
+                            function abi_decode_address(offset) -> value {
+                                value := calldataload(offset)
+                                if iszero(eq(value, and(value, sub(shl(160, 1), 1)))) { revert(0, 0) }
+                            }
+                        }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        runtime.deploy(&[]).unwrap();
+        runtime
+            .call(
+                &solidity_selector("test(uint256)"),
+                &encode_abi_parameters(&[(
+                    "1180591620717411303424".to_string(),
+                    ParamType::Uint(256),
+                )]),
+            )
+            .unwrap();
+        runtime.assert_result(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000203",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            // 2^70 + 1
+            "0000000000000000000000000000000000000000000000400000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000025",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            // the i32 0x80000000 is a positive u256
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            // 2^70
+            "0000000000000000000000000000000000000000000000400000000000000000",
+        ));
+    }
+}
@@ -0,0 +1,688 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Constant folding and dead code elimination on the Yul AST, run before
+//! lowering so less code reaches LLVM.
+//!
+//! - pure builtins with literal arguments are evaluated with EVM semantics
+//! - a `let` variable initialized with a constant and never assigned is
+//!   replaced by the constant
+//! - `keccak256` of memory written by constant `mstore`/`mstore8` earlier in
+//!   the same straight-line code is replaced by the hash
+//! - `if`, `switch` and `for` with a constant condition keep only the code
+//!   that can run, statements after a terminator are removed
+//! - `let` declarations of pure values that are never used are removed
+//!
+//! Yul doesn't allow shadowing and functions can't see the variables of
+//! the enclosing blocks, so variables are tracked by name per function.
+
+use crate::yul2ir::ast::{
+    Block, DecimalNumber, Expression, For, FunctionCall, FunctionDefinition, If, InnerSegment,
+    Literal, Object, Statement, Switch, SwitchOptions, VariableDeclaration,
+};
+use crate::yul2ir::yul_instruction::{parse_intrinsic_func_name, YulInstructionName};
+
+use ethereum_types::{U256, U512};
+use std::collections::{HashMap, HashSet};
+
+// only memory below this offset is tracked for folding keccak256
+const TRACKED_MEMORY_LIMIT: u64 = 0x10000;
+
+/// Returns the object with the code of it and its inner objects optimized
+pub fn optimize_object(object: &Object) -> Object {
+    Object {
+        name: object.name.clone(),
+        code: AstOptimizer::new(&object.code).block(&object.code),
+        inner_segments: object
+            .inner_segments
+            .iter()
+            .map(|segment| match segment {
+                InnerSegment::Object(inner) => {
+                    InnerSegment::Object(Box::new(optimize_object(inner)))
+                }
+                InnerSegment::Data(..) => segment.clone(),
+            })
+            .collect(),
+    }
+}
+
+pub fn optimize_function(func_def: &FunctionDefinition) -> FunctionDefinition {
+    FunctionDefinition {
+        name: func_def.name.clone(),
+        params: func_def.params.clone(),
+        body: AstOptimizer::new(&func_def.body).block(&func_def.body),
+        returns: func_def.returns.clone(),
+    }
+}
+
+/// Value of an untyped number or bool literal
+fn constant_value(expr: &Expression) -> Option<U256> {
+    match expr {
+        Expression::Literal(literal) => literal_value(literal),
+        _ => None,
+    }
+}
+
+fn literal_value(literal: &Literal) -> Option<U256> {
+    match literal {
+        Literal::DecimalNumberLiteral(dec, None) => U256::from_dec_str(&dec.dec).ok(),
+        Literal::HexNumberLiteral(hex, None) => U256::from_str_radix(&hex.hex, 16).ok(),
+        Literal::TrueLiteral(None) => Some(U256::one()),
+        Literal::FalseLiteral(None) => Some(U256::zero()),
+        _ => None,
+    }
+}
+
+fn constant_literal(value: U256) -> Expression {
+    Expression::Literal(Literal::DecimalNumberLiteral(
+        DecimalNumber {
+            dec: value.to_string(),
+        },
+        None,
+    ))
+}
+
+fn negate(value: U256) -> U256 {
+    (!value).overflowing_add(U256::one()).0
+}
+
+fn is_negative(value: U256) -> bool {
+    value.bit(255)
+}
+
+fn abs(value: U256) -> U256 {
+    if is_negative(value) {
+        negate(value)
+    } else {
+        value
+    }
+}
+
+fn signed_lt(a: U256, b: U256) -> bool {
+    match (is_negative(a), is_negative(b)) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => a < b,
+    }
+}
+
+fn u512_to_u256(value: U512) -> U256 {
+    let mut bytes = [0u8; 64];
+    value.to_big_endian(&mut bytes);
+    U256::from_big_endian(&bytes[32..])
+}
+
+/// Evaluates a pure builtin with EVM semantics
+fn evaluate(instr: &YulInstructionName, args: &[U256]) -> Option<U256> {
+    let from_bool = |value: bool| if value { U256::one() } else { U256::zero() };
+    let value = match (instr, args) {
+        (YulInstructionName::Add, [a, b]) => a.overflowing_add(*b).0,
+        (YulInstructionName::Sub, [a, b]) => a.overflowing_sub(*b).0,
+        (YulInstructionName::Mul, [a, b]) => a.overflowing_mul(*b).0,
+        (YulInstructionName::Div, [a, b]) => a.checked_div(*b).unwrap_or_default(),
+        (YulInstructionName::SDiv, [a, b]) => {
+            if b.is_zero() {
+                U256::zero()
+            } else {
+                let quotient = abs(*a) / abs(*b);
+                if is_negative(*a) != is_negative(*b) {
+                    negate(quotient)
+                } else {
+                    quotient
+                }
+            }
+        }
+        (YulInstructionName::Mod, [a, b]) => a.checked_rem(*b).unwrap_or_default(),
+        (YulInstructionName::SMod, [a, b]) => {
+            if b.is_zero() {
+                U256::zero()
+            } else {
+                let remainder = abs(*a) % abs(*b);
+                if is_negative(*a) {
+                    negate(remainder)
+                } else {
+                    remainder
+                }
+            }
+        }
+        (YulInstructionName::Exp, [a, b]) => a.overflowing_pow(*b).0,
+        (YulInstructionName::Not, [a]) => !*a,
+        (YulInstructionName::Lt, [a, b]) => from_bool(a < b),
+        (YulInstructionName::Gt, [a, b]) => from_bool(a > b),
+        (YulInstructionName::SLt, [a, b]) => from_bool(signed_lt(*a, *b)),
+        (YulInstructionName::SGt, [a, b]) => from_bool(signed_lt(*b, *a)),
+        (YulInstructionName::Eq, [a, b]) => from_bool(a == b),
+        (YulInstructionName::IsZero, [a]) => from_bool(a.is_zero()),
+        (YulInstructionName::And, [a, b]) => *a & *b,
+        (YulInstructionName::Or, [a, b]) => *a | *b,
+        (YulInstructionName::Xor, [a, b]) => *a ^ *b,
+        (YulInstructionName::Byte, [index, value]) => {
+            if *index < U256::from(32) {
+                (*value >> (8 * (31 - index.as_usize()))) & U256::from(0xff)
+            } else {
+                U256::zero()
+            }
+        }
+        (YulInstructionName::Shl, [shift, value]) => {
+            if *shift < U256::from(256) {
+                *value << shift.as_usize()
+            } else {
+                U256::zero()
+            }
+        }
+        (YulInstructionName::Shr, [shift, value]) => {
+            if *shift < U256::from(256) {
+                *value >> shift.as_usize()
+            } else {
+                U256::zero()
+            }
+        }
+        (YulInstructionName::Sar, [shift, value]) => {
+            let fill = if is_negative(*value) {
+                U256::MAX
+            } else {
+                U256::zero()
+            };
+            if *shift < U256::from(256) {
+                let shift = shift.as_usize();
+                (*value >> shift) | (fill & !(U256::MAX >> shift))
+            } else {
+                fill
+            }
+        }
+        (YulInstructionName::AddMod, [a, b, modulus]) => {
+            if modulus.is_zero() {
+                U256::zero()
+            } else {
+                u512_to_u256((U512::from(*a) + U512::from(*b)) % U512::from(*modulus))
+            }
+        }
+        (YulInstructionName::MulMod, [a, b, modulus]) => {
+            if modulus.is_zero() {
+                U256::zero()
+            } else {
+                u512_to_u256(a.full_mul(*b) % U512::from(*modulus))
+            }
+        }
+        (YulInstructionName::SignExtend, [byte_index, value]) => {
+            if *byte_index < U256::from(31) {
+                let sign_bit = byte_index.as_usize() * 8 + 7;
+                let mask = (U256::one() << (sign_bit + 1)) - U256::one();
+                if value.bit(sign_bit) {
+                    *value | !mask
+                } else {
+                    *value & mask
+                }
+            } else {
+                *value
+            }
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// No side effects, the expression can be removed if its value isn't used
+fn is_pure(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) | Expression::Literal(_) => true,
+        Expression::FunctionCall(call) => {
+            parse_intrinsic_func_name(&call.id.name).is_some_and(|instr| instr.is_pure())
+                && call.arguments.iter().all(is_pure)
+        }
+    }
+}
+
+/// The expression may write the EVM memory, user functions are assumed to
+fn writes_memory(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) | Expression::Literal(_) => false,
+        Expression::FunctionCall(call) => call_writes_memory(call),
+    }
+}
+
+fn call_writes_memory(call: &FunctionCall) -> bool {
+    parse_intrinsic_func_name(&call.id.name).is_none_or(|instr| instr.writes_memory())
+        || call.arguments.iter().any(writes_memory)
+}
+
+// statements after these in the same block never run
+fn is_terminator(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Leave | Statement::Break | Statement::Continue => true,
+        Statement::FunctionCall(call) => matches!(
+            parse_intrinsic_func_name(&call.id.name),
+            Some(
+                YulInstructionName::Return
+                    | YulInstructionName::Revert
+                    | YulInstructionName::Stop
+                    | YulInstructionName::Invalid
+            )
+        ),
+        _ => false,
+    }
+}
+
+fn collect_expr_names(expr: &Expression, names: &mut HashSet<String>) {
+    match expr {
+        Expression::Identifier(id) => {
+            names.insert(id.name.clone());
+        }
+        Expression::Literal(_) => {}
+        Expression::FunctionCall(call) => {
+            for arg in &call.arguments {
+                collect_expr_names(arg, names);
+            }
+        }
+    }
+}
+
+fn collect_block_names(block: &Block, names: &mut HashSet<String>) {
+    for stmt in &block.statements {
+        collect_names(stmt, names);
+    }
+}
+
+/// Collects the variables read or assigned by the statement, nested
+/// functions have their own variables
+fn collect_names(stmt: &Statement, names: &mut HashSet<String>) {
+    match stmt {
+        Statement::Assignment(assignment) => {
+            names.extend(assignment.identifiers.iter().map(|id| id.name.clone()));
+            collect_expr_names(&assignment.value, names);
+        }
+        Statement::VariableDeclaration(decl) => {
+            if let Some(value) = &decl.value {
+                collect_expr_names(value, names);
+            }
+        }
+        Statement::If(if_stmt) => {
+            collect_expr_names(&if_stmt.cond, names);
+            collect_block_names(&if_stmt.body, names);
+        }
+        Statement::For(for_stmt) => {
+            collect_block_names(&for_stmt.init_block, names);
+            collect_expr_names(&for_stmt.condition, names);
+            collect_block_names(&for_stmt.post_block, names);
+            collect_block_names(&for_stmt.execution_block, names);
+        }
+        Statement::Switch(switch) => {
+            collect_expr_names(&switch.condition, names);
+            match &switch.opt {
+                SwitchOptions::Cases(cases, default) => {
+                    for case in cases {
+                        collect_block_names(&case.body, names);
+                    }
+                    if let Some(default) = default {
+                        collect_block_names(&default.body, names);
+                    }
+                }
+                SwitchOptions::Default(default) => collect_block_names(&default.body, names),
+            }
+        }
+        Statement::Block(block) => collect_block_names(block, names),
+        Statement::FunctionCall(call) => {
+            for arg in &call.arguments {
+                collect_expr_names(arg, names);
+            }
+        }
+        Statement::FunctionDefinition(_)
+        | Statement::Leave
+        | Statement::Break
+        | Statement::Continue
+        | Statement::Comment => {}
+    }
+}
+
+fn collect_assigned_names(block: &Block, names: &mut HashSet<String>) {
+    for stmt in &block.statements {
+        match stmt {
+            Statement::Assignment(assignment) => {
+                names.extend(assignment.identifiers.iter().map(|id| id.name.clone()));
+            }
+            Statement::If(if_stmt) => collect_assigned_names(&if_stmt.body, names),
+            Statement::For(for_stmt) => {
+                collect_assigned_names(&for_stmt.init_block, names);
+                collect_assigned_names(&for_stmt.post_block, names);
+                collect_assigned_names(&for_stmt.execution_block, names);
+            }
+            Statement::Switch(switch) => match &switch.opt {
+                SwitchOptions::Cases(cases, default) => {
+                    for case in cases {
+                        collect_assigned_names(&case.body, names);
+                    }
+                    if let Some(default) = default {
+                        collect_assigned_names(&default.body, names);
+                    }
+                }
+                SwitchOptions::Default(default) => collect_assigned_names(&default.body, names),
+            },
+            Statement::Block(block) => collect_assigned_names(block, names),
+            _ => {}
+        }
+    }
+}
+
+/// Removes the declarations of pure values whose variables aren't used by
+/// the following statements of the block
+fn remove_unused_declarations(statements: Vec<Statement>) -> Vec<Statement> {
+    let mut used = HashSet::new();
+    let mut kept = vec![];
+    for stmt in statements.into_iter().rev() {
+        if let Statement::VariableDeclaration(decl) = &stmt {
+            let value_is_pure = match &decl.value {
+                Some(value) => is_pure(value),
+                None => true,
+            };
+            if value_is_pure
+                && decl
+                    .identifiers
+                    .iter()
+                    .all(|id| !used.contains(&id.identifier.name))
+            {
+                continue;
+            }
+        }
+        collect_names(&stmt, &mut used);
+        kept.push(stmt);
+    }
+    kept.reverse();
+    kept
+}
+
+struct AstOptimizer {
+    // variables assigned somewhere in the function, they are never constant
+    assigned: HashSet<String>,
+    // declared variables with a constant value
+    constants: HashMap<String, U256>,
+    // bytes of the EVM memory written by constant stores, offset => byte
+    memory: HashMap<u64, u8>,
+}
+
+impl AstOptimizer {
+    fn new(body: &Block) -> Self {
+        let mut assigned = HashSet::new();
+        collect_assigned_names(body, &mut assigned);
+        Self {
+            assigned,
+            constants: HashMap::new(),
+            memory: HashMap::new(),
+        }
+    }
+
+    /// Folds the expression, keccak256 is folded only if the expression
+    /// doesn't write memory before it is evaluated
+    fn expr(&self, expr: &Expression, fold_keccak: bool) -> Expression {
+        match expr {
+            Expression::Identifier(id) => match self.constants.get(&id.name) {
+                Some(value) => constant_literal(*value),
+                None => expr.clone(),
+            },
+            Expression::Literal(_) => expr.clone(),
+            Expression::FunctionCall(call) => {
+                let call = self.call(call, fold_keccak);
+                let Some(instr) = parse_intrinsic_func_name(&call.id.name) else {
+                    return Expression::FunctionCall(Box::new(call));
+                };
+                let args: Option<Vec<U256>> = call.arguments.iter().map(constant_value).collect();
+                let value = match (instr, args) {
+                    (YulInstructionName::Keccak256, Some(args)) if fold_keccak => match args[..] {
+                        [offset, length] => self.keccak256(offset, length),
+                        _ => None,
+                    },
+                    (instr, Some(args)) if instr.is_pure() => evaluate(&instr, &args),
+                    _ => None,
+                };
+                match value {
+                    Some(value) => constant_literal(value),
+                    None => Expression::FunctionCall(Box::new(call)),
+                }
+            }
+        }
+    }
+
+    fn call(&self, call: &FunctionCall, fold_keccak: bool) -> FunctionCall {
+        FunctionCall {
+            id: call.id.clone(),
+            arguments: call
+                .arguments
+                .iter()
+                .map(|arg| self.expr(arg, fold_keccak))
+                .collect(),
+        }
+    }
+
+    /// Hash of the memory range if all of its bytes are known
+    fn keccak256(&self, offset: U256, length: U256) -> Option<U256> {
+        if offset >= U256::from(TRACKED_MEMORY_LIMIT) || length > U256::from(TRACKED_MEMORY_LIMIT) {
+            return None;
+        }
+        let (offset, length) = (offset.as_u64(), length.as_u64());
+        let bytes = (offset..offset + length)
+            .map(|addr| self.memory.get(&addr).copied())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(U256::from_big_endian(
+            keccak_hash::keccak(&bytes).as_bytes(),
+        ))
+    }
+
+    /// Updates the known memory after a call statement
+    fn update_memory(&mut self, call: &FunctionCall) {
+        let args: Option<Vec<U256>> = call.arguments.iter().map(constant_value).collect();
+        let stored = match (call.id.name.as_str(), args.as_deref()) {
+            ("mstore", Some([offset, value])) => {
+                let mut bytes = vec![0u8; 32];
+                value.to_big_endian(&mut bytes);
+                Some((*offset, bytes))
+            }
+            ("mstore8", Some([offset, value])) => Some((*offset, vec![value.low_u32() as u8])),
+            _ => None,
+        };
+        match stored {
+            // above the tracked memory
+            Some((offset, _)) if offset >= U256::from(TRACKED_MEMORY_LIMIT) => {}
+            Some((offset, bytes))
+                if offset.as_u64() + bytes.len() as u64 <= TRACKED_MEMORY_LIMIT =>
+            {
+                for (addr, byte) in (offset.as_u64()..).zip(bytes) {
+                    self.memory.insert(addr, byte);
+                }
+            }
+            _ => {
+                if call_writes_memory(call) {
+                    self.memory.clear();
+                }
+            }
+        }
+    }
+
+    /// Optimizes the block, the variables it declares go out of scope
+    fn block(&mut self, block: &Block) -> Block {
+        let (statements, declared) = self.statements(&block.statements);
+        for name in declared {
+            self.constants.remove(&name);
+        }
+        Block {
+            statements: remove_unused_declarations(statements),
+        }
+    }
+
+    /// Optimizes the statements, returns them with the variables they declare
+    fn statements(&mut self, statements: &[Statement]) -> (Vec<Statement>, Vec<String>) {
+        let mut optimized = vec![];
+        let mut declared = vec![];
+        let mut terminated = false;
+        for stmt in statements {
+            if terminated {
+                // function definitions are visible in the whole block
+                if let Statement::FunctionDefinition(func_def) = stmt {
+                    optimized.push(Statement::FunctionDefinition(Box::new(optimize_function(
+                        func_def,
+                    ))));
+                }
+                continue;
+            }
+            terminated = is_terminator(stmt);
+            self.statement(stmt, &mut optimized, &mut declared);
+        }
+        (optimized, declared)
+    }
+
+    fn statement(
+        &mut self,
+        stmt: &Statement,
+        out: &mut Vec<Statement>,
+        declared: &mut Vec<String>,
+    ) {
+        match stmt {
+            Statement::VariableDeclaration(decl) => {
+                let value = decl
+                    .value
+                    .as_ref()
+                    .map(|value| self.expr(value, !writes_memory(value)));
+                for id in &decl.identifiers {
+                    self.constants.remove(&id.identifier.name);
+                    declared.push(id.identifier.name.clone());
+                }
+                if let ([id], Some(value)) = (decl.identifiers.as_slice(), &value) {
+                    if id.type_name.is_none() && !self.assigned.contains(&id.identifier.name) {
+                        if let Some(value) = constant_value(value) {
+                            self.constants.insert(id.identifier.name.clone(), value);
+                        }
+                    }
+                }
+                if decl.value.as_ref().is_some_and(writes_memory) {
+                    self.memory.clear();
+                }
+                out.push(Statement::VariableDeclaration(Box::new(
+                    VariableDeclaration {
+                        identifiers: decl.identifiers.clone(),
+                        value,
+                    },
+                )));
+            }
+            Statement::Assignment(assignment) => {
+                let mut assignment = assignment.clone();
+                let fold_keccak = !writes_memory(&assignment.value);
+                assignment.value = self.expr(&assignment.value, fold_keccak);
+                if !fold_keccak {
+                    self.memory.clear();
+                }
+                out.push(Statement::Assignment(assignment));
+            }
+            Statement::FunctionCall(call) => {
+                let call = self.call(call, !call_writes_memory(call));
+                self.update_memory(&call);
+                out.push(Statement::FunctionCall(Box::new(call)));
+            }
+            Statement::If(if_stmt) => {
+                let cond = self.expr(&if_stmt.cond, !writes_memory(&if_stmt.cond));
+                if writes_memory(&if_stmt.cond) {
+                    self.memory.clear();
+                }
+                match constant_value(&cond) {
+                    Some(value) if value.is_zero() => {}
+                    Some(_) => out.push(Statement::Block(Box::new(self.block(&if_stmt.body)))),
+                    None => out.push(Statement::If(Box::new(If {
+                        cond,
+                        body: self.block(&if_stmt.body),
+                    }))),
+                }
+                self.memory.clear();
+            }
+            Statement::Switch(switch) => {
+                let condition = self.expr(&switch.condition, !writes_memory(&switch.condition));
+                if writes_memory(&switch.condition) {
+                    self.memory.clear();
+                }
+                if let Some(selected) = constant_value(&condition)
+                    .and_then(|value| Self::selected_switch_body(switch, value))
+                {
+                    if let Some(body) = selected {
+                        out.push(Statement::Block(Box::new(self.block(body))));
+                    }
+                    self.memory.clear();
+                    return;
+                }
+                let opt = match &switch.opt {
+                    SwitchOptions::Cases(cases, default) => {
+                        let mut optimized_cases = vec![];
+                        for case in cases {
+                            let mut case = case.clone();
+                            let entry_memory = self.memory.clone();
+                            case.body = self.block(&case.body);
+                            self.memory = entry_memory;
+                            optimized_cases.push(case);
+                        }
+                        let default = default.as_ref().map(|default| {
+                            let mut default = default.clone();
+                            default.body = self.block(&default.body);
+                            default
+                        });
+                        SwitchOptions::Cases(optimized_cases, default)
+                    }
+                    SwitchOptions::Default(default) => {
+                        let mut default = default.clone();
+                        default.body = self.block(&default.body);
+                        SwitchOptions::Default(default)
+                    }
+                };
+                out.push(Statement::Switch(Box::new(Switch { condition, opt })));
+                self.memory.clear();
+            }
+            Statement::For(for_stmt) => {
+                let (init, init_declared) = self.statements(&for_stmt.init_block.statements);
+                // the condition and the blocks run again after the body
+                self.memory.clear();
+                let condition = self.expr(&for_stmt.condition, false);
+                if constant_value(&condition).is_some_and(|value| value.is_zero()) {
+                    // only the init block runs
+                    let statements = remove_unused_declarations(init);
+                    if !statements.is_empty() {
+                        out.push(Statement::Block(Box::new(Block { statements })));
+                    }
+                } else {
+                    let post_block = self.block(&for_stmt.post_block);
+                    self.memory.clear();
+                    let execution_block = self.block(&for_stmt.execution_block);
+                    out.push(Statement::For(Box::new(For {
+                        init_block: Block { statements: init },
+                        condition,
+                        post_block,
+                        execution_block,
+                    })));
+                }
+                for name in init_declared {
+                    self.constants.remove(&name);
+                }
+                self.memory.clear();
+            }
+            Statement::Block(block) => {
+                out.push(Statement::Block(Box::new(self.block(block))));
+                self.memory.clear();
+            }
+            Statement::FunctionDefinition(func_def) => out.push(Statement::FunctionDefinition(
+                Box::new(optimize_function(func_def)),
+            )),
+            Statement::Leave | Statement::Break | Statement::Continue | Statement::Comment => {
+                out.push(stmt.clone())
+            }
+        }
+    }
+
+    /// The body of the case the constant selects, Some(None) if no case runs
+    /// and None if the cases can't be compared at compile time
+    fn selected_switch_body(switch: &Switch, value: U256) -> Option<Option<&Block>> {
+        match &switch.opt {
+            SwitchOptions::Cases(cases, default) => {
+                let case_values: Option<Vec<U256>> =
+                    cases.iter().map(|case| literal_value(&case.case)).collect();
+                match case_values?.iter().position(|case| *case == value) {
+                    Some(index) => Some(Some(&cases[index].body)),
+                    None => Some(default.as_ref().map(|default| &default.body)),
+                }
+            }
+            SwitchOptions::Default(default) => Some(Some(&default.body)),
+        }
+    }
+}
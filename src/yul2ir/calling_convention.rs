@@ -0,0 +1,169 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Internal calling convention of the lowered Yul functions.
+//!
+//! With `CallingConvention::Value` Yul functions take u256 parameters as
+//! `i256` values and return tuples as LLVM structs, which the wasm32 backend
+//! legalizes into four i64 parameters per u256 and an sret pointer at every
+//! call site.
+//! With `CallingConvention::Pointer` u256/bytes32 parameters are passed by
+//! pointer into a frame allocated in the entry block of the caller, u256 and
+//! tuple returns are written through out-pointers appended to the parameters,
+//! and narrow integers and bytes32 pointers stay by value.
+
+use crate::yul2ir::config::CallingConvention;
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::yul_instruction::YulLowLevelFunctionType;
+
+use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType};
+use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue};
+
+impl<'a> Yul2IRContext<'a> {
+    /// Whether a parameter or return value of this type is passed by pointer
+    pub(crate) fn passed_by_pointer(&self, ty: BasicTypeEnum<'a>) -> bool {
+        self.opts.calling_convention == CallingConvention::Pointer
+            && (self.is_u256_type(&ty) || self.is_bytes32_type(&ty))
+    }
+
+    /// Whether the return values are written through out-pointers
+    pub(crate) fn returns_through_pointers(&self, returns: &[BasicTypeEnum<'a>]) -> bool {
+        if self.opts.calling_convention != CallingConvention::Pointer {
+            return false;
+        }
+        match returns {
+            [] => false,
+            [ret] => self.passed_by_pointer(*ret),
+            _ => true,
+        }
+    }
+
+    /// The LLVM function type of a Yul function under the pointer convention
+    pub(crate) fn pointer_convention_fn_type(
+        &self,
+        func_low_level_type: &YulLowLevelFunctionType<'a>,
+    ) -> FunctionType<'a> {
+        let ptr_ty = self.bytes32_pointer_type();
+        let mut params: Vec<BasicMetadataTypeEnum<'a>> = func_low_level_type
+            .params_inkwell_type
+            .iter()
+            .map(|ty| {
+                if self.passed_by_pointer(*ty) {
+                    ptr_ty.into()
+                } else {
+                    (*ty).into()
+                }
+            })
+            .collect();
+        let returns = &func_low_level_type.returns_inkwell_type;
+        if let [ret] = returns.as_slice() {
+            if !self.returns_through_pointers(returns) {
+                return ret.fn_type(&params, false);
+            }
+        }
+        params.extend(returns.iter().map(|_| BasicMetadataTypeEnum::from(ptr_ty)));
+        self.llvm_context.void_type().fn_type(&params, false)
+    }
+
+    /// Allocates a slot in the entry block of the current function, so calls
+    /// in loops reuse their frame instead of growing the stack
+    fn build_frame_alloca<T: BasicType<'a>>(
+        &self,
+        ty: T,
+        name: &str,
+    ) -> Result<PointerValue<'a>, ASTLoweringError> {
+        let entry_bb = self
+            .builder
+            .borrow()
+            .get_insert_block()
+            .and_then(|bb| bb.get_parent())
+            .and_then(|func| func.get_first_basic_block())
+            .ok_or_else(|| {
+                ASTLoweringError::BuilderError("call frame outside of a function".to_string())
+            })?;
+        let entry_builder = self.llvm_context.create_builder();
+        match entry_bb.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry_bb),
+        }
+        Ok(entry_builder.build_alloca(ty, name)?)
+    }
+
+    /// The argument passed for a parameter of the given type, u256/bytes32
+    /// values are stored in the frame of the caller
+    pub(crate) fn build_call_arg(
+        &self,
+        value: BasicValueEnum<'a>,
+        param_ty: BasicTypeEnum<'a>,
+    ) -> Result<BasicValueEnum<'a>, ASTLoweringError> {
+        if !self.passed_by_pointer(param_ty) {
+            return Ok(value);
+        }
+        let slot = self.build_frame_alloca(param_ty, "arg")?;
+        self.build_store(slot, value)?;
+        Ok(slot.into())
+    }
+
+    /// Calls a Yul function returning through out-pointers, the result is the
+    /// single return value or a struct of the returned tuple
+    pub(crate) fn build_out_pointer_call(
+        &self,
+        name: &str,
+        args: &[BasicValueEnum<'a>],
+        returns: &[BasicTypeEnum<'a>],
+    ) -> Result<BasicValueEnum<'a>, ASTLoweringError> {
+        let mut args = args.to_vec();
+        if let [ret] = returns {
+            let slot = self.build_frame_alloca(*ret, "ret")?;
+            args.push(slot.into());
+            self.build_void_call(name, &args)?;
+            return self.build_load(*ret, slot, "");
+        }
+        let tuple_ty = self.llvm_context.struct_type(returns, false);
+        let slot = self.build_frame_alloca(tuple_ty, "ret")?;
+        for index in 0..returns.len() {
+            let field =
+                self.builder
+                    .borrow_mut()
+                    .build_struct_gep(tuple_ty, slot, index as u32, "")?;
+            args.push(field.into());
+        }
+        self.build_void_call(name, &args)?;
+        self.build_load(tuple_ty, slot, "")
+    }
+
+    /// The value of the i-th parameter inside a Yul function
+    pub(crate) fn load_param(
+        &self,
+        func_value: FunctionValue<'a>,
+        index: usize,
+        param_ty: BasicTypeEnum<'a>,
+    ) -> Result<BasicValueEnum<'a>, ASTLoweringError> {
+        let param = func_value.get_nth_param(index as u32).unwrap();
+        if self.passed_by_pointer(param_ty) {
+            self.build_load(param_ty, param.into_pointer_value(), "")
+        } else {
+            Ok(param)
+        }
+    }
+
+    /// Stores the return values through the out-pointers after the
+    /// parameters and returns
+    pub(crate) fn build_out_pointer_return(
+        &self,
+        func_value: FunctionValue<'a>,
+        params_count: usize,
+        values: &[BasicValueEnum<'a>],
+    ) -> Result<(), ASTLoweringError> {
+        for (index, value) in values.iter().enumerate() {
+            let out = func_value
+                .get_nth_param((params_count + index) as u32)
+                .unwrap()
+                .into_pointer_value();
+            self.build_store(out, *value)?;
+        }
+        self.builder.borrow_mut().build_return(None)?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::yul2ir::gas_metering::is_gas_cost_name;
 use std::collections::HashMap;
 use std::fmt::Display;
 
@@ -50,6 +51,318 @@ impl Display for OptimizationLevel {
     }
 }
 
+/// How the lowered Yul functions pass u256/bytes32 values and return tuples
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CallingConvention {
+    /// u256 values are passed by value and tuples are returned as structs
+    #[default]
+    Value,
+    /// u256/bytes32 values are passed by pointer into a frame the caller owns
+    /// and returned through out-pointers, narrow integers stay by value
+    Pointer,
+}
+
+impl std::str::FromStr for CallingConvention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "value" => Ok(CallingConvention::Value),
+            "pointer" => Ok(CallingConvention::Pointer),
+            _ => Err(format!("Unknown calling convention: {}", s)),
+        }
+    }
+}
+
+impl Display for CallingConvention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallingConvention::Value => write!(f, "value"),
+            CallingConvention::Pointer => write!(f, "pointer"),
+        }
+    }
+}
+
+/// How the external dispatcher `switch` over the 4-byte selectors is lowered
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SelectorDispatch {
+    /// An LLVM `switch`, which the wasm backend lowers to compare chains for
+    /// sparse selectors
+    #[default]
+    Switch,
+    /// A binary search over the sorted selectors
+    BinarySearch,
+    /// A perfect hash of the selectors found at compile time into a
+    /// `br_table`, the binary search if none is found
+    PerfectHash,
+}
+
+impl std::str::FromStr for SelectorDispatch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "switch" => Ok(SelectorDispatch::Switch),
+            "binary-search" => Ok(SelectorDispatch::BinarySearch),
+            "perfect-hash" => Ok(SelectorDispatch::PerfectHash),
+            _ => Err(format!("Unknown selector dispatch: {}", s)),
+        }
+    }
+}
+
+impl Display for SelectorDispatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectorDispatch::Switch => write!(f, "switch"),
+            SelectorDispatch::BinarySearch => write!(f, "binary-search"),
+            SelectorDispatch::PerfectHash => write!(f, "perfect-hash"),
+        }
+    }
+}
+
+/// LLVM pass pipeline run on the module before code generation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum LlvmPipeline {
+    /// Picked from the optimization level and the optimizer options
+    #[default]
+    Default,
+    /// A new pass manager pipeline, like `function(sroa,instcombine)`
+    Custom(String),
+}
+
+impl std::str::FromStr for LlvmPipeline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("Empty LLVM pass pipeline".to_string()),
+            "default" => Ok(LlvmPipeline::Default),
+            pipeline => Ok(LlvmPipeline::Custom(pipeline.to_string())),
+        }
+    }
+}
+
+impl Display for LlvmPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LlvmPipeline::Default => write!(f, "default"),
+            LlvmPipeline::Custom(pipeline) => write!(f, "{pipeline}"),
+        }
+    }
+}
+
+/// File format of the optimization remarks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemarksFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for RemarksFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(RemarksFormat::Text),
+            "json" => Ok(RemarksFormat::Json),
+            _ => Err(format!("Unknown remarks format: {}", s)),
+        }
+    }
+}
+
+impl Display for RemarksFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemarksFormat::Text => write!(f, "text"),
+            RemarksFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Wasm proposals the generated code may use beyond the MVP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmFeature {
+    /// memory.copy/memory.fill for memcpy and memset
+    BulkMemory,
+    /// Yul functions return tuples as multiple results instead of through memory
+    MultiValue,
+    SignExt,
+    MutableGlobals,
+    /// return_call for calls in tail position
+    TailCall,
+}
+
+impl WasmFeature {
+    pub const ALL: [WasmFeature; 5] = [
+        WasmFeature::BulkMemory,
+        WasmFeature::MultiValue,
+        WasmFeature::SignExt,
+        WasmFeature::MutableGlobals,
+        WasmFeature::TailCall,
+    ];
+
+    /// Name of the feature in LLVM target features
+    pub fn llvm_name(&self) -> &'static str {
+        match self {
+            WasmFeature::BulkMemory => "bulk-memory",
+            WasmFeature::MultiValue => "multivalue",
+            WasmFeature::SignExt => "sign-ext",
+            WasmFeature::MutableGlobals => "mutable-globals",
+            WasmFeature::TailCall => "tail-call",
+        }
+    }
+}
+
+impl std::str::FromStr for WasmFeature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bulk-memory" => Ok(WasmFeature::BulkMemory),
+            "multi-value" => Ok(WasmFeature::MultiValue),
+            "sign-ext" => Ok(WasmFeature::SignExt),
+            "mutable-globals" => Ok(WasmFeature::MutableGlobals),
+            "tail-call" => Ok(WasmFeature::TailCall),
+            _ => Err(format!("Unknown wasm feature: {}", s)),
+        }
+    }
+}
+
+impl Display for WasmFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WasmFeature::BulkMemory => write!(f, "bulk-memory"),
+            WasmFeature::MultiValue => write!(f, "multi-value"),
+            WasmFeature::SignExt => write!(f, "sign-ext"),
+            WasmFeature::MutableGlobals => write!(f, "mutable-globals"),
+            WasmFeature::TailCall => write!(f, "tail-call"),
+        }
+    }
+}
+
+/// Parses a comma separated list of wasm features, empty for none
+pub fn parse_wasm_features(s: &str) -> Result<Vec<WasmFeature>, String> {
+    let mut features = vec![];
+    for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let feature = name.parse()?;
+        if !features.contains(&feature) {
+            features.push(feature);
+        }
+    }
+    Ok(features)
+}
+
+/// The chain runtime the contract is deployed to, decides which wasm
+/// features the contract may use
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ChainProfile {
+    /// DTVM, also the mock chain of the tests
+    #[default]
+    Dtvm,
+    /// Runtimes that only accept wasm MVP modules
+    Mvp,
+}
+
+impl ChainProfile {
+    pub fn supported_wasm_features(&self) -> &'static [WasmFeature] {
+        match self {
+            ChainProfile::Dtvm => &WasmFeature::ALL,
+            ChainProfile::Mvp => &[],
+        }
+    }
+
+    /// Whether the runtime implements the wasm simd128 proposal
+    pub fn supports_simd128(&self) -> bool {
+        match self {
+            ChainProfile::Dtvm => true,
+            ChainProfile::Mvp => false,
+        }
+    }
+
+    /// Features used when --wasm-features is not given, the ones the LLVM
+    /// generic wasm cpu enables
+    pub fn default_wasm_features(&self) -> Vec<WasmFeature> {
+        match self {
+            ChainProfile::Dtvm => vec![WasmFeature::SignExt, WasmFeature::MutableGlobals],
+            ChainProfile::Mvp => vec![],
+        }
+    }
+}
+
+impl std::str::FromStr for ChainProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dtvm" => Ok(ChainProfile::Dtvm),
+            "mvp" => Ok(ChainProfile::Mvp),
+            _ => Err(format!("Unknown chain profile: {}", s)),
+        }
+    }
+}
+
+impl Display for ChainProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainProfile::Dtvm => write!(f, "dtvm"),
+            ChainProfile::Mvp => write!(f, "mvp"),
+        }
+    }
+}
+
+/// Cost model of the compile-time gas metering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GasMetering {
+    /// Yul builtins cost about their static EVM gas
+    Evm,
+    /// LLVM instructions cost their wasm instruction weights
+    Wasm,
+}
+
+impl std::str::FromStr for GasMetering {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "evm" => Ok(GasMetering::Evm),
+            "wasm" => Ok(GasMetering::Wasm),
+            _ => Err(format!("Unknown gas metering: {}", s)),
+        }
+    }
+}
+
+impl Display for GasMetering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GasMetering::Evm => write!(f, "evm"),
+            GasMetering::Wasm => write!(f, "wasm"),
+        }
+    }
+}
+
+/// Parses a gas cost table of `name = cost` lines, `#` starts a comment
+pub fn parse_gas_cost_table(s: &str) -> Result<Vec<(String, u64)>, String> {
+    let mut costs = vec![];
+    for line in s.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((name, cost)) = line.split_once('=') else {
+            return Err(format!("Invalid gas cost line: {}", line));
+        };
+        let cost = cost
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid gas cost line: {}", line))?;
+        costs.push((name.trim().to_string(), cost));
+    }
+    Ok(costs)
+}
+
+/// Default largest EVM memory size of the safe memory mode, 16 MiB
+pub const DEFAULT_MAX_MEMORY_SIZE: u32 = 0x100_0000;
+
 /// Compile options.
 #[derive(Debug, Clone)]
 pub struct Yul2IROptions {
@@ -67,11 +380,53 @@ pub struct Yul2IROptions {
     pub ignore_unknown_linker_library: bool,
     pub minify_wasm_size: bool,
     pub disable_all_optimizers: bool,
+    /// LLVM pass pipeline, a named one or a new pass manager pipeline string
+    pub llvm_pipeline: LlvmPipeline,
     // Enabling this option will activate all possible optimizations
     // recommended for erc20 contracts
     pub enable_all_optimizers: bool,
     /// Enable storage load/store little endian
     pub enable_storage_load_store_little_endian: bool,
+    /// Shadow stack size in bytes, None to pick the default for the contract
+    pub stack_size: Option<u32>,
+    /// Check the stack pointer on function entry and revert on stack overflow
+    pub enable_stack_guard: bool,
+    /// Track static context and call depth at runtime, halt on state
+    /// modifications in static context and fail calls at the depth limit, for
+    /// hosts that don't enforce them themselves
+    pub enable_static_context_check: bool,
+    /// Internal calling convention of the lowered Yul functions
+    pub calling_convention: CallingConvention,
+    /// Lowering of the external dispatcher switch
+    pub selector_dispatch: SelectorDispatch,
+    /// Lower div/mod/sdiv/smod/addmod/mulmod/exp to the limb-based u256
+    /// functions of the stdlib, which don't need the host addmod/mulmod
+    pub enable_limb_u256_math: bool,
+    /// Lower bytes32 bitwise ops, comparisons and byte swaps to simd128
+    pub enable_simd128: bool,
+    /// Chain runtime the contract is compiled for
+    pub chain_profile: ChainProfile,
+    /// Wasm features enabled in code generation, must be supported by the
+    /// chain profile
+    pub wasm_features: Vec<WasmFeature>,
+    /// Check every EVM memory access against max_memory_size and revert
+    /// with Panic(0x41) beyond it, instead of truncating the offsets to 32 bits
+    pub enable_safe_memory: bool,
+    /// Largest EVM memory size in bytes with enable_safe_memory
+    pub max_memory_size: u32,
+    /// Charge the EVM memory expansion gas through the useGas host api
+    pub enable_memory_expansion_gas: bool,
+    /// Charge gas through the useGas host api at basic block boundaries
+    pub gas_metering: Option<GasMetering>,
+    /// Costs replacing the defaults of the gas metering, name => cost
+    pub gas_cost_overrides: Vec<(String, u64)>,
+    /// Write the static cost report of the entry points next to the build
+    pub enable_cost_report: bool,
+    /// Write the size attribution of the emitted wasm next to the build
+    pub enable_size_report: bool,
+    /// Write the rewrites applied or nearly applied and the LLVM remarks next
+    /// to the build in this format
+    pub remarks: Option<RemarksFormat>,
 }
 
 impl Default for Yul2IROptions {
@@ -90,8 +445,26 @@ impl Default for Yul2IROptions {
             ignore_unknown_linker_library: false,
             minify_wasm_size: false,
             disable_all_optimizers: false,
+            llvm_pipeline: LlvmPipeline::Default,
             enable_all_optimizers: false,
             enable_storage_load_store_little_endian: false,
+            stack_size: None,
+            enable_stack_guard: false,
+            enable_static_context_check: false,
+            calling_convention: CallingConvention::Value,
+            selector_dispatch: SelectorDispatch::Switch,
+            enable_limb_u256_math: false,
+            enable_simd128: false,
+            chain_profile: ChainProfile::Dtvm,
+            wasm_features: ChainProfile::Dtvm.default_wasm_features(),
+            enable_safe_memory: false,
+            max_memory_size: DEFAULT_MAX_MEMORY_SIZE,
+            enable_memory_expansion_gas: false,
+            gas_metering: None,
+            gas_cost_overrides: vec![],
+            enable_cost_report: false,
+            enable_size_report: false,
+            remarks: None,
         }
     }
 }
@@ -118,8 +491,26 @@ impl Yul2IROptions {
             generate_llvm_ir: false,
             minify_wasm_size: false,
             disable_all_optimizers: false,
+            llvm_pipeline: LlvmPipeline::Default,
             enable_all_optimizers: false,
             enable_storage_load_store_little_endian: true,
+            stack_size: None,
+            enable_stack_guard: false,
+            enable_static_context_check: false,
+            calling_convention: CallingConvention::Value,
+            selector_dispatch: SelectorDispatch::Switch,
+            enable_limb_u256_math: false,
+            enable_simd128: false,
+            chain_profile: ChainProfile::Dtvm,
+            wasm_features: ChainProfile::Dtvm.default_wasm_features(),
+            enable_safe_memory: false,
+            max_memory_size: DEFAULT_MAX_MEMORY_SIZE,
+            enable_memory_expansion_gas: false,
+            gas_metering: None,
+            gas_cost_overrides: vec![],
+            enable_cost_report: false,
+            enable_size_report: false,
+            remarks: None,
         }
     }
     #[allow(unused)]
@@ -149,8 +540,76 @@ impl Yul2IROptions {
             generate_llvm_ir: false,
             minify_wasm_size: false,
             disable_all_optimizers: false,
+            llvm_pipeline: LlvmPipeline::Default,
             enable_all_optimizers: false,
             enable_storage_load_store_little_endian: true,
+            stack_size: None,
+            enable_stack_guard: false,
+            enable_static_context_check: false,
+            calling_convention: CallingConvention::Value,
+            selector_dispatch: SelectorDispatch::Switch,
+            enable_limb_u256_math: false,
+            enable_simd128: false,
+            chain_profile: ChainProfile::Dtvm,
+            wasm_features: ChainProfile::Dtvm.default_wasm_features(),
+            enable_safe_memory: false,
+            max_memory_size: DEFAULT_MAX_MEMORY_SIZE,
+            enable_memory_expansion_gas: false,
+            gas_metering: None,
+            gas_cost_overrides: vec![],
+            enable_cost_report: false,
+            enable_size_report: false,
+            remarks: None,
+        }
+    }
+
+    /// Whether EVM memory expansion charges gas through useGas. The evm gas
+    /// metering only charges static costs, so it implies the memory gas
+    pub fn charges_memory_expansion_gas(&self) -> bool {
+        self.enable_memory_expansion_gas || self.gas_metering == Some(GasMetering::Evm)
+    }
+
+    pub fn has_wasm_feature(&self, feature: WasmFeature) -> bool {
+        self.wasm_features.contains(&feature)
+    }
+
+    /// Checks the wasm features and simd128 against the chain profile
+    pub fn validate_wasm_features(&self) -> Result<(), String> {
+        if self.enable_simd128 && !self.chain_profile.supports_simd128() {
+            return Err(format!(
+                "simd128 is not supported by the {} chain profile",
+                self.chain_profile
+            ));
+        }
+        let supported = self.chain_profile.supported_wasm_features();
+        match self
+            .wasm_features
+            .iter()
+            .find(|feature| !supported.contains(feature))
+        {
+            Some(feature) => Err(format!(
+                "wasm feature {} is not supported by the {} chain profile",
+                feature, self.chain_profile
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the names of the gas cost table against the gas metering
+    pub fn validate_gas_cost_table(&self) -> Result<(), String> {
+        let Some(gas_metering) = self.gas_metering else {
+            return Ok(());
+        };
+        match self
+            .gas_cost_overrides
+            .iter()
+            .find(|(name, _)| !is_gas_cost_name(gas_metering, name))
+        {
+            Some((name, _)) => Err(format!(
+                "unknown gas cost {} for the {} gas metering",
+                name, gas_metering
+            )),
+            None => Ok(()),
         }
     }
 
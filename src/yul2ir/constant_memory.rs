@@ -0,0 +1,136 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Constant contents of the EVM memory within a basic block.
+//!
+//! `mstore`/`mstore8` with constant offsets and values record the bytes they
+//! write. `keccak256(offset, length)` over known bytes only is hashed at
+//! compile time and lowered to a bytes32 global, e.g. the ERC-7201 namespace
+//! slots or the hash of an `mstore`d string literal.
+//! Other memory writes and calls of Yul functions forget the known bytes,
+//! as does leaving the basic block they were recorded in.
+
+use crate::yul2ir::ast::{Expression, Literal};
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::yul_instruction::{YulInstructionName, YulLowLevelValue, YulLowLevelValueType};
+
+use ethereum_types::U256;
+use inkwell::basic_block::BasicBlock;
+use std::collections::HashMap;
+
+// only memory below this offset is tracked
+const TRACKED_MEMORY_LIMIT: u64 = 0x10000;
+
+#[derive(Debug, Default)]
+pub struct ConstantMemory<'ctx> {
+    /// The basic block the bytes were written in
+    block: Option<BasicBlock<'ctx>>,
+    /// offset => byte
+    bytes: HashMap<u64, u8>,
+}
+
+impl<'a> Yul2IRContext<'a> {
+    pub(crate) fn forget_constant_memory(&self) {
+        self.constant_memory.borrow_mut().bytes.clear();
+    }
+
+    /// The 32 bytes `mstore` writes for a constant value
+    fn constant_store_bytes(&self, value: &Expression) -> Option<Vec<u8>> {
+        if let Expression::Literal(Literal::StringLiteral(string, _)) = value {
+            // left aligned like string_literal
+            let mut bytes = string.str.as_bytes().to_vec();
+            if bytes.len() > 32 {
+                return None;
+            }
+            bytes.resize(32, 0);
+            return Some(bytes);
+        }
+        let value = self.fetch_not_string_literal_constant(value)?;
+        let mut bytes = vec![0u8; 32];
+        value.to_big_endian(&mut bytes);
+        Some(bytes)
+    }
+
+    /// Records the bytes written by a lowered instruction, or forgets the
+    /// known bytes if it writes memory that isn't constant
+    pub(crate) fn track_constant_memory(&self, instr: &YulInstructionName, args: &[Expression]) {
+        if self.opts.disable_all_optimizers || !instr.writes_memory() {
+            return;
+        }
+        let offset = args
+            .first()
+            .and_then(|offset| self.fetch_not_string_literal_constant(offset));
+        let stored = match (instr, offset, args.get(1)) {
+            (YulInstructionName::MStore, Some(offset), Some(value)) => self
+                .constant_store_bytes(value)
+                .map(|bytes| (offset, bytes)),
+            (YulInstructionName::MStore8, Some(offset), Some(value)) => self
+                .fetch_not_string_literal_constant(value)
+                .map(|value| (offset, vec![value.low_u32() as u8])),
+            _ => None,
+        };
+        let current_block = self.builder.borrow().get_insert_block();
+        let mut memory = self.constant_memory.borrow_mut();
+        if memory.block != current_block {
+            memory.block = current_block;
+            memory.bytes.clear();
+        }
+        match stored {
+            // above the tracked memory
+            Some((offset, _)) if offset >= U256::from(TRACKED_MEMORY_LIMIT) => {}
+            Some((offset, bytes))
+                if offset.as_u64() + bytes.len() as u64 <= TRACKED_MEMORY_LIMIT =>
+            {
+                for (addr, byte) in (offset.as_u64()..).zip(bytes) {
+                    memory.bytes.insert(addr, byte);
+                }
+            }
+            _ => memory.bytes.clear(),
+        }
+    }
+
+    /// Hashes `keccak256(offset, length)` at compile time if the range is
+    /// known in the current basic block
+    pub(crate) fn try_constant_memory_keccak256(
+        &self,
+        instr: &YulInstructionName,
+        args: &[Expression],
+    ) -> Option<YulLowLevelValue<'a>> {
+        if self.opts.disable_all_optimizers || !matches!(instr, YulInstructionName::Keccak256) {
+            return None;
+        }
+        let (Some(offset), Some(length)) = (
+            args.first()
+                .and_then(|arg| self.fetch_not_string_literal_constant(arg)),
+            args.get(1)
+                .and_then(|arg| self.fetch_not_string_literal_constant(arg)),
+        ) else {
+            return None;
+        };
+        if offset >= U256::from(TRACKED_MEMORY_LIMIT) || length > U256::from(TRACKED_MEMORY_LIMIT) {
+            return None;
+        }
+        let bytes = {
+            let memory = self.constant_memory.borrow();
+            if memory.block != self.builder.borrow().get_insert_block() {
+                return None;
+            }
+            let (offset, length) = (offset.as_u64(), length.as_u64());
+            (offset..offset + length)
+                .map(|addr| memory.bytes.get(&addr).copied())
+                .collect::<Option<Vec<u8>>>()?
+        };
+
+        let hash = keccak_hash::keccak(&bytes);
+        let global_constant_name =
+            format!("global_constant_keccak256_{}", hex::encode(hash.as_bytes()));
+        let existing = self.llvm_module.borrow().get_global(&global_constant_name);
+        let global_value = existing.unwrap_or_else(|| {
+            self.add_global_constant_bytes32(hash.as_bytes(), &global_constant_name)
+        });
+        Some(YulLowLevelValue {
+            value_type: YulLowLevelValueType::Bytes32Pointer,
+            value: global_value.as_pointer_value().into(),
+        })
+    }
+}
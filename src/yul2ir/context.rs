@@ -2,18 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::yul2ir::ast::{FunctionDeclaration, FunctionDefinition, Object, TypeName};
-use crate::yul2ir::config::Yul2IROptions;
+use crate::yul2ir::config::{LlvmPipeline, OptimizationLevel, WasmFeature, Yul2IROptions};
+use crate::yul2ir::constant_memory::ConstantMemory;
+use crate::yul2ir::cost_report::CostReport;
 use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::pattern::RuleMatch;
+use crate::yul2ir::remarks::{LlvmRemarkCollector, Remark};
+use crate::yul2ir::size_report::SizeReport;
+use crate::yul2ir::stack_usage::{analyze_stack_usage, StackUsageReport, STACK_GUARD_RESERVE};
 use crate::yul2ir::stdlib::load_stdlib;
 use crate::yul2ir::var_scope::VarScope;
+use crate::yul2ir::wasm::WasmStages;
+use crate::yul2ir::width_inference::FunctionWidths;
 use ethereum_types::U256;
 use indexmap::IndexMap;
 use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
+use inkwell::intrinsics::Intrinsic;
 use inkwell::module::Module;
-use inkwell::passes::{PassManager, PassManagerBuilder};
-use inkwell::targets::{CodeModel, FileType, RelocMode, TargetTriple};
+use inkwell::passes::PassBuilderOptions;
+use inkwell::targets::{CodeModel, FileType, RelocMode, TargetMachine, TargetTriple};
 use inkwell::types::{
     ArrayType, BasicType, BasicTypeEnum, IntType, PointerType, StringRadix, VoidType,
 };
@@ -74,6 +83,17 @@ pub struct Yul2IRContext<'ctx> {
     pub functions_mapping: RefCell<HashMap<String, Rc<FunctionValue<'ctx>>>>,
     pub current_func_decls: RefCell<IndexMap<String, FunctionDeclaration>>,
     pub revert_zero_functions: RefCell<HashSet<String>>,
+    /// Functions equal to an earlier function up to renaming of their
+    /// variables, qualified name => qualified name of that earlier function
+    pub duplicate_functions: RefCell<HashMap<String, String>>,
+    /// Functions matched by a pattern rule, qualified name => match
+    pub pattern_rule_matches: RefCell<HashMap<String, RuleMatch>>,
+    /// Result of the mapping slot idiom being lowered, used for its keccak256(0, 0x40)
+    pub mapping_slot_hash: RefCell<Option<PointerValue<'ctx>>>,
+    /// EVM memory written by constant stores in the current basic block
+    pub constant_memory: RefCell<ConstantMemory<'ctx>>,
+    /// Inferred integer widths of the functions lowered with them, qualified name => widths
+    pub integer_widths: RefCell<HashMap<String, FunctionWidths>>,
 
     // yul function name => yul low level function type
     pub yul_func_infer_types: RefCell<HashMap<String, YulLowLevelFunctionType<'ctx>>>,
@@ -94,12 +114,39 @@ pub struct Yul2IRContext<'ctx> {
     pub global_bytes_values: RefCell<HashMap<String, GlobalValue<'ctx>>>,
 
     pub default_ret_type: YulLowLevelValueType,
+
+    /// Shadow stack usage of the optimized module, set by emit
+    pub stack_usage_report: RefCell<Option<StackUsageReport>>,
+    /// Static costs of the entry points, set by emit with enable_cost_report
+    pub cost_report: RefCell<Option<CostReport>>,
+    /// Size attribution of the emitted wasm, set by emit with enable_size_report
+    pub size_report: RefCell<Option<SizeReport>>,
+    /// Rewrites applied or nearly applied by the lowering, then the LLVM
+    /// remarks after emit, with remarks enabled
+    pub remarks: RefCell<Vec<Remark>>,
+    /// Variables assigned only the selector load, by qualified function name
+    pub selector_variables: RefCell<HashSet<(String, String)>>,
 }
 
 static LLVM_INIT: OnceCell<()> = OnceCell::new();
 
 pub const FUNCTION_RETURN_VALUE_NOT_FOUND_MSG: &str = "Function return value is not found";
 
+/// Function passes of the default pipeline without an optimization level or
+/// without inlining. They are the passes the legacy pass manager added in
+/// the same order, so debug builds keep their code after the move to the new
+/// pass manager. tailcallelim marks the calls in tail position that can
+/// become return_call
+const CLEANUP_FUNCTION_PASSES: &str = "loop(loop-rotate),loop-vectorize,\
+    loop(loop-idiom,loop-deletion,loop-reroll),adce,mem2reg,mldst-motion,reg2mem,memcpyopt,adce,\
+    loop-mssa(licm),gvn,newgvn,sccp,instcombine,instsimplify,jump-threading,simplifycfg,\
+    tailcallelim,reassociate,early-cse,early-cse<memssa>,correlated-propagation,dse,\
+    loop(indvars),lower-expect";
+
+/// Loop unrolling the legacy pass manager ran first unless the wasm size is
+/// minified
+const UNROLL_FUNCTION_PASSES: &str = "loop(loop-unroll-and-jam),loop-unroll";
+
 impl<'ctx> Yul2IRContext<'ctx> {
     pub fn new_with_object(
         context: &'ctx Context,
@@ -136,6 +183,11 @@ impl<'ctx> Yul2IRContext<'ctx> {
             functions_mapping: RefCell::new(Default::default()),
             current_func_decls: RefCell::new(Default::default()),
             revert_zero_functions: RefCell::new(Default::default()),
+            duplicate_functions: RefCell::new(Default::default()),
+            pattern_rule_matches: RefCell::new(Default::default()),
+            mapping_slot_hash: RefCell::new(None),
+            constant_memory: RefCell::new(Default::default()),
+            integer_widths: RefCell::new(Default::default()),
             yul_func_infer_types: RefCell::new(Default::default()),
             iden_id_gen: RefCell::new(0),
             exported_func_names: RefCell::new(vec![]),
@@ -145,88 +197,78 @@ impl<'ctx> Yul2IRContext<'ctx> {
             variable_usage: RefCell::new(HashMap::new()),
             // memptr_global: RefCell::new(None),
             default_ret_type: YulLowLevelValueType::U256,
+            stack_usage_report: RefCell::new(None),
+            cost_report: RefCell::new(None),
+            size_report: RefCell::new(None),
+            remarks: RefCell::new(vec![]),
+            selector_variables: RefCell::new(HashSet::new()),
         }
     }
 
     pub fn emit(&mut self, output_basename: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        // the LLVM passes and the code generation report remarks
+        let llvm_context = self.llvm_context;
+        let remark_collector =
+            LlvmRemarkCollector::install(llvm_context, self.opts.remarks.is_some());
         self.emit_code(output_basename)?;
-        self.link_code()
-    }
-
-    fn run_llvm_passes(&self) {
-        let opt_level = self.opts.opt_level.to_inkwell_optimization_level();
-        let pass_manager_builder = PassManagerBuilder::create();
-        pass_manager_builder.set_optimization_level(opt_level);
-        let mpm = PassManager::create(());
-
-        if self.opts.minify_wasm_size {
-            // disable inliner
-            pass_manager_builder.set_inliner_with_threshold(0);
-        } else {
-            // Inliner passes
-            if !self.opts.no_inline {
-                pass_manager_builder.set_inliner_with_threshold(512);
-                mpm.add_always_inliner_pass();
+        let stages = self.link_wasm_stages()?;
+        if self.opts.enable_size_report {
+            self.report_sizes(&stages, output_basename)?;
+        }
+        if let Some(remark_collector) = remark_collector {
+            self.report_remarks(remark_collector.take_remarks(), output_basename)?;
+        }
+        Ok(stages.into_output())
+    }
+
+    /// New pass manager pipeline of the options
+    fn llvm_pass_pipeline(&self) -> String {
+        let level = match self.opts.opt_level {
+            OptimizationLevel::None => "O0",
+            OptimizationLevel::Less => "O1",
+            OptimizationLevel::Default => "O2",
+            OptimizationLevel::Aggressive => "O3",
+        };
+        match &self.opts.llvm_pipeline {
+            LlvmPipeline::Custom(pipeline) => pipeline.clone(),
+            LlvmPipeline::Default if self.opts.disable_all_optimizers => {
+                // if you want to see the original llvm ir, disable all optimizers
+                format!("default<{level}>")
             }
-            if !self.opts.disable_all_optimizers {
-                // Unroll and jam loops to improve performance
-                mpm.add_loop_unroll_and_jam_pass();
-                // Unroll loops to reduce loop overhead and enable other optimizations
-                mpm.add_loop_unroll_pass();
+            LlvmPipeline::Default if self.opts.minify_wasm_size => "default<Oz>".to_string(),
+            LlvmPipeline::Default
+                if self.opts.no_inline
+                    || matches!(self.opts.opt_level, OptimizationLevel::None) =>
+            {
+                // the default pipelines inline at O1 and above, debug builds
+                // only clean up the lowered functions
+                let inliner = if self.opts.no_inline {
+                    ""
+                } else {
+                    "always-inline,"
+                };
+                format!(
+                    "{inliner}function({UNROLL_FUNCTION_PASSES},{CLEANUP_FUNCTION_PASSES}),\
+                    mergefunc,strip-dead-prototypes,function(scalarizer)"
+                )
             }
+            LlvmPipeline::Default => format!("default<{level}>"),
         }
+    }
 
-        if !self.opts.disable_all_optimizers {
-            // Loop optimization passes
-
-            // Rotate loops to make them more amenable to optimizations
-            mpm.add_loop_rotate_pass();
-            // Transform loops into vector operations where possible
-            mpm.add_loop_vectorize_pass();
-            // Replace loop patterns with library calls or simpler forms
-            mpm.add_loop_idiom_pass();
-            // Remove loops with no side effects
-            mpm.add_loop_deletion_pass();
-            // Reroll loops to reduce code size when beneficial
-            mpm.add_loop_reroll_pass();
-
-            // if you want to see the original llvm ir, disable all optimizers
-
-            // Additional aggressive optimizations for release mode
-            mpm.add_aggressive_dce_pass();
-
-            // Memory optimization passes
-            mpm.add_promote_memory_to_register_pass();
-            mpm.add_merged_load_store_motion_pass();
-            mpm.add_demote_memory_to_register_pass();
-            mpm.add_memcpy_optimize_pass();
-            mpm.add_aggressive_dce_pass();
-            mpm.add_licm_pass();
-            mpm.add_gvn_pass();
-            mpm.add_new_gvn_pass();
-            mpm.add_sccp_pass();
-            mpm.add_instruction_combining_pass();
-            mpm.add_instruction_simplify_pass();
-            mpm.add_jump_threading_pass();
-            mpm.add_cfg_simplification_pass();
-            mpm.add_tail_call_elimination_pass();
-            mpm.add_reassociate_pass();
-            mpm.add_early_cse_pass();
-            mpm.add_early_cse_mem_ssa_pass();
-            mpm.add_correlated_value_propagation_pass();
-            mpm.add_dead_store_elimination_pass();
-            mpm.add_ind_var_simplify_pass();
-            mpm.add_lower_expect_intrinsic_pass();
-            mpm.add_merge_functions_pass();
-            mpm.add_strip_dead_prototypes_pass();
-
-            mpm.add_scalarizer_pass();
-        }
-
-        // Populate the module pass manager with the passes configured in the pass manager builder
-        pass_manager_builder.populate_module_pass_manager(&mpm);
+    fn run_llvm_passes(&self) -> Result<(), String> {
+        let pipeline = self.llvm_pass_pipeline();
+        let options = PassBuilderOptions::create();
+        // vectorized code is scalarized again without simd128
+        options.set_loop_vectorization(self.opts.enable_simd128);
+        options.set_slp_vectorization(self.opts.enable_simd128);
+        options.set_merge_functions(!self.opts.disable_all_optimizers);
 
-        if !(mpm.run_on(&self.llvm_module.borrow_mut())) {
+        let result =
+            self.llvm_module
+                .borrow()
+                .run_passes(&pipeline, &self.create_target_machine(), options);
+        if let Err(err) = result {
             if self.opts.verbose {
                 let output_dir = &self.opts.output_dir;
                 // Output the LLVM IR file in case of an error
@@ -237,18 +279,31 @@ impl<'ctx> Yul2IRContext<'ctx> {
                     .print_to_file(ll_filepath)
                     .unwrap();
             }
-            panic!("Failed to run llvm passes: ");
+            return Err(format!("Failed to run llvm passes {pipeline}: {err}"));
         }
+        Ok(())
     }
 
     fn emit_code(&mut self, output_basename: &str) -> Result<String, Box<dyn Error>> {
+        self.opts.validate_wasm_features()?;
+        self.opts.validate_gas_cost_table()?;
         if let Err(e) = self.transform() {
             return Err(format!("Transform error: {}", e).into());
         }
 
         self.transform().unwrap();
+        self.apply_wasm_features()
+            .map_err(|e| format!("Wasm features error: {}", e))?;
+        self.build_memory_expansion_hook()
+            .map_err(|e| format!("Memory expansion hook error: {}", e))?;
         // Run LLVM pass on the LLVM module.
-        self.run_llvm_passes();
+        self.run_llvm_passes()?;
+        self.apply_gas_metering()
+            .map_err(|e| format!("Gas metering error: {}", e))?;
+        self.check_stack_usage();
+        if self.opts.enable_cost_report {
+            self.report_costs(output_basename)?;
+        }
         if self.opts.verbose {
             let output_dir = &self.opts.output_dir;
             let ll_filepath = &format!(
@@ -278,6 +333,36 @@ impl<'ctx> Yul2IRContext<'ctx> {
         Ok(self.llvm_module.borrow().print_to_string().to_string())
     }
 
+    fn check_stack_usage(&self) {
+        let target_machine = self.create_target_machine();
+        let report = analyze_stack_usage(
+            &self.llvm_module.borrow(),
+            &target_machine.get_target_data(),
+        );
+        if self.opts.verbose {
+            print!("{report}");
+        }
+        let stack_size = self.stack_size();
+        if report.worst_case_depth > stack_size as u64 {
+            eprintln!(
+                "warning: worst case stack depth {} bytes exceeds the stack size {} bytes",
+                report.worst_case_depth, stack_size
+            );
+        }
+        if !report.recursive_functions.is_empty() && !self.opts.enable_stack_guard {
+            eprintln!(
+                "warning: the stack depth of recursive functions is unbounded, use --stack-guard to revert on stack overflow"
+            );
+        }
+        *self.stack_usage_report.borrow_mut() = Some(report);
+    }
+
+    /// Shadow stack size of the linked wasm
+    pub fn stack_size(&self) -> u32 {
+        let has_sub_contract = has_sub_contract(self.yul_ast.as_ref().unwrap());
+        crate::yul2ir::wasm::stack_size(self.opts, has_sub_contract)
+    }
+
     fn llvm_target_name(&self) -> &'static str {
         "wasm32"
     }
@@ -286,24 +371,48 @@ impl<'ctx> Yul2IRContext<'ctx> {
         TargetTriple::create("wasm32-unknown-unknown-wasm")
     }
 
-    /// LLVM Target triple
-    fn llvm_features(&self) -> &'static str {
-        ""
+    /// LLVM target features, wasm features that aren't enabled are disabled
+    /// explicitly so the defaults of the generic cpu don't apply
+    pub(crate) fn llvm_features(&self) -> String {
+        let mut features: Vec<String> = WasmFeature::ALL
+            .iter()
+            .map(|feature| {
+                let sign = if self.opts.has_wasm_feature(*feature) {
+                    '+'
+                } else {
+                    '-'
+                };
+                format!("{sign}{}", feature.llvm_name())
+            })
+            .collect();
+        if self.opts.enable_simd128 {
+            features.push("+simd128".to_string());
+        }
+        features.join(",")
     }
 
-    pub fn link_code(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+    fn create_target_machine(&self) -> TargetMachine {
         let target = inkwell::targets::Target::from_name(self.llvm_target_name()).unwrap();
         let level = inkwell::OptimizationLevel::Default;
-        let target_machine = target
+        target
             .create_target_machine(
                 &self.llvm_target_triple(),
                 "",
-                self.llvm_features(),
+                &self.llvm_features(),
                 level,
                 RelocMode::Default,
                 CodeModel::Default,
             )
-            .unwrap();
+            .unwrap()
+    }
+
+    pub fn link_code(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(self.link_wasm_stages()?.into_output())
+    }
+
+    /// Links the module and keeps the wasm of each optimization step
+    pub fn link_wasm_stages(&mut self) -> Result<WasmStages, Box<dyn Error>> {
+        let target_machine = self.create_target_machine();
 
         let has_sub_contract = has_sub_contract(&self.yul_ast.clone().unwrap());
 
@@ -311,15 +420,16 @@ impl<'ctx> Yul2IRContext<'ctx> {
             Ok(out) => {
                 let slice = out.as_slice();
                 let export_names = self.exported_func_names.borrow().clone();
-                let bs = crate::yul2ir::wasm::link(
+                let stages = crate::yul2ir::wasm::link(
                     slice,
                     "wasm_module",
                     &export_names,
                     self.opts,
                     has_sub_contract,
                 );
+                self.report_duplicate_functions(stages.output());
 
-                Ok(bs)
+                Ok(stages)
             }
             Err(s) => Err(s.to_string().into()),
         }
@@ -375,6 +485,39 @@ impl<'ctx> Yul2IRContext<'ctx> {
         }
     }
 
+    /// Reverts with Error("stack overflow") when the current function's frame
+    /// leaves less than STACK_GUARD_RESERVE bytes of stack, or doesn't fit at all
+    pub fn build_stack_guard(&self) -> Result<(), ASTLoweringError> {
+        let stacksave = Intrinsic::find("llvm.stacksave")
+            .and_then(|intrinsic| intrinsic.get_declaration(&self.llvm_module.borrow(), &[]))
+            .unwrap();
+        let stack_pointer = self
+            .builder
+            .borrow_mut()
+            .build_call(stacksave, &[], "stack_pointer")?
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_pointer_value();
+        let stack_pointer = self.builder.borrow_mut().build_ptr_to_int(
+            stack_pointer,
+            self.i32_type(),
+            "stack_pointer_int",
+        )?;
+        self.build_void_call(
+            "wrapper_check_stack_pointer",
+            &[
+                stack_pointer.into(),
+                self.i32_type()
+                    .const_int(STACK_GUARD_RESERVE as u64, false)
+                    .into(),
+                self.i32_type()
+                    .const_int(self.stack_size() as u64, false)
+                    .into(),
+            ],
+        )
+    }
+
     #[allow(unused)]
     pub fn keep_instruction(&self, value: BasicValueEnum<'ctx>) {
         // create a virtual use, ensure the instruction is used
@@ -660,6 +803,10 @@ impl<'ctx> Yul2IRContext<'ctx> {
     pub fn matches_address_mask_pattern(&self, expr: &Expression) -> Option<Expression> {
         // Pattern: and(addr, sub(shl(160, 1), 1))
         if let Some(and_args) = self.matches_yul_instruction(expr, "and", 2) {
+            // the mask is a literal after constant folding
+            if self.matches_constant_literal(&and_args[1], (U256::one() << 160) - U256::one()) {
+                return Some(and_args[0].clone());
+            }
             if let Some(sub_args) = self.matches_yul_instruction(&and_args[1], "sub", 2) {
                 if let Some(shl_args) = self.matches_yul_instruction(&sub_args[0], "shl", 2) {
                     // Check if shl(160, 1) and sub(..., 1)
@@ -1001,10 +1148,7 @@ impl<'ctx> Yul2IRContext<'ctx> {
             let u256_ptr = self.get_value_pointer(*value)?;
             let ret_ty = self.bytes32_type();
             let ret_ptr = self.fast_alloca(ret_ty, "")?;
-            self.build_void_call(
-                "u256_to_big_endian_bytes",
-                &[u256_ptr.into(), ret_ptr.into()],
-            )?;
+            self.build_u256_to_big_endian(u256_ptr, ret_ptr)?;
             Ok(ret_ptr.into())
         } else if value.is_int_value() {
             let u256_value = self.int_as_u256(value.into_int_value())?;
@@ -1056,10 +1200,7 @@ impl<'ctx> Yul2IRContext<'ctx> {
             let ret_ty = self.u256_type();
             let ret_ptr = self.fast_alloca(ret_ty, "")?;
             let value_ptr = self.get_value_pointer(*value)?;
-            self.build_void_call(
-                "u256_from_big_endian_bytes",
-                &[value_ptr.into(), ret_ptr.into()],
-            )?;
+            self.build_u256_from_big_endian(value_ptr, ret_ptr)?;
             let res = self.build_load(ret_ty, ret_ptr, "")?;
             Ok(res.into_int_value())
         } else {
@@ -1077,7 +1218,7 @@ impl<'ctx> Yul2IRContext<'ctx> {
             // call u256_from_big_endian_bytes(bytes32*, uint256_t*)
             let ret_ty = self.u256_type();
             let ret_ptr = self.fast_alloca(ret_ty, "")?;
-            self.build_void_call("u256_from_big_endian_bytes", &[*value, ret_ptr.into()])?;
+            self.build_u256_from_big_endian(value.into_pointer_value(), ret_ptr)?;
             let res = self.build_load(ret_ty, ret_ptr, "")?;
             Ok(res.into_int_value())
         } else {
@@ -1095,10 +1236,7 @@ impl<'ctx> Yul2IRContext<'ctx> {
             let value_ptr = self.get_value_pointer(value)?;
             let ret_ty = self.bytes32_type();
             let ret_ptr = self.fast_alloca(ret_ty, "")?;
-            self.build_void_call(
-                "u256_to_big_endian_bytes",
-                &[value_ptr.into(), ret_ptr.into()],
-            )?;
+            self.build_u256_to_big_endian(value_ptr, ret_ptr)?;
             let res = self.build_load(ret_ty, ret_ptr, "")?;
             Ok(res)
         } else {
@@ -1132,7 +1270,7 @@ impl<'ctx> Yul2IRContext<'ctx> {
                 // call u256_from_big_endian_bytes(bytes32*, uint256_t*)
                 let ret_ty = self.u256_type();
                 let ret_ptr = self.fast_alloca(ret_ty, "")?;
-                self.build_void_call("u256_from_big_endian_bytes", &[value, ret_ptr.into()])?;
+                self.build_u256_from_big_endian(value.into_pointer_value(), ret_ptr)?;
                 let res = self.build_load(ret_ty, ret_ptr, "")?;
                 return Ok(res);
             }
@@ -1151,7 +1289,7 @@ impl<'ctx> Yul2IRContext<'ctx> {
             // For other cases, first convert to u256 then to target type
             let u256_type = self.u256_type();
             let u256_ptr = self.fast_alloca(u256_type, "")?;
-            self.build_void_call("u256_from_big_endian_bytes", &[value, u256_ptr.into()])?;
+            self.build_u256_from_big_endian(value.into_pointer_value(), u256_ptr)?;
             let u256_value = self.build_load(u256_type, u256_ptr, "")?;
             self.try_cast(u256_value, target_ty)
         } else if target_ty.is_pointer_type() {
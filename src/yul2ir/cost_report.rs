@@ -0,0 +1,649 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Static cost estimation of the external functions.
+//!
+//! The ABI functions of a contract are the cases of its selector dispatch, a
+//! `switch` on a value computed from `calldataload(0)`. Their costs are
+//! bounded on the optimized Yul AST, walked like the variable usage analysis:
+//! calls of Yul functions add the cost of the callee, branches take the most
+//! expensive arm and loops with a constant trip count are repeated that many
+//! times. Other loops and recursive calls are counted once and flagged, their
+//! cost depends on runtime values.
+//!
+//! The wasm instruction counts are estimates of the code lowered for each
+//! builtin on 64 bits limbs. The LLVM instruction count of every transformed
+//! function of the optimized module is reported next to them.
+
+use crate::yul2ir::ast::{Block, Expression, For, FunctionDefinition, Object, Statement};
+use crate::yul2ir::ast::{InnerSegment, SwitchOptions};
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::yul_instruction::{parse_intrinsic_func_name, YulInstructionName};
+
+use ethereum_types::U256;
+use inkwell::module::Module;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+
+/// Estimated wasm instructions of a call of a Yul function, besides its body
+const CALL_INSTRUCTIONS: u64 = 4;
+/// Estimated wasm instructions of a branch
+const BRANCH_INSTRUCTIONS: u64 = 2;
+
+/// Worst case cost of a piece of Yul code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostBound {
+    /// Calls of the costly host apis, Yul builtin name => count
+    pub host_calls: BTreeMap<String, u64>,
+    /// Estimated wasm instructions executed
+    pub wasm_instructions: u64,
+    /// Yul functions containing a loop without a constant trip count, the
+    /// object name for loops of the top level code
+    pub unbounded_loops: BTreeSet<String>,
+    /// Recursive Yul functions reached
+    pub recursive_functions: BTreeSet<String>,
+}
+
+impl CostBound {
+    fn instructions(wasm_instructions: u64) -> Self {
+        CostBound {
+            wasm_instructions,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the counts bound every execution
+    #[allow(unused)]
+    pub fn is_bounded(&self) -> bool {
+        self.unbounded_loops.is_empty() && self.recursive_functions.is_empty()
+    }
+
+    /// Adds the cost of code running after this one
+    fn then(&mut self, other: &CostBound) {
+        for (name, count) in &other.host_calls {
+            let total = self.host_calls.entry(name.clone()).or_default();
+            *total = total.saturating_add(*count);
+        }
+        self.wasm_instructions = self
+            .wasm_instructions
+            .saturating_add(other.wasm_instructions);
+        self.merge_flags(other);
+    }
+
+    /// Keeps the more expensive of this and another branch
+    fn max(&mut self, other: &CostBound) {
+        for (name, count) in &other.host_calls {
+            let max = self.host_calls.entry(name.clone()).or_default();
+            *max = (*max).max(*count);
+        }
+        self.wasm_instructions = self.wasm_instructions.max(other.wasm_instructions);
+        self.merge_flags(other);
+    }
+
+    fn repeat(&self, times: u64) -> CostBound {
+        CostBound {
+            host_calls: self
+                .host_calls
+                .iter()
+                .map(|(name, count)| (name.clone(), count.saturating_mul(times)))
+                .collect(),
+            wasm_instructions: self.wasm_instructions.saturating_mul(times),
+            unbounded_loops: self.unbounded_loops.clone(),
+            recursive_functions: self.recursive_functions.clone(),
+        }
+    }
+
+    fn merge_flags(&mut self, other: &CostBound) {
+        self.unbounded_loops
+            .extend(other.unbounded_loops.iter().cloned());
+        self.recursive_functions
+            .extend(other.recursive_functions.iter().cloned());
+    }
+}
+
+/// Cost of an entry point of a contract
+#[derive(Debug, Clone)]
+pub struct ExternalFunctionCost {
+    /// Object holding the entry point
+    pub object: String,
+    /// `0x` and the 4 bytes selector, `fallback` for the default case of the
+    /// dispatch and `constructor` for the code of a creation object
+    pub selector: String,
+    /// The Yul function a dispatch case consists of a call of
+    pub entry: Option<String>,
+    pub cost: CostBound,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CostReport {
+    /// Entry points in the order of the objects and their dispatch
+    pub functions: Vec<ExternalFunctionCost>,
+    /// Instructions of each transformed function left in the optimized module
+    pub llvm_instructions: BTreeMap<String, u64>,
+}
+
+impl Display for CostReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "cost report:")?;
+        for function in &self.functions {
+            let entry = match &function.entry {
+                Some(entry) => format!(" ({entry})"),
+                None => String::new(),
+            };
+            let host_calls: Vec<String> = function
+                .cost
+                .host_calls
+                .iter()
+                .map(|(name, count)| format!("{name} x{count}"))
+                .collect();
+            write!(
+                f,
+                "  {} {}{}: {} wasm instructions, host calls: {}",
+                function.object,
+                function.selector,
+                entry,
+                function.cost.wasm_instructions,
+                if host_calls.is_empty() {
+                    "none".to_string()
+                } else {
+                    host_calls.join(", ")
+                }
+            )?;
+            if !function.cost.unbounded_loops.is_empty() {
+                let loops: Vec<&str> = function
+                    .cost
+                    .unbounded_loops
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect();
+                write!(f, ", unbounded loops in {}", loops.join(", "))?;
+            }
+            if !function.cost.recursive_functions.is_empty() {
+                let recursive_functions: Vec<&str> = function
+                    .cost
+                    .recursive_functions
+                    .iter()
+                    .map(|s| s.as_str())
+                    .collect();
+                write!(
+                    f,
+                    ", unbounded recursion in {}",
+                    recursive_functions.join(", ")
+                )?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "  llvm instructions:")?;
+        for (name, count) in &self.llvm_instructions {
+            writeln!(f, "    {name}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Host apis whose cost dominates the cost of a call
+fn is_costly_host_call(instr: &YulInstructionName) -> bool {
+    use YulInstructionName::*;
+    matches!(
+        instr,
+        SLoad
+            | SStore
+            | TLoad
+            | TStore
+            | Call
+            | CallCode
+            | DelegateCall
+            | StaticCall
+            | Create
+            | Create2
+            | Log0
+            | Log1
+            | Log2
+            | Log3
+            | Log4
+            | Keccak256
+            | Balance
+            | ExtCodeSize
+            | ExtCodeHash
+            | ExtCodeCopy
+            | BlockHash
+            | SelfDestruct
+    )
+}
+
+/// Rough wasm instructions of a builtin on four 64 bits limbs, stdlib and
+/// host api calls include passing their operands through memory
+fn builtin_wasm_instructions(instr: &YulInstructionName) -> u64 {
+    use YulInstructionName::*;
+    match instr {
+        Pop | DataOffset | DataSize | MemoryGuard | LinkerSymbol | DebugPrint => 1,
+        And | Or | Xor | Not | IsZero | Eq | Lt | Gt => 8,
+        Add | Sub | SLt | SGt | Byte | SignExtend => 16,
+        Shl | Shr | Sar => 32,
+        MLoad | MStore | MStore8 | CallDataLoad => 20,
+        Mul => 48,
+        Div | SDiv | Mod | SMod | AddMod | MulMod | Exp => 120,
+        _ => 24,
+    }
+}
+
+/// Whether a statement of the block assigns the variable
+fn assigns_variable(block: &Block, name: &str) -> bool {
+    block.statements.iter().any(|stmt| match stmt {
+        Statement::Assignment(assign) => assign.identifiers.iter().any(|id| id.name == name),
+        Statement::If(if_stmt) => assigns_variable(&if_stmt.body, name),
+        Statement::For(for_stmt) => {
+            assigns_variable(&for_stmt.init_block, name)
+                || assigns_variable(&for_stmt.execution_block, name)
+                || assigns_variable(&for_stmt.post_block, name)
+        }
+        Statement::Switch(switch) => match &switch.opt {
+            SwitchOptions::Cases(cases, default) => {
+                cases.iter().any(|case| assigns_variable(&case.body, name))
+                    || default
+                        .as_ref()
+                        .is_some_and(|default| assigns_variable(&default.body, name))
+            }
+            SwitchOptions::Default(default) => assigns_variable(&default.body, name),
+        },
+        Statement::Block(block) => assigns_variable(block, name),
+        _ => false,
+    })
+}
+
+/// Every function defined in the block, including the nested definitions
+fn collect_function_definitions<'b>(
+    block: &'b Block,
+    definitions: &mut HashMap<String, &'b FunctionDefinition>,
+) {
+    for stmt in &block.statements {
+        match stmt {
+            Statement::FunctionDefinition(func_def) => {
+                definitions
+                    .entry(func_def.name.name.clone())
+                    .or_insert(func_def);
+                collect_function_definitions(&func_def.body, definitions);
+            }
+            Statement::Block(block) => collect_function_definitions(block, definitions),
+            _ => {}
+        }
+    }
+}
+
+/// Instructions of the functions of the module with one of the names
+pub fn llvm_instruction_counts(module: &Module, names: &HashSet<String>) -> BTreeMap<String, u64> {
+    let mut counts = BTreeMap::new();
+    for function in module.get_functions() {
+        let name = function.get_name().to_string_lossy().to_string();
+        if function.count_basic_blocks() == 0 || !names.contains(&name) {
+            continue;
+        }
+        let mut count = 0;
+        for bb in function.get_basic_blocks() {
+            let mut inst = bb.get_first_instruction();
+            while let Some(cur) = inst {
+                count += 1;
+                inst = cur.get_next_instruction();
+            }
+        }
+        counts.insert(name, count);
+    }
+    counts
+}
+
+/// State of the cost analysis of an object
+struct CostAnalysis<'b> {
+    functions: HashMap<String, &'b FunctionDefinition>,
+    function_costs: HashMap<String, CostBound>,
+    /// Yul functions being analyzed, innermost last
+    call_stack: Vec<String>,
+    /// Function or object the walked code belongs to
+    current: String,
+}
+
+impl<'a> Yul2IRContext<'a> {
+    /// Number of iterations of a `for { let i := a } lt(i, b) { i := add(i, c) }`
+    /// loop whose body doesn't assign `i`
+    fn constant_trip_count(&self, r#for: &For) -> Option<u64> {
+        let [init] = r#for.init_block.statements.as_slice() else {
+            return None;
+        };
+        let (counter, start) = self.matches_single_declare_instruction(init)?;
+        let start = self.fetch_not_string_literal_constant(&start)?;
+        let is_counter =
+            |expr: &Expression| matches!(expr, Expression::Identifier(id) if id.name == counter);
+
+        let end = if let Some(args) = self.matches_yul_instruction(&r#for.condition, "lt", 2) {
+            if !is_counter(&args[0]) {
+                return None;
+            }
+            self.fetch_not_string_literal_constant(&args[1])?
+        } else {
+            let args = self.matches_yul_instruction(&r#for.condition, "gt", 2)?;
+            if !is_counter(&args[1]) {
+                return None;
+            }
+            self.fetch_not_string_literal_constant(&args[0])?
+        };
+
+        let [Statement::Assignment(post)] = r#for.post_block.statements.as_slice() else {
+            return None;
+        };
+        if post.identifiers.len() != 1 || post.identifiers[0].name != counter {
+            return None;
+        }
+        let add_args = self.matches_yul_instruction(&post.value, "add", 2)?;
+        let step = if is_counter(&add_args[0]) {
+            self.fetch_not_string_literal_constant(&add_args[1])?
+        } else if is_counter(&add_args[1]) {
+            self.fetch_not_string_literal_constant(&add_args[0])?
+        } else {
+            return None;
+        };
+        if step.is_zero() || assigns_variable(&r#for.execution_block, &counter) {
+            return None;
+        }
+
+        if end <= start {
+            return Some(0);
+        }
+        // the counter doesn't wrap before reaching the end
+        let trips = (end - start - U256::one()) / step + U256::one();
+        (trips <= U256::from(u64::MAX)).then(|| trips.as_u64())
+    }
+
+    fn function_cost(&self, analysis: &mut CostAnalysis, name: &str) -> CostBound {
+        if analysis.call_stack.iter().any(|called| called == name) {
+            let mut cost = CostBound::default();
+            cost.recursive_functions.insert(name.to_string());
+            return cost;
+        }
+        if let Some(cost) = analysis.function_costs.get(name) {
+            return cost.clone();
+        }
+        // unknown functions are reported by the lowering
+        let Some(func_def) = analysis.functions.get(name).copied() else {
+            return CostBound::default();
+        };
+        analysis.call_stack.push(name.to_string());
+        let caller = std::mem::replace(&mut analysis.current, name.to_string());
+        let cost = self.block_cost(analysis, &func_def.body);
+        analysis.current = caller;
+        analysis.call_stack.pop();
+        analysis
+            .function_costs
+            .insert(name.to_string(), cost.clone());
+        cost
+    }
+
+    fn expression_cost(&self, analysis: &mut CostAnalysis, expr: &Expression) -> CostBound {
+        match expr {
+            Expression::Identifier(_) | Expression::Literal(_) => CostBound::instructions(1),
+            Expression::FunctionCall(func_call) => {
+                let mut cost = CostBound::default();
+                for arg in &func_call.arguments {
+                    cost.then(&self.expression_cost(analysis, arg));
+                }
+                let name = &func_call.id.name;
+                match parse_intrinsic_func_name(name) {
+                    Some(instr) => {
+                        cost.then(&CostBound::instructions(builtin_wasm_instructions(&instr)));
+                        if is_costly_host_call(&instr) {
+                            *cost.host_calls.entry(name.clone()).or_default() += 1;
+                        }
+                    }
+                    None => {
+                        cost.then(&CostBound::instructions(CALL_INSTRUCTIONS));
+                        cost.then(&self.function_cost(analysis, name));
+                    }
+                }
+                cost
+            }
+        }
+    }
+
+    fn for_cost(&self, analysis: &mut CostAnalysis, r#for: &For) -> CostBound {
+        let mut cost = self.block_cost(analysis, &r#for.init_block);
+        let condition = self.expression_cost(analysis, &r#for.condition);
+        let mut iteration = self.block_cost(analysis, &r#for.execution_block);
+        iteration.then(&self.block_cost(analysis, &r#for.post_block));
+        iteration.then(&CostBound::instructions(BRANCH_INSTRUCTIONS));
+        match self.constant_trip_count(r#for) {
+            Some(trips) => {
+                cost.then(&condition.repeat(trips.saturating_add(1)));
+                cost.then(&iteration.repeat(trips));
+            }
+            None => {
+                cost.then(&condition);
+                cost.then(&iteration);
+                cost.unbounded_loops.insert(analysis.current.clone());
+            }
+        }
+        cost
+    }
+
+    fn statement_cost(&self, analysis: &mut CostAnalysis, stmt: &Statement) -> CostBound {
+        match stmt {
+            Statement::Assignment(assign) => {
+                let mut cost = self.expression_cost(analysis, &assign.value);
+                cost.then(&CostBound::instructions(assign.identifiers.len() as u64));
+                cost
+            }
+            Statement::VariableDeclaration(decl) => {
+                let mut cost = match &decl.value {
+                    Some(value) => self.expression_cost(analysis, value),
+                    None => CostBound::default(),
+                };
+                cost.then(&CostBound::instructions(decl.identifiers.len() as u64));
+                cost
+            }
+            Statement::If(if_stmt) => {
+                // the worst case takes the branch
+                let mut cost = self.expression_cost(analysis, &if_stmt.cond);
+                cost.then(&CostBound::instructions(BRANCH_INSTRUCTIONS));
+                cost.then(&self.block_cost(analysis, &if_stmt.body));
+                cost
+            }
+            Statement::Switch(switch) => {
+                let mut cost = self.expression_cost(analysis, &switch.condition);
+                cost.then(&CostBound::instructions(BRANCH_INSTRUCTIONS));
+                let mut arms = CostBound::default();
+                match &switch.opt {
+                    SwitchOptions::Cases(cases, default) => {
+                        cost.then(&CostBound::instructions(cases.len() as u64));
+                        for case in cases {
+                            arms.max(&self.block_cost(analysis, &case.body));
+                        }
+                        if let Some(default) = default {
+                            arms.max(&self.block_cost(analysis, &default.body));
+                        }
+                    }
+                    SwitchOptions::Default(default) => {
+                        arms = self.block_cost(analysis, &default.body);
+                    }
+                }
+                cost.then(&arms);
+                cost
+            }
+            Statement::For(for_stmt) => self.for_cost(analysis, for_stmt),
+            Statement::Block(block) => self.block_cost(analysis, block),
+            Statement::FunctionCall(func_call) => {
+                self.expression_cost(analysis, &Expression::FunctionCall(func_call.clone()))
+            }
+            Statement::Leave | Statement::Break | Statement::Continue => CostBound::instructions(1),
+            // definitions are costed at their calls
+            Statement::FunctionDefinition(_) | Statement::Comment => CostBound::default(),
+        }
+    }
+
+    fn block_cost(&self, analysis: &mut CostAnalysis, block: &Block) -> CostBound {
+        let mut cost = CostBound::default();
+        for stmt in &block.statements {
+            cost.then(&self.statement_cost(analysis, stmt));
+        }
+        cost
+    }
+
+    /// Whether the value is derived from the first calldata word
+    fn is_selector_expression(&self, expr: &Expression, selector_vars: &HashSet<String>) -> bool {
+        match expr {
+            Expression::Identifier(id) => selector_vars.contains(&id.name),
+            Expression::FunctionCall(func_call) => {
+                if func_call.id.name == "calldataload" && func_call.arguments.len() == 1 {
+                    return self.matches_constant_literal(&func_call.arguments[0], U256::zero());
+                }
+                func_call
+                    .arguments
+                    .iter()
+                    .any(|arg| self.is_selector_expression(arg, selector_vars))
+            }
+            Expression::Literal(_) => false,
+        }
+    }
+
+    /// Costs the cases of the selector dispatches in the top level code
+    fn dispatch_costs(
+        &self,
+        analysis: &mut CostAnalysis,
+        object_name: &str,
+        block: &Block,
+        selector_vars: &mut HashSet<String>,
+        functions: &mut Vec<ExternalFunctionCost>,
+    ) {
+        for stmt in &block.statements {
+            match stmt {
+                Statement::VariableDeclaration(_) => {
+                    if let Some((name, value)) = self.matches_single_declare_instruction(stmt) {
+                        if self.is_selector_expression(&value, selector_vars) {
+                            selector_vars.insert(name);
+                        }
+                    }
+                }
+                Statement::If(if_stmt) => {
+                    self.dispatch_costs(
+                        analysis,
+                        object_name,
+                        &if_stmt.body,
+                        selector_vars,
+                        functions,
+                    );
+                }
+                Statement::Block(block) => {
+                    self.dispatch_costs(analysis, object_name, block, selector_vars, functions);
+                }
+                Statement::Switch(switch) => {
+                    let SwitchOptions::Cases(cases, default) = &switch.opt else {
+                        continue;
+                    };
+                    if !self.is_selector_expression(&switch.condition, selector_vars) {
+                        continue;
+                    }
+                    for case in cases {
+                        let Some(selector) = self.fetch_not_string_literal_constant(
+                            &Expression::Literal(case.case.clone()),
+                        ) else {
+                            continue;
+                        };
+                        let entry = match case.body.statements.as_slice() {
+                            [Statement::FunctionCall(func_call)]
+                                if parse_intrinsic_func_name(&func_call.id.name).is_none() =>
+                            {
+                                Some(func_call.id.name.clone())
+                            }
+                            _ => None,
+                        };
+                        functions.push(ExternalFunctionCost {
+                            object: object_name.to_string(),
+                            selector: format!("0x{:08x}", selector.low_u64()),
+                            entry,
+                            cost: self.block_cost(analysis, &case.body),
+                        });
+                    }
+                    if let Some(default) = default {
+                        if !default.body.statements.is_empty() {
+                            functions.push(ExternalFunctionCost {
+                                object: object_name.to_string(),
+                                selector: "fallback".to_string(),
+                                entry: None,
+                                cost: self.block_cost(analysis, &default.body),
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn object_costs(&self, object: &Object, functions: &mut Vec<ExternalFunctionCost>) {
+        let mut definitions = HashMap::new();
+        collect_function_definitions(&object.code, &mut definitions);
+        let mut analysis = CostAnalysis {
+            functions: definitions,
+            function_costs: HashMap::new(),
+            call_stack: vec![],
+            current: object.name.clone(),
+        };
+        if object.name.ends_with("_deployed") {
+            self.dispatch_costs(
+                &mut analysis,
+                &object.name,
+                &object.code,
+                &mut HashSet::new(),
+                functions,
+            );
+        } else if object
+            .code
+            .statements
+            .iter()
+            .any(|stmt| !matches!(stmt, Statement::FunctionDefinition(_)))
+        {
+            functions.push(ExternalFunctionCost {
+                object: object.name.clone(),
+                selector: "constructor".to_string(),
+                entry: None,
+                cost: self.block_cost(&mut analysis, &object.code),
+            });
+        }
+    }
+
+    /// Cost report of the objects compiled in this context and the optimized
+    /// module
+    fn build_cost_report(&self) -> CostReport {
+        let mut functions = vec![];
+        if let Some(object) = &self.yul_ast {
+            self.object_costs(object, &mut functions);
+            for inner_segment in &object.inner_segments {
+                if let InnerSegment::Object(inner_object) = inner_segment {
+                    if inner_object.name == format!("{}_deployed", object.name) {
+                        self.object_costs(inner_object, &mut functions);
+                    }
+                }
+            }
+        }
+        let mut names: HashSet<String> = self.functions_mapping.borrow().keys().cloned().collect();
+        names.extend(self.exported_func_names.borrow().iter().cloned());
+        CostReport {
+            functions,
+            llvm_instructions: llvm_instruction_counts(&self.llvm_module.borrow(), &names),
+        }
+    }
+
+    /// Writes the cost report next to the LLVM IR of the build
+    pub(crate) fn report_costs(&self, output_basename: &str) -> std::io::Result<()> {
+        let report = self.build_cost_report();
+        if self.opts.verbose {
+            print!("{report}");
+        }
+        let output_dir = &self.opts.output_dir;
+        std::fs::write(
+            format!(
+                "{output_dir}/{}_{}.cost.txt",
+                self.opts.main_contract_name, output_basename
+            ),
+            report.to_string(),
+        )?;
+        *self.cost_report.borrow_mut() = Some(report);
+        Ok(())
+    }
+}
@@ -1,15 +1,319 @@
 // Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::yul2ir::ast::FunctionDefinition;
+use crate::yul2ir::ast::{
+    Block, Expression, FunctionCall, FunctionDefinition, Literal, Object, Statement, SwitchOptions,
+    TypedIdentifier,
+};
+use crate::yul2ir::instruction::{has_address_decoder_lowering, ADDRESS_DECODER_LOWERED_BUILTINS};
 use crate::yul2ir::transform::UNIFIED_REVERT_ERROR_ZERO;
+use crate::yul2ir::wasm::function_body_sizes;
 use crate::yul2ir::{context::CompileResult, context::Yul2IRContext};
 
 use ethereum_types::U256;
 use inkwell::module::Linkage;
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
 use std::rc::Rc;
 
+/// Writes a function body in a form that doesn't depend on the names of its
+/// parameters, return variables and locals, so functions that only differ by
+/// those names (alpha-equivalent) get the same canonical form.
+/// Called user functions are written as their current representative.
+struct CanonicalFormWriter<'b> {
+    out: String,
+    locals: HashMap<&'b str, usize>,
+    representatives: &'b HashMap<String, String>,
+    /// The body calls a builtin whose lowering depends on the function name
+    calls_name_dependent_builtin: bool,
+}
+
+impl<'b> CanonicalFormWriter<'b> {
+    fn declare(&mut self, id: &'b TypedIdentifier) {
+        let index = self.locals.len();
+        self.locals
+            .entry(id.identifier.name.as_str())
+            .or_insert(index);
+        self.identifier(&id.identifier.name);
+        if let Some(ty) = &id.type_name {
+            let _ = write!(self.out, ":{}", ty.type_name.name);
+        }
+        self.out.push(',');
+    }
+
+    fn identifier(&mut self, name: &str) {
+        if let Some(index) = self.locals.get(name) {
+            let _ = write!(self.out, "${index}");
+        } else {
+            let _ = write!(self.out, "@{name}");
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        let (text, ty) = match literal {
+            Literal::TrueLiteral(ty) => ("true".to_string(), ty),
+            Literal::FalseLiteral(ty) => ("false".to_string(), ty),
+            Literal::HexNumberLiteral(hex, ty) => (hex.hex.clone(), ty),
+            Literal::DecimalNumberLiteral(dec, ty) => (dec.dec.clone(), ty),
+            Literal::StringLiteral(str, ty) => (format!("{:?}", str.str), ty),
+        };
+        let _ = write!(self.out, "#{text}");
+        if let Some(ty) = ty {
+            let _ = write!(self.out, ":{}", ty.name);
+        }
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        if ADDRESS_DECODER_LOWERED_BUILTINS.contains(&call.id.name.as_str()) {
+            self.calls_name_dependent_builtin = true;
+        }
+        let callee = self
+            .representatives
+            .get(&call.id.name)
+            .unwrap_or(&call.id.name);
+        let _ = write!(self.out, "{callee}(");
+        for arg in &call.arguments {
+            self.expr(arg);
+            self.out.push(',');
+        }
+        self.out.push(')');
+    }
+
+    fn expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Identifier(id) => self.identifier(&id.name),
+            Expression::Literal(literal) => self.literal(literal),
+            Expression::FunctionCall(call) => self.call(call),
+        }
+    }
+
+    /// Returns false if the block contains a construct that isn't handled
+    fn block(&mut self, block: &'b Block) -> bool {
+        self.out.push('{');
+        for stmt in &block.statements {
+            match stmt {
+                Statement::Assignment(assign) => {
+                    for id in &assign.identifiers {
+                        self.identifier(&id.name);
+                        self.out.push(',');
+                    }
+                    self.out.push_str(":=");
+                    self.expr(&assign.value);
+                }
+                Statement::VariableDeclaration(decl) => {
+                    self.out.push_str("let ");
+                    for id in &decl.identifiers {
+                        self.declare(id);
+                    }
+                    if let Some(value) = &decl.value {
+                        self.out.push_str(":=");
+                        self.expr(value);
+                    }
+                }
+                Statement::If(if_stmt) => {
+                    self.out.push_str("if ");
+                    self.expr(&if_stmt.cond);
+                    if !self.block(&if_stmt.body) {
+                        return false;
+                    }
+                }
+                Statement::For(for_stmt) => {
+                    self.out.push_str("for ");
+                    if !self.block(&for_stmt.init_block) {
+                        return false;
+                    }
+                    self.expr(&for_stmt.condition);
+                    if !self.block(&for_stmt.post_block) || !self.block(&for_stmt.execution_block) {
+                        return false;
+                    }
+                }
+                Statement::Switch(switch) => {
+                    self.out.push_str("switch ");
+                    self.expr(&switch.condition);
+                    let default = match &switch.opt {
+                        SwitchOptions::Cases(cases, default) => {
+                            for case in cases {
+                                self.out.push_str("case ");
+                                self.literal(&case.case);
+                                if !self.block(&case.body) {
+                                    return false;
+                                }
+                            }
+                            default.as_ref()
+                        }
+                        SwitchOptions::Default(default) => Some(default),
+                    };
+                    if let Some(default) = default {
+                        self.out.push_str("default ");
+                        if !self.block(&default.body) {
+                            return false;
+                        }
+                    }
+                }
+                Statement::Leave => self.out.push_str("leave"),
+                Statement::Break => self.out.push_str("break"),
+                Statement::Continue => self.out.push_str("continue"),
+                Statement::Block(block) => {
+                    if !self.block(block) {
+                        return false;
+                    }
+                }
+                Statement::FunctionCall(call) => self.call(call),
+                Statement::Comment => {}
+                // nested functions have their own scope, keep these functions as they are
+                Statement::FunctionDefinition(_) => return false,
+            }
+            self.out.push(';');
+        }
+        self.out.push('}');
+        true
+    }
+}
+
+/// Functions merged by find_duplicate_functions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeduplicationSavings {
+    /// Functions redirected to another one
+    pub merged: usize,
+    /// Functions they were redirected to
+    pub kept: usize,
+    /// Wasm code bytes of the merged functions
+    pub saved_bytes: usize,
+}
+
+impl Display for DeduplicationSavings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "function deduplication: merged {} functions into {}, saved {} bytes of wasm code",
+            self.merged, self.kept, self.saved_bytes
+        )
+    }
+}
+
+/// The canonical form of the function, None if it can't be merged. Bodies
+/// lowered differently because of the function name are tagged, so they are
+/// only merged with each other.
+fn canonical_form(
+    function: &FunctionDefinition,
+    representatives: &HashMap<String, String>,
+) -> Option<String> {
+    let mut writer = CanonicalFormWriter {
+        out: String::new(),
+        locals: HashMap::new(),
+        representatives,
+        calls_name_dependent_builtin: false,
+    };
+    writer.out.push('(');
+    for param in &function.params {
+        writer.declare(param);
+    }
+    writer.out.push_str(")->(");
+    for ret in &function.returns {
+        writer.declare(ret);
+    }
+    writer.out.push(')');
+    if !writer.block(&function.body) {
+        return None;
+    }
+    if writer.calls_name_dependent_builtin && has_address_decoder_lowering(&function.name.name) {
+        writer.out.insert_str(0, "address_decoder");
+    }
+    Some(writer.out)
+}
+
 impl<'a> Yul2IRContext<'a> {
+    /// Groups the functions of the object that are equal up to renaming of
+    /// their parameters, return variables and locals. Every function but the
+    /// first of each group is recorded in duplicate_functions, calls to it are
+    /// redirected to the first one and it is not lowered.
+    pub fn find_duplicate_functions(&self, object: &Object) {
+        let functions: Vec<&FunctionDefinition> = object
+            .code
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::FunctionDefinition(func_def) => Some(func_def.as_ref()),
+                _ => None,
+            })
+            .filter(|func_def| {
+                // calls to functions matched by a pattern rule are replaced
+                !self.is_revert_zero_function(func_def)
+                    && !self
+                        .pattern_rule_matches
+                        .borrow()
+                        .contains_key(&self.get_func_decl_qualifier_name(func_def))
+            })
+            .collect();
+
+        // Merging two functions can make their callers equal too, so repeat
+        // until no more functions are merged
+        let mut representatives: HashMap<String, String> = HashMap::new();
+        loop {
+            let mut classes: HashMap<String, &str> = HashMap::new();
+            let mut next_representatives = HashMap::new();
+            for func_def in &functions {
+                let name = func_def.name.name.as_str();
+                let Some(form) = canonical_form(func_def, &representatives) else {
+                    continue;
+                };
+                let representative = *classes.entry(form).or_insert(name);
+                if representative != name {
+                    next_representatives.insert(name.to_string(), representative.to_string());
+                }
+            }
+            if next_representatives.len() == representatives.len() {
+                break;
+            }
+            representatives = next_representatives;
+        }
+
+        let mut duplicate_functions = self.duplicate_functions.borrow_mut();
+        for (name, representative) in representatives {
+            duplicate_functions.insert(
+                self.get_func_decl_qualifier_name_by_str(&name),
+                self.get_func_decl_qualifier_name_by_str(&representative),
+            );
+        }
+    }
+
+    /// How many functions were merged and how much wasm code that saved,
+    /// counted as the code size of each kept function times its duplicates.
+    /// None if no function was merged
+    pub fn duplicate_function_savings(&self, wasm_bytes: &[u8]) -> Option<DeduplicationSavings> {
+        let duplicate_functions = self.duplicate_functions.borrow();
+        if duplicate_functions.is_empty() {
+            return None;
+        }
+        let mut counts: HashMap<&String, usize> = HashMap::new();
+        for representative in duplicate_functions.values() {
+            *counts.entry(representative).or_insert(0) += 1;
+        }
+        let body_sizes = function_body_sizes(wasm_bytes);
+        let saved_bytes: usize = counts
+            .iter()
+            .map(|(representative, count)| {
+                body_sizes.get(*representative).copied().unwrap_or(0) * count
+            })
+            .sum();
+        Some(DeduplicationSavings {
+            merged: duplicate_functions.len(),
+            kept: counts.len(),
+            saved_bytes,
+        })
+    }
+
+    /// Prints the deduplication savings with verbose or the size report, the
+    /// size report also records them
+    pub fn report_duplicate_functions(&self, wasm_bytes: &[u8]) {
+        if !self.opts.verbose && !self.opts.enable_size_report {
+            return;
+        }
+        if let Some(savings) = self.duplicate_function_savings(wasm_bytes) {
+            println!("{savings}");
+        }
+    }
+
     pub fn is_revert_zero_function(&self, function: &FunctionDefinition) -> bool {
         if function.body.statements.len() != 1 {
             return false;
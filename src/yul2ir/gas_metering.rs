@@ -0,0 +1,356 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compile-time gas metering.
+//!
+//! With `gas_metering` the contract charges its own gas through the `useGas`
+//! host api, for chains without engine-level metering. Every basic block
+//! charges the cost of its code once on entry, so the gas of a path only
+//! depends on the path.
+//! - `evm`: each lowered Yul builtin costs about its static EVM gas, plus
+//!   `function_call` per call of a Yul function and `loop_iteration` per
+//!   iteration of a `for` loop. The charges are emitted while lowering and
+//!   merged per basic block after the LLVM passes
+//! - `wasm`: each instruction of the optimized module costs the weight of its
+//!   class (`instruction`, `memory`, `call`, `mul`, `div`), times the number
+//!   of 64 bits words of wide integers
+//!
+//! The defaults can be overridden by a table of `name = cost` lines.
+
+use crate::yul2ir::config::GasMetering;
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::yul_instruction::{parse_intrinsic_func_name, YulInstructionName};
+
+use inkwell::attributes::AttributeLoc;
+use inkwell::basic_block::BasicBlock;
+use inkwell::types::AnyTypeEnum;
+use inkwell::values::{BasicValueEnum, FunctionValue, InstructionOpcode, InstructionValue};
+use std::collections::HashSet;
+
+const USE_GAS_IMPORT: &str = "useGas";
+
+/// Internal call of a Yul function: push the return label, jump in and out
+const FUNCTION_CALL_COST: (&str, u64) = ("function_call", 21);
+/// Back edge of a `for` loop: condition jump and jump back
+const LOOP_ITERATION_COST: (&str, u64) = ("loop_iteration", 19);
+
+const WASM_COSTS: [(&str, u64); 5] = [
+    ("instruction", 1),
+    ("memory", 2),
+    ("call", 5),
+    ("mul", 3),
+    ("div", 10),
+];
+
+/// Functions wizer runs at build time, where the host api isn't available.
+/// Everything they call is left unmetered too, including the helpers the
+/// contract calls again at runtime
+const UNMETERED_ENTRIES: [&str; 2] = ["_start", "__init_evm_heap"];
+
+/// Whether a gas cost table entry names a cost of the gas metering
+pub fn is_gas_cost_name(gas_metering: GasMetering, name: &str) -> bool {
+    match gas_metering {
+        GasMetering::Evm => {
+            name == FUNCTION_CALL_COST.0
+                || name == LOOP_ITERATION_COST.0
+                || parse_intrinsic_func_name(name).is_some()
+        }
+        GasMetering::Wasm => WASM_COSTS.iter().any(|(cost_name, _)| *cost_name == name),
+    }
+}
+
+/// Static EVM gas of a builtin, memory expansion, copied words and cold
+/// accesses aren't included
+fn evm_builtin_cost(instr: &YulInstructionName) -> u64 {
+    use YulInstructionName::*;
+    match instr {
+        Stop | Return | Revert | Invalid | DataOffset | DataSize | MemoryGuard | LinkerSymbol
+        | DebugPrint => 0,
+        Pop | MSize | Gas | Address | Caller | CallValue | CallDataSize | CodeSize
+        | ReturnDataSize | Origin | GasPrice | CoinBase | TimeStamp | Number | Difficulty
+        | Prevrandao | GasLimit | ChainID | BaseFee | BlobBaseFee => 2,
+        Add | Sub | Not | Lt | Gt | SLt | SGt | Eq | IsZero | And | Or | Xor | Byte | Shl | Shr
+        | Sar | CallDataLoad | MLoad | MStore | MStore8 | MCopy | CallDataCopy | CodeCopy
+        | ReturnDataCopy | DataCopy | BlobHash | LoadImmutable | SetImmutable => 3,
+        Mul | Div | SDiv | Mod | SMod | SignExtend | SelfBalance => 5,
+        AddMod | MulMod => 8,
+        Exp => 10,
+        BlockHash => 20,
+        Keccak256 => 30,
+        TLoad | TStore => 100,
+        Log0 => 375,
+        Log1 => 750,
+        Log2 => 1125,
+        Log3 => 1500,
+        Log4 => 1875,
+        SLoad => 2100,
+        Balance | ExtCodeSize | ExtCodeCopy | ExtCodeHash | Call | CallCode | DelegateCall
+        | StaticCall => 2600,
+        SelfDestruct => 5000,
+        SStore => 20000,
+        Create | Create2 => 32000,
+    }
+}
+
+/// Number of 64 bits words of the widest integer an instruction computes on
+fn int_words(instr: &InstructionValue) -> u64 {
+    let result_bits = match instr.get_type() {
+        AnyTypeEnum::IntType(ty) => ty.get_bit_width(),
+        _ => 0,
+    };
+    let operand_bits = match instr.get_operand(0).and_then(|operand| operand.left()) {
+        Some(BasicValueEnum::IntValue(value)) => value.get_type().get_bit_width(),
+        _ => 0,
+    };
+    (result_bits.max(operand_bits) as u64).div_ceil(64).max(1)
+}
+
+/// Name of the function a call instruction calls directly
+fn callee_name(instr: &InstructionValue) -> Option<String> {
+    if instr.get_opcode() != InstructionOpcode::Call {
+        return None;
+    }
+    match instr
+        .get_operand(instr.get_num_operands() - 1)
+        .and_then(|operand| operand.left())
+    {
+        Some(BasicValueEnum::PointerValue(callee)) => {
+            Some(callee.get_name().to_string_lossy().into_owned())
+        }
+        _ => None,
+    }
+}
+
+/// The first instruction after the phis and allocas of a block
+fn first_insertion_point<'a>(block: BasicBlock<'a>) -> Option<InstructionValue<'a>> {
+    let mut instr = block.get_first_instruction();
+    while let Some(current) = instr {
+        if !matches!(
+            current.get_opcode(),
+            InstructionOpcode::Phi | InstructionOpcode::Alloca
+        ) {
+            return Some(current);
+        }
+        instr = current.get_next_instruction();
+    }
+    None
+}
+
+impl<'a> Yul2IRContext<'a> {
+    fn gas_cost(&self, name: &str, default: u64) -> u64 {
+        self.opts
+            .gas_cost_overrides
+            .iter()
+            .rev()
+            .find(|(cost_name, _)| cost_name == name)
+            .map_or(default, |(_, cost)| *cost)
+    }
+
+    fn builtin_gas_cost(&self, instr: &YulInstructionName) -> u64 {
+        let discriminant = std::mem::discriminant(instr);
+        self.opts
+            .gas_cost_overrides
+            .iter()
+            .rev()
+            .find(|(name, _)| {
+                parse_intrinsic_func_name(name)
+                    .is_some_and(|named| std::mem::discriminant(&named) == discriminant)
+            })
+            .map_or_else(|| evm_builtin_cost(instr), |(_, cost)| *cost)
+    }
+
+    /// The useGas host api, declared here when the stdlib doesn't use it
+    fn use_gas_function(&self) -> FunctionValue<'a> {
+        let module = self.llvm_module.borrow();
+        if let Some(use_gas) = module.get_function(USE_GAS_IMPORT) {
+            return use_gas;
+        }
+        let fn_type = self
+            .llvm_context
+            .void_type()
+            .fn_type(&[self.i64_type().into()], false);
+        let use_gas = module.add_function(USE_GAS_IMPORT, fn_type, None);
+        for (key, value) in [
+            ("wasm-import-module", "env"),
+            ("wasm-import-name", USE_GAS_IMPORT),
+        ] {
+            use_gas.add_attribute(
+                AttributeLoc::Function,
+                self.llvm_context.create_string_attribute(key, value),
+            );
+        }
+        use_gas
+    }
+
+    fn build_gas_charge(&self, cost: u64) -> Result<(), ASTLoweringError> {
+        if cost == 0 {
+            return Ok(());
+        }
+        let use_gas = self.use_gas_function();
+        self.builder.borrow_mut().build_call(
+            use_gas,
+            &[self.i64_type().const_int(cost, false).into()],
+            "",
+        )?;
+        Ok(())
+    }
+
+    /// Charges a lowered builtin with the evm gas metering
+    pub(crate) fn build_builtin_gas_charge(
+        &self,
+        instr: &YulInstructionName,
+    ) -> Result<(), ASTLoweringError> {
+        if self.opts.gas_metering != Some(GasMetering::Evm) {
+            return Ok(());
+        }
+        self.build_gas_charge(self.builtin_gas_cost(instr))
+    }
+
+    /// Charges the entry of a Yul function with the evm gas metering
+    pub(crate) fn build_function_call_gas_charge(&self) -> Result<(), ASTLoweringError> {
+        if self.opts.gas_metering != Some(GasMetering::Evm) {
+            return Ok(());
+        }
+        self.build_gas_charge(self.gas_cost(FUNCTION_CALL_COST.0, FUNCTION_CALL_COST.1))
+    }
+
+    /// Charges an iteration of a `for` loop with the evm gas metering
+    pub(crate) fn build_loop_iteration_gas_charge(&self) -> Result<(), ASTLoweringError> {
+        if self.opts.gas_metering != Some(GasMetering::Evm) {
+            return Ok(());
+        }
+        self.build_gas_charge(self.gas_cost(LOOP_ITERATION_COST.0, LOOP_ITERATION_COST.1))
+    }
+
+    /// Cost of an instruction with the wasm gas metering
+    fn wasm_instruction_cost(&self, instr: &InstructionValue<'a>) -> u64 {
+        let (name, scaled) = match instr.get_opcode() {
+            InstructionOpcode::Phi | InstructionOpcode::Alloca => return 0,
+            InstructionOpcode::Call => {
+                let intrinsic = callee_name(instr).is_some_and(|name| name.starts_with("llvm."));
+                (if intrinsic { "instruction" } else { "call" }, false)
+            }
+            InstructionOpcode::Load | InstructionOpcode::Store => ("memory", false),
+            InstructionOpcode::Mul => ("mul", true),
+            InstructionOpcode::UDiv
+            | InstructionOpcode::SDiv
+            | InstructionOpcode::URem
+            | InstructionOpcode::SRem => ("div", true),
+            _ => ("instruction", true),
+        };
+        let default = WASM_COSTS
+            .iter()
+            .find(|(cost_name, _)| *cost_name == name)
+            .unwrap()
+            .1;
+        let cost = self.gas_cost(name, default);
+        if scaled {
+            cost * int_words(instr)
+        } else {
+            cost
+        }
+    }
+
+    /// Cost of a block with the wasm gas metering, or the sum of the charges
+    /// lowered into it with the evm gas metering, which are removed
+    fn take_block_gas_cost(
+        &self,
+        gas_metering: GasMetering,
+        block: BasicBlock<'a>,
+        use_gas: FunctionValue<'a>,
+    ) -> u64 {
+        let use_gas_ptr = use_gas.as_global_value().as_pointer_value();
+        let mut cost = 0u64;
+        let mut charges = vec![];
+        let mut instr = block.get_first_instruction();
+        while let Some(current) = instr {
+            match gas_metering {
+                GasMetering::Wasm => {
+                    cost = cost.saturating_add(self.wasm_instruction_cost(&current));
+                }
+                GasMetering::Evm => {
+                    let is_charge = current.get_opcode() == InstructionOpcode::Call
+                        && current
+                            .get_operand(current.get_num_operands() - 1)
+                            .and_then(|operand| operand.left())
+                            == Some(use_gas_ptr.into());
+                    let constant_cost = current
+                        .get_operand(0)
+                        .and_then(|operand| operand.left())
+                        .filter(|value| value.is_int_value())
+                        .and_then(|value| value.into_int_value().get_zero_extended_constant());
+                    if let (true, Some(charge)) = (is_charge, constant_cost) {
+                        cost = cost.saturating_add(charge);
+                        charges.push(current);
+                    }
+                }
+            }
+            instr = current.get_next_instruction();
+        }
+        for charge in charges {
+            charge.erase_from_basic_block();
+        }
+        cost
+    }
+
+    /// The functions wizer runs at build time and all functions they call
+    fn unmetered_functions(&self) -> HashSet<String> {
+        let module = self.llvm_module.borrow();
+        let mut unmetered = HashSet::new();
+        let mut pending = UNMETERED_ENTRIES.map(String::from).to_vec();
+        while let Some(name) = pending.pop() {
+            if !unmetered.insert(name.clone()) {
+                continue;
+            }
+            let Some(function) = module.get_function(&name) else {
+                continue;
+            };
+            for block in function.get_basic_blocks() {
+                let mut instr = block.get_first_instruction();
+                while let Some(current) = instr {
+                    pending.extend(callee_name(&current));
+                    instr = current.get_next_instruction();
+                }
+            }
+        }
+        unmetered
+    }
+
+    /// Charges every basic block once on entry, after the LLVM passes
+    pub(crate) fn apply_gas_metering(&self) -> Result<(), ASTLoweringError> {
+        let Some(gas_metering) = self.opts.gas_metering else {
+            return Ok(());
+        };
+        let use_gas = self.use_gas_function();
+        let unmetered = self.unmetered_functions();
+        let functions = self
+            .llvm_module
+            .borrow()
+            .get_functions()
+            .collect::<Vec<_>>();
+        let builder = self.llvm_context.create_builder();
+        for function in functions {
+            let name = function.get_name().to_string_lossy();
+            if function.count_basic_blocks() == 0 || unmetered.contains(name.as_ref()) {
+                continue;
+            }
+            for block in function.get_basic_blocks() {
+                let cost = self.take_block_gas_cost(gas_metering, block, use_gas);
+                if cost == 0 {
+                    continue;
+                }
+                let Some(insertion_point) = first_insertion_point(block) else {
+                    continue;
+                };
+                builder.position_before(&insertion_point);
+                builder.build_call(
+                    use_gas,
+                    &[self.i64_type().const_int(cost, false).into()],
+                    "",
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
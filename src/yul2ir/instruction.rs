@@ -3,6 +3,7 @@
 
 use crate::yul2ir::ast::{FunctionCall, Identifier, Literal};
 use crate::yul2ir::infer::ExpectedType;
+use crate::yul2ir::remarks::{call_source, Remark};
 use crate::yul2ir::yul_instruction::YulInstructionName;
 use crate::{
     yul2ir::ast::Expression,
@@ -11,7 +12,7 @@ use crate::{
 };
 use ethereum_types::U256;
 use inkwell::types::StringRadix;
-use inkwell::values::{BasicValue, BasicValueEnum, PointerValue};
+use inkwell::values::{BasicValue, BasicValueEnum, IntValue, PointerValue};
 use inkwell::IntPredicate;
 
 use super::yul_instruction::{YulLowLevelValue, YulLowLevelValueType};
@@ -32,7 +33,55 @@ fn check_args_count(
     Ok(())
 }
 
+/// `dataoffset` of a data segment is its wasm address minus this, a negative i32.
+const EVM_DATA_OFFSET_BASE: u64 = 0x8000_0000;
+
+/// Address that forge-std and hardhat `console.log` send their staticcalls to.
+const CONSOLE_LOG_ADDRESS_HEX: &str = "000000000000000000636F6e736F6c652e6c6f67";
+
+/// solc's address decoders, in which `iszero` and `and` are lowered assuming
+/// the value is an address read from calldata.
+pub(crate) fn has_address_decoder_lowering(yul_func_name: &str) -> bool {
+    yul_func_name.contains("abi_decode_address") || yul_func_name.contains("abi_decode_t_address")
+}
+
+/// Builtins lowered differently in the functions of has_address_decoder_lowering.
+pub(crate) const ADDRESS_DECODER_LOWERED_BUILTINS: [&str; 2] = ["iszero", "and"];
+
+/// Returns the stdlib wrapper implementing the precompiled contract at `address`,
+/// None for the precompiles that go through the host call path.
+fn precompile_wrapper_name(address: U256) -> Option<&'static str> {
+    if address > U256::from(0x09) {
+        return None;
+    }
+    match address.low_u32() {
+        0x02 => Some("wrapper_precompile_sha256"),
+        0x04 => Some("wrapper_precompile_identity"),
+        0x09 => Some("wrapper_precompile_blake2f"),
+        _ => None,
+    }
+}
+
 impl<'a> Yul2IRContext<'a> {
+    /// Returns the precompile wrapper to call when the callee of a call is a constant precompile address.
+    /// With the static context check the precompiles are called through the host, so the
+    /// call-depth limit applies to them too.
+    fn constant_precompile_wrapper(&self, callee_expr: &Expression) -> Option<&'static str> {
+        if self.opts.enable_static_context_check {
+            return None;
+        }
+        let address = self.fetch_not_string_literal_constant(callee_expr)?;
+        precompile_wrapper_name(address)
+    }
+
+    /// Returns true if the callee of a staticcall is the constant console.log address.
+    fn is_console_log_address(&self, callee_expr: &Expression) -> bool {
+        self.matches_constant_literal(
+            callee_expr,
+            U256::from_str_radix(CONSOLE_LOG_ADDRESS_HEX, 16).unwrap(),
+        )
+    }
+
     fn read_string_literal(&self, expr: &Expression) -> Option<String> {
         let mut result: Option<String> = None;
         if let Expression::Literal(Literal::StringLiteral(s, _)) = expr {
@@ -132,7 +181,7 @@ impl<'a> Yul2IRContext<'a> {
             ],
 
             // For contract calls, use appropriate types for gas, address, value, and memory params
-            YulInstructionName::Call => vec![
+            YulInstructionName::Call | YulInstructionName::CallCode => vec![
                 ExpectedType::I64,     // gas
                 ExpectedType::Bytes32, // address
                 ExpectedType::U256,    // value
@@ -173,6 +222,23 @@ impl<'a> Yul2IRContext<'a> {
         Option<YulLowLevelValue<'a>>,
         Option<Vec<YulLowLevelValue<'a>>>,
     ) {
+        // keccak256(0, 0x40) of the mapping slot idiom, already hashed from the key and slot
+        if matches!(instr, YulInstructionName::Keccak256)
+            && args_exprs.len() == 2
+            && self.matches_constant_literal(&args_exprs[0], U256::zero())
+            && self.matches_constant_literal(&args_exprs[1], U256::from(0x40))
+        {
+            if let Some(result) = *self.mapping_slot_hash.borrow() {
+                return (
+                    Some(YulLowLevelValue {
+                        value_type: YulLowLevelValueType::Bytes32Pointer,
+                        value: result.into(),
+                    }),
+                    None,
+                );
+            }
+        }
+
         // Special case optimization for function selector extraction: shr(224, calldataload(0))
         if matches!(instr, YulInstructionName::Shr) && args_exprs.len() == 2 {
             // shr 224 is special, it means read the last 4 bytes of second arg
@@ -190,6 +256,14 @@ impl<'a> Yul2IRContext<'a> {
                                     if let Ok(result) =
                                         self.build_call("wrapper_calldata_load_selector", &[])
                                     {
+                                        self.add_remark(|| {
+                                            Remark::applied(
+                                                "selector_load",
+                                                yul_func_name,
+                                                call_source("shr", args_exprs),
+                                                "loads the 4 selector bytes with wrapper_calldata_load_selector".to_string(),
+                                            )
+                                        });
                                         return (
                                             Some(YulLowLevelValue {
                                                 value_type: YulLowLevelValueType::I32,
@@ -206,6 +280,27 @@ impl<'a> Yul2IRContext<'a> {
             }
         }
 
+        if matches!(instr, YulInstructionName::Shr) && args_exprs.len() == 2 {
+            if let Some(load_args) = self.matches_yul_instruction(&args_exprs[1], "calldataload", 1)
+            {
+                if self.matches_constant_literal(&args_exprs[0], U256::from(224)) {
+                    self.add_remark(|| {
+                        let reason = if self.matches_constant_literal(&load_args[0], U256::zero()) {
+                            "the shift and the offset are only matched as the decimal literals 224 and 0"
+                        } else {
+                            "the calldataload offset isn't 0, it doesn't load the selector"
+                        };
+                        Remark::missed(
+                            "selector_load",
+                            yul_func_name,
+                            call_source("shr", args_exprs),
+                            reason.to_string(),
+                        )
+                    });
+                }
+            }
+        }
+
         // Optimize for shl with constant arguments
         if matches!(instr, YulInstructionName::Shl) && args_exprs.len() == 2 {
             let shift = args_exprs[0].clone();
@@ -282,23 +377,44 @@ impl<'a> Yul2IRContext<'a> {
                 .matches_yul_instruction(&args_exprs[0], "calldatasize", 0)
                 .is_some()
             {
-                // Check if second arg is not(3)
-                if let Some(not_call_args) = self.matches_yul_instruction(&args_exprs[1], "not", 1)
-                {
-                    if self.matches_constant_literal(&not_call_args[0], U256::from(3)) {
-                        // This is add(calldatasize(), not(3)), which is used for checking calldata size
-                        // calldatasize not too large, and not(3) equals -4, so if calldatasize() > 4, return minus result
-                        // else return negative result.
-                        // Optimize by directly calling a specialized wrapper
-                        if let Ok(result) = self.build_call("wrapper_calldata_size_minus_4", &[]) {
-                            return (
-                                Some(YulLowLevelValue {
-                                    value_type: YulLowLevelValueType::I32,
-                                    value: result,
-                                }),
-                                None,
-                            );
-                        }
+                // Check if second arg is not(3), or its value after constant folding
+                let is_not_3 = match self.matches_yul_instruction(&args_exprs[1], "not", 1) {
+                    Some(not_call_args) => {
+                        self.matches_constant_literal(&not_call_args[0], U256::from(3))
+                    }
+                    None => self.matches_constant_literal(&args_exprs[1], !U256::from(3)),
+                };
+                if !is_not_3 {
+                    self.add_remark(|| {
+                        Remark::missed(
+                            "calldatasize_minus_4",
+                            yul_func_name,
+                            call_source("add", args_exprs),
+                            "the addend isn't not(3)".to_string(),
+                        )
+                    });
+                }
+                if is_not_3 {
+                    // This is add(calldatasize(), not(3)), which is used for checking calldata size
+                    // calldatasize not too large, and not(3) equals -4, so if calldatasize() > 4, return minus result
+                    // else return negative result.
+                    // Optimize by directly calling a specialized wrapper
+                    if let Ok(result) = self.build_call("wrapper_calldata_size_minus_4", &[]) {
+                        self.add_remark(|| {
+                            Remark::applied(
+                                "calldatasize_minus_4",
+                                yul_func_name,
+                                call_source("add", args_exprs),
+                                "calls wrapper_calldata_size_minus_4".to_string(),
+                            )
+                        });
+                        return (
+                            Some(YulLowLevelValue {
+                                value_type: YulLowLevelValueType::I32,
+                                value: result,
+                            }),
+                            None,
+                        );
                     }
                 }
             }
@@ -360,9 +476,7 @@ impl<'a> Yul2IRContext<'a> {
 
         //  Optimize iszero(eq(value, and(value, sub(shl(160, 1), 1)))) by checking if the first 12 bytes of u256/bytes32 are zero
 
-        if yul_func_name.contains("abi_decode_address")
-            || yul_func_name.contains("abi_decode_t_address")
-        {
+        if has_address_decoder_lowering(yul_func_name) {
             // TODO: Since abi_decode_address only uses the last 20 bytes of the address, we can simply return false for the iszero check
             if let YulInstructionName::IsZero = instr {
                 return (
@@ -383,24 +497,44 @@ impl<'a> Yul2IRContext<'a> {
                 let rhs = args_exprs.get(1).unwrap();
 
                 // Use simplified address mask pattern matching
-                if let Some(Expression::Identifier(addr_iden)) = self.matches_address_mask_pattern(
-                    &Expression::FunctionCall(Box::new(FunctionCall {
+                match self.matches_address_mask_pattern(&Expression::FunctionCall(Box::new(
+                    FunctionCall {
                         id: Identifier {
                             name: "and".to_string(),
                         },
                         arguments: vec![lhs.clone(), rhs.clone()],
-                    })),
-                ) {
-                    // Optimize by directly returning the address since only the last 20 bytes will be used
-                    let result = self.walk_identifier(&addr_iden).unwrap();
-                    return (Some(result), None);
+                    },
+                ))) {
+                    Some(Expression::Identifier(addr_iden)) => {
+                        self.add_remark(|| {
+                            Remark::applied(
+                                "address_mask",
+                                yul_func_name,
+                                call_source("and", args_exprs),
+                                format!(
+                                    "uses {} unmasked, only its low 20 bytes are read",
+                                    addr_iden.name
+                                ),
+                            )
+                        });
+                        // Optimize by directly returning the address since only the last 20 bytes will be used
+                        let result = self.walk_identifier(&addr_iden).unwrap();
+                        return (Some(result), None);
+                    }
+                    Some(_) => self.add_remark(|| {
+                        Remark::missed(
+                            "address_mask",
+                            yul_func_name,
+                            call_source("and", args_exprs),
+                            "the masked address isn't a variable".to_string(),
+                        )
+                    }),
+                    None => {}
                 }
             }
         }
 
-        if yul_func_name.contains("abi_decode_address")
-            || yul_func_name.contains("abi_decode_t_address")
-        {
+        if has_address_decoder_lowering(yul_func_name) {
             // code like:
             // function abi_decode_address() -> value {
             //     value := calldataload(4)
@@ -418,15 +552,12 @@ impl<'a> Yul2IRContext<'a> {
                             .walk_expr_with_type(yul_func_name, rhs, ExpectedType::Bytes32)
                             .unwrap();
                         let rhs = self.try_into_bytes32_pointer(&rhs.value).unwrap();
-                        // call wrapper_bytes32_and
+                        // bytes32 and
                         let result = self
                             .fast_alloca(self.bytes32_type(), "bytes32_and_result")
                             .unwrap();
-                        self.build_void_call(
-                            "wrapper_bytes32_and",
-                            &[lhs_bytes32_ptr.into(), rhs, result.into()],
-                        )
-                        .unwrap();
+                        self.build_bytes32_bitwise(&instr, lhs_bytes32_ptr.into(), rhs, result)
+                            .unwrap();
                         return (
                             Some(YulLowLevelValue {
                                 value_type: YulLowLevelValueType::Bytes32Pointer,
@@ -438,13 +569,6 @@ impl<'a> Yul2IRContext<'a> {
                 }
             }
         }
-        // In the fun_transfer function, the is_zero instruction is often used to check if an address is the zero address. Therefore, we prioritize parsing the argument as an address (bytes32) type to avoid unnecessary encoding and decoding.
-        if yul_func_name.contains("fun_transfer") && matches!(instr, YulInstructionName::IsZero) {
-            // Process the argument as expected bytes32 type and continue execution
-            let args = self.walk_args(yul_func_name, args_exprs, &[ExpectedType::Bytes32]);
-            return (None, Some(args));
-        }
-
         // Optimize mstore(64, value) and mload(64) operations to use wasm global variables
         if self.opts.enable_all_optimizers {
             if matches!(instr, YulInstructionName::MStore) && args_exprs.len() == 2 {
@@ -461,6 +585,14 @@ impl<'a> Yul2IRContext<'a> {
                         let value = self.try_into_i32_value(&value.value, &value_expr).unwrap();
                         self.build_void_call("wrapper_set_memptr_global", &[value.into()])
                             .unwrap();
+                        self.add_remark(|| {
+                            Remark::applied(
+                                "memptr_global",
+                                yul_func_name,
+                                call_source("mstore", args_exprs),
+                                "stores the free memory pointer in a wasm global".to_string(),
+                            )
+                        });
 
                         // let memptr_global = self.memptr_global.borrow().unwrap();
                         // self.build_store(
@@ -487,6 +619,14 @@ impl<'a> Yul2IRContext<'a> {
                         // mload(64)
                         // call wrapper_get_memptr_global()
                         let result = self.build_call("wrapper_get_memptr_global", &[]).unwrap();
+                        self.add_remark(|| {
+                            Remark::applied(
+                                "memptr_global",
+                                yul_func_name,
+                                call_source("mload", args_exprs),
+                                "loads the free memory pointer from a wasm global".to_string(),
+                            )
+                        });
 
                         // let memptr_global = self.memptr_global.borrow().unwrap();
                         // let result = self
@@ -504,6 +644,30 @@ impl<'a> Yul2IRContext<'a> {
             }
         }
 
+        if !self.opts.enable_all_optimizers
+            && matches!(
+                instr,
+                YulInstructionName::MStore | YulInstructionName::MLoad
+            )
+            && !args_exprs.is_empty()
+            && self.matches_constant_literal(&args_exprs[0], U256::from(64))
+        {
+            self.add_remark(|| {
+                let name = if args_exprs.len() == 2 {
+                    "mstore"
+                } else {
+                    "mload"
+                };
+                Remark::missed(
+                    "memptr_global",
+                    yul_func_name,
+                    call_source(name, args_exprs),
+                    "the free memory pointer stays in EVM memory without --enable-all-optimizers"
+                        .to_string(),
+                )
+            });
+        }
+
         (None, None)
     }
 
@@ -530,12 +694,51 @@ impl<'a> Yul2IRContext<'a> {
         args
     }
 
+    /// Calls a stdlib u256 function taking its operands and result by pointer
+    fn build_u256_math_call(
+        &self,
+        func_name: &str,
+        operands: &[IntValue<'a>],
+        result_name: &str,
+    ) -> CompileResult<'a> {
+        let mut call_args: Vec<BasicValueEnum<'a>> = vec![];
+        for operand in operands {
+            call_args.push(self.get_value_pointer(*operand)?.into());
+        }
+        let result_ptr = self.fast_alloca(self.u256_type(), result_name)?;
+        call_args.push(result_ptr.into());
+        self.build_void_call(func_name, &call_args)?;
+        Ok(YulLowLevelValue {
+            value_type: YulLowLevelValueType::U256,
+            value: self.build_load(self.u256_type(), result_ptr, "")?,
+        })
+    }
+
     pub(crate) fn walk_yul_instruction(
         &self,
         yul_func_name: &str,
         instr: YulInstructionName,
         args: &[Expression],
         expected_type: ExpectedType,
+    ) -> CompileResult<'a> {
+        if let Some(hash) = self.try_constant_memory_keccak256(&instr, args) {
+            return Ok(hash);
+        }
+        if let Some(result) = self.try_strength_reduce(yul_func_name, &instr, args)? {
+            return Ok(result);
+        }
+        let result =
+            self.lower_yul_instruction(yul_func_name, instr.clone(), args, expected_type)?;
+        self.track_constant_memory(&instr, args);
+        Ok(result)
+    }
+
+    fn lower_yul_instruction(
+        &self,
+        yul_func_name: &str,
+        instr: YulInstructionName,
+        args: &[Expression],
+        expected_type: ExpectedType,
     ) -> CompileResult<'a> {
         let args_exprs = args;
 
@@ -547,6 +750,11 @@ impl<'a> Yul2IRContext<'a> {
             args.len(),
         );
 
+        if self.opts.enable_static_context_check && instr.is_state_modifying() {
+            self.build_void_call("wrapper_check_static_context", &[])?;
+        }
+        self.build_builtin_gas_charge(&instr)?;
+
         // This is done to avoid redundant walk_args calls (hostapi calls will still be preserved)
         let (optimized_result, optimized_args) = self.try_optimize_instruction_pattern(
             yul_func_name,
@@ -570,6 +778,10 @@ impl<'a> Yul2IRContext<'a> {
             .map(|arg| arg.get_value())
             .collect::<Vec<_>>();
 
+        if self.opts.enable_safe_memory {
+            self.build_memory_range_checks(&instr, &args)?;
+        }
+
         match instr {
             YulInstructionName::Stop => {
                 self.build_void_call("wrapper_stop", &args)?;
@@ -661,6 +873,19 @@ impl<'a> Yul2IRContext<'a> {
                 let a = args.first().unwrap();
                 let b = args.get(1).unwrap();
                 let m = args.get(2).unwrap();
+                if self.opts.enable_limb_u256_math {
+                    // computed in the stdlib, hosts don't need the addmod import
+                    let operands = [
+                        self.try_into_u256(a)?,
+                        self.try_into_u256(b)?,
+                        self.try_into_u256(m)?,
+                    ];
+                    return self.build_u256_math_call(
+                        "wrapper_limb_u256_addmod",
+                        &operands,
+                        "addmod_result",
+                    );
+                }
                 let (a, b) = self.unify_to_bytes32_pointer(a, b)?;
                 let m = self.try_into_bytes32_pointer(m)?;
                 let result_ptr = self.fast_alloca(self.bytes32_type(), "addmod_result")?;
@@ -676,6 +901,19 @@ impl<'a> Yul2IRContext<'a> {
                 let a = args.first().unwrap();
                 let b = args.get(1).unwrap();
                 let m = args.get(2).unwrap();
+                if self.opts.enable_limb_u256_math {
+                    // computed in the stdlib, hosts don't need the mulmod import
+                    let operands = [
+                        self.try_into_u256(a)?,
+                        self.try_into_u256(b)?,
+                        self.try_into_u256(m)?,
+                    ];
+                    return self.build_u256_math_call(
+                        "wrapper_limb_u256_mulmod",
+                        &operands,
+                        "mulmod_result",
+                    );
+                }
                 let (a, b) = self.unify_to_bytes32_pointer(a, b)?;
                 let m = self.try_into_bytes32_pointer(m)?;
                 let result_ptr = self.fast_alloca(self.bytes32_type(), "mulmod_result")?;
@@ -710,15 +948,13 @@ impl<'a> Yul2IRContext<'a> {
                     self.try_into_int(args.first().unwrap())?,
                     self.try_into_int(args.get(1).unwrap())?,
                 )?;
-                let ret_value_ty = YulLowLevelValueType::from_int_type(arg0.get_type());
-                Ok(YulLowLevelValue {
-                    value_type: ret_value_ty,
-                    value: self
-                        .builder
-                        .borrow_mut()
-                        .build_int_unsigned_div(arg0, arg1, "div_result")?
-                        .into(),
-                })
+                // zero divisors and narrow operands are handled in the stdlib
+                let func_name = if self.opts.enable_limb_u256_math {
+                    "wrapper_limb_u256_div"
+                } else {
+                    "wrapper_u256_div"
+                };
+                self.build_u256_math_call(func_name, &[arg0, arg1], "div_result")
             }
             YulInstructionName::SDiv => {
                 check_args_count(&instr, &args, 2)?;
@@ -726,6 +962,13 @@ impl<'a> Yul2IRContext<'a> {
                     self.try_into_int(args.first().unwrap())?,
                     self.try_into_int(args.get(1).unwrap())?,
                 )?;
+                if self.opts.enable_limb_u256_math {
+                    return self.build_u256_math_call(
+                        "wrapper_limb_u256_sdiv",
+                        &[arg0, arg1],
+                        "sdiv_result",
+                    );
+                }
                 let ret_value_ty = YulLowLevelValueType::from_int_type(arg0.get_type());
                 Ok(YulLowLevelValue {
                     value_type: ret_value_ty,
@@ -742,15 +985,12 @@ impl<'a> Yul2IRContext<'a> {
                     self.try_into_int(args.first().unwrap())?,
                     self.try_into_int(args.get(1).unwrap())?,
                 )?;
-                let ret_value_ty = YulLowLevelValueType::from_int_type(arg0.get_type());
-                Ok(YulLowLevelValue {
-                    value_type: ret_value_ty,
-                    value: self
-                        .builder
-                        .borrow_mut()
-                        .build_int_unsigned_rem(arg0, arg1, "mod_result")?
-                        .into(),
-                })
+                let func_name = if self.opts.enable_limb_u256_math {
+                    "wrapper_limb_u256_mod"
+                } else {
+                    "wrapper_u256_mod"
+                };
+                self.build_u256_math_call(func_name, &[arg0, arg1], "mod_result")
             }
             YulInstructionName::SMod => {
                 check_args_count(&instr, &args, 2)?;
@@ -758,6 +998,13 @@ impl<'a> Yul2IRContext<'a> {
                     self.try_into_int(args.first().unwrap())?,
                     self.try_into_int(args.get(1).unwrap())?,
                 )?;
+                if self.opts.enable_limb_u256_math {
+                    return self.build_u256_math_call(
+                        "wrapper_limb_u256_smod",
+                        &[arg0, arg1],
+                        "smod_result",
+                    );
+                }
                 let ret_value_ty = YulLowLevelValueType::from_int_type(arg0.get_type());
                 Ok(YulLowLevelValue {
                     value_type: ret_value_ty,
@@ -776,8 +1023,13 @@ impl<'a> Yul2IRContext<'a> {
                 let exp_ptr = self.get_value_pointer(exp)?;
                 let result_ptr: PointerValue<'a> =
                     self.fast_alloca(self.u256_type(), "exp_result")?;
+                let func_name = if self.opts.enable_limb_u256_math {
+                    "wrapper_limb_u256_exp"
+                } else {
+                    "wrapper_exp"
+                };
                 self.build_void_call(
-                    "wrapper_exp",
+                    func_name,
                     &[base_ptr.into(), exp_ptr.into(), result_ptr.into()],
                 )?;
                 // u256 pointer can't return directly, so we need to load the result
@@ -794,10 +1046,7 @@ impl<'a> Yul2IRContext<'a> {
                 if self.is_bytes32_value(value) {
                     let value_ptr = self.get_value_pointer(*value)?;
                     let result_ptr = self.fast_alloca(self.bytes32_type(), "not_result")?;
-                    self.build_void_call(
-                        "wrapper_bytes32_not",
-                        &[value_ptr.into(), result_ptr.into()],
-                    )?;
+                    self.build_bytes32_not(value_ptr.into(), result_ptr)?;
                     // bytes32 pointer can return directly
                     return Ok(YulLowLevelValue {
                         value_type: YulLowLevelValueType::Bytes32Pointer,
@@ -806,7 +1055,7 @@ impl<'a> Yul2IRContext<'a> {
                 }
                 if self.is_bytes32_pointer_value(value) {
                     let result_ptr = self.fast_alloca(self.bytes32_type(), "not_result")?;
-                    self.build_void_call("wrapper_bytes32_not", &[*value, result_ptr.into()])?;
+                    self.build_bytes32_not(*value, result_ptr)?;
                     // bytes32 pointer can return directly
                     return Ok(YulLowLevelValue {
                         value_type: YulLowLevelValueType::Bytes32Pointer,
@@ -914,9 +1163,10 @@ impl<'a> Yul2IRContext<'a> {
                 //     });
                 // }
 
-                let arg0 = self.try_into_int(arg0)?;
-                let arg1 = self.try_into_int(arg1)?;
-                let (arg0, arg1) = self.unify_ints(arg0, arg1)?;
+                // narrow operands are zero extended to u256, comparing them as signed
+                // at their own width would read their top bit as the sign
+                let arg0 = self.try_into_u256(arg0)?;
+                let arg1 = self.try_into_u256(arg1)?;
                 // For u256 == u256 comparison, we need to convert the result from i1 to i32 type with zero extension,
                 // otherwise the higher bits will be incorrect
                 let cmp_result_i1 = self.builder.borrow_mut().build_int_compare(
@@ -949,9 +1199,10 @@ impl<'a> Yul2IRContext<'a> {
                 //     });
                 // }
 
-                let arg0 = self.try_into_int(arg0)?;
-                let arg1 = self.try_into_int(arg1)?;
-                let (arg0, arg1) = self.unify_ints(arg0, arg1)?;
+                // narrow operands are zero extended to u256, comparing them as signed
+                // at their own width would read their top bit as the sign
+                let arg0 = self.try_into_u256(arg0)?;
+                let arg1 = self.try_into_u256(arg1)?;
                 // For u256 == u256 comparison, we need to convert the result from i1 to i32 type with zero extension, otherwise the higher bits will be incorrect
                 let cmp_result_i1 = self.builder.borrow_mut().build_int_compare(
                     IntPredicate::SGT,
@@ -978,7 +1229,7 @@ impl<'a> Yul2IRContext<'a> {
                 {
                     let (arg0, arg1) = self.unify_to_bytes32_pointer(arg0, arg1)?;
                     // call bytes32_eq to compare
-                    let result_bool_i32 = self.build_call("wrapper_bytes32_eq", &[arg0, arg1])?;
+                    let result_bool_i32 = self.build_bytes32_eq(arg0, arg1)?;
                     return Ok(YulLowLevelValue {
                         value_type: YulLowLevelValueType::I32,
                         value: result_bool_i32,
@@ -1006,8 +1257,7 @@ impl<'a> Yul2IRContext<'a> {
                 let value = args.first().unwrap();
                 if self.is_bytes32_value(value) || self.is_bytes32_pointer_value(value) {
                     let value_ptr = self.try_into_bytes32_pointer(value)?;
-                    let result_bool_i32 =
-                        self.build_call("wrapper_bytes32_iszero", &[value_ptr])?;
+                    let result_bool_i32 = self.build_bytes32_iszero(value_ptr)?;
                     return Ok(YulLowLevelValue {
                         value_type: YulLowLevelValueType::I32,
                         value: result_bool_i32,
@@ -1043,7 +1293,7 @@ impl<'a> Yul2IRContext<'a> {
                 {
                     let (arg0, arg1) = self.unify_to_bytes32_pointer(arg0, arg1)?;
                     let result_ptr = self.fast_alloca(self.bytes32_type(), "and_result")?;
-                    self.build_void_call("wrapper_bytes32_and", &[arg0, arg1, result_ptr.into()])?;
+                    self.build_bytes32_bitwise(&instr, arg0, arg1, result_ptr)?;
                     return Ok(YulLowLevelValue {
                         value_type: YulLowLevelValueType::Bytes32Pointer,
                         value: result_ptr.into(),
@@ -1071,9 +1321,11 @@ impl<'a> Yul2IRContext<'a> {
                     let arg0_ptr = self.get_value_pointer(arg0)?;
                     let arg1_ptr = self.get_value_pointer(arg1)?;
                     let result_ptr = self.fast_alloca(self.bytes32_type(), "or_result")?;
-                    self.build_void_call(
-                        "wrapper_bytes32_or",
-                        &[arg0_ptr.into(), arg1_ptr.into(), result_ptr.into()],
+                    self.build_bytes32_bitwise(
+                        &instr,
+                        arg0_ptr.into(),
+                        arg1_ptr.into(),
+                        result_ptr,
                     )?;
                     // bytes32 pointer can return directly
                     return Ok(YulLowLevelValue {
@@ -1102,9 +1354,11 @@ impl<'a> Yul2IRContext<'a> {
                     let arg0_ptr = self.get_value_pointer(arg0)?;
                     let arg1_ptr = self.get_value_pointer(arg1)?;
                     let result_ptr = self.fast_alloca(self.bytes32_type(), "xor_result")?;
-                    self.build_void_call(
-                        "wrapper_bytes32_xor",
-                        &[arg0_ptr.into(), arg1_ptr.into(), result_ptr.into()],
+                    self.build_bytes32_bitwise(
+                        &instr,
+                        arg0_ptr.into(),
+                        arg1_ptr.into(),
+                        result_ptr,
                     )?;
                     // bytes32 pointer can return directly
                     return Ok(YulLowLevelValue {
@@ -1810,18 +2064,11 @@ impl<'a> Yul2IRContext<'a> {
                 // The first 4 bytes contain the length in big endian format, but we don't skip them
                 let data_offset = global_var_addr;
 
-                let memory_begin = self.build_call(
-                    "evm_get_memory_addr",
-                    &[self.i32_type().const_int(0, false).into()],
-                )?;
-                let memory_begin = self.builder.borrow_mut().build_ptr_to_int(
-                    memory_begin.into_pointer_value(),
-                    self.i32_type(),
-                    "",
-                )?;
+                // Tagged with the sign bit instead of relative to the EVM memory,
+                // which can move when it grows (EVM_DATA_OFFSET_BASE in evm_memory.h)
                 let result = self.builder.borrow_mut().build_int_sub(
                     data_offset,
-                    memory_begin,
+                    self.i32_type().const_int(EVM_DATA_OFFSET_BASE, false),
                     "data_offset_result",
                 )?;
                 Ok(YulLowLevelValue {
@@ -2180,8 +2427,13 @@ impl<'a> Yul2IRContext<'a> {
 
                 let ret_ty = self.bytes32_type();
                 let result_ptr: PointerValue<'a> = self.fast_alloca(ret_ty, "")?;
+                let wrapper_name = if self.opts.enable_static_context_check {
+                    "wrapper_create_in_context"
+                } else {
+                    "wrapper_create"
+                };
                 self.build_void_call(
-                    "wrapper_create",
+                    wrapper_name,
                     &[
                         value_ptr.into(),
                         code_evm_mem.into(),
@@ -2212,8 +2464,13 @@ impl<'a> Yul2IRContext<'a> {
 
                 let ret_ty = self.bytes32_type();
                 let result_ptr: PointerValue<'a> = self.fast_alloca(ret_ty, "")?;
+                let wrapper_name = if self.opts.enable_static_context_check {
+                    "wrapper_create2_in_context"
+                } else {
+                    "wrapper_create2"
+                };
                 self.build_void_call(
-                    "wrapper_create2",
+                    wrapper_name,
                     &[
                         value_ptr.into(),
                         code_evm_mem.into(),
@@ -2227,7 +2484,7 @@ impl<'a> Yul2IRContext<'a> {
                     value: result_ptr.into(),
                 })
             }
-            YulInstructionName::Call => {
+            YulInstructionName::Call | YulInstructionName::CallCode => {
                 check_args_count(&instr, &args, 7)?;
                 let gas: &BasicValueEnum<'a> = args.first().unwrap();
                 let gas = self.try_into_i64(gas)?;
@@ -2249,8 +2506,39 @@ impl<'a> Yul2IRContext<'a> {
                 let out_length =
                     self.try_into_i32_value(args.get(6).unwrap(), args_exprs.get(6).unwrap())?;
 
+                // A call without value to a constant precompile address is served by the stdlib
+                if matches!(instr, YulInstructionName::Call)
+                    && self.matches_constant_literal(&args_exprs[2], U256::zero())
+                {
+                    if let Some(wrapper_name) = self.constant_precompile_wrapper(&args_exprs[1]) {
+                        let result = self.build_call(
+                            wrapper_name,
+                            &[
+                                gas.into(),
+                                in_offset.into(),
+                                in_length.into(),
+                                out_evm_offset.into(),
+                                out_length.into(),
+                            ],
+                        )?;
+                        return Ok(YulLowLevelValue {
+                            value_type: YulLowLevelValueType::I32,
+                            value: result,
+                        });
+                    }
+                }
+
+                // callcode runs the callee's code in the current account's context,
+                // otherwise it has the same arguments and result as call
+                let is_call_code = matches!(instr, YulInstructionName::CallCode);
+                let wrapper_name = match (is_call_code, self.opts.enable_static_context_check) {
+                    (true, false) => "wrapper_callcode",
+                    (true, true) => "wrapper_callcode_in_context",
+                    (false, false) => "wrapper_call_contract",
+                    (false, true) => "wrapper_call_contract_in_context",
+                };
                 let result = self.build_call(
-                    "wrapper_call_contract",
+                    wrapper_name,
                     &[
                         gas.into(),
                         callee_addr,
@@ -2266,9 +2554,6 @@ impl<'a> Yul2IRContext<'a> {
                     value: result,
                 })
             }
-            YulInstructionName::CallCode => {
-                unreachable!("callcode not supported, use delegatecall instead")
-            }
             YulInstructionName::DelegateCall => {
                 check_args_count(&instr, &args, 6)?;
                 let gas: &BasicValueEnum<'a> = args.first().unwrap();
@@ -2287,8 +2572,13 @@ impl<'a> Yul2IRContext<'a> {
                 let out_length =
                     self.try_into_i32_value(args.get(5).unwrap(), args_exprs.get(5).unwrap())?;
 
+                let wrapper_name = if self.opts.enable_static_context_check {
+                    "wrapper_delegatecall_in_context"
+                } else {
+                    "wrapper_delegatecall"
+                };
                 let result = self.build_call(
-                    "wrapper_delegatecall",
+                    wrapper_name,
                     &[
                         gas.into(),
                         callee_addr,
@@ -2321,8 +2611,40 @@ impl<'a> Yul2IRContext<'a> {
                 let out_length =
                     self.try_into_i32_value(args.get(5).unwrap(), args_exprs.get(5).unwrap())?;
 
+                if let Some(wrapper_name) = self.constant_precompile_wrapper(&args_exprs[1]) {
+                    let result = self.build_call(
+                        wrapper_name,
+                        &[
+                            gas.into(),
+                            in_offset.into(),
+                            in_length.into(),
+                            out_evm_offset.into(),
+                            out_length.into(),
+                        ],
+                    )?;
+                    return Ok(YulLowLevelValue {
+                        value_type: YulLowLevelValueType::I32,
+                        value: result,
+                    });
+                }
+
+                // console.log calls print through the debug host api instead of calling a non-existent account
+                if self.opts.debug_mode && self.is_console_log_address(&args_exprs[1]) {
+                    let result = self
+                        .build_call("wrapper_console_log", &[in_offset.into(), in_length.into()])?;
+                    return Ok(YulLowLevelValue {
+                        value_type: YulLowLevelValueType::I32,
+                        value: result,
+                    });
+                }
+
+                let wrapper_name = if self.opts.enable_static_context_check {
+                    "wrapper_staticcall_in_context"
+                } else {
+                    "wrapper_staticcall"
+                };
                 let result = self.build_call(
-                    "wrapper_staticcall",
+                    wrapper_name,
                     &[
                         gas.into(),
                         callee_addr,
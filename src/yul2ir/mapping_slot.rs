@@ -0,0 +1,381 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lowering of the mapping slot idiom that solc emits for every mapping access:
+//! `mstore(0, key) mstore(0x20, slot)` followed by a statement using
+//! `keccak256(0, 0x40)`.
+//!
+//! The key and slot are hashed from a stack buffer by one runtime call instead
+//! of being stored to the EVM scratch space and hashed from there. The scratch
+//! space writes are only kept when a later statement can observe them.
+
+use crate::yul2ir::ast::{Expression, FunctionCall, Statement, SwitchOptions};
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::infer::ExpectedType;
+use crate::yul2ir::yul_instruction::{parse_intrinsic_func_name, YulInstructionName};
+
+use ethereum_types::U256;
+use std::collections::HashSet;
+
+/// Memory below this offset is the scratch space solc uses for hashing
+const SCRATCH_SPACE_END: u64 = 0x40;
+/// The free memory pointer is stored here and never points below 0x80
+const FREE_MEMORY_POINTER_OFFSET: u64 = 0x40;
+
+#[derive(Debug)]
+pub struct MappingSlotIdiom<'b> {
+    pub key: &'b Expression,
+    pub slot: &'b Expression,
+    /// The statement using keccak256(0, 0x40)
+    pub user: &'b Statement,
+    /// A later statement can read the key and slot from the scratch space
+    pub keep_scratch: bool,
+}
+
+fn builtin_call(expr: &Expression) -> Option<(YulInstructionName, &[Expression])> {
+    match expr {
+        Expression::FunctionCall(call) => {
+            parse_intrinsic_func_name(&call.id.name).map(|instr| (instr, call.arguments.as_slice()))
+        }
+        _ => None,
+    }
+}
+
+/// The expression neither reads nor writes memory
+fn is_memory_free(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) | Expression::Literal(_) => true,
+        Expression::FunctionCall(call) => {
+            parse_intrinsic_func_name(&call.id.name).is_some_and(|instr| !instr.accesses_memory())
+                && call.arguments.iter().all(is_memory_free)
+        }
+    }
+}
+
+/// Finds out whether the statements following the idiom read the scratch
+/// space before it is overwritten or the execution ends
+struct ScratchSpaceScan<'c, 'a> {
+    ctx: &'c Yul2IRContext<'a>,
+    // locals holding the free memory pointer, they never point into the
+    // scratch space
+    free_pointers: HashSet<String>,
+}
+
+impl ScratchSpaceScan<'_, '_> {
+    fn is_free_memory_pointer(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Identifier(id) => self.free_pointers.contains(&id.name),
+            _ => match builtin_call(expr) {
+                Some((YulInstructionName::MLoad, [offset])) => self
+                    .ctx
+                    .matches_constant_literal(offset, U256::from(FREE_MEMORY_POINTER_OFFSET)),
+                Some((YulInstructionName::Add, [a, b])) => {
+                    (self.is_free_memory_pointer(a)
+                        && self.ctx.fetch_not_string_literal_constant(b).is_some())
+                        || (self.is_free_memory_pointer(b)
+                            && self.ctx.fetch_not_string_literal_constant(a).is_some())
+                }
+                _ => false,
+            },
+        }
+    }
+
+    fn overlaps_scratch(&self, offset: &Expression, size: Option<U256>) -> bool {
+        if size == Some(U256::zero()) || self.is_free_memory_pointer(offset) {
+            return false;
+        }
+        match self.ctx.fetch_not_string_literal_constant(offset) {
+            Some(offset) => offset < U256::from(SCRATCH_SPACE_END),
+            None => true,
+        }
+    }
+
+    fn expr_observes(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::FunctionCall(call) => self.call_observes(call),
+            _ => false,
+        }
+    }
+
+    fn call_observes(&self, call: &FunctionCall) -> bool {
+        if call.arguments.iter().any(|arg| self.expr_observes(arg)) {
+            return true;
+        }
+        let Some(instr) = parse_intrinsic_func_name(&call.id.name) else {
+            // user functions can read any memory
+            return true;
+        };
+        match (&instr, call.arguments.as_slice()) {
+            (YulInstructionName::MLoad, [offset]) => {
+                self.overlaps_scratch(offset, Some(U256::from(32)))
+            }
+            (YulInstructionName::MCopy, [_, src, size]) => {
+                self.overlaps_scratch(src, self.ctx.fetch_not_string_literal_constant(size))
+            }
+            (
+                YulInstructionName::Keccak256
+                | YulInstructionName::Return
+                | YulInstructionName::Revert
+                | YulInstructionName::Log0
+                | YulInstructionName::Log1
+                | YulInstructionName::Log2
+                | YulInstructionName::Log3
+                | YulInstructionName::Log4,
+                [offset, size, ..],
+            ) => self.overlaps_scratch(offset, self.ctx.fetch_not_string_literal_constant(size)),
+            // dropping the scratch space writes can shrink the memory size
+            (YulInstructionName::MSize, _) => true,
+            // these only write memory
+            (
+                YulInstructionName::MStore
+                | YulInstructionName::MStore8
+                | YulInstructionName::CallDataCopy
+                | YulInstructionName::CodeCopy
+                | YulInstructionName::ExtCodeCopy
+                | YulInstructionName::DataCopy
+                | YulInstructionName::ReturnDataCopy,
+                _,
+            ) => false,
+            _ => instr.accesses_memory(),
+        }
+    }
+
+    fn block_observes(&mut self, stmts: &[Statement]) -> bool {
+        stmts.iter().any(|stmt| self.stmt_observes(stmt))
+    }
+
+    fn stmt_observes(&mut self, stmt: &Statement) -> bool {
+        match stmt {
+            Statement::VariableDeclaration(var_decl) => {
+                let Some(value) = &var_decl.value else {
+                    return false;
+                };
+                if var_decl.identifiers.len() == 1 && self.is_free_memory_pointer(value) {
+                    self.free_pointers
+                        .insert(var_decl.identifiers[0].identifier.name.clone());
+                }
+                self.expr_observes(value)
+            }
+            Statement::Assignment(assign) => {
+                for id in &assign.identifiers {
+                    self.free_pointers.remove(&id.name);
+                }
+                self.expr_observes(&assign.value)
+            }
+            Statement::FunctionCall(call) => self.call_observes(call),
+            Statement::If(r#if) => {
+                self.expr_observes(&r#if.cond) || self.block_observes(&r#if.body.statements)
+            }
+            Statement::Switch(switch) => {
+                if self.expr_observes(&switch.condition) {
+                    return true;
+                }
+                match &switch.opt {
+                    SwitchOptions::Cases(cases, default) => {
+                        cases
+                            .iter()
+                            .any(|case| self.block_observes(&case.body.statements))
+                            || default.as_ref().is_some_and(|default| {
+                                self.block_observes(&default.body.statements)
+                            })
+                    }
+                    SwitchOptions::Default(default) => {
+                        self.block_observes(&default.body.statements)
+                    }
+                }
+            }
+            Statement::For(r#for) => {
+                self.block_observes(&r#for.init_block.statements)
+                    || self.expr_observes(&r#for.condition)
+                    || self.block_observes(&r#for.execution_block.statements)
+                    || self.block_observes(&r#for.post_block.statements)
+            }
+            Statement::Block(block) => self.block_observes(&block.statements),
+            // the code after the loop or the caller can read the scratch space
+            Statement::Leave | Statement::Break | Statement::Continue => true,
+            Statement::FunctionDefinition(_) | Statement::Comment => false,
+        }
+    }
+
+    /// Whether the scratch space written before `stmts` can be read by them or
+    /// by the code running after them
+    fn scratch_observed(&mut self, stmts: &[Statement]) -> bool {
+        let mut overwritten = [false, false];
+        for stmt in stmts {
+            if let Statement::FunctionCall(call) = stmt {
+                if let (Some(YulInstructionName::MStore), [offset, value]) = (
+                    parse_intrinsic_func_name(&call.id.name),
+                    call.arguments.as_slice(),
+                ) {
+                    if self.expr_observes(offset) || self.expr_observes(value) {
+                        return true;
+                    }
+                    if self.ctx.matches_constant_literal(offset, U256::zero()) {
+                        overwritten[0] = true;
+                    } else if self.ctx.matches_constant_literal(offset, U256::from(0x20)) {
+                        overwritten[1] = true;
+                    }
+                    if overwritten == [true, true] {
+                        return false;
+                    }
+                    continue;
+                }
+            }
+            if self.stmt_observes(stmt) {
+                return true;
+            }
+            // the execution ends here without reading the scratch space
+            if let Statement::FunctionCall(call) = stmt {
+                if matches!(
+                    parse_intrinsic_func_name(&call.id.name),
+                    Some(
+                        YulInstructionName::Return
+                            | YulInstructionName::Revert
+                            | YulInstructionName::Stop
+                            | YulInstructionName::Invalid
+                    )
+                ) {
+                    return false;
+                }
+            }
+        }
+        // the code after this block or the caller can read the scratch space
+        true
+    }
+}
+
+impl<'a> Yul2IRContext<'a> {
+    fn matches_scratch_mstore<'b>(
+        &self,
+        stmt: &'b Statement,
+        offset: u64,
+    ) -> Option<&'b Expression> {
+        match stmt {
+            Statement::FunctionCall(call)
+                if call.id.name == "mstore"
+                    && call.arguments.len() == 2
+                    && self.matches_constant_literal(&call.arguments[0], U256::from(offset))
+                    && is_memory_free(&call.arguments[1]) =>
+            {
+                Some(&call.arguments[1])
+            }
+            _ => None,
+        }
+    }
+
+    fn is_mapping_slot_hash(&self, expr: &Expression) -> bool {
+        matches!(builtin_call(expr), Some((YulInstructionName::Keccak256, [offset, size]))
+            if self.matches_constant_literal(offset, U256::zero())
+                && self.matches_constant_literal(size, U256::from(0x40)))
+    }
+
+    // The expression uses keccak256(0, 0x40) and doesn't access memory otherwise,
+    // so the hash is the only thing that sees the scratch space writes
+    fn uses_mapping_slot_hash(&self, expr: &Expression, found: &mut bool) -> bool {
+        if self.is_mapping_slot_hash(expr) {
+            *found = true;
+            return true;
+        }
+        match expr {
+            Expression::Identifier(_) | Expression::Literal(_) => true,
+            Expression::FunctionCall(call) => {
+                parse_intrinsic_func_name(&call.id.name)
+                    .is_some_and(|instr| !instr.accesses_memory())
+                    && call
+                        .arguments
+                        .iter()
+                        .all(|arg| self.uses_mapping_slot_hash(arg, found))
+            }
+        }
+    }
+
+    fn is_mapping_slot_user(&self, stmt: &Statement) -> bool {
+        let mut found = false;
+        let memory_free = match stmt {
+            Statement::VariableDeclaration(var_decl) => var_decl
+                .value
+                .as_ref()
+                .is_some_and(|value| self.uses_mapping_slot_hash(value, &mut found)),
+            Statement::Assignment(assign) => self.uses_mapping_slot_hash(&assign.value, &mut found),
+            Statement::FunctionCall(call) => call
+                .arguments
+                .iter()
+                .all(|arg| self.uses_mapping_slot_hash(arg, &mut found)),
+            _ => false,
+        };
+        memory_free && found
+    }
+
+    pub fn matches_mapping_slot_idiom<'b>(
+        &self,
+        stmts: &'b [Statement],
+    ) -> Option<MappingSlotIdiom<'b>> {
+        let [key_store, slot_store, user, rest @ ..] = stmts else {
+            return None;
+        };
+        let key = self.matches_scratch_mstore(key_store, 0x00)?;
+        let slot = self.matches_scratch_mstore(slot_store, 0x20)?;
+        if !self.is_mapping_slot_user(user) {
+            return None;
+        }
+        let mut scan = ScratchSpaceScan {
+            ctx: self,
+            free_pointers: HashSet::new(),
+        };
+        Some(MappingSlotIdiom {
+            key,
+            slot,
+            user,
+            keep_scratch: scan.scratch_observed(rest),
+        })
+    }
+
+    /// Walks the statement at `index`, or the mapping slot idiom starting there.
+    /// Returns the number of statements walked
+    pub(crate) fn walk_stmt_at(
+        &self,
+        yul_func_name: &str,
+        stmts: &[Statement],
+        index: usize,
+    ) -> Result<usize, ASTLoweringError> {
+        if !self.opts.disable_all_optimizers {
+            if let Some(idiom) = self.matches_mapping_slot_idiom(&stmts[index..]) {
+                self.walk_mapping_slot_idiom(yul_func_name, &idiom)?;
+                return Ok(3);
+            }
+        }
+        self.walk_stmt(yul_func_name, &stmts[index])?;
+        Ok(1)
+    }
+
+    fn walk_mapping_slot_idiom(
+        &self,
+        yul_func_name: &str,
+        idiom: &MappingSlotIdiom,
+    ) -> Result<(), ASTLoweringError> {
+        // the scratch space isn't written by constant stores
+        self.forget_constant_memory();
+        // key is stored before slot is evaluated, keep that order
+        let key =
+            self.walk_expr_with_type(yul_func_name, idiom.key, ExpectedType::Bytes32Pointer)?;
+        let key = self.try_into_bytes32_pointer(&key.get_value())?;
+        let slot =
+            self.walk_expr_with_type(yul_func_name, idiom.slot, ExpectedType::Bytes32Pointer)?;
+        let slot = self.try_into_bytes32_pointer(&slot.get_value())?;
+        let result = self.fast_alloca(self.bytes32_type(), "mapping_slot")?;
+        let wrapper = if idiom.keep_scratch {
+            "wrapper_mapping_slot_with_scratch"
+        } else {
+            "wrapper_mapping_slot"
+        };
+        self.build_void_call(wrapper, &[key, slot, result.into()])?;
+
+        // keccak256(0, 0x40) in the statement is lowered to the result
+        *self.mapping_slot_hash.borrow_mut() = Some(result);
+        let walked = self.walk_stmt(yul_func_name, idiom.user);
+        *self.mapping_slot_hash.borrow_mut() = None;
+        walked?;
+        Ok(())
+    }
+}
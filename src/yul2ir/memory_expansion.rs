@@ -0,0 +1,40 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Memory expansion gas hook.
+//!
+//! The stdlib tracks msize like the EVM and calls
+//! `evm_charge_memory_expansion(gas)` with the memory gas of every expansion,
+//! `3 * words + words^2 / 512` minus what the previous size cost. The hook is
+//! only declared by the stdlib: with `enable_memory_expansion_gas` or the
+//! `evm` gas metering it is defined here to charge the gas through the `useGas` host api, otherwise it
+//! is empty and inlined away, for hosts that meter the wasm execution itself.
+
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+
+use inkwell::module::Linkage;
+
+impl<'a> Yul2IRContext<'a> {
+    /// Defines the memory expansion gas hook of the stdlib
+    pub(crate) fn build_memory_expansion_hook(&self) -> Result<(), ASTLoweringError> {
+        let module = self.llvm_module.borrow();
+        let Some(hook) = module.get_function("evm_charge_memory_expansion") else {
+            return Ok(());
+        };
+        if hook.count_basic_blocks() > 0 {
+            return Ok(());
+        }
+        let entry = self.llvm_context.append_basic_block(hook, "entry");
+        let builder = self.llvm_context.create_builder();
+        builder.position_at_end(entry);
+        if self.opts.charges_memory_expansion_gas() {
+            let use_gas = module.get_function("wrapper_use_gas").unwrap();
+            let gas = hook.get_nth_param(0).unwrap();
+            builder.build_call(use_gas, &[gas.into()], "")?;
+        }
+        builder.build_return(None)?;
+        hook.set_linkage(Linkage::Internal);
+        Ok(())
+    }
+}
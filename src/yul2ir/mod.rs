@@ -5,16 +5,33 @@ use lalrpop_util::lalrpop_mod;
 lalrpop_mod!(pub yul); // synthesized by LALRPOP
 
 pub mod ast;
+pub mod ast_optimizer;
+pub mod calling_convention;
 pub mod config;
+pub mod constant_memory;
 pub mod context;
+pub mod cost_report;
 pub mod errors;
 pub mod function_deduplicator;
+pub mod gas_metering;
 pub mod infer;
 pub mod instruction;
+pub mod mapping_slot;
+pub mod memory_expansion;
+pub mod pattern;
+pub mod remarks;
+pub mod safe_memory;
+pub mod selector_dispatch;
+pub mod simd;
+pub mod size_report;
+pub mod stack_usage;
 pub mod stdlib;
+pub mod strength_reduction;
 pub mod transform;
 pub mod usage;
 pub mod utils;
 pub mod var_scope;
 pub mod wasm;
+pub mod wasm_features;
+pub mod width_inference;
 pub mod yul_instruction;
@@ -0,0 +1,831 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Yul function patterns that are replaced by calls to runtime intrinsics.
+//!
+//! A rule describes the function it replaces with a Yul template. The
+//! template is a function definition where
+//! - parameters, return variables and locals match any names, so the rule
+//!   doesn't depend on how solc numbered the variables or named the function
+//! - `$name` matches any expression and binds it, a later `$name` must be the
+//!   same expression. `$_` matches any expression without binding it
+//! - a call to `$rule(...)` matches a call to a function that `rule` matched
+//! - the statement `$revert_error(selector, args...)` ends a block and
+//!   matches the code solc emits to revert with a custom error: the 4-byte
+//!   selector then one word per argument, written with `mstore` to the
+//!   scratch space or to the free memory pointer, then `revert`. Each
+//!   argument is matched like an expression, so the replaced function
+//!   reverts with the same data as the intrinsic
+//! - builtin and other function names and literals must be equal
+//!
+//! Templates and functions are compared after constant folding, so a rule
+//! matches whether or not the AST optimizer already ran on the object.
+//!
+//! Calls to a matched function become a call to the rule's intrinsic with
+//! the arguments listed by the rule.
+
+use crate::yul2ir::ast::{
+    Block, Expression, FunctionCall, FunctionDefinition, Literal, Object, Statement, SwitchOptions,
+    TypedIdentifier,
+};
+use crate::yul2ir::ast_optimizer::optimize_function;
+use crate::yul2ir::context::{CompileResult, Yul2IRContext};
+use crate::yul2ir::infer::ExpectedType;
+use crate::yul2ir::remarks::{expression_source, Remark};
+use crate::yul2ir::utils::remove_comments;
+use crate::yul2ir::yul;
+use crate::yul2ir::yul_instruction::{YulLowLevelValue, YulLowLevelValueType};
+
+use ethereum_types::U256;
+use std::collections::{HashMap, HashSet};
+
+// `$` can't start a Yul identifier, templates are parsed with `$` replaced
+const CAPTURE_PREFIX: &str = "capture$";
+const REVERT_ERROR: &str = "revert_error";
+const ANY_EXPRESSION: &str = "_";
+
+/// Argument of the intrinsic call that replaces a matched function call
+#[derive(Debug, Clone, Copy)]
+pub enum RuleArg {
+    /// The argument of the replaced call at this index
+    Param(usize),
+    /// The literal bound to `$name` in the template
+    Constant(&'static str),
+    /// The constant argument at this index of the intrinsic of the function
+    /// called as `$rule(...)`
+    CalleeConstant(&'static str, usize),
+}
+
+#[derive(Debug)]
+pub struct PatternRule {
+    pub name: &'static str,
+    /// A function matches the rule if it matches one of the templates
+    pub templates: &'static [&'static str],
+    pub intrinsic: &'static str,
+    pub args: &'static [RuleArg],
+}
+
+/// Intrinsic call argument resolved for a matched function
+#[derive(Debug, Clone)]
+pub enum RuleMatchArg {
+    Param(usize),
+    Constant(Expression),
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleMatch {
+    pub rule: &'static str,
+    pub intrinsic: &'static str,
+    pub args: Vec<RuleMatchArg>,
+    /// The replaced function returns a value, the intrinsic returns it too
+    pub has_return: bool,
+}
+
+/// Standard ERC20 functions (OpenZeppelin) compiled by solc with the optimizer.
+/// The storage slots of balances, allowances and totalSupply are captured
+/// from the function, the intrinsics store values little endian so these
+/// rules are only used with enable_storage_load_store_little_endian
+pub const ERC20_RULES: &[PatternRule] = &[
+    PatternRule {
+        name: "erc20_transfer",
+        templates: &[
+            r#"
+            function $f(from, to, value) {
+                let mask := sub(shl(160, 1), 1)
+                let fromAddr := and(from, mask)
+                if iszero(fromAddr) { $revert_error(0x96c6fd1e, 0) }
+                let toAddr := and(to, mask)
+                if iszero(toAddr) { $revert_error(0xec442f05, 0) }
+                mstore(0, fromAddr)
+                mstore(0x20, $balances)
+                let fromBalance := sload(keccak256(0, 0x40))
+                if lt(fromBalance, value) {
+                    $revert_error(0xe450d38c, fromAddr, fromBalance, value)
+                }
+                mstore(0, fromAddr)
+                mstore(0x20, $balances)
+                sstore(keccak256(0, 0x40), sub(fromBalance, value))
+                mstore(0, toAddr)
+                let toSlot := keccak256(0, 0x40)
+                sstore(toSlot, add(sload(toSlot), value))
+                let memPos := mload(0x40)
+                mstore(memPos, value)
+                log3(memPos, 0x20, 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef, fromAddr, toAddr)
+            }
+            "#,
+            r#"
+            function $f(from, to, value) {
+                let fromAddr := and(from, sub(shl(160, 1), 1))
+                let fromIsZero := iszero(fromAddr)
+                if fromIsZero { $revert_error(0x96c6fd1e, 0) }
+                let toAddr := and(to, sub(shl(160, 1), 1))
+                let toIsZero := iszero(toAddr)
+                if toIsZero { $revert_error(0xec442f05, 0) }
+                fromIsZero := 0
+                mstore(0, fromAddr)
+                mstore(0x20, $balances)
+                let fromBalance := sload(keccak256(0, 0x40))
+                if lt(fromBalance, value) {
+                    $revert_error(0xe450d38c, fromAddr, fromBalance, value)
+                }
+                mstore(0, fromAddr)
+                mstore(0x20, $balances)
+                sstore(keccak256(0, 0x40), sub(fromBalance, value))
+                toIsZero := 0
+                mstore(0, toAddr)
+                mstore(0x20, $balances)
+                let toSlot := keccak256(0, 0x40)
+                sstore(toSlot, add(sload(toSlot), value))
+                let memPos := mload(64)
+                mstore(memPos, value)
+                log3(memPos, 32, 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef, fromAddr, toAddr)
+            }
+            "#,
+        ],
+        intrinsic: "wrapper_optimized_erc20_fun_transfer",
+        args: &[
+            RuleArg::Param(0),
+            RuleArg::Param(1),
+            RuleArg::Param(2),
+            RuleArg::Constant("balances"),
+        ],
+    },
+    PatternRule {
+        name: "erc20_transfer_from",
+        templates: &[r#"
+            function $f(from, to, value) -> ret {
+                mstore(0, and(from, sub(shl(160, 1), 1)))
+                mstore(0x20, $allowances)
+                let ownerSlot := keccak256(0, 0x40)
+                mstore(0, and(caller(), sub(shl(160, 1), 1)))
+                mstore(0x20, ownerSlot)
+                let allowanceSlot := keccak256(0, 0x40)
+                let currentAllowance := sload(allowanceSlot)
+                if iszero(eq(currentAllowance, not(0))) {
+                    if lt(currentAllowance, value) {
+                        $revert_error(0xfb8f41b2, caller(), currentAllowance, value)
+                    }
+                    sstore(allowanceSlot, sub(currentAllowance, value))
+                }
+                $erc20_transfer(from, to, value)
+                ret := 1
+            }
+            "#],
+        intrinsic: "wrapper_optimized_erc20_fun_transfer_from",
+        args: &[
+            RuleArg::Param(0),
+            RuleArg::Param(1),
+            RuleArg::Param(2),
+            RuleArg::Constant("allowances"),
+            RuleArg::CalleeConstant("erc20_transfer", 3),
+        ],
+    },
+    PatternRule {
+        name: "erc20_approve",
+        templates: &[r#"
+            function $f(owner, spender, value) {
+                let ownerAddr := and(owner, sub(shl(160, 1), 1))
+                if iszero(ownerAddr) { $revert_error(0xe602df05, 0) }
+                let spenderAddr := and(spender, sub(shl(160, 1), 1))
+                if iszero(spenderAddr) { $revert_error(0x94280d62, 0) }
+                mstore(0, ownerAddr)
+                mstore(0x20, $allowances)
+                let ownerSlot := keccak256(0, 0x40)
+                mstore(0, spenderAddr)
+                mstore(0x20, ownerSlot)
+                sstore(keccak256(0, 0x40), value)
+                let memPos := mload(0x40)
+                mstore(memPos, value)
+                log3(memPos, 0x20, 0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925, ownerAddr, spenderAddr)
+            }
+            "#],
+        intrinsic: "wrapper_optimized_erc20_fun_approve",
+        args: &[
+            RuleArg::Param(0),
+            RuleArg::Param(1),
+            RuleArg::Param(2),
+            RuleArg::Constant("allowances"),
+        ],
+    },
+    PatternRule {
+        name: "erc20_mint",
+        templates: &[r#"
+            function $f(account, value) {
+                let accountAddr := and(account, sub(shl(160, 1), 1))
+                if iszero(accountAddr) { $revert_error(0xec442f05, 0) }
+                let supply := sload($totalSupply)
+                let newSupply := add(supply, value)
+                if gt(supply, newSupply) { $revert_error(0x4e487b71, 0x11) }
+                sstore($totalSupply, newSupply)
+                mstore(0, accountAddr)
+                mstore(0x20, $balances)
+                let accountSlot := keccak256(0, 0x40)
+                sstore(accountSlot, add(sload(accountSlot), value))
+                let memPos := mload(0x40)
+                mstore(memPos, value)
+                log3(memPos, 0x20, 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef, 0, accountAddr)
+            }
+            "#],
+        intrinsic: "wrapper_optimized_erc20_fun_mint",
+        args: &[
+            RuleArg::Param(0),
+            RuleArg::Param(1),
+            RuleArg::Constant("totalSupply"),
+            RuleArg::Constant("balances"),
+        ],
+    },
+];
+
+/// Parses the template of a rule into its function definition
+pub fn parse_template(template: &str) -> FunctionDefinition {
+    let source = format!(
+        "object \"pattern\" {{ code {{ {} }} }}",
+        remove_comments(&template.replace('$', CAPTURE_PREFIX))
+    );
+    let object = yul::ObjectParser::new()
+        .parse(&source)
+        .unwrap_or_else(|err| panic!("invalid pattern template {template}: {err}"));
+    match object.code.statements.into_iter().next() {
+        Some(Statement::FunctionDefinition(func_def)) => *func_def,
+        _ => panic!("pattern template must be a function definition: {template}"),
+    }
+}
+
+fn literal_value(literal: &Literal) -> Option<U256> {
+    match literal {
+        Literal::DecimalNumberLiteral(dec, _) => U256::from_dec_str(&dec.dec).ok(),
+        Literal::HexNumberLiteral(hex, _) => U256::from_str_radix(&hex.hex, 16).ok(),
+        Literal::TrueLiteral(_) => Some(U256::from(1)),
+        Literal::FalseLiteral(_) => Some(U256::from(0)),
+        Literal::StringLiteral(..) => None,
+    }
+}
+
+fn literal_eq(a: &Literal, b: &Literal) -> bool {
+    match (a, b) {
+        (Literal::StringLiteral(a, _), Literal::StringLiteral(b, _)) => a.str == b.str,
+        _ => literal_value(a).is_some_and(|a| literal_value(b) == Some(a)),
+    }
+}
+
+fn expr_eq(a: &Expression, b: &Expression) -> bool {
+    match (a, b) {
+        (Expression::Identifier(a), Expression::Identifier(b)) => a.name == b.name,
+        (Expression::Literal(a), Expression::Literal(b)) => literal_eq(a, b),
+        (Expression::FunctionCall(a), Expression::FunctionCall(b)) => {
+            a.id.name == b.id.name
+                && a.arguments.len() == b.arguments.len()
+                && a.arguments
+                    .iter()
+                    .zip(&b.arguments)
+                    .all(|(a, b)| expr_eq(a, b))
+        }
+        _ => false,
+    }
+}
+
+fn capture_name(name: &str) -> Option<&str> {
+    name.strip_prefix(CAPTURE_PREFIX)
+}
+
+fn revert_error_call(stmt: &Statement) -> Option<&FunctionCall> {
+    match stmt {
+        Statement::FunctionCall(call) if capture_name(&call.id.name) == Some(REVERT_ERROR) => {
+            Some(call.as_ref())
+        }
+        _ => None,
+    }
+}
+
+fn builtin_call<'e>(
+    expr: &'e Expression,
+    name: &str,
+    args_count: usize,
+) -> Option<&'e [Expression]> {
+    match expr {
+        Expression::FunctionCall(call)
+            if call.id.name == name && call.arguments.len() == args_count =>
+        {
+            Some(call.arguments.as_slice())
+        }
+        _ => None,
+    }
+}
+
+fn expr_value(expr: &Expression) -> Option<U256> {
+    match expr {
+        Expression::Literal(literal) => literal_value(literal),
+        _ => None,
+    }
+}
+
+/// Offset written or reverted by the error encoding, relative to the free
+/// memory pointer in `base` if the encoding loaded it
+fn revert_data_offset(expr: &Expression, base: Option<&str>) -> Option<U256> {
+    let Some(base) = base else {
+        return expr_value(expr);
+    };
+    let is_base = |expr: &Expression| matches!(expr, Expression::Identifier(id) if id.name == base);
+    if is_base(expr) {
+        return Some(U256::zero());
+    }
+    let args = builtin_call(expr, "add", 2)?;
+    match (is_base(&args[0]), is_base(&args[1])) {
+        (true, false) => expr_value(&args[1]),
+        (false, true) => expr_value(&args[0]),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Bindings {
+    // template variable => function variable
+    names: HashMap<String, String>,
+    // function variables bound to a template variable
+    bound: HashSet<String>,
+    captures: HashMap<String, Expression>,
+    // rule name => function called as `$rule(...)`
+    callees: HashMap<String, String>,
+}
+
+struct PatternMatcher<'b> {
+    // functions of the object matched so far, function name => match
+    matches: &'b HashMap<String, RuleMatch>,
+}
+
+impl PatternMatcher<'_> {
+    fn bind(&self, template: &str, actual: &str, bindings: &mut Bindings) -> bool {
+        if let Some(bound) = bindings.names.get(template) {
+            return bound == actual;
+        }
+        if !bindings.bound.insert(actual.to_string()) {
+            return false;
+        }
+        bindings
+            .names
+            .insert(template.to_string(), actual.to_string());
+        true
+    }
+
+    fn declare(
+        &self,
+        template: &[TypedIdentifier],
+        actual: &[TypedIdentifier],
+        bindings: &mut Bindings,
+    ) -> bool {
+        template.len() == actual.len()
+            && template.iter().zip(actual).all(|(t, a)| {
+                t.type_name.as_ref().map(|ty| &ty.type_name.name)
+                    == a.type_name.as_ref().map(|ty| &ty.type_name.name)
+                    && self.bind(&t.identifier.name, &a.identifier.name, bindings)
+            })
+    }
+
+    fn identifier(&self, template: &str, actual: &str, bindings: &Bindings) -> bool {
+        match bindings.names.get(template) {
+            Some(bound) => bound == actual,
+            // not declared in the template, the same free name
+            None => template == actual && !bindings.bound.contains(actual),
+        }
+    }
+
+    fn call(
+        &self,
+        template: &FunctionCall,
+        actual: &FunctionCall,
+        bindings: &mut Bindings,
+    ) -> bool {
+        if template.arguments.len() != actual.arguments.len() {
+            return false;
+        }
+        if let Some(rule) = capture_name(&template.id.name) {
+            let callee_matches = self
+                .matches
+                .get(&actual.id.name)
+                .is_some_and(|m| m.rule == rule);
+            if !callee_matches {
+                return false;
+            }
+            let callee = bindings
+                .callees
+                .entry(rule.to_string())
+                .or_insert_with(|| actual.id.name.clone());
+            if *callee != actual.id.name {
+                return false;
+            }
+        } else if template.id.name != actual.id.name {
+            return false;
+        }
+        template
+            .arguments
+            .iter()
+            .zip(&actual.arguments)
+            .all(|(t, a)| self.expr(t, a, bindings))
+    }
+
+    fn expr(&self, template: &Expression, actual: &Expression, bindings: &mut Bindings) -> bool {
+        match (template, actual) {
+            (Expression::Identifier(id), _) if capture_name(&id.name).is_some() => {
+                let name = capture_name(&id.name).unwrap();
+                if name == ANY_EXPRESSION {
+                    return true;
+                }
+                match bindings.captures.get(name) {
+                    Some(captured) => expr_eq(captured, actual),
+                    None => {
+                        bindings.captures.insert(name.to_string(), actual.clone());
+                        true
+                    }
+                }
+            }
+            (Expression::Identifier(t), Expression::Identifier(a)) => {
+                self.identifier(&t.name, &a.name, bindings)
+            }
+            (Expression::Literal(t), Expression::Literal(a)) => literal_eq(t, a),
+            (Expression::FunctionCall(t), Expression::FunctionCall(a)) => self.call(t, a, bindings),
+            _ => false,
+        }
+    }
+
+    fn block(&self, template: &Block, actual: &Block, bindings: &mut Bindings) -> bool {
+        self.statements(&template.statements, &actual.statements, bindings)
+    }
+
+    fn statements(
+        &self,
+        template: &[Statement],
+        actual: &[Statement],
+        bindings: &mut Bindings,
+    ) -> bool {
+        let Some((first, rest)) = template.split_first() else {
+            return actual.is_empty();
+        };
+        if let Some(revert_error) = revert_error_call(first) {
+            let mut next = bindings.clone();
+            if rest.is_empty() && self.revert_error(revert_error, actual, &mut next) {
+                *bindings = next;
+                return true;
+            }
+            return false;
+        }
+        let Some((actual_first, actual_rest)) = actual.split_first() else {
+            return false;
+        };
+        let mut next = bindings.clone();
+        if self.statement(first, actual_first, &mut next)
+            && self.statements(rest, actual_rest, &mut next)
+        {
+            *bindings = next;
+            return true;
+        }
+        false
+    }
+
+    /// Matches the statements that revert with the custom error of
+    /// `$revert_error(selector, args...)`
+    fn revert_error(
+        &self,
+        template: &FunctionCall,
+        actual: &[Statement],
+        bindings: &mut Bindings,
+    ) -> bool {
+        let Some((selector, args)) = template.arguments.split_first() else {
+            return false;
+        };
+        let Some(selector) = expr_value(selector) else {
+            return false;
+        };
+        let Some((Statement::FunctionCall(revert), stores)) = actual.split_last() else {
+            return false;
+        };
+        // `let memPos := mload(64)` when the error is encoded at the free memory pointer
+        let (base, stores) = match stores.split_first() {
+            Some((Statement::VariableDeclaration(decl), stores))
+                if decl.identifiers.len() == 1
+                    && decl
+                        .value
+                        .as_ref()
+                        .and_then(|value| builtin_call(value, "mload", 1))
+                        .and_then(|args| expr_value(&args[0]))
+                        == Some(U256::from(64)) =>
+            {
+                (Some(decl.identifiers[0].identifier.name.as_str()), stores)
+            }
+            _ => (None, stores),
+        };
+        if stores.len() != args.len() + 1
+            || revert.id.name != "revert"
+            || revert.arguments.len() != 2
+            || revert_data_offset(&revert.arguments[0], base) != Some(U256::zero())
+            || expr_value(&revert.arguments[1]) != Some(U256::from(4 + 32 * args.len()))
+        {
+            return false;
+        }
+        // the selector word, then the arguments from offset 4
+        stores.iter().enumerate().all(|(index, stmt)| {
+            let Statement::FunctionCall(store) = stmt else {
+                return false;
+            };
+            if store.id.name != "mstore" || store.arguments.len() != 2 {
+                return false;
+            }
+            let store_args = &store.arguments;
+            let offset = if index == 0 { 0 } else { 4 + 32 * (index - 1) };
+            if revert_data_offset(&store_args[0], base) != Some(U256::from(offset)) {
+                return false;
+            }
+            if index == 0 {
+                expr_value(&store_args[1]) == Some(selector << 224)
+            } else {
+                self.expr(&args[index - 1], &store_args[1], bindings)
+            }
+        })
+    }
+
+    fn statement(&self, template: &Statement, actual: &Statement, bindings: &mut Bindings) -> bool {
+        match (template, actual) {
+            (Statement::VariableDeclaration(t), Statement::VariableDeclaration(a)) => {
+                let value_matches = match (&t.value, &a.value) {
+                    (Some(t), Some(a)) => self.expr(t, a, bindings),
+                    (None, None) => true,
+                    _ => false,
+                };
+                value_matches && self.declare(&t.identifiers, &a.identifiers, bindings)
+            }
+            (Statement::Assignment(t), Statement::Assignment(a)) => {
+                t.identifiers.len() == a.identifiers.len()
+                    && t.identifiers
+                        .iter()
+                        .zip(&a.identifiers)
+                        .all(|(t, a)| self.identifier(&t.name, &a.name, bindings))
+                    && self.expr(&t.value, &a.value, bindings)
+            }
+            (Statement::If(t), Statement::If(a)) => {
+                self.expr(&t.cond, &a.cond, bindings) && self.block(&t.body, &a.body, bindings)
+            }
+            (Statement::For(t), Statement::For(a)) => {
+                self.block(&t.init_block, &a.init_block, bindings)
+                    && self.expr(&t.condition, &a.condition, bindings)
+                    && self.block(&t.post_block, &a.post_block, bindings)
+                    && self.block(&t.execution_block, &a.execution_block, bindings)
+            }
+            (Statement::Switch(t), Statement::Switch(a)) => {
+                if !self.expr(&t.condition, &a.condition, bindings) {
+                    return false;
+                }
+                match (&t.opt, &a.opt) {
+                    (
+                        SwitchOptions::Cases(t_cases, t_default),
+                        SwitchOptions::Cases(a_cases, a_default),
+                    ) => {
+                        t_cases.len() == a_cases.len()
+                            && t_cases.iter().zip(a_cases).all(|(t, a)| {
+                                literal_eq(&t.case, &a.case)
+                                    && self.block(&t.body, &a.body, bindings)
+                            })
+                            && match (t_default, a_default) {
+                                (Some(t), Some(a)) => self.block(&t.body, &a.body, bindings),
+                                (None, None) => true,
+                                _ => false,
+                            }
+                    }
+                    (SwitchOptions::Default(t), SwitchOptions::Default(a)) => {
+                        self.block(&t.body, &a.body, bindings)
+                    }
+                    _ => false,
+                }
+            }
+            (Statement::Block(t), Statement::Block(a)) => self.block(t, a, bindings),
+            (Statement::FunctionCall(t), Statement::FunctionCall(a)) => self.call(t, a, bindings),
+            (Statement::Leave, Statement::Leave)
+            | (Statement::Break, Statement::Break)
+            | (Statement::Continue, Statement::Continue)
+            | (Statement::Comment, Statement::Comment) => true,
+            _ => false,
+        }
+    }
+
+    /// Matches the function against the template and resolves the intrinsic
+    /// arguments of the rule
+    fn function(
+        &self,
+        rule: &'static PatternRule,
+        template: &FunctionDefinition,
+        actual: &FunctionDefinition,
+    ) -> Option<RuleMatch> {
+        let mut bindings = Bindings::default();
+        if !self.declare(&template.params, &actual.params, &mut bindings)
+            || !self.declare(&template.returns, &actual.returns, &mut bindings)
+            || !self.block(&template.body, &actual.body, &mut bindings)
+        {
+            return None;
+        }
+        let mut args = vec![];
+        for arg in rule.args {
+            let arg = match arg {
+                RuleArg::Param(index) => RuleMatchArg::Param(*index),
+                RuleArg::Constant(name) => match bindings.captures.get(*name) {
+                    Some(literal @ Expression::Literal(_)) => {
+                        RuleMatchArg::Constant(literal.clone())
+                    }
+                    _ => return None,
+                },
+                RuleArg::CalleeConstant(callee_rule, index) => {
+                    let callee = bindings.callees.get(*callee_rule)?;
+                    match self.matches[callee].args.get(*index) {
+                        Some(constant @ RuleMatchArg::Constant(_)) => constant.clone(),
+                        _ => return None,
+                    }
+                }
+            };
+            args.push(arg);
+        }
+        Some(RuleMatch {
+            rule: rule.name,
+            intrinsic: rule.intrinsic,
+            args,
+            has_return: !actual.returns.is_empty(),
+        })
+    }
+}
+
+/// Solidity functions like `fun_transfer_123` or `fun__mint`, compiled from
+/// the ERC20 functions the rules describe
+fn looks_like_erc20_function(name: &str) -> bool {
+    let Some(name) = name.strip_prefix("fun_") else {
+        return false;
+    };
+    let name = name.trim_start_matches('_').to_lowercase();
+    ["transfer", "approve", "mint"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// Matches the functions defined in the object against the rules, returns
+/// function name => match
+pub fn match_pattern_rules(
+    object: &Object,
+    rules: &'static [PatternRule],
+) -> HashMap<String, RuleMatch> {
+    let templates: Vec<(&'static PatternRule, Vec<FunctionDefinition>)> = rules
+        .iter()
+        .map(|rule| {
+            (
+                rule,
+                rule.templates
+                    .iter()
+                    .map(|t| optimize_function(&parse_template(t)))
+                    .collect(),
+            )
+        })
+        .collect();
+    let functions: Vec<FunctionDefinition> = object
+        .code
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Statement::FunctionDefinition(func_def) => Some(optimize_function(func_def)),
+            _ => None,
+        })
+        .collect();
+
+    // a rule can require a callee matched by another rule, so repeat until
+    // no more functions are matched
+    let mut matches: HashMap<String, RuleMatch> = HashMap::new();
+    loop {
+        let mut new_matches = vec![];
+        for func_def in &functions {
+            if matches.contains_key(&func_def.name.name) {
+                continue;
+            }
+            let matcher = PatternMatcher { matches: &matches };
+            let rule_match = templates.iter().find_map(|(rule, templates)| {
+                templates
+                    .iter()
+                    .find_map(|template| matcher.function(*rule, template, func_def))
+            });
+            if let Some(rule_match) = rule_match {
+                new_matches.push((func_def.name.name.clone(), rule_match));
+            }
+        }
+        if new_matches.is_empty() {
+            break;
+        }
+        matches.extend(new_matches);
+    }
+    matches
+}
+
+impl<'a> Yul2IRContext<'a> {
+    /// Records the functions of the object matched by the ERC20 rules
+    pub fn find_pattern_rule_matches(&self, object: &Object) {
+        let matches = match_pattern_rules(object, ERC20_RULES);
+        if self.opts.remarks.is_some() {
+            self.remark_pattern_rule_near_misses(object, &matches);
+        }
+        let mut pattern_rule_matches = self.pattern_rule_matches.borrow_mut();
+        for (name, rule_match) in matches {
+            pattern_rule_matches
+                .insert(self.get_func_decl_qualifier_name_by_str(&name), rule_match);
+        }
+    }
+
+    /// Remarks for the matched functions that won't be replaced, and the
+    /// functions named like an ERC20 function that no rule matched
+    fn remark_pattern_rule_near_misses(
+        &self,
+        object: &Object,
+        matches: &HashMap<String, RuleMatch>,
+    ) {
+        for stmt in &object.code.statements {
+            let Statement::FunctionDefinition(func_def) = stmt else {
+                continue;
+            };
+            let name = &func_def.name.name;
+            let qualified_name = self.get_func_decl_qualifier_name_by_str(name);
+            match matches.get(name) {
+                Some(rule_match) if !self.opts.enable_storage_load_store_little_endian => {
+                    self.add_remark(|| {
+                        Remark::missed(
+                            "erc20_intrinsic",
+                            &qualified_name,
+                            format!("function {name}"),
+                            format!(
+                                "{name} matched {} but the intrinsics need little endian storage load/store",
+                                rule_match.rule
+                            ),
+                        )
+                    })
+                }
+                Some(_) => {}
+                None if looks_like_erc20_function(name) => self.add_remark(|| {
+                    Remark::missed(
+                        "erc20_intrinsic",
+                        &qualified_name,
+                        format!("function {name}"),
+                        format!("{name} is named like an ERC20 function but matched no rule template"),
+                    )
+                }),
+                None => {}
+            }
+        }
+    }
+
+    /// Lowers a call to a function matched by a rule as the call of its intrinsic
+    pub(crate) fn walk_pattern_rule_call(
+        &self,
+        yul_func_name: &str,
+        func_call: &FunctionCall,
+        rule_match: &RuleMatch,
+    ) -> CompileResult<'a> {
+        // Yul evaluates the call arguments right to left, all of them run even
+        // if the intrinsic doesn't take them
+        let mut params = vec![None; func_call.arguments.len()];
+        for (index, expr) in func_call.arguments.iter().enumerate().rev() {
+            let value =
+                self.walk_expr_with_type(yul_func_name, expr, ExpectedType::Bytes32Pointer)?;
+            params[index] = Some(self.try_into_bytes32_pointer(&value.get_value())?);
+        }
+        let mut args = vec![];
+        for arg in &rule_match.args {
+            match arg {
+                RuleMatchArg::Param(index) => args.push(params[*index].unwrap()),
+                RuleMatchArg::Constant(expr) => {
+                    let value = self.walk_expr_with_type(
+                        yul_func_name,
+                        expr,
+                        ExpectedType::Bytes32Pointer,
+                    )?;
+                    args.push(self.try_into_bytes32_pointer(&value.get_value())?);
+                }
+            }
+        }
+        self.add_remark(|| {
+            Remark::applied(
+                "erc20_intrinsic",
+                yul_func_name,
+                expression_source(&Expression::FunctionCall(Box::new(func_call.clone()))),
+                format!(
+                    "{} matched {}, calls {}",
+                    func_call.id.name, rule_match.rule, rule_match.intrinsic
+                ),
+            )
+        });
+        // the ERC20 intrinsics all modify the state
+        if self.opts.enable_static_context_check {
+            self.build_void_call("wrapper_check_static_context", &[])?;
+        }
+        if rule_match.has_return {
+            let value = self.build_call(rule_match.intrinsic, &args)?;
+            return Ok(YulLowLevelValue {
+                value_type: YulLowLevelValueType::I32,
+                value,
+            });
+        }
+        self.build_void_call(rule_match.intrinsic, &args)?;
+        Ok(YulLowLevelValue {
+            value_type: YulLowLevelValueType::I32,
+            value: self.i32_type().const_zero().into(),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+// Optimization remarks of one LLVM context
+#include "llvm/IR/DiagnosticHandler.h"
+#include "llvm/IR/DiagnosticInfo.h"
+#include "llvm/IR/DiagnosticPrinter.h"
+#include "llvm/IR/Function.h"
+#include "llvm/IR/LLVMContext.h"
+#include "llvm/Support/raw_ostream.h"
+
+#include <memory>
+#include <string>
+
+typedef void (*YulRemarkHandler)(void *handler_context, int severity,
+				 const char *function, const char *description);
+
+// Enables the missed remarks of every pass and the passed remarks of the
+// inliner on the context it's set on, instead of the -pass-remarks options
+// which apply to every context of the process
+class YulRemarkDiagnosticHandler : public llvm::DiagnosticHandler {
+public:
+	YulRemarkDiagnosticHandler(YulRemarkHandler handler, void *handler_context)
+		: handler(handler), handler_context(handler_context)
+	{
+	}
+
+	bool handleDiagnostics(const llvm::DiagnosticInfo &info) override
+	{
+		std::string function;
+		if (auto *remark = llvm::dyn_cast<llvm::DiagnosticInfoOptimizationBase>(&info)) {
+			function = remark->getFunction().getName().str();
+		}
+		std::string description;
+		llvm::raw_string_ostream stream(description);
+		llvm::DiagnosticPrinterRawOStream printer(stream);
+		info.print(printer);
+		stream.flush();
+		handler(handler_context, info.getSeverity(), function.c_str(), description.c_str());
+		return true;
+	}
+
+	bool isAnalysisRemarkEnabled(llvm::StringRef) const override { return false; }
+
+	bool isMissedOptRemarkEnabled(llvm::StringRef) const override { return true; }
+
+	bool isPassedOptRemarkEnabled(llvm::StringRef pass_name) const override
+	{
+		return pass_name == "inline";
+	}
+
+	bool isAnyRemarkEnabled() const override { return true; }
+
+private:
+	YulRemarkHandler handler;
+	void *handler_context;
+};
+
+// Sets the remark handler of the context, or restores the default one
+extern "C" void YulSetRemarkHandler(LLVMContextRef context, YulRemarkHandler handler,
+				    void *handler_context)
+{
+	llvm::LLVMContext &llvm_context = *llvm::unwrap(context);
+	if (handler) {
+		llvm_context.setDiagnosticHandler(
+			std::make_unique<YulRemarkDiagnosticHandler>(handler, handler_context));
+	} else {
+		llvm_context.setDiagnosticHandler(std::make_unique<llvm::DiagnosticHandler>());
+	}
+}
@@ -0,0 +1,339 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optimization remarks.
+//!
+//! The lowering records each custom rewrite it applies, and the reason a
+//! rewrite was rejected where the code nearly matched it, with the Yul
+//! function and the Yul source it rewrote (the AST has no source positions).
+//! LLVM reports its remarks through a diagnostic handler set on the context
+//! only (remarks.cpp), other contexts of the process report none: the inliner
+//! decisions and the missed optimizations of every pass, each with the
+//! function it was reported in.
+
+use crate::yul2ir::ast::{Expression, Literal};
+use crate::yul2ir::config::RemarksFormat;
+use crate::yul2ir::context::Yul2IRContext;
+
+use inkwell::context::Context;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemarkKind {
+    Applied,
+    /// The code nearly matched, the message tells why it was rejected
+    Missed,
+}
+
+impl Display for RemarkKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemarkKind::Applied => write!(f, "applied"),
+            RemarkKind::Missed => write!(f, "missed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remark {
+    /// The rewrite, like `selector_load`, `inline` for the LLVM inliner or
+    /// `llvm` for the other LLVM passes
+    pub pass: String,
+    pub kind: RemarkKind,
+    /// Qualified name of the Yul function, empty if LLVM didn't name one
+    pub function: String,
+    /// The Yul source rewritten, empty for LLVM remarks
+    pub location: String,
+    pub message: String,
+}
+
+impl Remark {
+    pub fn applied(pass: &str, function: &str, location: String, message: String) -> Self {
+        Remark {
+            pass: pass.to_string(),
+            kind: RemarkKind::Applied,
+            function: function.to_string(),
+            location,
+            message,
+        }
+    }
+
+    pub fn missed(pass: &str, function: &str, location: String, message: String) -> Self {
+        Remark {
+            pass: pass.to_string(),
+            kind: RemarkKind::Missed,
+            function: function.to_string(),
+            location,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RemarkReport {
+    /// Rewrites of the lowering in order, then the LLVM remarks
+    pub remarks: Vec<Remark>,
+}
+
+impl RemarkReport {
+    /// Remarks of the pass with the kind
+    pub fn find(&self, pass: &str, kind: RemarkKind) -> Vec<&Remark> {
+        self.remarks
+            .iter()
+            .filter(|remark| remark.pass == pass && remark.kind == kind)
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let remarks: Vec<String> = self
+            .remarks
+            .iter()
+            .map(|remark| {
+                format!(
+                    "  {{\"pass\": {}, \"kind\": \"{}\", \"function\": {}, \"location\": {}, \"message\": {}}}",
+                    json_string(&remark.pass),
+                    remark.kind,
+                    json_string(&remark.function),
+                    json_string(&remark.location),
+                    json_string(&remark.message)
+                )
+            })
+            .collect();
+        if remarks.is_empty() {
+            return "[]\n".to_string();
+        }
+        format!("[\n{}\n]\n", remarks.join(",\n"))
+    }
+}
+
+impl Display for RemarkReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "remarks:")?;
+        let mut functions: BTreeMap<&str, Vec<&Remark>> = BTreeMap::new();
+        for remark in &self.remarks {
+            functions
+                .entry(remark.function.as_str())
+                .or_default()
+                .push(remark);
+        }
+        for (function, remarks) in functions {
+            if function.is_empty() {
+                writeln!(f, "  (module):")?;
+            } else {
+                writeln!(f, "  {function}:")?;
+            }
+            for remark in remarks {
+                if remark.location.is_empty() {
+                    writeln!(f, "    {} {}: {}", remark.kind, remark.pass, remark.message)?;
+                } else {
+                    writeln!(
+                        f,
+                        "    {} {} at {}: {}",
+                        remark.kind, remark.pass, remark.location, remark.message
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Yul source of the expression
+pub fn expression_source(expr: &Expression) -> String {
+    match expr {
+        Expression::Identifier(id) => id.name.clone(),
+        Expression::Literal(literal) => match literal {
+            Literal::DecimalNumberLiteral(dec, _) => dec.dec.clone(),
+            Literal::HexNumberLiteral(hex, _) => hex.hex.clone(),
+            Literal::StringLiteral(string, _) => format!("\"{}\"", string.str),
+            Literal::TrueLiteral(_) => "true".to_string(),
+            Literal::FalseLiteral(_) => "false".to_string(),
+        },
+        Expression::FunctionCall(func_call) => {
+            call_source(&func_call.id.name, &func_call.arguments)
+        }
+    }
+}
+
+/// Yul source of the call
+pub fn call_source(name: &str, args: &[Expression]) -> String {
+    let args: Vec<String> = args.iter().map(expression_source).collect();
+    format!("{name}({})", args.join(", "))
+}
+
+// LLVMDiagnosticSeverity
+const LLVM_DS_ERROR: c_int = 0;
+const LLVM_DS_WARNING: c_int = 1;
+const LLVM_DS_REMARK: c_int = 2;
+
+type RemarkHandler = extern "C" fn(*mut c_void, c_int, *const c_char, *const c_char);
+
+extern "C" {
+    fn YulSetRemarkHandler(
+        context: *mut c_void,
+        handler: Option<RemarkHandler>,
+        handler_context: *mut c_void,
+    );
+}
+
+extern "C" fn handle_llvm_diagnostic(
+    handler_context: *mut c_void,
+    severity: c_int,
+    function: *const c_char,
+    description: *const c_char,
+) {
+    let (function, description) = unsafe {
+        (
+            CStr::from_ptr(function).to_string_lossy().into_owned(),
+            CStr::from_ptr(description).to_string_lossy().into_owned(),
+        )
+    };
+    match severity {
+        LLVM_DS_REMARK => {
+            let remarks = unsafe { &*(handler_context as *const RefCell<Vec<(String, String)>>) };
+            remarks.borrow_mut().push((function, description));
+        }
+        // the handler replaces the default one, which prints the others
+        LLVM_DS_ERROR => eprintln!("error: {description}"),
+        LLVM_DS_WARNING => eprintln!("warning: {description}"),
+        _ => eprintln!("note: {description}"),
+    }
+}
+
+/// Collects the remarks LLVM reports on the context until it's dropped
+pub(crate) struct LlvmRemarkCollector<'ctx> {
+    context: &'ctx Context,
+    // function and description of each remark, boxed for an address that
+    // stays valid in the handler
+    descriptions: Box<RefCell<Vec<(String, String)>>>,
+}
+
+impl<'ctx> LlvmRemarkCollector<'ctx> {
+    /// A collector if remarks are wanted
+    pub(crate) fn install(context: &'ctx Context, remarks_wanted: bool) -> Option<Self> {
+        if !remarks_wanted {
+            return None;
+        }
+        let descriptions = Box::new(RefCell::new(vec![]));
+        unsafe {
+            YulSetRemarkHandler(
+                context.raw() as *mut c_void,
+                Some(handle_llvm_diagnostic),
+                &*descriptions as *const RefCell<Vec<(String, String)>> as *mut c_void,
+            );
+        }
+        Some(LlvmRemarkCollector {
+            context,
+            descriptions,
+        })
+    }
+
+    /// The remarks collected so far
+    pub(crate) fn take_remarks(&self) -> Vec<Remark> {
+        self.descriptions
+            .borrow_mut()
+            .drain(..)
+            .map(|(function, description)| llvm_remark(&function, &description))
+            .collect()
+    }
+}
+
+impl Drop for LlvmRemarkCollector<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            YulSetRemarkHandler(
+                self.context.raw() as *mut c_void,
+                None,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+}
+
+/// Remark of an LLVM diagnostic description, `<location>: <message>`,
+/// reported in the function
+fn llvm_remark(function: &str, description: &str) -> Remark {
+    let message = description
+        .strip_prefix("<unknown>:0:0: ")
+        .unwrap_or(description)
+        .to_string();
+    // the inliner names the caller:
+    // 'callee' inlined into 'caller' with (cost=...)
+    // 'callee' not inlined into 'caller' because ...
+    // callee will not be inlined into caller because ...
+    let Some((decision, rest)) = message.split_once(" inlined into ") else {
+        return Remark::missed("llvm", function, String::new(), message);
+    };
+    let caller = rest
+        .trim_start_matches('\'')
+        .split(['\'', ' '])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if decision.ends_with(" not") || decision.ends_with(" not be") {
+        Remark::missed("inline", &caller, String::new(), message)
+    } else {
+        Remark::applied("inline", &caller, String::new(), message)
+    }
+}
+
+impl<'a> Yul2IRContext<'a> {
+    /// Records the remark built by the closure if remarks are enabled
+    pub(crate) fn add_remark(&self, remark: impl FnOnce() -> Remark) {
+        if self.opts.remarks.is_some() {
+            self.remarks.borrow_mut().push(remark());
+        }
+    }
+
+    /// Writes the remarks of the lowering and LLVM next to the LLVM IR of the build
+    pub(crate) fn report_remarks(
+        &self,
+        llvm_remarks: Vec<Remark>,
+        output_basename: &str,
+    ) -> std::io::Result<()> {
+        let Some(format) = self.opts.remarks else {
+            return Ok(());
+        };
+        let mut remarks = self.remarks.borrow_mut();
+        remarks.extend(llvm_remarks);
+        let report = RemarkReport {
+            remarks: remarks.clone(),
+        };
+        if self.opts.verbose {
+            print!("{report}");
+        }
+        let (extension, contents) = match format {
+            RemarksFormat::Text => ("txt", report.to_string()),
+            RemarksFormat::Json => ("json", report.to_json()),
+        };
+        let output_dir = &self.opts.output_dir;
+        std::fs::write(
+            format!(
+                "{output_dir}/{}_{}.remarks.{extension}",
+                self.opts.main_contract_name, output_basename
+            ),
+            contents,
+        )
+    }
+}
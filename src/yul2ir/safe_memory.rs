@@ -0,0 +1,99 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bounds-checked EVM memory accesses.
+//!
+//! The memory wrappers of the stdlib take 32 bits offsets and sizes, so an
+//! offset like `2^64 + 0x40` silently aliases `0x40`. With
+//! `enable_safe_memory` the full offset and size of every memory range an
+//! instruction touches are passed, saturated to 64 bits, to
+//! `wrapper_check_memory_range` before the instruction runs. It reverts with
+//! Panic(0x41) when the range ends beyond `max_memory_size` and expands the
+//! memory otherwise.
+
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::yul_instruction::YulInstructionName;
+
+use inkwell::values::{BasicValueEnum, IntValue};
+use inkwell::IntPredicate;
+
+/// Size of an EVM memory range
+#[derive(Debug, Clone, Copy)]
+enum RangeSize {
+    Constant(u64),
+    /// Index of the argument holding the size
+    Arg(usize),
+}
+
+/// (offset argument index, size) of the memory ranges an instruction reads
+/// or writes
+fn memory_ranges(instr: &YulInstructionName) -> &'static [(usize, RangeSize)] {
+    use RangeSize::{Arg, Constant};
+    use YulInstructionName::*;
+    match instr {
+        MLoad | MStore => &[(0, Constant(32))],
+        MStore8 => &[(0, Constant(1))],
+        MCopy => &[(0, Arg(2)), (1, Arg(2))],
+        CallDataCopy | CodeCopy | ReturnDataCopy | DataCopy => &[(0, Arg(2))],
+        ExtCodeCopy => &[(1, Arg(3))],
+        Keccak256 | Return | Revert | Log0 | Log1 | Log2 | Log3 | Log4 => &[(0, Arg(1))],
+        Create | Create2 => &[(1, Arg(2))],
+        Call | CallCode => &[(3, Arg(4)), (5, Arg(6))],
+        DelegateCall | StaticCall => &[(2, Arg(3)), (4, Arg(5))],
+        _ => &[],
+    }
+}
+
+impl<'a> Yul2IRContext<'a> {
+    /// The value as i64, u64::MAX if it doesn't fit in 64 bits
+    fn build_saturated_u64(
+        &self,
+        value: &BasicValueEnum<'a>,
+    ) -> Result<IntValue<'a>, ASTLoweringError> {
+        if value.is_int_value() && value.into_int_value().get_type().get_bit_width() <= 64 {
+            return self.int_as_i64(value.into_int_value());
+        }
+        let value = self.try_into_u256(value)?;
+        let i64_type = self.i64_type();
+        let u256_type = self.u256_type();
+        let builder = self.builder.borrow_mut();
+        let high = builder.build_right_shift(value, u256_type.const_int(64, false), false, "")?;
+        let fits = builder.build_int_compare(IntPredicate::EQ, high, u256_type.const_zero(), "")?;
+        let low = builder.build_int_truncate(value, i64_type, "")?;
+        Ok(builder
+            .build_select(fits, low, i64_type.const_all_ones(), "saturated")?
+            .into_int_value())
+    }
+
+    /// Checks the memory ranges of the instruction with the lowered args,
+    /// before the instruction itself is lowered
+    pub(crate) fn build_memory_range_checks(
+        &self,
+        instr: &YulInstructionName,
+        args: &[BasicValueEnum<'a>],
+    ) -> Result<(), ASTLoweringError> {
+        let max_size = self
+            .i64_type()
+            .const_int(self.opts.max_memory_size as u64, false);
+        for (offset_index, size) in memory_ranges(instr) {
+            // the argument count is checked when the instruction is lowered
+            let Some(offset) = args.get(*offset_index) else {
+                continue;
+            };
+            let size = match size {
+                RangeSize::Constant(size) => self.i64_type().const_int(*size, false),
+                RangeSize::Arg(size_index) => match args.get(*size_index) {
+                    Some(size) => self.build_saturated_u64(size)?,
+                    None => continue,
+                },
+            };
+            let offset = self.build_saturated_u64(offset)?;
+            self.build_void_call(
+                "wrapper_check_memory_range",
+                &[offset.into(), size.into(), max_size.into()],
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lowering of the external dispatcher.
+//!
+//! Solidity dispatches the calls with a `switch shr(224, calldataload(0))`
+//! over the 4-byte selectors. As an LLVM `switch` the sparse selectors are
+//! lowered by the wasm backend to compare chains. With many cases the
+//! dispatcher is lowered instead to
+//! - `binary-search`: a tree of `lt` comparisons over the sorted selectors,
+//!   with small `switch`es at the leaves
+//! - `perfect-hash`: `(selector * multiplier) >> (32 - bits)`, with a
+//!   multiplier searched at compile time that gives each selector its own
+//!   slot, then a dense `switch` on the slot, lowered to a `br_table`, and
+//!   one comparison of the selector of the slot
+
+use crate::yul2ir::ast::Expression;
+use crate::yul2ir::config::SelectorDispatch;
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::remarks::{expression_source, Remark};
+
+use ethereum_types::U256;
+use inkwell::basic_block::BasicBlock;
+use inkwell::values::IntValue;
+use inkwell::IntPredicate;
+
+/// Dispatchers with fewer cases keep the LLVM `switch`. Not measured: 8 only
+/// keeps the compare chains of small contracts, bench_selector_dispatch.sh
+/// times DispatchBench with 64 functions, not the break-even point
+pub const SELECTOR_DISPATCH_MIN_CASES: usize = 8;
+// the binary search ends in a `switch` of at most this many selectors
+const BINARY_SEARCH_LEAF_CASES: usize = 4;
+// the hash table has at most 2^PERFECT_HASH_EXTRA_BITS slots per selector
+const PERFECT_HASH_EXTRA_BITS: u32 = 4;
+// multipliers tried per table size
+const PERFECT_HASH_ATTEMPTS: u32 = 1 << 16;
+
+/// Slot of the selector in a table of 2^bits slots
+pub fn perfect_hash_slot(selector: u32, multiplier: u32, bits: u32) -> u32 {
+    selector.wrapping_mul(multiplier) >> (32 - bits)
+}
+
+/// (multiplier, bits) of a hash giving each selector its own slot, with the
+/// smallest table found. The multipliers are odd and pseudo-random from a
+/// fixed seed, so the same selectors always get the same hash
+pub fn find_perfect_hash(selectors: &[u32]) -> Option<(u32, u32)> {
+    let min_bits = selectors.len().max(2).next_power_of_two().trailing_zeros();
+    for bits in min_bits..=(min_bits + PERFECT_HASH_EXTRA_BITS).min(31) {
+        let mut used = vec![false; 1 << bits];
+        // xorshift32
+        let mut state: u32 = 0x9e37_79b9;
+        for _ in 0..PERFECT_HASH_ATTEMPTS {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let multiplier = state | 1;
+            used.fill(false);
+            let is_perfect = selectors.iter().all(|selector| {
+                let slot = perfect_hash_slot(*selector, multiplier, bits) as usize;
+                !std::mem::replace(&mut used[slot], true)
+            });
+            if is_perfect {
+                return Some((multiplier, bits));
+            }
+        }
+    }
+    None
+}
+
+impl<'a> Yul2IRContext<'a> {
+    /// Whether the switch condition is the selector of the call, lowered to
+    /// an i32 by wrapper_calldata_load_selector
+    fn is_selector_load(&self, condition: &Expression) -> bool {
+        let Some(shr_args) = self.matches_yul_instruction(condition, "shr", 2) else {
+            return false;
+        };
+        let Some(load_args) = self.matches_yul_instruction(&shr_args[1], "calldataload", 1) else {
+            return false;
+        };
+        self.matches_constant_literal(&shr_args[0], U256::from(224))
+            && self.matches_constant_literal(&load_args[0], U256::zero())
+    }
+
+    /// Records the variable if it's declared with the selector load and never
+    /// assigned again, a switch over it is a dispatcher too
+    pub(crate) fn record_selector_variable(
+        &self,
+        yul_func_name: &str,
+        name: &str,
+        value: &Expression,
+    ) {
+        let assigned_once = self
+            .get_variable_usage(yul_func_name, name)
+            .is_some_and(|usage| usage.writes == 1);
+        if assigned_once && self.is_selector_load(value) {
+            self.selector_variables
+                .borrow_mut()
+                .insert((yul_func_name.to_string(), name.to_string()));
+        }
+    }
+
+    /// Whether the switch condition is the selector load or a variable only
+    /// assigned it
+    fn is_selector_condition(&self, yul_func_name: &str, condition: &Expression) -> bool {
+        if let Expression::Identifier(id) = condition {
+            return self
+                .selector_variables
+                .borrow()
+                .contains(&(yul_func_name.to_string(), id.name.clone()));
+        }
+        self.is_selector_load(condition)
+    }
+
+    /// Branches to the block of the case equal to the condition value, or
+    /// to otherwise_block. Lowers the dispatcher switch as configured by
+    /// selector_dispatch, the other switches to an LLVM `switch`
+    pub(crate) fn build_switch_dispatch(
+        &self,
+        yul_func_name: &str,
+        condition: &Expression,
+        cond_value: IntValue<'a>,
+        otherwise_block: BasicBlock<'a>,
+        cases: &[(IntValue<'a>, BasicBlock<'a>)],
+    ) -> Result<(), ASTLoweringError> {
+        if self.opts.selector_dispatch == SelectorDispatch::Switch
+            || cases.len() < SELECTOR_DISPATCH_MIN_CASES
+            || cond_value.get_type().get_bit_width() != 32
+            || !self.is_selector_condition(yul_func_name, condition)
+        {
+            self.builder
+                .borrow_mut()
+                .build_switch(cond_value, otherwise_block, cases)?;
+            return Ok(());
+        }
+        let Some(mut selectors) = cases
+            .iter()
+            .map(|(case_value, block)| {
+                let selector = u32::try_from(case_value.get_zero_extended_constant()?).ok()?;
+                Some((selector, *block))
+            })
+            .collect::<Option<Vec<(u32, BasicBlock<'a>)>>>()
+        else {
+            self.builder
+                .borrow_mut()
+                .build_switch(cond_value, otherwise_block, cases)?;
+            return Ok(());
+        };
+        selectors.sort_by_key(|(selector, _)| *selector);
+        let location = format!("switch {}", expression_source(condition));
+
+        if self.opts.selector_dispatch == SelectorDispatch::PerfectHash {
+            let keys: Vec<u32> = selectors.iter().map(|(selector, _)| *selector).collect();
+            if let Some((multiplier, bits)) = find_perfect_hash(&keys) {
+                self.add_remark(|| {
+                    Remark::applied(
+                        "selector_dispatch",
+                        yul_func_name,
+                        location,
+                        format!(
+                            "{} selectors dispatched by a perfect hash into a {} slots br_table",
+                            selectors.len(),
+                            1u32 << bits
+                        ),
+                    )
+                });
+                return self.build_selector_perfect_hash(
+                    cond_value,
+                    otherwise_block,
+                    &selectors,
+                    multiplier,
+                    bits,
+                );
+            }
+            self.add_remark(|| {
+                Remark::missed(
+                    "selector_dispatch",
+                    yul_func_name,
+                    location,
+                    format!(
+                        "no perfect hash found for the {} selectors, dispatched by binary search",
+                        selectors.len()
+                    ),
+                )
+            });
+        } else {
+            self.add_remark(|| {
+                Remark::applied(
+                    "selector_dispatch",
+                    yul_func_name,
+                    location,
+                    format!("{} selectors dispatched by binary search", selectors.len()),
+                )
+            });
+        }
+        self.build_selector_binary_search(cond_value, otherwise_block, &selectors)
+    }
+
+    /// Binary search of the sorted selectors, ends with a `switch` of a few
+    fn build_selector_binary_search(
+        &self,
+        selector: IntValue<'a>,
+        otherwise_block: BasicBlock<'a>,
+        selectors: &[(u32, BasicBlock<'a>)],
+    ) -> Result<(), ASTLoweringError> {
+        let i32_type = self.i32_type();
+        if selectors.len() <= BINARY_SEARCH_LEAF_CASES {
+            let cases: Vec<(IntValue<'a>, BasicBlock<'a>)> = selectors
+                .iter()
+                .map(|(value, block)| (i32_type.const_int(*value as u64, false), *block))
+                .collect();
+            self.builder
+                .borrow_mut()
+                .build_switch(selector, otherwise_block, &cases)?;
+            return Ok(());
+        }
+        let (low, high) = selectors.split_at(selectors.len() / 2);
+        let function = otherwise_block.get_parent().unwrap();
+        let low_block = self
+            .llvm_context
+            .append_basic_block(function, "selector_low");
+        let high_block = self
+            .llvm_context
+            .append_basic_block(function, "selector_high");
+        let is_low = self.builder.borrow_mut().build_int_compare(
+            IntPredicate::ULT,
+            selector,
+            i32_type.const_int(high[0].0 as u64, false),
+            "selector_is_low",
+        )?;
+        self.builder
+            .borrow_mut()
+            .build_conditional_branch(is_low, low_block, high_block)?;
+        self.builder.borrow_mut().position_at_end(low_block);
+        self.build_selector_binary_search(selector, otherwise_block, low)?;
+        self.builder.borrow_mut().position_at_end(high_block);
+        self.build_selector_binary_search(selector, otherwise_block, high)
+    }
+
+    /// Jumps through the slot of the selector, each used slot compares its
+    /// selector. Every slot is a case and the default is unreachable, so the
+    /// `switch` stays dense and LLVM lowers it to a `br_table`
+    fn build_selector_perfect_hash(
+        &self,
+        selector: IntValue<'a>,
+        otherwise_block: BasicBlock<'a>,
+        selectors: &[(u32, BasicBlock<'a>)],
+        multiplier: u32,
+        bits: u32,
+    ) -> Result<(), ASTLoweringError> {
+        let i32_type = self.i32_type();
+        let function = otherwise_block.get_parent().unwrap();
+        let product = self.builder.borrow_mut().build_int_mul(
+            selector,
+            i32_type.const_int(multiplier as u64, false),
+            "selector_hash_product",
+        )?;
+        let slot = self.builder.borrow_mut().build_right_shift(
+            product,
+            i32_type.const_int((32 - bits) as u64, false),
+            false,
+            "selector_slot",
+        )?;
+        let mut slot_blocks = vec![otherwise_block; 1 << bits];
+        for (value, _) in selectors {
+            slot_blocks[perfect_hash_slot(*value, multiplier, bits) as usize] = self
+                .llvm_context
+                .append_basic_block(function, "selector_slot");
+        }
+        let unreachable_block = self
+            .llvm_context
+            .append_basic_block(function, "selector_slot_unreachable");
+        let cases: Vec<(IntValue<'a>, BasicBlock<'a>)> = slot_blocks
+            .iter()
+            .enumerate()
+            .map(|(slot, block)| (i32_type.const_int(slot as u64, false), *block))
+            .collect();
+        self.builder
+            .borrow_mut()
+            .build_switch(slot, unreachable_block, &cases)?;
+        self.builder.borrow_mut().position_at_end(unreachable_block);
+        self.builder.borrow_mut().build_unreachable()?;
+
+        for (value, case_block) in selectors {
+            let slot_block = slot_blocks[perfect_hash_slot(*value, multiplier, bits) as usize];
+            self.builder.borrow_mut().position_at_end(slot_block);
+            let is_selector = self.builder.borrow_mut().build_int_compare(
+                IntPredicate::EQ,
+                selector,
+                i32_type.const_int(*value as u64, false),
+                "is_selector",
+            )?;
+            self.builder.borrow_mut().build_conditional_branch(
+                is_selector,
+                *case_block,
+                otherwise_block,
+            )?;
+        }
+        Ok(())
+    }
+}
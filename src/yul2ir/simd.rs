@@ -0,0 +1,232 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bytes32 operations on two v128 halves.
+//!
+//! Without `enable_simd128` these call the stdlib wrappers (byte loops or four
+//! i64 operations per bytes32). With it `and`/`or`/`xor`/`not`, `eq`/`iszero`
+//! and the big endian conversions of u256 values are emitted inline as v128
+//! loads, bitwise ops, `any_true` and `i8x16.shuffle`, and the target machine
+//! is created with `+simd128`.
+
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::yul_instruction::YulInstructionName;
+
+use inkwell::intrinsics::Intrinsic;
+use inkwell::types::{StructType, VectorType};
+use inkwell::values::{BasicValue, BasicValueEnum, PointerValue, VectorValue};
+
+impl<'a> Yul2IRContext<'a> {
+    fn v128_type(&self) -> VectorType<'a> {
+        self.llvm_context.i8_type().vec_type(16)
+    }
+
+    /// A bytes32 or u256 in memory as two v128 halves
+    fn v128_pair_type(&self) -> StructType<'a> {
+        self.llvm_context
+            .struct_type(&[self.v128_type().into(), self.v128_type().into()], false)
+    }
+
+    fn build_v128_load(
+        &self,
+        ptr: PointerValue<'a>,
+        half: u32,
+    ) -> Result<VectorValue<'a>, ASTLoweringError> {
+        let builder = self.builder.borrow_mut();
+        let half_ptr = builder.build_struct_gep(self.v128_pair_type(), ptr, half, "")?;
+        let value = builder.build_load(self.v128_type(), half_ptr, "")?;
+        // bytes32 values are byte aligned
+        value
+            .as_instruction_value()
+            .unwrap()
+            .set_alignment(1)
+            .map_err(|err| ASTLoweringError::BuilderError(err.to_string()))?;
+        Ok(value.into_vector_value())
+    }
+
+    fn build_v128_store(
+        &self,
+        ptr: PointerValue<'a>,
+        half: u32,
+        value: VectorValue<'a>,
+    ) -> Result<(), ASTLoweringError> {
+        let builder = self.builder.borrow_mut();
+        let half_ptr = builder.build_struct_gep(self.v128_pair_type(), ptr, half, "")?;
+        builder
+            .build_store(half_ptr, value)?
+            .set_alignment(1)
+            .map_err(|err| ASTLoweringError::BuilderError(err.to_string()))?;
+        Ok(())
+    }
+
+    /// `v128.any_true` of a vector, as i32
+    fn build_v128_any_true(
+        &self,
+        value: VectorValue<'a>,
+    ) -> Result<BasicValueEnum<'a>, ASTLoweringError> {
+        let any_true = Intrinsic::find("llvm.wasm.anytrue")
+            .and_then(|intrinsic| {
+                intrinsic.get_declaration(&self.llvm_module.borrow(), &[self.v128_type().into()])
+            })
+            .unwrap();
+        Ok(self
+            .builder
+            .borrow_mut()
+            .build_call(any_true, &[value.into()], "any_true")?
+            .try_as_basic_value()
+            .left()
+            .unwrap())
+    }
+
+    /// result = lhs and/or/xor rhs, all bytes32 pointers
+    pub(crate) fn build_bytes32_bitwise(
+        &self,
+        instr: &YulInstructionName,
+        lhs: BasicValueEnum<'a>,
+        rhs: BasicValueEnum<'a>,
+        result_ptr: PointerValue<'a>,
+    ) -> Result<(), ASTLoweringError> {
+        let func_name = match instr {
+            YulInstructionName::And => "wrapper_bytes32_and",
+            YulInstructionName::Or => "wrapper_bytes32_or",
+            YulInstructionName::Xor => "wrapper_bytes32_xor",
+            _ => unreachable!("not a bytes32 bitwise instruction {instr:?}"),
+        };
+        if !self.opts.enable_simd128 {
+            return self.build_void_call(func_name, &[lhs, rhs, result_ptr.into()]);
+        }
+        for half in 0..2 {
+            let a = self.build_v128_load(lhs.into_pointer_value(), half)?;
+            let b = self.build_v128_load(rhs.into_pointer_value(), half)?;
+            let builder = self.builder.borrow_mut();
+            let value = match instr {
+                YulInstructionName::And => builder.build_and(a, b, "")?,
+                YulInstructionName::Or => builder.build_or(a, b, "")?,
+                _ => builder.build_xor(a, b, "")?,
+            };
+            drop(builder);
+            self.build_v128_store(result_ptr, half, value)?;
+        }
+        Ok(())
+    }
+
+    /// result = not value, both bytes32 pointers
+    pub(crate) fn build_bytes32_not(
+        &self,
+        value: BasicValueEnum<'a>,
+        result_ptr: PointerValue<'a>,
+    ) -> Result<(), ASTLoweringError> {
+        if !self.opts.enable_simd128 {
+            return self.build_void_call("wrapper_bytes32_not", &[value, result_ptr.into()]);
+        }
+        for half in 0..2 {
+            let v = self.build_v128_load(value.into_pointer_value(), half)?;
+            let not = self.builder.borrow_mut().build_not(v, "")?;
+            self.build_v128_store(result_ptr, half, not)?;
+        }
+        Ok(())
+    }
+
+    /// lhs == rhs of two bytes32 pointers, as i32
+    pub(crate) fn build_bytes32_eq(
+        &self,
+        lhs: BasicValueEnum<'a>,
+        rhs: BasicValueEnum<'a>,
+    ) -> Result<BasicValueEnum<'a>, ASTLoweringError> {
+        if !self.opts.enable_simd128 {
+            return self.build_call("wrapper_bytes32_eq", &[lhs, rhs]);
+        }
+        let mut diffs = vec![];
+        for half in 0..2 {
+            let a = self.build_v128_load(lhs.into_pointer_value(), half)?;
+            let b = self.build_v128_load(rhs.into_pointer_value(), half)?;
+            diffs.push(self.builder.borrow_mut().build_xor(a, b, "")?);
+        }
+        let diff = self.builder.borrow_mut().build_or(diffs[0], diffs[1], "")?;
+        self.build_v128_none_true(diff)
+    }
+
+    /// value == 0 of a bytes32 pointer, as i32
+    pub(crate) fn build_bytes32_iszero(
+        &self,
+        value: BasicValueEnum<'a>,
+    ) -> Result<BasicValueEnum<'a>, ASTLoweringError> {
+        if !self.opts.enable_simd128 {
+            return self.build_call("wrapper_bytes32_iszero", &[value]);
+        }
+        let low = self.build_v128_load(value.into_pointer_value(), 0)?;
+        let high = self.build_v128_load(value.into_pointer_value(), 1)?;
+        let bits = self.builder.borrow_mut().build_or(low, high, "")?;
+        self.build_v128_none_true(bits)
+    }
+
+    fn build_v128_none_true(
+        &self,
+        value: VectorValue<'a>,
+    ) -> Result<BasicValueEnum<'a>, ASTLoweringError> {
+        let any_true = self.build_v128_any_true(value)?.into_int_value();
+        let i32_type = self.i32_type();
+        Ok(self
+            .builder
+            .borrow_mut()
+            .build_xor(any_true, i32_type.const_int(1, false), "none_true")?
+            .into())
+    }
+
+    /// Reverses the 32 bytes at src into dst: the halves swap places and each
+    /// is reversed by an `i8x16.shuffle`. Converts u256 (little endian) to
+    /// bytes32 (big endian) and back
+    fn build_v128_byte_swap(
+        &self,
+        src: PointerValue<'a>,
+        dst: PointerValue<'a>,
+    ) -> Result<(), ASTLoweringError> {
+        let i32_type = self.i32_type();
+        let reverse_mask = VectorType::const_vector(
+            &(0..16)
+                .rev()
+                .map(|index| i32_type.const_int(index, false))
+                .collect::<Vec<_>>(),
+        );
+        let low = self.build_v128_load(src, 0)?;
+        let high = self.build_v128_load(src, 1)?;
+        let builder = self.builder.borrow_mut();
+        let undef = self.v128_type().get_undef();
+        let low = builder.build_shuffle_vector(low, undef, reverse_mask, "")?;
+        let high = builder.build_shuffle_vector(high, undef, reverse_mask, "")?;
+        drop(builder);
+        self.build_v128_store(dst, 0, high)?;
+        self.build_v128_store(dst, 1, low)
+    }
+
+    /// Writes the u256 at u256_ptr to bytes32_ptr in big endian
+    pub(crate) fn build_u256_to_big_endian(
+        &self,
+        u256_ptr: PointerValue<'a>,
+        bytes32_ptr: PointerValue<'a>,
+    ) -> Result<(), ASTLoweringError> {
+        if !self.opts.enable_simd128 {
+            return self.build_void_call(
+                "u256_to_big_endian_bytes",
+                &[u256_ptr.into(), bytes32_ptr.into()],
+            );
+        }
+        self.build_v128_byte_swap(u256_ptr, bytes32_ptr)
+    }
+
+    /// Reads the big endian bytes32 at bytes32_ptr into u256_ptr
+    pub(crate) fn build_u256_from_big_endian(
+        &self,
+        bytes32_ptr: PointerValue<'a>,
+        u256_ptr: PointerValue<'a>,
+    ) -> Result<(), ASTLoweringError> {
+        if !self.opts.enable_simd128 {
+            return self.build_void_call(
+                "u256_from_big_endian_bytes",
+                &[bytes32_ptr.into(), u256_ptr.into()],
+            );
+        }
+        self.build_v128_byte_swap(bytes32_ptr, u256_ptr)
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Wasm size attribution.
+//!
+//! The emitted module is decoded after every build step: wasm-ld, wasm-opt
+//! and wizer. The code size of the final module is attributed to its
+//! functions by the name section, or by the exports when the names are
+//! stripped, and split between the functions transformed from Yul and the
+//! stdlib. Data is attributed to the data segments and embedded sub-objects
+//! (the `{parent}.{child}` globals) by the sizes they were emitted with, the
+//! rest of the data section holds the constants of the stdlib and the code.
+
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::function_deduplicator::DeduplicationSavings;
+use crate::yul2ir::wasm::WasmStages;
+
+use parity_wasm::elements::{ImportCountType, Internal, Module, Section, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+
+/// Sizes of the sections of a module
+#[derive(Debug, Clone, Default)]
+pub struct ModuleSizes {
+    pub total: usize,
+    /// (section name, size in bytes) in module order, empty if the module
+    /// can't be decoded
+    pub sections: Vec<(String, usize)>,
+}
+
+impl ModuleSizes {
+    /// Size of the sections with the name
+    pub fn section(&self, name: &str) -> usize {
+        self.sections
+            .iter()
+            .filter(|(section, _)| section == name)
+            .map(|(_, size)| size)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionSize {
+    pub name: String,
+    /// Code bytes of the function body
+    pub size: usize,
+    /// Transformed from Yul, otherwise a stdlib or compiler runtime function
+    pub is_yul: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SizeReport {
+    /// (build step, sizes of its output), None for the steps that didn't run
+    pub steps: Vec<(String, Option<ModuleSizes>)>,
+    /// Named functions of the final module, largest first
+    pub functions: Vec<FunctionSize>,
+    /// `name section` or `exports`, empty if the module can't be decoded
+    pub function_names_source: String,
+    /// Code bytes of the functions without a name
+    pub unnamed_code_size: usize,
+    /// Bytes of each data segment and embedded sub-object, qualified name =>
+    /// size with the 4 bytes length prefix
+    pub data_objects: BTreeMap<String, usize>,
+    /// Code saved by merging duplicate functions, None if none was merged
+    pub deduplication: Option<DeduplicationSavings>,
+}
+
+impl SizeReport {
+    /// Sizes of the final module
+    pub fn output_sizes(&self) -> Option<&ModuleSizes> {
+        self.steps
+            .iter()
+            .rev()
+            .find_map(|(_, sizes)| sizes.as_ref())
+    }
+}
+
+impl Display for SizeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "size report:")?;
+        writeln!(f, "  steps:")?;
+        for (step, sizes) in &self.steps {
+            match sizes {
+                Some(sizes) if sizes.sections.is_empty() => {
+                    writeln!(f, "    {step}: {} bytes (not decodable)", sizes.total)?
+                }
+                Some(sizes) => writeln!(
+                    f,
+                    "    {step}: {} bytes (code {}, data {})",
+                    sizes.total,
+                    sizes.section("code"),
+                    sizes.section("data")
+                )?,
+                None => writeln!(f, "    {step}: skipped")?,
+            }
+        }
+        if let Some(deduplication) = &self.deduplication {
+            writeln!(f, "  {deduplication}")?;
+        }
+        let Some(output) = self.output_sizes() else {
+            return Ok(());
+        };
+        writeln!(f, "  sections:")?;
+        for (section, size) in &output.sections {
+            writeln!(f, "    {section}: {size} bytes")?;
+        }
+        if self.function_names_source.is_empty() {
+            writeln!(f, "  functions: not decodable")?;
+        } else {
+            let yul_size: usize = self
+                .functions
+                .iter()
+                .filter(|function| function.is_yul)
+                .map(|function| function.size)
+                .sum();
+            let stdlib_size: usize = self
+                .functions
+                .iter()
+                .filter(|function| !function.is_yul)
+                .map(|function| function.size)
+                .sum();
+            writeln!(
+                f,
+                "  functions (names from the {}): yul {} bytes, stdlib {} bytes, unnamed {} bytes",
+                self.function_names_source, yul_size, stdlib_size, self.unnamed_code_size
+            )?;
+            for function in &self.functions {
+                writeln!(
+                    f,
+                    "    {}: {} bytes{}",
+                    function.name,
+                    function.size,
+                    if function.is_yul { "" } else { " (stdlib)" }
+                )?;
+            }
+        }
+        writeln!(f, "  data:")?;
+        for (name, size) in &self.data_objects {
+            writeln!(f, "    {name}: {size} bytes")?;
+        }
+        let data_objects_size: usize = self.data_objects.values().sum();
+        writeln!(
+            f,
+            "    other: {} bytes",
+            output.section("data").saturating_sub(data_objects_size)
+        )?;
+        Ok(())
+    }
+}
+
+fn section_name(section: &Section) -> String {
+    match section {
+        Section::Custom(custom) => format!("custom {}", custom.name()),
+        Section::Name(_) => "custom name".to_string(),
+        Section::Type(_) => "type".to_string(),
+        Section::Import(_) => "import".to_string(),
+        Section::Function(_) => "function".to_string(),
+        Section::Table(_) => "table".to_string(),
+        Section::Memory(_) => "memory".to_string(),
+        Section::Global(_) => "global".to_string(),
+        Section::Export(_) => "export".to_string(),
+        Section::Start(_) => "start".to_string(),
+        Section::Element(_) => "element".to_string(),
+        Section::Code(_) => "code".to_string(),
+        Section::Data(_) => "data".to_string(),
+        _ => "other".to_string(),
+    }
+}
+
+fn serialized_size<T: Serialize>(value: T) -> usize {
+    let mut buf = vec![];
+    value.serialize(&mut buf).map_or(0, |_| buf.len())
+}
+
+/// Section sizes of a module
+pub fn module_sizes(wasm_bytes: &[u8]) -> ModuleSizes {
+    // parity-wasm can't decode some instructions, like the tail calls
+    let sections = match parity_wasm::deserialize_buffer::<Module>(wasm_bytes) {
+        Ok(module) => module
+            .sections()
+            .iter()
+            .map(|section| (section_name(section), serialized_size(section.clone())))
+            .collect(),
+        Err(_) => vec![],
+    };
+    ModuleSizes {
+        total: wasm_bytes.len(),
+        sections,
+    }
+}
+
+/// Body size of each defined function of a module, the name of each from the
+/// name section or the exports, and where the names come from
+fn function_code_sizes(
+    wasm_bytes: &[u8],
+) -> Option<(Vec<usize>, HashMap<usize, String>, &'static str)> {
+    let module = parity_wasm::deserialize_buffer::<Module>(wasm_bytes).ok()?;
+    let imported_count = module.import_count(ImportCountType::Function);
+    let body_sizes: Vec<usize> = module
+        .code_section()
+        .map(|code| {
+            code.bodies()
+                .iter()
+                .map(|body| serialized_size(body.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut names = HashMap::new();
+    let module = module.parse_names().unwrap_or_else(|(_, module)| module);
+    if let Some(function_names) = module.names_section().and_then(|names| names.functions()) {
+        for (index, name) in function_names.names() {
+            if let Some(index) = (index as usize).checked_sub(imported_count) {
+                names.insert(index, name.clone());
+            }
+        }
+        return Some((body_sizes, names, "name section"));
+    }
+    for export in module
+        .export_section()
+        .map_or(&[][..], |exports| exports.entries())
+    {
+        if let Internal::Function(index) = export.internal() {
+            if let Some(index) = (*index as usize).checked_sub(imported_count) {
+                names
+                    .entry(index)
+                    .or_insert_with(|| export.field().to_string());
+            }
+        }
+    }
+    Some((body_sizes, names, "exports"))
+}
+
+impl<'a> Yul2IRContext<'a> {
+    fn build_size_report(&self, stages: &WasmStages) -> SizeReport {
+        let mut report = SizeReport {
+            steps: vec![
+                ("wasm-ld".to_string(), Some(module_sizes(&stages.linked))),
+                (
+                    "wasm-opt".to_string(),
+                    stages.binaryen_optimized.as_deref().map(module_sizes),
+                ),
+                (
+                    "wizer".to_string(),
+                    stages.start_optimized.as_deref().map(module_sizes),
+                ),
+            ],
+            data_objects: self
+                .global_bytes_lengths
+                .borrow()
+                .iter()
+                .map(|(name, size)| (name.clone(), *size))
+                .collect(),
+            deduplication: self.duplicate_function_savings(stages.output()),
+            ..Default::default()
+        };
+
+        let Some((body_sizes, names, names_source)) = function_code_sizes(stages.output()) else {
+            return report;
+        };
+        let mut yul_names: HashSet<String> =
+            self.functions_mapping.borrow().keys().cloned().collect();
+        yul_names.extend(self.exported_func_names.borrow().iter().cloned());
+        for (index, size) in body_sizes.into_iter().enumerate() {
+            match names.get(&index) {
+                Some(name) => report.functions.push(FunctionSize {
+                    name: name.clone(),
+                    size,
+                    is_yul: yul_names.contains(name),
+                }),
+                None => report.unnamed_code_size += size,
+            }
+        }
+        report
+            .functions
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        report.function_names_source = names_source.to_string();
+        report
+    }
+
+    /// Writes the size report next to the LLVM IR of the build
+    pub(crate) fn report_sizes(
+        &self,
+        stages: &WasmStages,
+        output_basename: &str,
+    ) -> std::io::Result<()> {
+        let report = self.build_size_report(stages);
+        if self.opts.verbose {
+            print!("{report}");
+        }
+        let output_dir = &self.opts.output_dir;
+        std::fs::write(
+            format!(
+                "{output_dir}/{}_{}.size.txt",
+                self.opts.main_contract_name, output_basename
+            ),
+            report.to_string(),
+        )?;
+        *self.size_report.borrow_mut() = Some(report);
+        Ok(())
+    }
+}
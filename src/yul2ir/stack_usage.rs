@@ -0,0 +1,275 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Static shadow stack usage analysis.
+//!
+//! wasm keeps every address-taken local (u256 and bytes32 temporaries passed
+//! by pointer to the stdlib) on the shadow stack in linear memory. The frame
+//! size of a function is estimated from the allocas left in the optimized
+//! LLVM module, and the worst case stack depth is the heaviest call chain in
+//! the call graph without the calls between mutually recursive functions.
+
+use inkwell::module::Module;
+use inkwell::targets::TargetData;
+use inkwell::values::{BasicValueEnum, FunctionValue, InstructionOpcode};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+
+/// wasm32 keeps the stack pointer 16 bytes aligned
+const STACK_ALIGNMENT: u64 = 16;
+
+/// Stack space kept free below a guarded function for the stdlib functions
+/// it calls, which are not guarded themselves
+pub const STACK_GUARD_RESERVE: u32 = 2048;
+
+#[derive(Debug, Clone, Default)]
+pub struct StackUsageReport {
+    /// Estimated frame size in bytes of each function defined in the module
+    pub frame_sizes: BTreeMap<String, u64>,
+    /// The deepest call chain, outermost function first
+    pub worst_case_path: Vec<String>,
+    /// Stack bytes used by worst_case_path
+    pub worst_case_depth: u64,
+    /// Functions on a cycle of the call graph, the stack depth they reach
+    /// depends on runtime values and is not included in worst_case_depth
+    pub recursive_functions: BTreeSet<String>,
+}
+
+impl Display for StackUsageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "stack usage:")?;
+        writeln!(
+            f,
+            "  worst case depth: {} bytes ({})",
+            self.worst_case_depth,
+            self.worst_case_path.join(" -> ")
+        )?;
+        if !self.recursive_functions.is_empty() {
+            let recursive_functions: Vec<&str> = self
+                .recursive_functions
+                .iter()
+                .map(|s| s.as_str())
+                .collect();
+            writeln!(
+                f,
+                "  recursive functions (unbounded): {}",
+                recursive_functions.join(", ")
+            )?;
+        }
+        let mut frame_sizes: Vec<(&String, &u64)> = self
+            .frame_sizes
+            .iter()
+            .filter(|(_, size)| **size > 0)
+            .collect();
+        frame_sizes.sort_by(|a, b| b.1.cmp(a.1));
+        writeln!(f, "  frame sizes:")?;
+        for (name, size) in frame_sizes {
+            writeln!(f, "    {name}: {size} bytes")?;
+        }
+        Ok(())
+    }
+}
+
+fn align_to(size: u64, alignment: u64) -> u64 {
+    size.div_ceil(alignment) * alignment
+}
+
+/// Sum of the static allocas of the function, rounded up to the stack alignment
+pub fn frame_size(function: FunctionValue, target_data: &TargetData) -> u64 {
+    let mut size = 0;
+    for bb in function.get_basic_blocks() {
+        let mut inst = bb.get_first_instruction();
+        while let Some(cur) = inst {
+            if cur.get_opcode() == InstructionOpcode::Alloca {
+                if let Ok(ty) = cur.get_allocated_type() {
+                    let count = cur
+                        .get_operand(0)
+                        .and_then(|op| op.left())
+                        .and_then(|op| match op {
+                            BasicValueEnum::IntValue(v) => v.get_zero_extended_constant(),
+                            _ => None,
+                        })
+                        .unwrap_or(1);
+                    let alignment = cur.get_alignment().unwrap_or(1).max(1) as u64;
+                    size = align_to(size, alignment) + target_data.get_abi_size(&ty) * count;
+                }
+            }
+            inst = cur.get_next_instruction();
+        }
+    }
+    align_to(size, STACK_ALIGNMENT)
+}
+
+/// Names of the functions defined in the module that `function` calls directly
+fn callees<'ctx>(function: FunctionValue<'ctx>, module: &Module<'ctx>) -> BTreeSet<String> {
+    let mut callees = BTreeSet::new();
+    for bb in function.get_basic_blocks() {
+        let mut inst = bb.get_first_instruction();
+        while let Some(cur) = inst {
+            if cur.get_opcode() == InstructionOpcode::Call {
+                // the called value is the last operand of a call instruction
+                let callee = cur
+                    .get_operand(cur.get_num_operands() - 1)
+                    .and_then(|op| op.left());
+                if let Some(BasicValueEnum::PointerValue(callee)) = callee {
+                    let name = callee.get_name().to_string_lossy().to_string();
+                    if module
+                        .get_function(&name)
+                        .is_some_and(|f| f.count_basic_blocks() > 0)
+                    {
+                        callees.insert(name);
+                    }
+                }
+            }
+            inst = cur.get_next_instruction();
+        }
+    }
+    callees
+}
+
+// Tarjan's algorithm, numbers the strongly connected components of the call graph
+struct SccFinder<'a> {
+    call_graph: &'a BTreeMap<String, BTreeSet<String>>,
+    index: HashMap<String, usize>,
+    lowlink: HashMap<String, usize>,
+    stack: Vec<String>,
+    on_stack: HashSet<String>,
+    component: HashMap<String, usize>,
+    recursive_functions: BTreeSet<String>,
+}
+
+impl SccFinder<'_> {
+    fn visit(&mut self, name: &str) {
+        let index = self.index.len();
+        self.index.insert(name.to_string(), index);
+        self.lowlink.insert(name.to_string(), index);
+        self.stack.push(name.to_string());
+        self.on_stack.insert(name.to_string());
+
+        let call_graph = self.call_graph;
+        for callee in &call_graph[name] {
+            if !self.index.contains_key(callee) {
+                self.visit(callee);
+                let lowlink = self.lowlink[name].min(self.lowlink[callee]);
+                self.lowlink.insert(name.to_string(), lowlink);
+            } else if self.on_stack.contains(callee) {
+                let lowlink = self.lowlink[name].min(self.index[callee]);
+                self.lowlink.insert(name.to_string(), lowlink);
+            }
+        }
+
+        if self.lowlink[name] == index {
+            let component = self.component.len();
+            let mut members = vec![];
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(&member);
+                let done = member == name;
+                members.push(member);
+                if done {
+                    break;
+                }
+            }
+            if members.len() > 1 || self.call_graph[name].contains(name) {
+                self.recursive_functions.extend(members.iter().cloned());
+            }
+            for member in members {
+                self.component.insert(member, component);
+            }
+        }
+    }
+}
+
+struct StackDepthWalker<'a> {
+    frame_sizes: &'a BTreeMap<String, u64>,
+    call_graph: &'a BTreeMap<String, BTreeSet<String>>,
+    component: &'a HashMap<String, usize>,
+    // stack bytes used by the deepest call chain starting at the function
+    depths: HashMap<String, u64>,
+    // next function on that call chain
+    deepest_callee: HashMap<String, String>,
+}
+
+impl StackDepthWalker<'_> {
+    fn visit(&mut self, name: &str) -> u64 {
+        if let Some(depth) = self.depths.get(name) {
+            return *depth;
+        }
+        let mut deepest = 0;
+        let call_graph = self.call_graph;
+        for callee in &call_graph[name] {
+            // calls inside a recursive component are left out, this keeps
+            // the walk on the acyclic part of the call graph
+            if self.component[callee] == self.component[name] {
+                continue;
+            }
+            let depth = self.visit(callee);
+            if depth > deepest {
+                deepest = depth;
+                self.deepest_callee
+                    .insert(name.to_string(), callee.to_string());
+            }
+        }
+        let depth = self.frame_sizes[name] + deepest;
+        self.depths.insert(name.to_string(), depth);
+        depth
+    }
+}
+
+pub fn analyze_stack_usage(module: &Module, target_data: &TargetData) -> StackUsageReport {
+    let mut frame_sizes = BTreeMap::new();
+    let mut call_graph = BTreeMap::new();
+    for function in module.get_functions() {
+        if function.count_basic_blocks() == 0 {
+            continue;
+        }
+        let name = function.get_name().to_string_lossy().to_string();
+        frame_sizes.insert(name.clone(), frame_size(function, target_data));
+        call_graph.insert(name, callees(function, module));
+    }
+
+    let mut scc_finder = SccFinder {
+        call_graph: &call_graph,
+        index: HashMap::new(),
+        lowlink: HashMap::new(),
+        stack: vec![],
+        on_stack: HashSet::new(),
+        component: HashMap::new(),
+        recursive_functions: BTreeSet::new(),
+    };
+    for name in call_graph.keys() {
+        if !scc_finder.index.contains_key(name) {
+            scc_finder.visit(name);
+        }
+    }
+
+    let mut walker = StackDepthWalker {
+        frame_sizes: &frame_sizes,
+        call_graph: &call_graph,
+        component: &scc_finder.component,
+        depths: HashMap::new(),
+        deepest_callee: HashMap::new(),
+    };
+    let mut worst_case_root = None;
+    let mut worst_case_depth = 0;
+    for name in frame_sizes.keys() {
+        let depth = walker.visit(name);
+        if worst_case_root.is_none() || depth > worst_case_depth {
+            worst_case_root = Some(name.clone());
+            worst_case_depth = depth;
+        }
+    }
+
+    let mut worst_case_path = vec![];
+    let mut cur = worst_case_root;
+    while let Some(name) = cur {
+        cur = walker.deepest_callee.get(&name).cloned();
+        worst_case_path.push(name);
+    }
+
+    StackUsageReport {
+        recursive_functions: scc_finder.recursive_functions,
+        frame_sizes,
+        worst_case_path,
+        worst_case_depth,
+    }
+}
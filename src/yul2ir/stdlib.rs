@@ -1,7 +1,7 @@
 // Copyright (c) the DTVM authors Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::yul2ir::config::Yul2IROptions;
+use crate::yul2ir::config::{WasmFeature, Yul2IROptions};
 use inkwell::context::Context;
 use inkwell::memory_buffer::MemoryBuffer;
 use inkwell::module::Module;
@@ -22,6 +22,10 @@ pub fn load_stdlib<'a>(
             to_link_modules.push(m);
         }
     }
+    // i128 builtins returning multiple values instead of the compiler-rt ones
+    if opts.has_wasm_feature(WasmFeature::MultiValue) {
+        to_link_modules.push(&MULTI_VALUE_WASM_IR);
+    }
 
     for bc in to_link_modules.iter() {
         let memory = MemoryBuffer::create_from_memory_range(bc, "wasm_bc");
@@ -43,21 +47,29 @@ pub fn load_stdlib<'a>(
 }
 
 #[cfg(debug_assertions)]
-static WASM_IR: [&[u8]; 5] = [
+static WASM_IR: [&[u8]; 9] = [
     include_bytes!("../../stdlib/wasm/debug/stdlib.bc"),
     include_bytes!("../../stdlib/wasm/debug/chain.bc"),
     include_bytes!("../../stdlib/wasm/debug/utils.bc"),
     include_bytes!("../../stdlib/wasm/debug/evm_memory.bc"),
     include_bytes!("../../stdlib/wasm/debug/chain_math.bc"),
+    include_bytes!("../../stdlib/wasm/debug/precompile.bc"),
+    include_bytes!("../../stdlib/wasm/debug/console_log.bc"),
+    include_bytes!("../../stdlib/wasm/debug/static_context.bc"),
+    include_bytes!("../../stdlib/wasm/debug/u256_limbs.bc"),
 ];
 
 #[cfg(not(debug_assertions))]
-static WASM_IR: [&[u8]; 5] = [
+static WASM_IR: [&[u8]; 9] = [
     include_bytes!("../../stdlib/wasm/release/stdlib.bc"),
     include_bytes!("../../stdlib/wasm/release/chain.bc"),
     include_bytes!("../../stdlib/wasm/release/utils.bc"),
     include_bytes!("../../stdlib/wasm/release/evm_memory.bc"),
     include_bytes!("../../stdlib/wasm/release/chain_math.bc"),
+    include_bytes!("../../stdlib/wasm/release/precompile.bc"),
+    include_bytes!("../../stdlib/wasm/release/console_log.bc"),
+    include_bytes!("../../stdlib/wasm/release/static_context.bc"),
+    include_bytes!("../../stdlib/wasm/release/u256_limbs.bc"),
 ];
 
 #[cfg(debug_assertions)]
@@ -67,3 +79,9 @@ static RELEASE_MODE_EXTRA_WASM_IR: [&[u8]; 0] = [];
 static RELEASE_MODE_EXTRA_WASM_IR: [&[u8]; 1] = [include_bytes!(
     "../../stdlib/wasm/release/debug_in_release.bc"
 )];
+
+#[cfg(debug_assertions)]
+static MULTI_VALUE_WASM_IR: &[u8] = include_bytes!("../../stdlib/wasm/debug/int128_builtins.bc");
+
+#[cfg(not(debug_assertions))]
+static MULTI_VALUE_WASM_IR: &[u8] = include_bytes!("../../stdlib/wasm/release/int128_builtins.bc");
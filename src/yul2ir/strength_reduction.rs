@@ -0,0 +1,245 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Strength reduction of u256 arithmetic with constant operands.
+//!
+//! - `mul`/`div`/`mod` by a power of two become shifts and masks, `sdiv` by a
+//!   power of two an arithmetic shift rounding towards zero
+//! - `div` by other constants becomes a multiply-high by the magic number of
+//!   the divisor (Granlund-Montgomery), the wasm32 backend otherwise expands
+//!   i256 division into a shift-subtract loop
+//! - `exp(2^j, n)` becomes a shift and `exp(x, k)` with a small constant `k`
+//!   a multiply chain instead of a `wrapper_exp` call
+//! - division and remainder by constant zero are zero like in the EVM
+
+use crate::yul2ir::ast::Expression;
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+use crate::yul2ir::infer::ExpectedType;
+use crate::yul2ir::yul_instruction::{YulInstructionName, YulLowLevelValue, YulLowLevelValueType};
+
+use ethereum_types::{U256, U512};
+use inkwell::types::StringRadix;
+use inkwell::values::IntValue;
+use inkwell::IntPredicate;
+
+// exp(x, k) is lowered to a multiply chain up to this exponent
+const MAX_MULTIPLY_CHAIN_EXPONENT: u64 = 8;
+
+fn power_of_two(value: U256) -> Option<u32> {
+    (!value.is_zero() && (value & (value - 1)).is_zero()).then(|| value.trailing_zeros())
+}
+
+impl<'a> Yul2IRContext<'a> {
+    fn u256_constant(&self, value: U256) -> IntValue<'a> {
+        self.u256_type()
+            .const_int_from_string(&format!("{value:x}"), StringRadix::Hexadecimal)
+            .unwrap()
+    }
+
+    fn walk_u256_arg(
+        &self,
+        yul_func_name: &str,
+        arg: &Expression,
+    ) -> Result<IntValue<'a>, ASTLoweringError> {
+        let value = self.walk_expr_with_type(yul_func_name, arg, ExpectedType::Untyped)?;
+        self.try_into_u256(&value.get_value())
+    }
+
+    /// Lowers an arithmetic instruction with a constant operand to cheaper
+    /// operations, None if it has no reduction
+    pub(crate) fn try_strength_reduce(
+        &self,
+        yul_func_name: &str,
+        instr: &YulInstructionName,
+        args: &[Expression],
+    ) -> Result<Option<YulLowLevelValue<'a>>, ASTLoweringError> {
+        if self.opts.disable_all_optimizers || args.len() != 2 {
+            return Ok(None);
+        }
+        let lhs_constant = self.fetch_not_string_literal_constant(&args[0]);
+        let rhs_constant = self.fetch_not_string_literal_constant(&args[1]);
+        let result = match (instr, lhs_constant, rhs_constant) {
+            (YulInstructionName::Mul, _, Some(c)) if power_of_two(c).is_some() => {
+                let x = self.walk_u256_arg(yul_func_name, &args[0])?;
+                self.build_shl_constant(x, power_of_two(c).unwrap())?
+            }
+            (YulInstructionName::Mul, Some(c), _) if power_of_two(c).is_some() => {
+                let x = self.walk_u256_arg(yul_func_name, &args[1])?;
+                self.build_shl_constant(x, power_of_two(c).unwrap())?
+            }
+            (YulInstructionName::Div, _, Some(d)) => {
+                let x = self.walk_u256_arg(yul_func_name, &args[0])?;
+                if d.is_zero() {
+                    self.u256_type().const_zero()
+                } else if let Some(k) = power_of_two(d) {
+                    self.builder.borrow_mut().build_right_shift(
+                        x,
+                        self.u256_type().const_int(k as u64, false),
+                        false,
+                        "div_result",
+                    )?
+                } else {
+                    self.build_div_by_magic(x, d)?
+                }
+            }
+            (YulInstructionName::Mod, _, Some(d)) if d.is_zero() || power_of_two(d).is_some() => {
+                let x = self.walk_u256_arg(yul_func_name, &args[0])?;
+                if d.is_zero() {
+                    self.u256_type().const_zero()
+                } else {
+                    self.builder.borrow_mut().build_and(
+                        x,
+                        self.u256_constant(d - 1),
+                        "mod_result",
+                    )?
+                }
+            }
+            // 2^255 is negative as a signed divisor
+            (YulInstructionName::SDiv, _, Some(d))
+                if d.is_zero() || matches!(power_of_two(d), Some(0..=254)) =>
+            {
+                let x = self.walk_u256_arg(yul_func_name, &args[0])?;
+                match power_of_two(d) {
+                    Some(k) => self.build_sdiv_by_power_of_two(x, k)?,
+                    None => self.u256_type().const_zero(),
+                }
+            }
+            (YulInstructionName::Exp, _, Some(k))
+                if k <= U256::from(MAX_MULTIPLY_CHAIN_EXPONENT) =>
+            {
+                let x = self.walk_u256_arg(yul_func_name, &args[0])?;
+                self.build_multiply_chain(x, k.as_u64())?
+            }
+            (YulInstructionName::Exp, Some(c), _) if power_of_two(c).is_some() => {
+                let n = self.walk_u256_arg(yul_func_name, &args[1])?;
+                self.build_exp_of_power_of_two(power_of_two(c).unwrap(), n)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(YulLowLevelValue {
+            value_type: YulLowLevelValueType::U256,
+            value: result.into(),
+        }))
+    }
+
+    fn build_shl_constant(
+        &self,
+        x: IntValue<'a>,
+        shift: u32,
+    ) -> Result<IntValue<'a>, ASTLoweringError> {
+        Ok(self.builder.borrow_mut().build_left_shift(
+            x,
+            self.u256_type().const_int(shift as u64, false),
+            "mul_result",
+        )?)
+    }
+
+    /// x / d for a constant d that isn't a power of two: with l = ceil(log2 d)
+    /// and m = 2^256 * (2^l - d) / d + 1, t = mulhi(m, x) and
+    /// x / d = (t + (x - t) / 2) >> (l - 1)
+    fn build_div_by_magic(
+        &self,
+        x: IntValue<'a>,
+        d: U256,
+    ) -> Result<IntValue<'a>, ASTLoweringError> {
+        let l = 256 - (d - 1).leading_zeros();
+        let magic = (((U512::one() << l as usize) - U512::from(d)) << 256) / U512::from(d) + 1;
+        let i512_type = self.llvm_context.custom_width_int_type(512);
+        let magic = i512_type
+            .const_int_from_string(&format!("{magic:x}"), StringRadix::Hexadecimal)
+            .unwrap();
+        let builder = self.builder.borrow_mut();
+        let wide_x = builder.build_int_z_extend(x, i512_type, "")?;
+        let product = builder.build_int_mul(wide_x, magic, "")?;
+        let high =
+            builder.build_right_shift(product, i512_type.const_int(256, false), false, "")?;
+        let t = builder.build_int_truncate(high, self.u256_type(), "")?;
+        let diff = builder.build_int_sub(x, t, "")?;
+        let half_diff =
+            builder.build_right_shift(diff, self.u256_type().const_int(1, false), false, "")?;
+        let sum = builder.build_int_add(t, half_diff, "")?;
+        Ok(builder.build_right_shift(
+            sum,
+            self.u256_type().const_int((l - 1) as u64, false),
+            false,
+            "div_result",
+        )?)
+    }
+
+    /// Signed division rounding towards zero, negative values are biased by
+    /// 2^k - 1 before the arithmetic shift
+    fn build_sdiv_by_power_of_two(
+        &self,
+        x: IntValue<'a>,
+        k: u32,
+    ) -> Result<IntValue<'a>, ASTLoweringError> {
+        if k == 0 {
+            return Ok(x);
+        }
+        let u256_type = self.u256_type();
+        let builder = self.builder.borrow_mut();
+        let sign = builder.build_right_shift(x, u256_type.const_int(255, false), true, "")?;
+        let bias = builder.build_right_shift(
+            sign,
+            u256_type.const_int(256 - k as u64, false),
+            false,
+            "",
+        )?;
+        let biased = builder.build_int_add(x, bias, "")?;
+        Ok(builder.build_right_shift(
+            biased,
+            u256_type.const_int(k as u64, false),
+            true,
+            "sdiv_result",
+        )?)
+    }
+
+    /// x^k by square and multiply
+    fn build_multiply_chain(
+        &self,
+        x: IntValue<'a>,
+        k: u64,
+    ) -> Result<IntValue<'a>, ASTLoweringError> {
+        let builder = self.builder.borrow_mut();
+        let mut result: Option<IntValue<'a>> = None;
+        let mut square = x;
+        let mut remaining = k;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = Some(match result {
+                    Some(result) => builder.build_int_mul(result, square, "exp_result")?,
+                    None => square,
+                });
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                square = builder.build_int_mul(square, square, "")?;
+            }
+        }
+        Ok(result.unwrap_or_else(|| self.u256_type().const_int(1, false)))
+    }
+
+    /// (2^j)^n = 1 << (j * n) while j * n < 256, zero above
+    fn build_exp_of_power_of_two(
+        &self,
+        j: u32,
+        n: IntValue<'a>,
+    ) -> Result<IntValue<'a>, ASTLoweringError> {
+        let u256_type = self.u256_type();
+        if j == 0 {
+            return Ok(u256_type.const_int(1, false));
+        }
+        let builder = self.builder.borrow_mut();
+        let bound = u256_type.const_int(256_u64.div_ceil(j as u64), false);
+        let in_range = builder.build_int_compare(IntPredicate::ULT, n, bound, "")?;
+        let shift = builder.build_int_mul(n, u256_type.const_int(j as u64, false), "")?;
+        let shift = builder
+            .build_select(in_range, shift, u256_type.const_zero(), "")?
+            .into_int_value();
+        let power = builder.build_left_shift(u256_type.const_int(1, false), shift, "")?;
+        Ok(builder
+            .build_select(in_range, power, u256_type.const_zero(), "exp_result")?
+            .into_int_value())
+    }
+}
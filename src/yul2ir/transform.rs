@@ -4,7 +4,10 @@
 
 use std::rc::Rc;
 
+use crate::yul2ir::ast_optimizer::optimize_object;
+use crate::yul2ir::config::CallingConvention;
 use crate::yul2ir::context::CompileFunctionResult;
+use crate::yul2ir::remarks::Remark;
 use crate::yul2ir::utils::merge_sub_wasm_with_length_prefix;
 use crate::yul2ir::var_scope::ScopeGuard;
 use crate::{
@@ -51,8 +54,9 @@ pub fn has_sub_contract(object: &Object) -> bool {
 impl<'a> Yul2IRContext<'a> {
     pub(crate) fn walk_block(&self, yul_func_name: &str, block: &Block) -> CompileResult<'a> {
         let _scope_guard = ScopeGuard::new(self);
-        for stmt in &block.statements {
-            self.walk_stmt(yul_func_name, stmt)?;
+        let mut index = 0;
+        while index < block.statements.len() {
+            index += self.walk_stmt_at(yul_func_name, &block.statements, index)?;
         }
         self.ok_result()
     }
@@ -142,9 +146,13 @@ impl<'a> Yul2IRContext<'a> {
                 let otherwise_block = self
                     .llvm_context
                     .append_basic_block(cur_func_value, "switch_otherwise");
-                self.builder
-                    .borrow_mut()
-                    .build_switch(cond_value, otherwise_block, &jump_blocks);
+                self.build_switch_dispatch(
+                    yul_func_name,
+                    &switch.condition,
+                    cond_value,
+                    otherwise_block,
+                    &jump_blocks,
+                )?;
 
                 for (_, bb) in jump_tbl {
                     self.builder.borrow_mut().position_at_end(bb.0);
@@ -201,8 +209,9 @@ impl<'a> Yul2IRContext<'a> {
         let _scope_guard = ScopeGuard::new(self);
 
         let _init_scope_guard = ScopeGuard::new(self);
-        for stmt in &r#for.init_block.statements {
-            self.walk_stmt(yul_func_name, stmt)?;
+        let mut index = 0;
+        while index < r#for.init_block.statements.len() {
+            index += self.walk_stmt_at(yul_func_name, &r#for.init_block.statements, index)?;
         }
         let cur_func = self.current_function.borrow().clone().unwrap();
         let cur_func_value = *cur_func.clone();
@@ -227,6 +236,7 @@ impl<'a> Yul2IRContext<'a> {
             .build_unconditional_branch(cond_block);
 
         self.builder.borrow_mut().position_at_end(cond_block);
+        self.build_loop_iteration_gas_charge()?;
         // For conditions, we prefer boolean (i32) results
         let cond_value =
             self.walk_expr_with_type(yul_func_name, &r#for.condition, ExpectedType::I32)?;
@@ -298,9 +308,10 @@ impl<'a> Yul2IRContext<'a> {
             self.i32_type().const_zero(),
             "nop",
         )?;
-        for stmt in &r#if.body.statements {
-            self.walk_stmt(yul_func_name, stmt)?;
-            unreachableflag = self.is_unreachable_node(stmt);
+        let mut index = 0;
+        while index < r#if.body.statements.len() {
+            index += self.walk_stmt_at(yul_func_name, &r#if.body.statements, index)?;
+            unreachableflag = self.is_unreachable_node(&r#if.body.statements[index - 1]);
         }
         // The internal then block should branch to the end block
         self.builder
@@ -497,6 +508,7 @@ impl<'a> Yul2IRContext<'a> {
                     return self.ok_result();
                 }
             }
+            self.record_selector_variable(yul_func_name, &name, val);
         }
 
         // Get the expected type based on the variable's type
@@ -585,6 +597,19 @@ impl<'a> Yul2IRContext<'a> {
             self.default_primitive_type_low_level()
         };
 
+        // wide enough for all values assigned to the variable later
+        let (ty, var_low_level_value_type) = match self.inferred_local_type(yul_func_name, &name) {
+            Some(inferred_ty)
+                if var_decl.identifiers[0].type_name.is_none() && ty.is_int_type() =>
+            {
+                (
+                    inferred_ty,
+                    YulLowLevelValueType::from_basic_type_enum(inferred_ty),
+                )
+            }
+            _ => (ty, var_low_level_value_type),
+        };
+
         let llvm_var = self.fast_alloca(ty, &format!("var_{name}"))?;
         self.set_var(&name, ty, var_low_level_value_type, llvm_var, false)?;
 
@@ -778,7 +803,15 @@ impl<'a> Yul2IRContext<'a> {
         func_call: &ast::FunctionCall,
     ) -> CompileResult<'a> {
         let func_name = func_call.id.name.clone();
-        let qualifier_func_name = self.get_func_decl_qualifier_name_by_str(&func_name);
+        if parse_intrinsic_func_name(&func_name).is_none() {
+            // the called function may write memory
+            self.forget_constant_memory();
+        }
+        let mut qualifier_func_name = self.get_func_decl_qualifier_name_by_str(&func_name);
+        if let Some(representative) = self.duplicate_functions.borrow().get(&qualifier_func_name) {
+            // same body as representative up to renaming, call that one instead
+            qualifier_func_name = representative.clone();
+        }
 
         if self
             .revert_zero_functions
@@ -808,39 +841,15 @@ impl<'a> Yul2IRContext<'a> {
         }
 
         if self.opts.enable_all_optimizers && self.opts.enable_storage_load_store_little_endian {
-            // The Solidity ERC20 transfer function contains redundant keccak256 calls that need optimization.
-            // Currently using C implementation for optimization.
-
-            // the function logic check is in the transform_func
-            if (func_call.id.name == "fun_transfer" || func_call.id.name == "fun__transfer")
-                && func_call.arguments.len() == 3
-            {
-                let from_arg = self.walk_expr_with_type(
-                    yul_func_name,
-                    &func_call.arguments[0],
-                    ExpectedType::Bytes32Pointer,
-                )?;
-                let from_arg = self.try_into_bytes32_pointer(&from_arg.get_value())?;
-                let to_arg = self.walk_expr_with_type(
-                    yul_func_name,
-                    &func_call.arguments[1],
-                    ExpectedType::Bytes32Pointer,
-                )?;
-                let to_arg = self.try_into_bytes32_pointer(&to_arg.get_value())?;
-                let value_arg = self.walk_expr_with_type(
-                    yul_func_name,
-                    &func_call.arguments[2],
-                    ExpectedType::Bytes32Pointer,
-                )?;
-                let value_arg = self.try_into_bytes32_pointer(&value_arg.get_value())?;
-                self.build_void_call(
-                    "wrapper_optimized_erc20_fun_transfer",
-                    &[from_arg, to_arg, value_arg],
-                )?;
-                return Ok(YulLowLevelValue {
-                    value_type: YulLowLevelValueType::I32,
-                    value: self.i32_type().const_zero().into(),
-                });
+            // Standard ERC20 functions contain redundant keccak256 calls, functions
+            // matched by a pattern rule are replaced by their C implementation
+            let rule_match = self
+                .pattern_rule_matches
+                .borrow()
+                .get(&qualifier_func_name)
+                .cloned();
+            if let Some(rule_match) = rule_match {
+                return self.walk_pattern_rule_call(yul_func_name, func_call, &rule_match);
             }
         }
 
@@ -904,7 +913,7 @@ impl<'a> Yul2IRContext<'a> {
                     // Try to cast to the expected parameter type
                     let param_type = infered_yul_func_ty.params_inkwell_type[i];
                     let expr = self.try_cast(expr.get_value(), param_type)?;
-                    let expr: BasicValueEnum = expr;
+                    let expr: BasicValueEnum = self.build_call_arg(expr, param_type)?;
                     call_args.push(expr)
                 }
                 e => return e,
@@ -914,12 +923,16 @@ impl<'a> Yul2IRContext<'a> {
         let func_def = self.current_func_decls.borrow().get(&func_name).unwrap();
 
         if has_ret {
-            let result = self.build_call(&qualifier_func_name, &call_args)?;
-            // TODO: Set the return value type based on the function's inferred return type from self.yul_func_infer_types
+            let returns = &infered_yul_func_ty.returns_inkwell_type;
+            let result = if self.returns_through_pointers(returns) {
+                self.build_out_pointer_call(&qualifier_func_name, &call_args, returns)?
+            } else {
+                self.build_call(&qualifier_func_name, &call_args)?
+            };
             let func_result_low_level_value_ty = if has_ret && is_tuple_ret_ty {
                 YulLowLevelValueType::Tuple
             } else if has_ret {
-                self.default_func_return_low_level_value_type()
+                infered_yul_func_ty.returns[0]
             } else {
                 // When a Yul function has no return value
                 YulLowLevelValueType::None
@@ -1091,12 +1104,17 @@ impl<'a> Yul2IRContext<'a> {
 
 impl<'a> Yul2IRContext<'a> {
     pub fn transform(&mut self) -> CompileResult<'a> {
+        // emit_code transforms twice, keep the remarks of the last one
+        self.remarks.borrow_mut().clear();
+        if !self.opts.disable_all_optimizers {
+            self.yul_ast = self.yul_ast.as_ref().map(optimize_object);
+        }
         self.scan_func_decls(&self.yul_ast.clone().unwrap())?;
         self.transform_object(&self.yul_ast.clone().unwrap(), true)?;
         self.ok_result()
     }
 
-    fn get_func_decl_qualifier_name_by_str(&self, func_name: &str) -> String {
+    pub(crate) fn get_func_decl_qualifier_name_by_str(&self, func_name: &str) -> String {
         // TODO: Use nested object names
         let module_name = self.current_module_name.borrow().clone();
         let contract_name = self.current_contract_name.borrow().clone();
@@ -1107,187 +1125,6 @@ impl<'a> Yul2IRContext<'a> {
         self.get_func_decl_qualifier_name_by_str(&func_def.name.name)
     }
 
-    fn matches_fun_transfer_pattern(&self, function: &FunctionDefinition) -> bool {
-        // this pattern is foundry compiled with optimizer, by solc 0.8.25
-        // optimized implementation of fun_transfer for standard ERC20:
-        // yul function:
-        // function fun_transfer(var_from, var_to, var_value)
-        // {
-        //     let _1 := and(var_from, sub(shl(160, 1), 1))
-        //     let _2 := iszero(_1)
-        //     if _2
-        //     {
-        //         mstore(0x00,shl(225, 0x4b637e8f))
-        //         mstore(4,0x00)
-        //         revert(0x00, 36)
-        //     }
-        //     let _3 := and(var_to, sub(shl(160, 1), 1))
-        //     let _4 := iszero(_3)
-        //     if _4
-        //     {
-        //         mstore(0x00, shl(224, 0xec442f05))
-        //         mstore(4, 0x00)
-        //         revert(0x00, 36)
-        //     }
-        //     _2 := 0x00
-        //     mstore(0x00, _1)
-        //     mstore(0x20, 0x00)
-        //     let _5 := sload(keccak256(0x00, 0x40))
-        //     if lt(_5, var_value)
-        //     {
-        //         mstore(0x00, shl(226, 0x391434e3))
-        //         mstore(4, _1)
-        //         mstore(36, _5)
-        //         mstore(68, var_value)
-        //         revert(0x00, 100)
-        //     }
-        //     mstore(0x00, _1)
-        //     mstore(0x20, 0x00)
-        //     sstore(keccak256(0x00, 0x40), sub(_5, var_value))
-        //     _4 := 0x00
-        //     mstore(0x00,  _3)
-        //     mstore(0x20, 0x00)
-        //     let dataSlot := keccak256(0x00, 0x40)
-        //     sstore(dataSlot, add(sload(dataSlot), var_value))
-        //     let _6 := mload(64)
-        //     mstore(_6, var_value)
-        //     log3(_6, 32,
-        //     0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef, _1,
-        //     _3)
-        // }
-        if !function.name.name.contains("fun_transfer")
-            && !function.name.name.contains("fun__transfer")
-        {
-            return false;
-        }
-        if !self.current_module_name.borrow().contains("Token")
-            && !self.current_module_name.borrow().contains("ERC20")
-        {
-            return false;
-        }
-        if function.params.len() != 3 {
-            return false;
-        }
-        let _param0_iden = function.params[0].identifier.name.clone();
-        let _param1_iden = function.params[1].identifier.name.clone();
-        let _param2_iden = function.params[2].identifier.name.clone();
-        let stmts = &function.body.statements;
-        if stmts.len() < 18 || stmts.len() > 22 {
-            return false;
-        }
-        // let _1 := sub(shl(160, 1), 1)
-        let var_1_instr = self.matches_single_declare_instruction(&stmts[0]);
-        if var_1_instr.is_none() {
-            return false;
-        }
-        let (var_1_iden, var_1_expr) = var_1_instr.unwrap();
-        if var_1_iden != "_1" {
-            return false;
-        }
-        if self
-            .matches_yul_instruction(&var_1_expr, "sub", 2)
-            .is_none()
-        {
-            return false;
-        }
-        // let _2 := and(var_from, _1)
-        let var_2_instr = self.matches_single_declare_instruction(&stmts[1]);
-        if var_2_instr.is_none() {
-            return false;
-        }
-        let (var_2_iden, var_2_expr) = var_2_instr.unwrap();
-        if var_2_iden != "_2" {
-            return false;
-        }
-        if self
-            .matches_yul_instruction(&var_2_expr, "and", 2)
-            .is_none()
-        {
-            return false;
-        }
-        // if iszero(_2)
-        if !matches!(stmts[2], Statement::If(_)) {
-            return false;
-        }
-        // let _4 := and(var_to, _1)
-        let var_4_instr = self.matches_single_declare_instruction(&stmts[3]);
-        if var_4_instr.is_none() {
-            return false;
-        }
-        let (var_4_iden, _) = var_4_instr.unwrap();
-        if var_4_iden != "_4" {
-            return false;
-        }
-        // if iszero(_4)
-        if !matches!(stmts[4], Statement::If(_)) {
-            return false;
-        }
-        // mstore(0, _2)
-        if self.matches_mstore_statement(&stmts[5]).is_none() {
-            return false;
-        }
-        // mstore(0x20, 0)
-        if self.matches_mstore_statement(&stmts[6]).is_none() {
-            return false;
-        }
-        // let _6 := sload(keccak256(0, 0x40))
-        let var_6_instr = self.matches_single_declare_instruction(&stmts[7]);
-        if var_6_instr.is_none() {
-            return false;
-        }
-        let (var_6_iden, _) = var_6_instr.unwrap();
-        if var_6_iden != "_6" {
-            return false;
-        }
-        // if lt(_6, var_value)
-        if !matches!(stmts[8], Statement::If(_)) {
-            return false;
-        }
-        // mstore(0, _2)
-        if self.matches_mstore_statement(&stmts[9]).is_none() {
-            return false;
-        }
-        // mstore(0x20, 0)
-        if self.matches_mstore_statement(&stmts[10]).is_none() {
-            return false;
-        }
-        // sstore(keccak256(0, 0x40), sub(_6, var_value))
-        if self.matches_sstore_statement(&stmts[11]).is_none() {
-            return false;
-        }
-        // mstore(0, _4)
-        if self.matches_mstore_statement(&stmts[12]).is_none() {
-            return false;
-        }
-        // let dataSlot := keccak256(0, 0x40)
-        if self
-            .matches_single_declare_instruction(&stmts[13])
-            .is_none()
-        {
-            return false;
-        }
-        // sstore(dataSlot, add(sload(dataSlot), var_value))
-        if self.matches_sstore_statement(&stmts[14]).is_none() {
-            return false;
-        }
-        // let _8 := mload(0x40)
-        if self
-            .matches_single_declare_instruction(&stmts[15])
-            .is_none()
-        {
-            return false;
-        }
-        // mstore(_8, var_value)
-        if self.matches_mstore_statement(&stmts[16]).is_none() {
-            return false;
-        }
-        //  log3
-        if self.matches_function_call(&stmts[17], "log3", 5).is_none() {
-            return false;
-        }
-        true
-    }
-
     fn transform_func_llvm_ty(
         &self,
         function: &FunctionDefinition,
@@ -1298,23 +1135,12 @@ impl<'a> Yul2IRContext<'a> {
 
         for (i, param) in function.params.iter().enumerate() {
             let _param_name = param.identifier.name.clone();
-            let mut ir_ty: BasicTypeEnum<'a> =
-                self.parse_ty_name_or_default(&param.type_name, self.default_param_type());
-
-            let mut param_low_level_type = YulLowLevelValueType::from_basic_type_enum(ir_ty);
+            let ir_ty: BasicTypeEnum<'a> =
+                self.inferred_param_type(function, i).unwrap_or_else(|| {
+                    self.parse_ty_name_or_default(&param.type_name, self.default_param_type())
+                });
 
-            if (function.name.name.contains("fun_transfer")
-                || function.name.name.contains("fun__transfer"))
-                && function.params.len() == 3
-                && ((i == 0 || i == 1) || (self.opts.enable_all_optimizers && i == 2))
-            {
-                // The first two parameters of fun_transfer are typically addresses, and the third parameter is usually uint256
-                // Therefore, we prioritize using appropriate parameter types
-                // When dealing with ERC20 and using the enable_all_optimizers option, we match the fun_transfer pattern and use the C implementation
-
-                ir_ty = self.bytes32_pointer_type().into();
-                param_low_level_type = YulLowLevelValueType::Bytes32Pointer;
-            }
+            let param_low_level_type = YulLowLevelValueType::from_basic_type_enum(ir_ty);
 
             let id = self.next_iden_id();
             params.push(ir_ty);
@@ -1334,8 +1160,13 @@ impl<'a> Yul2IRContext<'a> {
                 .void_type()
                 .fn_type(&params_meta_types, false),
             1 => {
-                let ret_ty = self.default_func_return_element_type();
-                let ret_low_level_value_type = self.default_func_return_low_level_value_type();
+                let (ret_ty, ret_low_level_value_type) = match self.inferred_return_type(function) {
+                    Some(ret_ty) => (ret_ty, YulLowLevelValueType::from_basic_type_enum(ret_ty)),
+                    None => (
+                        self.default_func_return_element_type(),
+                        self.default_func_return_low_level_value_type(),
+                    ),
+                };
 
                 let id = self.next_iden_id();
                 let _ret_name = &function.returns[0].identifier.name;
@@ -1364,6 +1195,10 @@ impl<'a> Yul2IRContext<'a> {
                 ret_struct.fn_type(&params_meta_types, false)
             }
         };
+        let func_ty = match self.opts.calling_convention {
+            CallingConvention::Value => func_ty,
+            CallingConvention::Pointer => self.pointer_convention_fn_type(&func_low_level_type),
+        };
 
         (func_ty, func_low_level_type)
     }
@@ -1374,6 +1209,16 @@ impl<'a> Yul2IRContext<'a> {
         *self.current_module_name.borrow_mut() = object.name.clone();
         *self.current_contract_name.borrow_mut() = object.name.clone();
 
+        if self.opts.enable_all_optimizers {
+            self.find_pattern_rule_matches(object);
+        }
+        if !self.opts.disable_all_optimizers {
+            self.find_duplicate_functions(object);
+        }
+        if self.opts.enable_all_optimizers {
+            self.infer_integer_widths(object);
+        }
+
         // Scan function declarations in current object
         for func in object
             .code
@@ -1390,12 +1235,30 @@ impl<'a> Yul2IRContext<'a> {
                     .insert(qualifier_func_name.clone(), func_low_level_type);
 
                 if self.is_revert_zero_function(func_def) {
+                    self.add_remark(|| {
+                        Remark::applied(
+                            "revert_zero",
+                            &qualifier_func_name,
+                            format!("function {}", func_def.name.name),
+                            format!(
+                                "the body is revert(0, 0), calls go to {UNIFIED_REVERT_ERROR_ZERO}"
+                            ),
+                        )
+                    });
                     self.revert_zero_functions
                         .borrow_mut()
                         .insert(qualifier_func_name.clone());
                     continue;
                 }
 
+                if self
+                    .duplicate_functions
+                    .borrow()
+                    .contains_key(&qualifier_func_name)
+                {
+                    continue;
+                }
+
                 // Add function to module
                 let function = self.llvm_module.borrow_mut().add_function(
                     &qualifier_func_name,
@@ -1589,6 +1452,13 @@ impl<'a> Yul2IRContext<'a> {
                     contract_name,
                     func_def.name.name.clone()
                 );
+                if self
+                    .duplicate_functions
+                    .borrow()
+                    .contains_key(&qualifier_func_name)
+                {
+                    continue;
+                }
                 let func_def: Rc<FunctionValue> =
                     Rc::new(self.transform_func(func_def, qualifier_func_name.clone())?);
 
@@ -1633,6 +1503,16 @@ impl<'a> Yul2IRContext<'a> {
             .borrow_mut()
             .push(export_func_name.to_string());
 
+        if self.opts.enable_static_context_check {
+            if is_deployed_object {
+                // the host calls call_static instead of call when the contract is
+                // entered through a staticcall
+                self.transform_call_static_func(&init_def_qualified_name)?;
+            }
+            self.transform_set_call_depth_func()?;
+            self.transform_is_static_context_func()?;
+        }
+
         if !*(self.wasm_start_inited.borrow()) {
             // if the wasm start function hasn't been added yet,
             // add one to initialize the EVM heap(__init_evm_heap) and other functions
@@ -1663,6 +1543,86 @@ impl<'a> Yul2IRContext<'a> {
         self.ok_result()
     }
 
+    fn transform_call_static_func(&self, init_func_name: &str) -> CompileResult<'a> {
+        let func_value = self.llvm_module.borrow_mut().add_function(
+            "call_static",
+            self.void_type().fn_type(&[], false),
+            None,
+        );
+        let entry_bb = self.llvm_context.append_basic_block(func_value, "entry");
+        self.functions.borrow_mut().push(Rc::new(func_value));
+        self.builder.borrow_mut().position_at_end(entry_bb);
+        *self.current_function.borrow_mut() = Some(Rc::new(func_value));
+        *self.current_function_definition.borrow_mut() = None;
+        self.build_void_call("wrapper_enter_static_context", &[])?;
+        self.build_void_call(init_func_name, &[])?;
+        self.builder.borrow_mut().build_return(None)?;
+        self.exported_func_names
+            .borrow_mut()
+            .push("call_static".to_string());
+        self.ok_result()
+    }
+
+    /// Exported entry the host calls before deploy, call or call_static with
+    /// the depth of the new frame, added once per module
+    fn transform_set_call_depth_func(&self) -> CompileResult<'a> {
+        if self
+            .llvm_module
+            .borrow()
+            .get_function("set_call_depth")
+            .is_some()
+        {
+            return self.ok_result();
+        }
+        let func_value = self.llvm_module.borrow_mut().add_function(
+            "set_call_depth",
+            self.void_type().fn_type(&[self.i32_type().into()], false),
+            None,
+        );
+        let entry_bb = self.llvm_context.append_basic_block(func_value, "entry");
+        self.functions.borrow_mut().push(Rc::new(func_value));
+        self.builder.borrow_mut().position_at_end(entry_bb);
+        *self.current_function.borrow_mut() = Some(Rc::new(func_value));
+        *self.current_function_definition.borrow_mut() = None;
+        let depth = func_value.get_nth_param(0).unwrap();
+        self.build_void_call("wrapper_set_call_depth", &[depth])?;
+        self.builder.borrow_mut().build_return(None)?;
+        self.exported_func_names
+            .borrow_mut()
+            .push("set_call_depth".to_string());
+        self.ok_result()
+    }
+
+    /// Exported entry the host asks on the caller of a delegatecall or
+    /// callcode, the callee runs through call_static when it returns 1. Added
+    /// once per module
+    fn transform_is_static_context_func(&self) -> CompileResult<'a> {
+        if self
+            .llvm_module
+            .borrow()
+            .get_function("is_static_context")
+            .is_some()
+        {
+            return self.ok_result();
+        }
+        let func_value = self.llvm_module.borrow_mut().add_function(
+            "is_static_context",
+            self.i32_type().fn_type(&[], false),
+            None,
+        );
+        let entry_bb = self.llvm_context.append_basic_block(func_value, "entry");
+        self.functions.borrow_mut().push(Rc::new(func_value));
+        self.builder.borrow_mut().position_at_end(entry_bb);
+        *self.current_function.borrow_mut() = Some(Rc::new(func_value));
+        *self.current_function_definition.borrow_mut() = None;
+        let is_static = self.build_call("wrapper_is_static_context", &[])?;
+        self.builder.borrow_mut().build_return(Some(&is_static))?;
+        self.exported_func_names
+            .borrow_mut()
+            .push("is_static_context".to_string());
+        self.ok_result()
+    }
+
     fn transform_init_func(&self, object: &Object) -> (CompileFunctionResult<'a>, String) {
         let module_name = self.current_module_name.borrow().clone();
         let contract_name = self.current_contract_name.borrow().clone();
@@ -1691,10 +1651,13 @@ impl<'a> Yul2IRContext<'a> {
         *self.current_function_definition.borrow_mut() = None;
 
         let _scope_guard = ScopeGuard::new(self);
+        self.analyze_code_usage(&qualifier_func_name, &object.code);
 
-        for stmt in &object.code.statements {
-            if let Err(err) = self.walk_stmt(&qualifier_func_name, stmt) {
-                return (Err(err), qualifier_func_name);
+        let mut index = 0;
+        while index < object.code.statements.len() {
+            match self.walk_stmt_at(&qualifier_func_name, &object.code.statements, index) {
+                Ok(walked) => index += walked,
+                Err(err) => return (Err(err), qualifier_func_name),
             }
         }
         // Jump to exit block since each basic block must end with an explicit branch or return
@@ -1746,6 +1709,11 @@ impl<'a> Yul2IRContext<'a> {
         *self.current_function.borrow_mut() = Some(Rc::new(func_value));
         *self.current_function_definition.borrow_mut() = Some(function.clone());
 
+        if self.opts.enable_stack_guard {
+            self.build_stack_guard()?;
+        }
+        self.build_function_call_gas_charge()?;
+
         let _scope_guard = ScopeGuard::new(self);
 
         let infered_yul_func_ty = self
@@ -1762,7 +1730,7 @@ impl<'a> Yul2IRContext<'a> {
             let param_low_level_value_type = infered_yul_func_ty.params[i];
             let param_var_pointer = self.fast_alloca(param_ty, param_name)?;
             // Store the parameter value in this variable
-            let param_value = func_value.get_nth_param(i as u32).unwrap();
+            let param_value = self.load_param(func_value, i, param_ty)?;
             let param_value = self.try_cast(param_value, param_ty)?;
             self.build_store(param_var_pointer, param_value)?;
             self.set_var(
@@ -1804,20 +1772,9 @@ impl<'a> Yul2IRContext<'a> {
             )?;
         }
 
-        if self.opts.enable_all_optimizers {
-            // For standard ERC20 implementations, we can use the optimized C implementation for fun_transfer
-            // Since walk_function_call will handle the call replacement, we only need to verify if the function logic can be replaced
-            if (function.name.name.contains("fun_transfer")
-                || function.name.name.contains("fun__transfer"))
-                && function.params.len() == 3
-                && !self.matches_fun_transfer_pattern(function)
-            {
-                panic!("fun_transfer is not a standard ERC20 implementation, please disable the --enable-all-optimizers option for this contract");
-            }
-        }
-
-        for stmt in &function.body.statements {
-            self.walk_stmt(&qualifier_func_name, stmt)?;
+        let mut index = 0;
+        while index < function.body.statements.len() {
+            index += self.walk_stmt_at(&qualifier_func_name, &function.body.statements, index)?;
         }
         // Entering the exit function label
         // The previous logic needs to jump to the exit basic block,
@@ -1829,6 +1786,26 @@ impl<'a> Yul2IRContext<'a> {
 
         self.builder.borrow_mut().position_at_end(exit_bb);
 
+        if self.returns_through_pointers(&func_low_level_type.returns_inkwell_type) {
+            let mut ret_values = vec![];
+            for ret_info in &function.returns {
+                let ret_name = &ret_info.identifier.name;
+                let (ret_var_ty, _ret_var_low_level_value_type, ret_var_pointer, _) =
+                    self.get_var(ret_name).unwrap();
+                let ret_value = if self.default_func_return_low_level_value_type()
+                    == YulLowLevelValueType::Bytes32Pointer
+                {
+                    // the bytes32 is allocated in memory, write out its pointer
+                    ret_var_pointer.as_basic_value_enum()
+                } else {
+                    self.build_load(ret_var_ty, ret_var_pointer, ret_name)?
+                };
+                ret_values.push(ret_value);
+            }
+            self.build_out_pointer_return(func_value, function.params.len(), &ret_values)?;
+            return Ok(func_value);
+        }
+
         // Adding return values for the current function
         match function.returns.len() {
             0 => {
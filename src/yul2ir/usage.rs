@@ -111,15 +111,20 @@ impl<'a> Yul2IRContext<'a> {
     #[allow(unused)]
     pub fn analyze_function_usage(&self, func_def: &FunctionDefinition) {
         let func_qualifier = self.get_func_decl_qualifier_name(func_def);
+        self.analyze_code_usage(&func_qualifier, &func_def.body);
+    }
+
+    /// Analyzes the body of a function or the code of an object
+    pub fn analyze_code_usage(&self, func_qualifier: &str, body: &Block) {
         // Avoid re-analyzing if already done (e.g., if called multiple times)
-        if self.variable_usage.borrow().contains_key(&func_qualifier) {
+        if self.variable_usage.borrow().contains_key(func_qualifier) {
             return;
         }
         let mut usage_map = HashMap::new();
-        self.analyze_block_usage(&func_def.body, &mut usage_map);
+        self.analyze_block_usage(body, &mut usage_map);
         self.variable_usage
             .borrow_mut()
-            .insert(func_qualifier, usage_map.clone());
+            .insert(func_qualifier.to_string(), usage_map.clone());
     }
 
     #[allow(unused)]
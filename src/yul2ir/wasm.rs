@@ -5,9 +5,10 @@
 use crate::yul2ir::utils;
 use inkwell::OptimizationLevel;
 use parity_wasm::builder;
-use parity_wasm::elements::{InitExpr, Instruction, Module};
+use parity_wasm::elements::{ImportCountType, InitExpr, Instruction, Module, Serialize};
 use rand::distr::Alphanumeric;
 use rand::Rng;
+use std::collections::HashMap;
 use std::ffi::CString;
 use std::fs::{self, File};
 use std::io::Read;
@@ -16,7 +17,7 @@ use std::process::Command;
 use tempfile::tempdir;
 use wizer::Wizer;
 
-use super::config::Yul2IROptions;
+use super::config::{WasmFeature, Yul2IROptions};
 
 fn generate_string(len: usize) -> String {
     rand::rngs::ThreadRng::default()
@@ -75,14 +76,35 @@ fn run_wasm_optimizer(
 }
 
 #[allow(unused)]
-fn do_binaryen_optimize(input: &[u8]) -> Option<Vec<u8>> {
-    run_wasm_optimizer(input, "wasm-opt", &["-O2", "-g"], Some("wasm-opt"))
+fn do_binaryen_optimize(input: &[u8], opts: &Yul2IROptions) -> Option<Vec<u8>> {
+    // wasm-opt enables some post-MVP features by default, start from the MVP
+    // and enable exactly the features of the options
+    let mut optimizer_args = vec!["-O2", "-g", "--mvp-features"];
+    if opts.enable_simd128 {
+        optimizer_args.push("--enable-simd");
+    }
+    for feature in &opts.wasm_features {
+        optimizer_args.push(match feature {
+            WasmFeature::BulkMemory => "--enable-bulk-memory",
+            WasmFeature::MultiValue => "--enable-multivalue",
+            WasmFeature::SignExt => "--enable-sign-ext",
+            WasmFeature::MutableGlobals => "--enable-mutable-globals",
+            WasmFeature::TailCall => "--enable-tail-call",
+        });
+    }
+    run_wasm_optimizer(input, "wasm-opt", &optimizer_args, Some("wasm-opt"))
 }
 
 #[allow(unused)]
-fn do_start_optimize(input: &[u8]) -> Option<Vec<u8>> {
+fn do_start_optimize(input: &[u8], opts: &Yul2IROptions) -> Option<Vec<u8>> {
     // use wizer to optimize the wasm
-    let initialized_wasm_bytes = Wizer::new().init_func("_start").run(input);
+    let mut wizer = Wizer::new();
+    wizer
+        .init_func("_start")
+        .wasm_bulk_memory(opts.has_wasm_feature(WasmFeature::BulkMemory))
+        .wasm_multi_value(opts.has_wasm_feature(WasmFeature::MultiValue))
+        .wasm_simd(opts.enable_simd128);
+    let initialized_wasm_bytes = wizer.run(input);
     if let Err(e) = initialized_wasm_bytes {
         println!(
             "wizer error: {}, this is optional optimizer, we will continue",
@@ -142,13 +164,55 @@ fn clang_opt_level_string(level: &inkwell::OptimizationLevel) -> String {
     }
 }
 
+const DEFAULT_STACK_SIZE: u32 = 0x10000; // 64K stack size default
+
+/// The wasm module after each step of the build
+#[derive(Debug, Clone)]
+pub struct WasmStages {
+    /// Output of wasm-ld
+    pub linked: Vec<u8>,
+    /// Output of wasm-opt, None if it didn't run
+    pub binaryen_optimized: Option<Vec<u8>>,
+    /// Output of wizer, None if it failed
+    pub start_optimized: Option<Vec<u8>>,
+}
+
+impl WasmStages {
+    /// Output of the last step that ran
+    pub fn output(&self) -> &[u8] {
+        self.start_optimized
+            .as_ref()
+            .or(self.binaryen_optimized.as_ref())
+            .unwrap_or(&self.linked)
+    }
+
+    pub fn into_output(self) -> Vec<u8> {
+        self.start_optimized
+            .or(self.binaryen_optimized)
+            .unwrap_or(self.linked)
+    }
+}
+
+/// Size of the shadow stack placed at the start of linear memory (--stack-first)
+pub fn stack_size(opts: &Yul2IROptions, has_sub_contract: bool) -> u32 {
+    if let Some(stack_size) = opts.stack_size {
+        stack_size
+    } else if opts.enable_all_optimizers && !has_sub_contract {
+        // half page if it's small contract
+        // use half page stack, so we can have some space for const segments and init evm memory
+        32768
+    } else {
+        DEFAULT_STACK_SIZE
+    }
+}
+
 pub fn link(
     input: &[u8],
     name: &str,
     export_names: &[String],
     opts: &Yul2IROptions,
     has_sub_contract: bool,
-) -> Vec<u8> {
+) -> WasmStages {
     let dir = tempdir().expect("failed to create temp directory for linking");
 
     let object_filename = dir.path().join(format!("{name}.o"));
@@ -185,12 +249,13 @@ pub fn link(
     ];
     command_line.push(CString::new("--no-entry").unwrap());
 
-    let mut stack_size = 0x10000; // 64K stack size default
+    let stack_size = stack_size(opts, has_sub_contract);
 
-    if opts.enable_all_optimizers && !has_sub_contract {
+    // parity-wasm can't decode return_call, with tail calls the stack pointer
+    // set up by wasm-ld is kept instead of patched below
+    let tail_call = opts.has_wasm_feature(WasmFeature::TailCall);
+    if stack_size != DEFAULT_STACK_SIZE || tail_call {
         command_line.push(CString::new("-z").unwrap());
-        stack_size = 32768; // half page if it's small contract
-                            // use half page stack, so we can have some space for const segments and init evm memory
         command_line.push(CString::new(format!("stack-size={stack_size}")).unwrap());
     }
 
@@ -240,6 +305,12 @@ pub fn link(
         .read_to_end(&mut output)
         .expect("failed to read output file");
 
+    if tail_call {
+        // --stack-first places the stack at address 0, so the stack pointer
+        // starts at the stack size
+        return optimize_linked_wasm(output, opts);
+    }
+
     let mut module: Module =
         parity_wasm::deserialize_buffer(&output).expect("cannot deserialize llvm wasm");
 
@@ -252,7 +323,10 @@ pub fn link(
         .next()
     {
         let init_expr = global.init_expr_mut();
-        *init_expr = InitExpr::new(vec![Instruction::I32Const(stack_size), Instruction::End]);
+        *init_expr = InitExpr::new(vec![
+            Instruction::I32Const(stack_size as i32),
+            Instruction::End,
+        ]);
     }
 
     let linked = builder::module().with_module(module);
@@ -260,12 +334,53 @@ pub fn link(
     let linked_wasm_bytes =
         parity_wasm::serialize(linked.build()).expect("cannot serialize linked wasm");
 
+    optimize_linked_wasm(linked_wasm_bytes, opts)
+}
+
+fn optimize_linked_wasm(linked_wasm_bytes: Vec<u8>, opts: &Yul2IROptions) -> WasmStages {
     // use optimizers to optimize the wasm
-    let linked_wasm_bytes = if !opts.no_binaryen_optimize {
-        do_binaryen_optimize(&linked_wasm_bytes).unwrap_or(linked_wasm_bytes)
+    let binaryen_optimized = if !opts.no_binaryen_optimize {
+        do_binaryen_optimize(&linked_wasm_bytes, opts)
     } else {
-        linked_wasm_bytes
+        None
     };
 
-    do_start_optimize(&linked_wasm_bytes).unwrap_or(linked_wasm_bytes)
+    let start_optimized = do_start_optimize(
+        binaryen_optimized.as_ref().unwrap_or(&linked_wasm_bytes),
+        opts,
+    );
+    WasmStages {
+        linked: linked_wasm_bytes,
+        binaryen_optimized,
+        start_optimized,
+    }
+}
+
+/// Code size in bytes of each named function of a wasm module, empty when
+/// the module has no name section
+pub fn function_body_sizes(wasm_bytes: &[u8]) -> HashMap<String, usize> {
+    let mut sizes = HashMap::new();
+    let Ok(module) = parity_wasm::deserialize_buffer::<Module>(wasm_bytes) else {
+        return sizes;
+    };
+    let module = module.parse_names().unwrap_or_else(|(_, module)| module);
+    let (Some(names), Some(code)) = (
+        module.names_section().and_then(|names| names.functions()),
+        module.code_section(),
+    ) else {
+        return sizes;
+    };
+    let imported_count = module.import_count(ImportCountType::Function) as u32;
+    for (index, name) in names.names() {
+        if index < imported_count {
+            continue;
+        }
+        if let Some(body) = code.bodies().get((index - imported_count) as usize) {
+            let mut buf = vec![];
+            if body.clone().serialize(&mut buf).is_ok() {
+                sizes.insert(name.clone(), buf.len());
+            }
+        }
+    }
+    sizes
 }
@@ -0,0 +1,86 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Wasm features of the generated module.
+//!
+//! The target machine is created with the enabled features, but the stdlib
+//! bitcode carries the `target-features` of the clang invocation that built
+//! it, so every defined function gets the features of the options here.
+//! - `bulk-memory`: `memcpy`, `__memmove` and `__memset` of the stdlib are
+//!   replaced by `memory.copy`/`memory.fill` instead of byte loops
+//! - `multi-value`: Yul functions returning tuples (value calling convention)
+//!   return multiple results instead of writing them through an sret pointer.
+//!   The i128 builtins of compiler-rt return through a pointer, so the stdlib
+//!   links its own ones returning multiple results
+//! - `sign-ext`: sign extensions of narrow integers use `i64.extend8_s` and
+//!   friends instead of shift pairs
+//! - `tail-call`: calls in tail position become `return_call`
+
+use crate::yul2ir::config::WasmFeature;
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::errors::ASTLoweringError;
+
+use inkwell::attributes::AttributeLoc;
+use inkwell::values::FunctionValue;
+
+impl<'a> Yul2IRContext<'a> {
+    /// Applies the enabled wasm features to all functions of the module
+    pub(crate) fn apply_wasm_features(&self) -> Result<(), ASTLoweringError> {
+        let features = self.llvm_features();
+        let module = self.llvm_module.borrow();
+        for function in module.get_functions() {
+            if function.count_basic_blocks() == 0 {
+                continue;
+            }
+            function.add_attribute(
+                AttributeLoc::Function,
+                self.llvm_context
+                    .create_string_attribute("target-features", &features),
+            );
+        }
+        if self.opts.has_wasm_feature(WasmFeature::BulkMemory) {
+            for name in ["memcpy", "__memmove"] {
+                if let Some(function) = module.get_function(name) {
+                    self.build_bulk_memory_body(function, false)?;
+                }
+            }
+            if let Some(function) = module.get_function("__memset") {
+                self.build_bulk_memory_body(function, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepends an entry block doing the whole copy (dest, src, length) or
+    /// fill (dest, val, length) with one bulk memory instruction, the byte
+    /// loop of the stdlib becomes unreachable
+    fn build_bulk_memory_body(
+        &self,
+        function: FunctionValue<'a>,
+        fill: bool,
+    ) -> Result<(), ASTLoweringError> {
+        let Some(old_entry) = function.get_first_basic_block() else {
+            return Ok(());
+        };
+        let entry = self
+            .llvm_context
+            .prepend_basic_block(old_entry, "bulk_memory");
+        let builder = self.llvm_context.create_builder();
+        builder.position_at_end(entry);
+        let dest = function.get_nth_param(0).unwrap().into_pointer_value();
+        let length = function.get_nth_param(2).unwrap().into_int_value();
+        if fill {
+            let val = function.get_nth_param(1).unwrap().into_int_value();
+            builder.build_memset(dest, 1, val, length)?;
+        } else {
+            let src = function.get_nth_param(1).unwrap().into_pointer_value();
+            // memmove semantics, memory.copy handles overlapping ranges
+            builder.build_memmove(dest, 1, src, 1, length)?;
+        }
+        match function.get_type().get_return_type() {
+            Some(_) => builder.build_return(Some(&dest))?,
+            None => builder.build_return(None)?,
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,600 @@
+// Copyright (C) 2024-2025 the DTVM authors. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interprocedural integer width inference for the Yul functions of an object.
+//!
+//! Every Yul value is a u256, but memory offsets, lengths, loop counters and
+//! booleans are much smaller. Each variable of a function is bounded by the
+//! widest value assigned to it and each parameter by the widest argument of
+//! all its call sites, repeated over the call graph until no bound grows.
+//! Parameters, single returns and locals that provably fit in 32 or 64 bits
+//! are lowered to i32/i64, so wasm runs native integer operations instead of
+//! i256 emulation. Parameters only read as addresses, `and(p, 0xff..ff)` or
+//! passed on to such a parameter, are lowered to bytes32 pointers.
+//!
+//! Narrow values are zero extended when widened, `slt`/`sgt` widen their
+//! operands to u256 before the signed comparison.
+
+use crate::yul2ir::ast::{
+    Block, Expression, For, FunctionCall, FunctionDefinition, Object, Statement, SwitchOptions,
+    TypedIdentifier,
+};
+use crate::yul2ir::context::Yul2IRContext;
+use crate::yul2ir::yul_instruction::{
+    parse_intrinsic_func_name, YulInstructionName, YulLowLevelValueType,
+};
+
+use ethereum_types::U256;
+use inkwell::types::{BasicType, BasicTypeEnum};
+use std::collections::{HashMap, HashSet};
+
+/// Upper bound of an unsigned value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValueWidth {
+    /// 0 or 1
+    Bool,
+    /// below 2^32
+    I32,
+    /// below 2^64
+    I64,
+    U256,
+}
+
+impl ValueWidth {
+    fn of_constant(value: U256) -> Self {
+        match value.bits() {
+            0 | 1 => ValueWidth::Bool,
+            2..=32 => ValueWidth::I32,
+            33..=64 => ValueWidth::I64,
+            _ => ValueWidth::U256,
+        }
+    }
+
+    /// The width of the sum of two values of this width
+    fn sum(self) -> Self {
+        match self {
+            ValueWidth::Bool => ValueWidth::I32,
+            ValueWidth::I32 => ValueWidth::I64,
+            ValueWidth::I64 | ValueWidth::U256 => ValueWidth::U256,
+        }
+    }
+}
+
+/// Inferred widths of a function lowered with them
+#[derive(Debug, Clone, Default)]
+pub struct FunctionWidths {
+    pub params: Vec<ValueWidth>,
+    /// Parameters only read as addresses, lowered to bytes32 pointers
+    pub address_params: Vec<bool>,
+    pub returns: Vec<ValueWidth>,
+    /// variable name => width, including the parameters and returns
+    pub variables: HashMap<String, ValueWidth>,
+}
+
+/// The width of the result of a builtin with arguments of the given widths
+fn builtin_width(instr: &YulInstructionName, args: &[ValueWidth]) -> ValueWidth {
+    match (instr, args) {
+        (
+            YulInstructionName::Lt
+            | YulInstructionName::Gt
+            | YulInstructionName::SLt
+            | YulInstructionName::SGt
+            | YulInstructionName::Eq
+            | YulInstructionName::IsZero,
+            _,
+        ) => ValueWidth::Bool,
+        (YulInstructionName::And | YulInstructionName::Mod, [a, b]) => (*a).min(*b),
+        (YulInstructionName::Or | YulInstructionName::Xor, [a, b]) => (*a).max(*b),
+        (YulInstructionName::Add, [a, b]) => (*a).max(*b).sum(),
+        (YulInstructionName::Mul, [ValueWidth::Bool, width])
+        | (YulInstructionName::Mul, [width, ValueWidth::Bool]) => *width,
+        (YulInstructionName::Mul, [ValueWidth::I32, ValueWidth::I32]) => ValueWidth::I64,
+        (YulInstructionName::Div, [a, _]) => *a,
+        (YulInstructionName::Shr, [_, value]) => *value,
+        (YulInstructionName::MemoryGuard, [size]) => *size,
+        (
+            YulInstructionName::Byte
+            | YulInstructionName::CallDataSize
+            | YulInstructionName::ReturnDataSize
+            | YulInstructionName::CodeSize
+            | YulInstructionName::ExtCodeSize
+            | YulInstructionName::MSize
+            | YulInstructionName::DataSize
+            | YulInstructionName::DataOffset,
+            _,
+        ) => ValueWidth::I32,
+        (
+            YulInstructionName::Gas
+            | YulInstructionName::GasLimit
+            | YulInstructionName::TimeStamp
+            | YulInstructionName::Number,
+            _,
+        ) => ValueWidth::I64,
+        _ => ValueWidth::U256,
+    }
+}
+
+/// How a function body reads and writes one of its variables
+#[derive(Debug, Default)]
+struct VariableUses<'a> {
+    assigned: bool,
+    /// reads other than as an address
+    value_reads: usize,
+    /// reads as `and(x, 0xff..ff)`
+    address_reads: usize,
+    /// (function, parameter index) the variable is passed to
+    passed_to: Vec<(&'a str, usize)>,
+}
+
+struct WidthInference<'a, 'ctx> {
+    context: &'a Yul2IRContext<'ctx>,
+    functions: HashMap<&'a str, &'a FunctionDefinition>,
+    /// duplicate function => the function called instead
+    aliases: HashMap<&'a str, &'a str>,
+    /// function name => variable name => width, "" for the object code
+    variables: HashMap<String, HashMap<String, ValueWidth>>,
+    changed: bool,
+}
+
+impl<'a, 'ctx> WidthInference<'a, 'ctx> {
+    fn callee(&self, name: &str) -> Option<&'a FunctionDefinition> {
+        if parse_intrinsic_func_name(name).is_some() {
+            return None;
+        }
+        let name = self.aliases.get(name).copied().unwrap_or(name);
+        self.functions.get(name).copied()
+    }
+
+    fn variable(&self, function: &str, variable: &str) -> ValueWidth {
+        // no value is assigned yet
+        self.variables
+            .get(function)
+            .and_then(|variables| variables.get(variable))
+            .copied()
+            .unwrap_or(ValueWidth::Bool)
+    }
+
+    fn join(&mut self, function: &str, variable: &str, width: ValueWidth) {
+        let current = self
+            .variables
+            .entry(function.to_string())
+            .or_default()
+            .entry(variable.to_string())
+            .or_insert(ValueWidth::Bool);
+        if width > *current {
+            *current = width;
+            self.changed = true;
+        }
+    }
+
+    /// Widens what an identifier or a function call expression reads to at least width
+    fn pin(&mut self, function: &str, expr: &Expression, width: ValueWidth) {
+        match expr {
+            Expression::Identifier(id) => self.join(function, &id.name, width),
+            Expression::FunctionCall(call) => {
+                if let Some(callee) = self.callee(&call.id.name) {
+                    if let [ret] = callee.returns.as_slice() {
+                        self.join(&callee.name.name, &ret.identifier.name, width);
+                    }
+                }
+            }
+            Expression::Literal(_) => {}
+        }
+    }
+
+    fn expression(&mut self, function: &str, expr: &Expression) -> ValueWidth {
+        match expr {
+            Expression::Literal(_) => self
+                .context
+                .fetch_not_string_literal_constant(expr)
+                .map_or(ValueWidth::U256, ValueWidth::of_constant),
+            Expression::Identifier(id) => self.variable(function, &id.name),
+            Expression::FunctionCall(call) => self.call(function, call),
+        }
+    }
+
+    fn call(&mut self, function: &str, call: &FunctionCall) -> ValueWidth {
+        let args: Vec<ValueWidth> = call
+            .arguments
+            .iter()
+            .map(|arg| self.expression(function, arg))
+            .collect();
+        if let Some(instr) = parse_intrinsic_func_name(&call.id.name) {
+            return builtin_width(&instr, &args);
+        }
+        let Some(callee) = self.callee(&call.id.name) else {
+            return ValueWidth::U256;
+        };
+        for (param, width) in callee.params.iter().zip(args) {
+            self.join(&callee.name.name, &param.identifier.name, width);
+        }
+        match callee.returns.as_slice() {
+            [ret] => self.variable(&callee.name.name, &ret.identifier.name),
+            // tuples are returned as u256
+            _ => ValueWidth::U256,
+        }
+    }
+
+    fn block(&mut self, function: &str, block: &Block) {
+        for stmt in &block.statements {
+            self.statement(function, stmt);
+        }
+    }
+
+    fn statement(&mut self, function: &str, stmt: &Statement) {
+        match stmt {
+            Statement::VariableDeclaration(var_decl) => {
+                // variables without a value are 0
+                let width = match &var_decl.value {
+                    Some(value) => self.expression(function, value),
+                    None => ValueWidth::Bool,
+                };
+                for typed_id in &var_decl.identifiers {
+                    self.join(function, &typed_id.identifier.name, width);
+                }
+            }
+            Statement::Assignment(assign) => {
+                let width = self.expression(function, &assign.value);
+                for id in &assign.identifiers {
+                    self.join(function, &id.name, width);
+                }
+            }
+            Statement::If(r#if) => {
+                self.expression(function, &r#if.cond);
+                self.block(function, &r#if.body);
+            }
+            Statement::Switch(switch) => {
+                self.expression(function, &switch.condition);
+                let (cases, default) = match &switch.opt {
+                    SwitchOptions::Cases(cases, default) => (cases.as_slice(), default.as_ref()),
+                    SwitchOptions::Default(default) => (&[][..], Some(default)),
+                };
+                for case in cases {
+                    // the case values are cast to the type of the condition
+                    let case_width =
+                        self.expression(function, &Expression::Literal(case.case.clone()));
+                    self.pin(function, &switch.condition, case_width);
+                    self.block(function, &case.body);
+                }
+                if let Some(default) = default {
+                    self.block(function, &default.body);
+                }
+            }
+            Statement::For(r#for) => self.for_loop(function, r#for),
+            Statement::Block(block) => self.block(function, block),
+            Statement::FunctionCall(call) => {
+                self.call(function, call);
+            }
+            Statement::FunctionDefinition(_)
+            | Statement::Leave
+            | Statement::Break
+            | Statement::Continue
+            | Statement::Comment => {}
+        }
+    }
+
+    fn for_loop(&mut self, function: &str, r#for: &For) {
+        self.block(function, &r#for.init_block);
+        self.expression(function, &r#for.condition);
+        self.block(function, &r#for.execution_block);
+        match self.loop_counter(r#for) {
+            // i < bound before the increment, so i + 1 <= bound
+            Some((counter, bound)) => {
+                let width = self.expression(function, bound);
+                self.join(function, counter, width);
+            }
+            None => self.block(function, &r#for.post_block),
+        }
+    }
+
+    /// Matches `for { } lt(i, bound) { i := add(i, 1) } { }` with a body that doesn't assign i
+    fn loop_counter<'f>(&self, r#for: &'f For) -> Option<(&'f str, &'f Expression)> {
+        let (counter, bound) = match &r#for.condition {
+            Expression::FunctionCall(call) => match (call.id.name.as_str(), &call.arguments[..]) {
+                ("lt", [Expression::Identifier(id), bound]) => (id.name.as_str(), bound),
+                ("gt", [bound, Expression::Identifier(id)]) => (id.name.as_str(), bound),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let [Statement::Assignment(increment)] = r#for.post_block.statements.as_slice() else {
+            return None;
+        };
+        let adds_one = |lhs: &Expression, rhs: &Expression| {
+            matches!(lhs, Expression::Identifier(id) if id.name == counter)
+                && self.context.matches_constant_literal(rhs, U256::one())
+        };
+        let is_increment = match (&increment.identifiers[..], &increment.value) {
+            ([id], Expression::FunctionCall(add)) if id.name == counter && add.id.name == "add" => {
+                match &add.arguments[..] {
+                    [lhs, rhs] => adds_one(lhs, rhs) || adds_one(rhs, lhs),
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        let mut uses = VariableUses::default();
+        self.block_uses(&r#for.execution_block, counter, &mut uses);
+        (is_increment && !uses.assigned).then_some((counter, bound))
+    }
+
+    fn block_uses(&self, block: &Block, name: &str, uses: &mut VariableUses<'a>) {
+        for stmt in &block.statements {
+            self.statement_uses(stmt, name, uses);
+        }
+    }
+
+    fn statement_uses(&self, stmt: &Statement, name: &str, uses: &mut VariableUses<'a>) {
+        match stmt {
+            Statement::VariableDeclaration(var_decl) => {
+                if let Some(value) = &var_decl.value {
+                    self.expression_uses(value, name, uses);
+                }
+            }
+            Statement::Assignment(assign) => {
+                uses.assigned |= assign.identifiers.iter().any(|id| id.name == name);
+                self.expression_uses(&assign.value, name, uses);
+            }
+            Statement::If(r#if) => {
+                self.expression_uses(&r#if.cond, name, uses);
+                self.block_uses(&r#if.body, name, uses);
+            }
+            Statement::Switch(switch) => {
+                self.expression_uses(&switch.condition, name, uses);
+                match &switch.opt {
+                    SwitchOptions::Cases(cases, default) => {
+                        for case in cases {
+                            self.block_uses(&case.body, name, uses);
+                        }
+                        if let Some(default) = default {
+                            self.block_uses(&default.body, name, uses);
+                        }
+                    }
+                    SwitchOptions::Default(default) => self.block_uses(&default.body, name, uses),
+                }
+            }
+            Statement::For(r#for) => {
+                self.block_uses(&r#for.init_block, name, uses);
+                self.expression_uses(&r#for.condition, name, uses);
+                self.block_uses(&r#for.post_block, name, uses);
+                self.block_uses(&r#for.execution_block, name, uses);
+            }
+            Statement::Block(block) => self.block_uses(block, name, uses),
+            Statement::FunctionCall(call) => self.call_uses(call, name, uses),
+            Statement::FunctionDefinition(_)
+            | Statement::Leave
+            | Statement::Break
+            | Statement::Continue
+            | Statement::Comment => {}
+        }
+    }
+
+    fn expression_uses(&self, expr: &Expression, name: &str, uses: &mut VariableUses<'a>) {
+        match expr {
+            Expression::Identifier(id) if id.name == name => uses.value_reads += 1,
+            Expression::FunctionCall(call) => self.call_uses(call, name, uses),
+            _ => {}
+        }
+    }
+
+    fn call_uses(&self, call: &FunctionCall, name: &str, uses: &mut VariableUses<'a>) {
+        if call.id.name == "and" {
+            let masked = self
+                .context
+                .matches_address_mask_pattern(&Expression::FunctionCall(Box::new(call.clone())));
+            if let Some(Expression::Identifier(addr_iden)) = masked {
+                if addr_iden.name == name {
+                    uses.address_reads += 1;
+                }
+                return;
+            }
+        }
+        let callee = self.callee(&call.id.name);
+        for (i, arg) in call.arguments.iter().enumerate() {
+            match (callee, arg) {
+                (Some(callee), Expression::Identifier(id)) if id.name == name => {
+                    uses.passed_to.push((callee.name.name.as_str(), i));
+                }
+                _ => self.expression_uses(arg, name, uses),
+            }
+        }
+    }
+
+    /// Parameters of the lowered functions that are only read as addresses
+    fn address_params(&self, lowered: &HashSet<&'a str>) -> HashSet<(&'a str, usize)> {
+        let mut candidates = HashMap::new();
+        for (&name, func_def) in &self.functions {
+            if !lowered.contains(name) {
+                continue;
+            }
+            for (i, param) in func_def.params.iter().enumerate() {
+                let param_name = &param.identifier.name;
+                if self.variable(name, param_name) != ValueWidth::U256 {
+                    continue;
+                }
+                let mut uses = VariableUses::default();
+                self.block_uses(&func_def.body, param_name, &mut uses);
+                if !uses.assigned
+                    && uses.value_reads == 0
+                    && (uses.address_reads > 0 || !uses.passed_to.is_empty())
+                {
+                    candidates.insert((name, i), uses);
+                }
+            }
+        }
+        // a parameter passed on is an address only if the parameter it is passed to is
+        loop {
+            let rejected: Vec<(&str, usize)> = candidates
+                .iter()
+                .filter(|(_, uses)| {
+                    uses.passed_to
+                        .iter()
+                        .any(|target| !candidates.contains_key(target))
+                })
+                .map(|(param, _)| *param)
+                .collect();
+            if rejected.is_empty() {
+                break;
+            }
+            for param in rejected {
+                candidates.remove(&param);
+            }
+        }
+        candidates.into_keys().collect()
+    }
+}
+
+impl<'a> Yul2IRContext<'a> {
+    /// Functions whose types come from elsewhere or that aren't lowered
+    fn keeps_default_types(&self, func_def: &FunctionDefinition) -> bool {
+        let qualifier_func_name = self.get_func_decl_qualifier_name(func_def);
+        self.is_revert_zero_function(func_def)
+            || self
+                .duplicate_functions
+                .borrow()
+                .contains_key(&qualifier_func_name)
+            || self
+                .pattern_rule_matches
+                .borrow()
+                .contains_key(&qualifier_func_name)
+            || func_def
+                .params
+                .iter()
+                .chain(&func_def.returns)
+                .any(|typed_id| typed_id.type_name.is_some())
+    }
+
+    /// Infers the widths of the variables of the functions of the object,
+    /// after its duplicate functions and pattern rule matches are found
+    pub fn infer_integer_widths(&self, object: &Object) {
+        let functions: HashMap<&str, &FunctionDefinition> = object
+            .code
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::FunctionDefinition(func_def) => {
+                    Some((func_def.name.name.as_str(), func_def.as_ref()))
+                }
+                _ => None,
+            })
+            .collect();
+        let qualified_names: HashMap<String, &str> = functions
+            .keys()
+            .map(|&name| (self.get_func_decl_qualifier_name_by_str(name), name))
+            .collect();
+        let aliases = {
+            let duplicate_functions = self.duplicate_functions.borrow();
+            qualified_names
+                .iter()
+                .filter_map(|(qualified, &name)| {
+                    let representative =
+                        qualified_names.get(duplicate_functions.get(qualified)?)?;
+                    Some((name, *representative))
+                })
+                .collect()
+        };
+
+        let mut inference = WidthInference {
+            context: self,
+            functions,
+            aliases,
+            variables: HashMap::new(),
+            changed: true,
+        };
+        while inference.changed {
+            inference.changed = false;
+            inference.block("", &object.code);
+            for func_def in inference.functions.clone().into_values() {
+                inference.block(&func_def.name.name, &func_def.body);
+            }
+        }
+
+        let lowered: HashSet<&str> = inference
+            .functions
+            .iter()
+            .filter(|(_, func_def)| !self.keeps_default_types(func_def))
+            .map(|(&name, _)| name)
+            .collect();
+        let address_params = inference.address_params(&lowered);
+        let mut integer_widths = self.integer_widths.borrow_mut();
+        for &name in &lowered {
+            let func_def = inference.functions[&name];
+            let widths_of = |typed_ids: &[TypedIdentifier]| -> Vec<ValueWidth> {
+                typed_ids
+                    .iter()
+                    .map(|typed_id| inference.variable(name, &typed_id.identifier.name))
+                    .collect()
+            };
+            let params = widths_of(&func_def.params);
+            let returns = widths_of(&func_def.returns);
+            integer_widths.insert(
+                self.get_func_decl_qualifier_name(func_def),
+                FunctionWidths {
+                    params,
+                    address_params: (0..func_def.params.len())
+                        .map(|i| address_params.contains(&(name, i)))
+                        .collect(),
+                    returns,
+                    variables: inference.variables.remove(name).unwrap_or_default(),
+                },
+            );
+        }
+    }
+
+    /// The LLVM type of an inferred width, None if it isn't narrower than u256
+    fn narrow_int_type(&self, width: ValueWidth) -> Option<BasicTypeEnum<'a>> {
+        match width {
+            ValueWidth::Bool | ValueWidth::I32 => Some(self.i32_type().as_basic_type_enum()),
+            ValueWidth::I64 => Some(self.i64_type().as_basic_type_enum()),
+            ValueWidth::U256 => None,
+        }
+    }
+
+    pub(crate) fn inferred_param_type(
+        &self,
+        function: &FunctionDefinition,
+        index: usize,
+    ) -> Option<BasicTypeEnum<'a>> {
+        let integer_widths = self.integer_widths.borrow();
+        let widths = integer_widths.get(&self.get_func_decl_qualifier_name(function))?;
+        if widths.address_params[index] {
+            return Some(self.bytes32_pointer_type().as_basic_type_enum());
+        }
+        self.narrow_int_type(widths.params[index])
+    }
+
+    /// The narrowed type of the return of a function with a single return
+    pub(crate) fn inferred_return_type(
+        &self,
+        function: &FunctionDefinition,
+    ) -> Option<BasicTypeEnum<'a>> {
+        // bytes32 returns are read and written through pointers
+        if self.default_func_return_low_level_value_type() == YulLowLevelValueType::Bytes32Pointer {
+            return None;
+        }
+        let integer_widths = self.integer_widths.borrow();
+        let widths = integer_widths.get(&self.get_func_decl_qualifier_name(function))?;
+        match widths.returns.as_slice() {
+            [width] => self.narrow_int_type(*width),
+            _ => None,
+        }
+    }
+
+    /// The type of an untyped local variable, wide enough for all values assigned to it
+    pub(crate) fn inferred_local_type(
+        &self,
+        yul_func_name: &str,
+        var_name: &str,
+    ) -> Option<BasicTypeEnum<'a>> {
+        let width = *self
+            .integer_widths
+            .borrow()
+            .get(yul_func_name)?
+            .variables
+            .get(var_name)?;
+        Some(
+            self.narrow_int_type(width)
+                .unwrap_or_else(|| self.u256_type().as_basic_type_enum()),
+        )
+    }
+}
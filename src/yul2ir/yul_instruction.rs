@@ -199,6 +199,109 @@ pub enum YulInstructionName {
     DebugPrint,
 }
 
+impl YulInstructionName {
+    /// Instructions that are not allowed in a static context (EIP-214),
+    /// value-carrying call is checked at runtime
+    pub fn is_state_modifying(&self) -> bool {
+        matches!(
+            self,
+            YulInstructionName::SStore
+                | YulInstructionName::TStore
+                | YulInstructionName::Log0
+                | YulInstructionName::Log1
+                | YulInstructionName::Log2
+                | YulInstructionName::Log3
+                | YulInstructionName::Log4
+                | YulInstructionName::Create
+                | YulInstructionName::Create2
+                | YulInstructionName::SelfDestruct
+        )
+    }
+
+    /// Instructions that read or write the EVM memory
+    pub fn accesses_memory(&self) -> bool {
+        matches!(
+            self,
+            YulInstructionName::Keccak256
+                | YulInstructionName::MLoad
+                | YulInstructionName::MStore
+                | YulInstructionName::MStore8
+                | YulInstructionName::MCopy
+                | YulInstructionName::MSize
+                | YulInstructionName::CallDataCopy
+                | YulInstructionName::CodeCopy
+                | YulInstructionName::ExtCodeCopy
+                | YulInstructionName::DataCopy
+                | YulInstructionName::ReturnDataCopy
+                | YulInstructionName::Create
+                | YulInstructionName::Create2
+                | YulInstructionName::Call
+                | YulInstructionName::CallCode
+                | YulInstructionName::DelegateCall
+                | YulInstructionName::StaticCall
+                | YulInstructionName::Return
+                | YulInstructionName::Revert
+                | YulInstructionName::Log0
+                | YulInstructionName::Log1
+                | YulInstructionName::Log2
+                | YulInstructionName::Log3
+                | YulInstructionName::Log4
+                | YulInstructionName::DebugPrint
+        )
+    }
+
+    /// Instructions that only compute a value from their arguments
+    pub fn is_pure(&self) -> bool {
+        matches!(
+            self,
+            YulInstructionName::Add
+                | YulInstructionName::Sub
+                | YulInstructionName::Mul
+                | YulInstructionName::Div
+                | YulInstructionName::SDiv
+                | YulInstructionName::Mod
+                | YulInstructionName::SMod
+                | YulInstructionName::Exp
+                | YulInstructionName::Not
+                | YulInstructionName::Lt
+                | YulInstructionName::Gt
+                | YulInstructionName::SLt
+                | YulInstructionName::SGt
+                | YulInstructionName::Eq
+                | YulInstructionName::IsZero
+                | YulInstructionName::And
+                | YulInstructionName::Or
+                | YulInstructionName::Xor
+                | YulInstructionName::Byte
+                | YulInstructionName::Shl
+                | YulInstructionName::Shr
+                | YulInstructionName::Sar
+                | YulInstructionName::AddMod
+                | YulInstructionName::MulMod
+                | YulInstructionName::SignExtend
+        )
+    }
+
+    /// Instructions that write the EVM memory
+    pub fn writes_memory(&self) -> bool {
+        matches!(
+            self,
+            YulInstructionName::MStore
+                | YulInstructionName::MStore8
+                | YulInstructionName::MCopy
+                | YulInstructionName::CallDataCopy
+                | YulInstructionName::CodeCopy
+                | YulInstructionName::ExtCodeCopy
+                | YulInstructionName::DataCopy
+                | YulInstructionName::ReturnDataCopy
+                | YulInstructionName::Call
+                | YulInstructionName::CallCode
+                | YulInstructionName::DelegateCall
+                | YulInstructionName::StaticCall
+        )
+    }
+}
+
 impl From<String> for YulInstructionName {
     fn from(s: String) -> YulInstructionName {
         match parse_intrinsic_func_name(&s) {
@@ -5,6 +5,7 @@
 #include "debug.h"
 #include "evm_memory.h"
 #include "hostapi.h"
+#include "precompile.h"
 #include "utils.h"
 
 static void u256_to_big_endian(const uint256_t value, uint8_t *memory_ptr) {
@@ -229,14 +230,45 @@ void wrapper_self_balance(uint256_t *result) {
 }
 
 void wrapper_revert(int32_t error_msg_evm_mem, uint32_t size) {
+  evm_touch_memory(error_msg_evm_mem, size);
   uint8_t *error_msg_ptr = evm_get_memory_addr(error_msg_evm_mem);
   revert((ADDRESS_UINT)error_msg_ptr, size);
 }
 
+// Error(string) revert data with the message "stack overflow"
+static const uint8_t stack_overflow_error[100] = {
+    0x08, 0xc3, 0x79, 0xa0, [35] = 0x20, [67] = 0x0e, 's', 't', 'a', 'c', 'k',
+    ' ',  'o',  'v',  'e',  'r',  'f',  'l',  'o',  'w'};
+
+void wrapper_check_stack_pointer(ADDRESS_UINT stack_pointer,
+                                 uint32_t stack_low, uint32_t stack_high) {
+  // The stack grows down from stack_high towards 0 (--stack-first), so a
+  // frame that doesn't fit anymore wraps the stack pointer above stack_high
+  if (stack_pointer < stack_low || stack_pointer > stack_high) {
+    revert((ADDRESS_UINT)stack_overflow_error, sizeof(stack_overflow_error));
+  }
+}
+
+// Panic(0x41) revert data, the panic code for too much memory allocated
+static const uint8_t memory_limit_panic[36] = {0x4e, 0x48, 0x7b, 0x71,
+                                               [35] = 0x41};
+
+void wrapper_check_memory_range(uint64_t offset, uint64_t size,
+                                uint64_t max_size) {
+  // Accesses of zero bytes don't expand the memory, whatever their offset
+  if (size == 0) {
+    return;
+  }
+  if (size > max_size || offset > max_size - size) {
+    revert((ADDRESS_UINT)memory_limit_panic, sizeof(memory_limit_panic));
+  }
+  evm_make_sure_memory((uint32_t)(offset + size));
+}
+
 void wrapper_stop() { finish(0, 0); }
 void wrapper_codecopy(int32_t target_evm_mem_offset, int32_t evm_memory_offset,
                       uint32_t size) {
-  evm_make_sure_memory(target_evm_mem_offset + size);
+  evm_touch_memory(target_evm_mem_offset, size);
   uint8_t *target_mem_ptr = evm_get_memory_addr(target_evm_mem_offset);
   // For Yul, codecopy is sometimes used to copy actual calldata during contract
   // deployment (since in EVM, when deploying a contract, the contract bytecode
@@ -250,7 +282,7 @@ void wrapper_codecopy(int32_t target_evm_mem_offset, int32_t evm_memory_offset,
   if ((int32_t)evm_memory_offset < 0) {
     // Copy from EVM contract bytecode, actually copying from WASM data segment
     // (linear memory) e.g. for codecopy(xxx, dataoffset(xxx), xxx)
-    uint8_t *src_ptr = evm_get_memory_addr(evm_memory_offset);
+    uint8_t *src_ptr = evm_get_data_addr(evm_memory_offset);
     memcpy(target_mem_ptr, src_ptr, size);
     return;
   }
@@ -287,14 +319,14 @@ uint32_t wrapper_memory_guard(uint32_t size) {
 }
 
 void wrapper_mstore_bytes32(int32_t evm_mem, bytes32 *value_ptr) {
-  evm_make_sure_memory(evm_mem + 32);
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   memcpy(memory_ptr, value_ptr, 32);
 }
 void wrapper_mstore_u256(int32_t evm_mem, uint256_t *value_ptr) {
   uint256_t value = *value_ptr;
   // Automatically expand memory if it exceeds EVM accessible memory
-  evm_make_sure_memory(evm_mem + 32);
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   u256_to_big_endian(value, memory_ptr);
 }
@@ -303,7 +335,7 @@ void wrapper_mstore_u32(int32_t evm_mem, uint32_t value) {
   // Although it writes 32 bytes, the first 28 bytes can be directly set to 0
 
   // Automatically expand memory if it exceeds EVM accessible memory
-  evm_make_sure_memory(evm_mem + 32);
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
 
   // mstore(64, value) in Solidity reads/writes memptr value, not using the
@@ -330,7 +362,7 @@ void wrapper_mstore_u64(int32_t evm_mem, uint64_t value) {
   // Although it writes 32 bytes, the first 24 bytes can be directly set to 0
 
   // Automatically expand memory if it exceeds EVM accessible memory
-  evm_make_sure_memory(evm_mem + 32);
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
 
   // Write 0 to first 24 bytes (3 i64)
@@ -349,17 +381,20 @@ void wrapper_mstore_u64(int32_t evm_mem, uint64_t value) {
 
 void wrapper_mstore_u8(int32_t evm_mem, uint8_t value) {
   // Automatically expand memory if it exceeds EVM accessible memory
-  evm_make_sure_memory(evm_mem + 32);
+  evm_touch_memory(evm_mem, 1);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   *memory_ptr = value & 0xff;
 }
 
 void wrapper_mload_u256(int32_t evm_mem, uint256_t *result) {
+  // mload expands the memory like mstore
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   u256_from_big_endian_bytes(memory_ptr, result);
 }
 
 uint32_t wrapper_mload_u32(int32_t evm_mem) {
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   // Read 4 bytes in big-endian order from the last 4 bytes of the 32-byte slot
   uint32_t result = 0;
@@ -370,6 +405,7 @@ uint32_t wrapper_mload_u32(int32_t evm_mem) {
 }
 
 uint64_t wrapper_mload_u64(int32_t evm_mem) {
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   // Read 8 bytes in big-endian order from the last 8 bytes of the 32-byte slot
   uint64_t result = 0;
@@ -380,16 +416,20 @@ uint64_t wrapper_mload_u64(int32_t evm_mem) {
 }
 
 void wrapper_mload_bytes32(int32_t evm_mem, bytes32 *result) {
+  evm_touch_memory(evm_mem, 32);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   // Copy all 32 bytes directly
   memcpy(result, memory_ptr, 32);
 }
 
 void wrapper_mcopy(int32_t evm_dst, int32_t evm_src, uint32_t size) {
-  evm_make_sure_memory(evm_dst + size);
+  // mcopy expands the memory for both ranges
+  evm_touch_memory(evm_src, size);
+  evm_touch_memory(evm_dst, size);
   uint8_t *dst_memory = evm_get_memory_addr(evm_dst);
   uint8_t *src_memory = evm_get_memory_addr(evm_src);
-  memcpy(dst_memory, src_memory, size);
+  // the ranges of mcopy may overlap
+  __memmove(dst_memory, src_memory, size);
 }
 
 void wrapper_sstore_u256(uint256_t *slot_ptr, uint256_t *value_ptr) {
@@ -493,11 +533,32 @@ void wrapper_loadimmutable(uint256_t *slot_ptr, uint256_t *result) {
 }
 
 void wrapper_keccak256(int32_t evm_mem, uint32_t size, bytes32 *result) {
+  evm_touch_memory(evm_mem, size);
   uint8_t *memory_ptr = evm_get_memory_addr(evm_mem);
   keccak256((ADDRESS_UINT)memory_ptr, size, (ADDRESS_UINT)result);
 }
 
+void wrapper_mapping_slot(bytes32 *key, bytes32 *slot, bytes32 *result) {
+  // mstore(0, key) mstore(0x20, slot) keccak256(0, 0x40) without touching
+  // the scratch space of the EVM memory
+  uint8_t buffer[64];
+  memcpy(buffer, key, 32);
+  memcpy(buffer + 32, slot, 32);
+  keccak256((ADDRESS_UINT)buffer, 64, (ADDRESS_UINT)result);
+}
+
+void wrapper_mapping_slot_with_scratch(bytes32 *key, bytes32 *slot,
+                                       bytes32 *result) {
+  // the scratch space is read later, so the key and slot are stored there
+  evm_touch_memory(0, 64);
+  uint8_t *memory_ptr = evm_get_memory_addr(0);
+  memcpy(memory_ptr, key, 32);
+  memcpy(memory_ptr + 32, slot, 32);
+  keccak256((ADDRESS_UINT)memory_ptr, 64, (ADDRESS_UINT)result);
+}
+
 void wrapper_return(int32_t src_evm_mem, uint32_t size) {
+  evm_touch_memory(src_evm_mem, size);
   uint8_t *memory_ptr = evm_get_memory_addr(src_evm_mem);
   finish((ADDRESS_UINT)memory_ptr, size);
 }
@@ -508,6 +569,7 @@ void wrapper_decode_big_endian_i256_from_wasm_mem(uint8_t *wasm_mem,
 }
 
 void wrapper_log0(int32_t data_evm_mem, uint32_t data_size) {
+  evm_touch_memory(data_evm_mem, data_size);
   uint8_t *memory_ptr = evm_get_memory_addr(data_evm_mem);
   uint32_t num_topics = 0;
   emitLogEvent((ADDRESS_UINT)memory_ptr, data_size, num_topics, 0, 0, 0, 0);
@@ -515,6 +577,7 @@ void wrapper_log0(int32_t data_evm_mem, uint32_t data_size) {
 
 void wrapper_log1(int32_t data_evm_mem, uint32_t data_size,
                   bytes32 *topic0_ptr) {
+  evm_touch_memory(data_evm_mem, data_size);
   uint8_t *memory_ptr = evm_get_memory_addr(data_evm_mem);
 
   uint32_t num_topics = 1;
@@ -526,6 +589,7 @@ void wrapper_log1(int32_t data_evm_mem, uint32_t data_size,
 
 void wrapper_log2(int32_t data_evm_mem, uint32_t data_size, bytes32 *topic0_ptr,
                   bytes32 *topic1_ptr) {
+  evm_touch_memory(data_evm_mem, data_size);
   uint8_t *memory_ptr = evm_get_memory_addr(data_evm_mem);
 
   uint8_t *topic0 = (uint8_t *)topic0_ptr;
@@ -539,6 +603,7 @@ void wrapper_log2(int32_t data_evm_mem, uint32_t data_size, bytes32 *topic0_ptr,
 
 void wrapper_log3(int32_t data_evm_mem, uint32_t data_size, bytes32 *topic0_ptr,
                   bytes32 *topic1_ptr, bytes32 *topic2_ptr) {
+  evm_touch_memory(data_evm_mem, data_size);
   uint8_t *memory_ptr = evm_get_memory_addr(data_evm_mem);
 
   uint8_t *topic0 = (uint8_t *)topic0_ptr;
@@ -555,6 +620,7 @@ void wrapper_log3(int32_t data_evm_mem, uint32_t data_size, bytes32 *topic0_ptr,
 void wrapper_log4(int32_t data_evm_mem, uint32_t data_size, bytes32 *topic0_ptr,
                   bytes32 *topic1_ptr, bytes32 *topic2_ptr,
                   bytes32 *topic3_ptr) {
+  evm_touch_memory(data_evm_mem, data_size);
   uint8_t *memory_ptr = evm_get_memory_addr(data_evm_mem);
 
   uint8_t *topic0 = (uint8_t *)topic0_ptr;
@@ -574,6 +640,7 @@ void wrapper_create(uint256_t *value, int32_t code_evm_mem, int32_t code_length,
   uint8_t value_bytes[32];
   u256_to_big_endian(value_u256, value_bytes);
 
+  evm_touch_memory(code_evm_mem, code_length);
   uint8_t *code_mem = evm_get_memory_addr(code_evm_mem);
 
   // The first 4 bytes of code_mem is a big-endian uint32_t representing the
@@ -587,6 +654,7 @@ void wrapper_create(uint256_t *value, int32_t code_evm_mem, int32_t code_length,
   uint8_t *calldata_mem = code_mem + 4 + wasm_code_length;
 
   // Use ABI format (4 bytes wasm length + wasm code)
+  precompile_clear_return_data();
   int32_t ret_code =
       createContract((ADDRESS_UINT)&value_bytes, (ADDRESS_UINT)code_mem,
                      wasm_code_length + 4, (ADDRESS_UINT)calldata_mem,
@@ -602,6 +670,7 @@ void wrapper_create2(uint256_t *value, int32_t code_evm_mem,
   uint8_t value_bytes[32];
   u256_to_big_endian(value_u256, value_bytes);
 
+  evm_touch_memory(code_evm_mem, code_length);
   uint8_t *code_mem = evm_get_memory_addr(code_evm_mem);
 
   // The first 4 bytes of code_mem is a big-endian uint32_t representing the
@@ -618,6 +687,7 @@ void wrapper_create2(uint256_t *value, int32_t code_evm_mem,
   u256_to_big_endian(salt_u256, salt_bytes);
 
   // Use ABI format (4 bytes wasm length + wasm code)
+  precompile_clear_return_data();
   int32_t ret_code = createContract(
       (ADDRESS_UINT)&value_bytes, (ADDRESS_UINT)code_mem, wasm_code_length + 4,
       (ADDRESS_UINT)calldata_mem, calldata_length, (ADDRESS_UINT)&salt_bytes, 1,
@@ -687,8 +757,10 @@ int wrapper_call_contract(uint64_t gas, bytes32 *callee_addr_ptr,
   uint8_t value_bytes[32];
   u256_to_big_endian(value_u256, value_bytes);
 
+  evm_touch_call_memory(in_evm_offset, in_length, out_evm_offset, out_length);
   uint8_t *in_offset = evm_get_memory_addr(in_evm_offset);
 
+  precompile_clear_return_data();
   int32_t retCode = callContract(gas, 12 + (ADDRESS_UINT)callee_addr_ptr,
                                  (ADDRESS_UINT)&value_bytes,
                                  (ADDRESS_UINT)in_offset, in_length);
@@ -701,10 +773,35 @@ int wrapper_call_contract(uint64_t gas, bytes32 *callee_addr_ptr,
   return write_call_data(retCode, out_evm_offset, out_length);
 }
 
+int wrapper_callcode(uint64_t gas, bytes32 *callee_addr_ptr, uint256_t *value,
+                     int32_t in_evm_offset, int32_t in_length,
+                     int32_t out_evm_offset, int32_t out_length) {
+  uint256_t value_u256 = *value;
+  uint8_t value_bytes[32];
+  u256_to_big_endian(value_u256, value_bytes);
+
+  evm_touch_call_memory(in_evm_offset, in_length, out_evm_offset, out_length);
+  uint8_t *in_offset = evm_get_memory_addr(in_evm_offset);
+
+  precompile_clear_return_data();
+  int32_t retCode = callCode(gas, 12 + (ADDRESS_UINT)callee_addr_ptr,
+                             (ADDRESS_UINT)&value_bytes,
+                             (ADDRESS_UINT)in_offset, in_length);
+
+  if (retCode != 0 && is_precompiled_contract(callee_addr_ptr)) {
+    // if the callee is a precompiled contract address, no return data if failed
+    return 0;
+  }
+
+  return write_call_data(retCode, out_evm_offset, out_length);
+}
+
 int wrapper_delegatecall(uint64_t gas, bytes32 *callee_addr_ptr,
                          int32_t in_evm_offset, int32_t in_length,
                          int32_t out_evm_offset, int32_t out_length) {
+  evm_touch_call_memory(in_evm_offset, in_length, out_evm_offset, out_length);
   uint8_t *in_offset = evm_get_memory_addr(in_evm_offset);
+  precompile_clear_return_data();
   int32_t retCode = callDelegate(gas, 12 + (ADDRESS_UINT)callee_addr_ptr,
                                  (ADDRESS_UINT)in_offset, in_length);
 
@@ -719,7 +816,9 @@ int wrapper_delegatecall(uint64_t gas, bytes32 *callee_addr_ptr,
 int wrapper_staticcall(uint64_t gas, bytes32 *callee_addr_ptr,
                        int32_t in_evm_offset, int32_t in_length,
                        int32_t out_evm_offset, int32_t out_length) {
+  evm_touch_call_memory(in_evm_offset, in_length, out_evm_offset, out_length);
   uint8_t *in_offset = evm_get_memory_addr(in_evm_offset);
+  precompile_clear_return_data();
   int32_t retCode = callStatic(gas, 12 + (ADDRESS_UINT)callee_addr_ptr,
                                (ADDRESS_UINT)in_offset, in_length);
   if (retCode != 0 && is_precompiled_contract(callee_addr_ptr)) {
@@ -777,9 +876,9 @@ void wrapper_exp(uint256_t *base_ptr, uint256_t *exp_ptr, uint256_t *result) {
   *result = result_u256;
 }
 
-uint64_t wrapper_memory_size() {
-  return (uint64_t)__builtin_wasm_memory_size(0);
-}
+uint64_t wrapper_memory_size() { return evm_memory_msize(); }
+
+void wrapper_use_gas(uint64_t gas) { useGas((int64_t)gas); }
 
 uint64_t wrapper_gas() { return (uint64_t)getGasLeft(); }
 
@@ -795,11 +894,16 @@ void wrapper_gas_price(uint256_t *result) {
   u256_from_big_endian_bytes(data, result);
 }
 
-uint32_t wrapper_returndata_size() { return (uint32_t)getReturnDataSize(); }
+uint32_t wrapper_returndata_size() {
+  if (precompile_has_return_data()) {
+    return precompile_return_data_size();
+  }
+  return (uint32_t)getReturnDataSize();
+}
 
 void wrapper_calldata_copy(int32_t dst_evm, uint32_t calldata_offset,
                            uint32_t len) {
-  evm_make_sure_memory(dst_evm + len);
+  evm_touch_memory(dst_evm, len);
   uint8_t *dst_memory_ptr = evm_get_memory_addr(dst_evm);
 
   // For out of bound bytes, 0s will be copied.
@@ -817,9 +921,13 @@ void wrapper_calldata_copy(int32_t dst_evm, uint32_t calldata_offset,
 
 void wrapper_returndata_copy(int32_t dst_evm, uint32_t return_data_offset,
                              uint32_t len) {
-  evm_make_sure_memory(dst_evm + len);
+  evm_touch_memory(dst_evm, len);
   uint8_t *dst_memory_ptr = evm_get_memory_addr(dst_evm);
 
+  if (precompile_has_return_data()) {
+    precompile_return_data_copy(dst_memory_ptr, return_data_offset, len);
+    return;
+  }
   returnDataCopy((ADDRESS_UINT)dst_memory_ptr, (uint32_t)return_data_offset,
                  len);
 }
@@ -852,7 +960,7 @@ uint32_t wrapper_extcode_size(bytes32 *addr_ptr) {
 
 void wrapper_extcode_copy(bytes32 *addr_ptr, int32_t dst_evm, uint32_t offset,
                           uint32_t len) {
-  evm_make_sure_memory(dst_evm + len);
+  evm_touch_memory(dst_evm, len);
   uint8_t *dst = evm_get_memory_addr(dst_evm);
   externalCodeCopy(12 + (ADDRESS_UINT)addr_ptr, (ADDRESS_UINT)dst, offset, len);
 }
@@ -877,9 +985,11 @@ void wrapper_extcode_hash(bytes32 *addr_ptr, bytes32 *result) {
 }
 
 void wrapper_data_copy(int32_t dst_evm, int32_t src_evm, uint32_t len) {
-  evm_make_sure_memory(dst_evm + len);
+  evm_touch_memory(dst_evm, len);
   uint8_t *dst = evm_get_memory_addr(dst_evm);
-  uint8_t *src = evm_get_memory_addr(src_evm);
+  // the source is the dataoffset of a data segment
+  uint8_t *src = src_evm < 0 ? evm_get_data_addr(src_evm)
+                             : evm_get_memory_addr(src_evm);
   memcpy(dst, src, len);
 }
 
@@ -982,8 +1092,17 @@ uint256_t *memory_alloca_u256() { return (uint256_t *)memory_alloca_bytes32(); }
 // instructions will operate on this value
 static int32_t evm_memptr_global = 0;
 
-void wrapper_set_memptr_global(int32_t evm_mem) { evm_memptr_global = evm_mem; }
-int32_t wrapper_get_memptr_global() { return evm_memptr_global; }
+// The free memory pointer word is in the EVM memory even though it is kept
+// here, its mstore and mload expand the memory like any other
+void wrapper_set_memptr_global(int32_t evm_mem) {
+  evm_touch_memory(64, 32);
+  evm_memptr_global = evm_mem;
+}
+
+int32_t wrapper_get_memptr_global() {
+  evm_touch_memory(64, 32);
+  return evm_memptr_global;
+}
 
 int32_t wrapper_allocate_memory(uint32_t size) {
   // Implementation of memory allocation logic equivalent to:
@@ -1019,8 +1138,16 @@ static BOOL is_zero_address(uint8_t *bytes20) {
   uint32_t *bytes20_u32 = (uint32_t *)bytes20;
   return bytes20_u64[0] == 0 && bytes20_u64[1] == 0 && bytes20_u32[4] == 0;
 }
+// and(value, sub(shl(160, 1), 1)), the intrinsics only use masked addresses
+// like the Yul functions they replace
+static void erc20_mask_address(bytes32 *value, bytes32 *address) {
+  memset(address, 0, 12);
+  memcpy(((uint8_t *)address) + 12, ((uint8_t *)value) + 12, 20);
+}
+
 void wrapper_optimized_erc20_fun_transfer(bytes32 *from, bytes32 *to,
-                                          bytes32 *var_value) {
+                                          bytes32 *var_value,
+                                          bytes32 *balances_slot) {
   // optimized implementation of fun_transfer for standard ERC20:
   // yul function:
   // function fun_transfer(var_from, var_to, var_value)
@@ -1067,6 +1194,10 @@ void wrapper_optimized_erc20_fun_transfer(bytes32 *from, bytes32 *to,
   //     0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef, _1,
   //     _3)
   // }
+  bytes32 from_addr;
+  erc20_mask_address(from, &from_addr);
+  bytes32 to_addr;
+  erc20_mask_address(to, &to_addr);
   if (is_zero_address(((uint8_t *)from) + 12)) {
     // shl(225, 0x4b637e8f) ==
     // 0x96c6fd1e00000000000000000000000000000000000000000000000000000000
@@ -1089,8 +1220,9 @@ void wrapper_optimized_erc20_fun_transfer(bytes32 *from, bytes32 *to,
   }
   uint8_t tmp_keccak256_result[32];
   // calculate the slot of the balance of the from address
-  uint8_t tmp_to_keccak256[64] = {0};
-  memcpy(tmp_to_keccak256, from, 32);
+  uint8_t tmp_to_keccak256[64];
+  memcpy(tmp_to_keccak256, &from_addr, 32);
+  memcpy(tmp_to_keccak256 + 32, balances_slot, 32);
   keccak256((ADDRESS_UINT)tmp_to_keccak256, 0x40,
             (ADDRESS_UINT)tmp_keccak256_result);
   uint256_t tmp_balance_u256;
@@ -1105,7 +1237,7 @@ void wrapper_optimized_erc20_fun_transfer(bytes32 *from, bytes32 *to,
     // shl(226, 0x391434e3) =
     // 0xe450d38c00000000000000000000000000000000000000000000000000000000
     uint8_t revert_msg[100] = {0xe4, 0x50, 0xd3, 0x8c};
-    memcpy(revert_msg + 4, from, 32);
+    memcpy(revert_msg + 4, &from_addr, 32);
     uint8_t bigendian_balance[32];
     u256_to_big_endian_bytes(&tmp_balance_u256, (uint8_t *)bigendian_balance);
     memcpy(revert_msg + 36, bigendian_balance, 32);
@@ -1121,7 +1253,7 @@ void wrapper_optimized_erc20_fun_transfer(bytes32 *from, bytes32 *to,
   storageStoreLittleEndian((ADDRESS_UINT)tmp_keccak256_result,
                            (ADDRESS_UINT)&new_from_balance_u256);
   // calculate the slot of the balance of the to address
-  memcpy(tmp_to_keccak256, to, 32);
+  memcpy(tmp_to_keccak256, &to_addr, 32);
   keccak256((ADDRESS_UINT)tmp_to_keccak256, 0x40,
             (ADDRESS_UINT)tmp_keccak256_result);
   storageLoadLittleEndian((ADDRESS_UINT)tmp_keccak256_result,
@@ -1141,7 +1273,137 @@ void wrapper_optimized_erc20_fun_transfer(bytes32 *from, bytes32 *to,
                             0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16,
                             0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef};
   emitLogEvent((ADDRESS_UINT)var_value, 32, 3, (ADDRESS_UINT)log_topic1,
-               (ADDRESS_UINT)from, (ADDRESS_UINT)to, 0);
+               (ADDRESS_UINT)&from_addr, (ADDRESS_UINT)&to_addr, 0);
+}
+
+// keccak256(key . slot), the storage slot of mapping[key] for a mapping at slot
+static void erc20_mapping_slot(bytes32 *key, bytes32 *slot, uint8_t *result) {
+  uint8_t tmp_to_keccak256[64];
+  memcpy(tmp_to_keccak256, key, 32);
+  memcpy(tmp_to_keccak256 + 32, slot, 32);
+  keccak256((ADDRESS_UINT)tmp_to_keccak256, 0x40, (ADDRESS_UINT)result);
+}
+
+// revert with selector(address(0)), the zero address errors of ERC20
+static void erc20_revert_zero_address(uint8_t selector0, uint8_t selector1,
+                                      uint8_t selector2, uint8_t selector3) {
+  uint8_t revert_msg[36] = {selector0, selector1, selector2, selector3};
+  revert((ADDRESS_UINT)revert_msg, 36);
+}
+
+int32_t wrapper_optimized_erc20_fun_transfer_from(bytes32 *from, bytes32 *to,
+                                                  bytes32 *var_value,
+                                                  bytes32 *allowances_slot,
+                                                  bytes32 *balances_slot) {
+  // spends the allowance of the caller like _spendAllowance does, an
+  // allowance of type(uint256).max is never decreased, then moves the tokens
+  bytes32 from_addr;
+  erc20_mask_address(from, &from_addr);
+  bytes32 spender = {0};
+  wrapper_caller(&spender);
+  uint8_t owner_slot[32];
+  erc20_mapping_slot(&from_addr, allowances_slot, owner_slot);
+  uint8_t allowance_slot[32];
+  erc20_mapping_slot(&spender, (bytes32 *)owner_slot, allowance_slot);
+  uint256_t allowance_u256;
+  storageLoadLittleEndian((ADDRESS_UINT)allowance_slot,
+                          (ADDRESS_UINT)&allowance_u256);
+  if (allowance_u256 != ~(uint256_t)0) {
+    uint256_t var_value_u256;
+    u256_from_big_endian_bytes((uint8_t *)var_value, &var_value_u256);
+    if (allowance_u256 < var_value_u256) {
+      // ERC20InsufficientAllowance(spender, allowance, value)
+      uint8_t revert_msg[100] = {0xfb, 0x8f, 0x41, 0xb2};
+      memcpy(revert_msg + 4, &spender, 32);
+      u256_to_big_endian_bytes(&allowance_u256, revert_msg + 36);
+      memcpy(revert_msg + 68, var_value, 32);
+      revert((ADDRESS_UINT)revert_msg, 100);
+      return 0;
+    }
+    uint256_t new_allowance_u256 = allowance_u256 - var_value_u256;
+    storageStoreLittleEndian((ADDRESS_UINT)allowance_slot,
+                             (ADDRESS_UINT)&new_allowance_u256);
+  }
+  wrapper_optimized_erc20_fun_transfer(from, to, var_value, balances_slot);
+  return 1;
+}
+
+void wrapper_optimized_erc20_fun_approve(bytes32 *owner, bytes32 *spender,
+                                         bytes32 *var_value,
+                                         bytes32 *allowances_slot) {
+  bytes32 owner_addr;
+  erc20_mask_address(owner, &owner_addr);
+  bytes32 spender_addr;
+  erc20_mask_address(spender, &spender_addr);
+  if (is_zero_address(((uint8_t *)owner) + 12)) {
+    // ERC20InvalidApprover(address(0))
+    erc20_revert_zero_address(0xe6, 0x02, 0xdf, 0x05);
+    return;
+  }
+  if (is_zero_address(((uint8_t *)spender) + 12)) {
+    // ERC20InvalidSpender(address(0))
+    erc20_revert_zero_address(0x94, 0x28, 0x0d, 0x62);
+    return;
+  }
+  uint8_t owner_slot[32];
+  erc20_mapping_slot(&owner_addr, allowances_slot, owner_slot);
+  uint8_t allowance_slot[32];
+  erc20_mapping_slot(&spender_addr, (bytes32 *)owner_slot, allowance_slot);
+  uint256_t var_value_u256;
+  u256_from_big_endian_bytes((uint8_t *)var_value, &var_value_u256);
+  storageStoreLittleEndian((ADDRESS_UINT)allowance_slot,
+                           (ADDRESS_UINT)&var_value_u256);
+  // Approval(address,address,uint256)
+  uint8_t log_topic1[32] = {0x8c, 0x5b, 0xe1, 0xe5, 0xeb, 0xec, 0x7d, 0x5b,
+                            0xd1, 0x4f, 0x71, 0x42, 0x7d, 0x1e, 0x84, 0xf3,
+                            0xdd, 0x03, 0x14, 0xc0, 0xf7, 0xb2, 0x29, 0x1e,
+                            0x5b, 0x20, 0x0a, 0xc8, 0xc7, 0xc3, 0xb9, 0x25};
+  emitLogEvent((ADDRESS_UINT)var_value, 32, 3, (ADDRESS_UINT)log_topic1,
+               (ADDRESS_UINT)&owner_addr, (ADDRESS_UINT)&spender_addr, 0);
+}
+
+void wrapper_optimized_erc20_fun_mint(bytes32 *account, bytes32 *var_value,
+                                      bytes32 *total_supply_slot,
+                                      bytes32 *balances_slot) {
+  bytes32 account_addr;
+  erc20_mask_address(account, &account_addr);
+  if (is_zero_address(((uint8_t *)account) + 12)) {
+    // ERC20InvalidReceiver(address(0))
+    erc20_revert_zero_address(0xec, 0x44, 0x2f, 0x05);
+    return;
+  }
+  uint256_t var_value_u256;
+  u256_from_big_endian_bytes((uint8_t *)var_value, &var_value_u256);
+  uint256_t total_supply_u256;
+  storageLoadLittleEndian((ADDRESS_UINT)total_supply_slot,
+                          (ADDRESS_UINT)&total_supply_u256);
+  uint256_t new_total_supply_u256 = total_supply_u256 + var_value_u256;
+  if (new_total_supply_u256 < total_supply_u256) {
+    // Panic(0x11), arithmetic overflow
+    uint8_t revert_msg[36] = {0x4e, 0x48, 0x7b, 0x71};
+    revert_msg[35] = 0x11;
+    revert((ADDRESS_UINT)revert_msg, 36);
+    return;
+  }
+  storageStoreLittleEndian((ADDRESS_UINT)total_supply_slot,
+                           (ADDRESS_UINT)&new_total_supply_u256);
+  // the balance can't overflow as it is at most the total supply
+  uint8_t balance_slot[32];
+  erc20_mapping_slot(&account_addr, balances_slot, balance_slot);
+  uint256_t balance_u256;
+  storageLoadLittleEndian((ADDRESS_UINT)balance_slot,
+                          (ADDRESS_UINT)&balance_u256);
+  uint256_t new_balance_u256 = balance_u256 + var_value_u256;
+  storageStoreLittleEndian((ADDRESS_UINT)balance_slot,
+                           (ADDRESS_UINT)&new_balance_u256);
+  // Transfer(address(0), account, value)
+  uint8_t log_topic1[32] = {0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b,
+                            0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37, 0x8d, 0xaa,
+                            0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16,
+                            0x28, 0xf5, 0x5a, 0x4d, 0xf5, 0x23, 0xb3, 0xef};
+  emitLogEvent((ADDRESS_UINT)var_value, 32, 3, (ADDRESS_UINT)log_topic1,
+               (ADDRESS_UINT)wrapper_zero_bytes32(),
+               (ADDRESS_UINT)&account_addr, 0);
 }
 
 void wrapper_debug_i256(uint256_t *value_ptr) {
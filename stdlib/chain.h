@@ -45,6 +45,19 @@ void wrapper_self_balance(uint256_t *result);
 
 void wrapper_revert(int32_t error_msg_evm_mem, uint32_t size);
 
+// Reverts with Error("stack overflow") when the stack pointer has left
+// [stack_low, stack_high]. Called on function entry when the stack guard is
+// enabled.
+void wrapper_check_stack_pointer(ADDRESS_UINT stack_pointer,
+                                 uint32_t stack_low, uint32_t stack_high);
+
+// Reverts with Panic(0x41) when the EVM memory range [offset, offset + size)
+// ends beyond max_size, otherwise makes sure the memory covers it. Offsets and
+// sizes above 64 bits are passed saturated. Called before every EVM memory
+// access when safe memory is enabled.
+void wrapper_check_memory_range(uint64_t offset, uint64_t size,
+                                uint64_t max_size);
+
 void wrapper_stop();
 
 void wrapper_codecopy(int32_t target_evm_mem_offset, int32_t evm_memory_offset,
@@ -100,6 +113,13 @@ void wrapper_loadimmutable(uint256_t *slot_ptr, uint256_t *result);
 
 void wrapper_keccak256(int32_t evm_mem, uint32_t size, bytes32 *result);
 
+// keccak256(key . slot), the storage slot of a mapping value
+void wrapper_mapping_slot(bytes32 *key, bytes32 *slot, bytes32 *result);
+
+// wrapper_mapping_slot that also writes key and slot to EVM memory [0, 0x40)
+void wrapper_mapping_slot_with_scratch(bytes32 *key, bytes32 *slot,
+                                       bytes32 *result);
+
 void wrapper_return(int32_t src_evm_mem, uint32_t size);
 
 void wrapper_decode_big_endian_i256_from_wasm_mem(uint8_t *wasm_mem,
@@ -150,6 +170,10 @@ int wrapper_call_contract(uint64_t gas, bytes32 *callee_addr_ptr,
                           int32_t in_length, int32_t out_evm_offset,
                           int32_t out_length);
 
+int wrapper_callcode(uint64_t gas, bytes32 *callee_addr_ptr, uint256_t *value,
+                     int32_t in_evm_offset, int32_t in_length,
+                     int32_t out_evm_offset, int32_t out_length);
+
 int wrapper_delegatecall(uint64_t gas, bytes32 *callee_addr_ptr,
                          int32_t in_evm_offset, int32_t in_length,
                          int32_t out_evm_offset, int32_t out_length);
@@ -162,8 +186,12 @@ void wrapper_selfdestruct(bytes32 *addr_bytes);
 
 void wrapper_invalid();
 
+// msize: the highest touched word of the EVM memory times 32
 uint64_t wrapper_memory_size();
 
+// Charges gas through the useGas host api
+void wrapper_use_gas(uint64_t gas);
+
 uint64_t wrapper_gas();
 uint64_t wrapper_gas_limit();
 void wrapper_gas_price(uint256_t *result);
@@ -230,10 +258,25 @@ void wrapper_addmod(bytes32 *a_ptr, bytes32 *b_ptr, bytes32 *mod_ptr,
 void wrapper_mulmod(bytes32 *a_ptr, bytes32 *b_ptr, bytes32 *mod_ptr,
                     bytes32 *result_ptr);
 
-// Optimized implementation of fun_transfer for standard ERC20
+// Optimized implementations of standard ERC20 functions, the Yul functions
+// are matched by the rules in src/yul2ir/pattern.rs
 // only available when enable_all_optimizers is true
 void wrapper_optimized_erc20_fun_transfer(bytes32 *from, bytes32 *to,
-                                          bytes32 *var_value);
+                                          bytes32 *var_value,
+                                          bytes32 *balances_slot);
+
+int32_t wrapper_optimized_erc20_fun_transfer_from(bytes32 *from, bytes32 *to,
+                                                  bytes32 *var_value,
+                                                  bytes32 *allowances_slot,
+                                                  bytes32 *balances_slot);
+
+void wrapper_optimized_erc20_fun_approve(bytes32 *owner, bytes32 *spender,
+                                         bytes32 *var_value,
+                                         bytes32 *allowances_slot);
+
+void wrapper_optimized_erc20_fun_mint(bytes32 *account, bytes32 *var_value,
+                                      bytes32 *total_supply_slot,
+                                      bytes32 *balances_slot);
 
 void wrapper_debug_i256(uint256_t *value_ptr);
 
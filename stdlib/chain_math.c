@@ -267,3 +267,41 @@ void wrapper_bytes32_add(const bytes32 *a, const bytes32 *b, bytes32 *result) {
     carry = sum >> 8; // Check for overflow, 1 or 0
   }
 }
+
+// Division by zero is zero like in the EVM. Operands that fit in 64 or 128
+// bits use the native or compiler-rt division instead of the generic 256 bits
+// shift-subtract loop
+void wrapper_u256_div(const uint256_t *a, const uint256_t *b,
+                      uint256_t *result) {
+  uint256_t dividend = *a;
+  uint256_t divisor = *b;
+  if (divisor == 0 || dividend < divisor) {
+    *result = 0;
+  } else if ((dividend >> 64) == 0) {
+    // the divisor is not larger than the dividend
+    *result = (uint64_t)dividend / (uint64_t)divisor;
+  } else if ((dividend >> 128) == 0) {
+    *result = (uint128_t)dividend / (uint128_t)divisor;
+  } else {
+    *result = dividend / divisor;
+  }
+}
+
+// Remainder by zero is zero like in the EVM, with the same fast paths as
+// wrapper_u256_div
+void wrapper_u256_mod(const uint256_t *a, const uint256_t *b,
+                      uint256_t *result) {
+  uint256_t dividend = *a;
+  uint256_t divisor = *b;
+  if (divisor == 0) {
+    *result = 0;
+  } else if (dividend < divisor) {
+    *result = dividend;
+  } else if ((dividend >> 64) == 0) {
+    *result = (uint64_t)dividend % (uint64_t)divisor;
+  } else if ((dividend >> 128) == 0) {
+    *result = (uint128_t)dividend % (uint128_t)divisor;
+  } else {
+    *result = dividend % divisor;
+  }
+}
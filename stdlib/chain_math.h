@@ -40,6 +40,12 @@ void wrapper_u256_shr(const uint256_t *value, int32_t shift, uint256_t *result);
 
 void wrapper_bytes32_add(const bytes32 *a, const bytes32 *b, bytes32 *result);
 
+void wrapper_u256_div(const uint256_t *a, const uint256_t *b,
+                      uint256_t *result);
+
+void wrapper_u256_mod(const uint256_t *a, const uint256_t *b,
+                      uint256_t *result);
+
 #ifdef __cplusplus
 }
 #endif
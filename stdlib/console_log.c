@@ -0,0 +1,291 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#include "console_log.h"
+#include "chain.h"
+#include "debug.h"
+#include "evm_memory.h"
+#include "precompile.h"
+
+// Parameter types of a console.log overload, one char per parameter:
+// u: uint256, i: int256, s: string, b: bool, a: address, B: bytes,
+// y: bytes32
+typedef struct {
+  uint32_t selector;
+  const char *param_types;
+} console_log_signature;
+
+// Selectors of the log overloads in forge-std console.sol with up to three
+// uint256/string/bool/address parameters, plus the single typed variants
+static const console_log_signature console_log_signatures[] = {
+    {0x51973ec9, ""}, // log()
+    {0xf82c50f1, "u"}, // log(uint256)
+    {0xf5b1bba9, "u"}, // log(uint)
+    {0x2d5b6cb9, "i"}, // log(int256)
+    {0x4e0c1d1d, "i"}, // log(int)
+    {0x41304fac, "s"}, // log(string)
+    {0x32458eed, "b"}, // log(bool)
+    {0x2c2ecbc2, "a"}, // log(address)
+    {0x0be77f56, "B"}, // log(bytes)
+    {0x27b7cf85, "y"}, // log(bytes32)
+    {0x9905b744, "u"}, // logUint(uint256)
+    {0x6525b5f5, "i"}, // logInt(int256)
+    {0x0bb563d6, "s"}, // logString(string)
+    {0xba7ab84e, "b"}, // logBool(bool)
+    {0x5f91b0af, "a"}, // logAddress(address)
+    {0xe17bf956, "B"}, // logBytes(bytes)
+    {0x2d21d6f7, "y"}, // logBytes32(bytes32)
+    {0xf666715a, "uu"}, // log(uint256,uint256)
+    {0x643fd0df, "us"}, // log(uint256,string)
+    {0x1c9d7eb3, "ub"}, // log(uint256,bool)
+    {0x69276c86, "ua"}, // log(uint256,address)
+    {0xb60e72cc, "su"}, // log(string,uint256)
+    {0x4b5c4277, "ss"}, // log(string,string)
+    {0xc3b55635, "sb"}, // log(string,bool)
+    {0x319af333, "sa"}, // log(string,address)
+    {0x399174d3, "bu"}, // log(bool,uint256)
+    {0x8feac525, "bs"}, // log(bool,string)
+    {0x2a110e83, "bb"}, // log(bool,bool)
+    {0x853c4849, "ba"}, // log(bool,address)
+    {0x8309e8a8, "au"}, // log(address,uint256)
+    {0x759f86bb, "as"}, // log(address,string)
+    {0x75b605d3, "ab"}, // log(address,bool)
+    {0xdaf0d4aa, "aa"}, // log(address,address)
+    {0xd1ed7a3c, "uuu"}, // log(uint256,uint256,uint256)
+    {0x71d04af2, "uus"}, // log(uint256,uint256,string)
+    {0x4766da72, "uub"}, // log(uint256,uint256,bool)
+    {0x5c96b331, "uua"}, // log(uint256,uint256,address)
+    {0x37aa7d4c, "usu"}, // log(uint256,string,uint256)
+    {0xb115611f, "uss"}, // log(uint256,string,string)
+    {0x4ceda75a, "usb"}, // log(uint256,string,bool)
+    {0x7afac959, "usa"}, // log(uint256,string,address)
+    {0x20098014, "ubu"}, // log(uint256,bool,uint256)
+    {0x85775021, "ubs"}, // log(uint256,bool,string)
+    {0x20718650, "ubb"}, // log(uint256,bool,bool)
+    {0x35085f7b, "uba"}, // log(uint256,bool,address)
+    {0x5a9b5ed5, "uau"}, // log(uint256,address,uint256)
+    {0x63cb41f9, "uas"}, // log(uint256,address,string)
+    {0x9b6ec042, "uab"}, // log(uint256,address,bool)
+    {0xbcfd9be0, "uaa"}, // log(uint256,address,address)
+    {0xca47c4eb, "suu"}, // log(string,uint256,uint256)
+    {0x5970e089, "sus"}, // log(string,uint256,string)
+    {0xca7733b1, "sub"}, // log(string,uint256,bool)
+    {0x1c7ec448, "sua"}, // log(string,uint256,address)
+    {0x5821efa1, "ssu"}, // log(string,string,uint256)
+    {0x2ced7cef, "sss"}, // log(string,string,string)
+    {0xb0e0f9b5, "ssb"}, // log(string,string,bool)
+    {0x95ed0195, "ssa"}, // log(string,string,address)
+    {0xc95958d6, "sbu"}, // log(string,bool,uint256)
+    {0xe298f47d, "sbs"}, // log(string,bool,string)
+    {0x850b7ad6, "sbb"}, // log(string,bool,bool)
+    {0x932bbb38, "sba"}, // log(string,bool,address)
+    {0x0d26b925, "sau"}, // log(string,address,uint256)
+    {0xe0e9ad4f, "sas"}, // log(string,address,string)
+    {0xc91d5ed4, "sab"}, // log(string,address,bool)
+    {0xfcec75e0, "saa"}, // log(string,address,address)
+    {0x37103367, "buu"}, // log(bool,uint256,uint256)
+    {0xc3fc3970, "bus"}, // log(bool,uint256,string)
+    {0xe8defba9, "bub"}, // log(bool,uint256,bool)
+    {0x088ef9d2, "bua"}, // log(bool,uint256,address)
+    {0x1093ee11, "bsu"}, // log(bool,string,uint256)
+    {0xb076847f, "bss"}, // log(bool,string,string)
+    {0xdbb4c247, "bsb"}, // log(bool,string,bool)
+    {0x9591b953, "bsa"}, // log(bool,string,address)
+    {0x12f21602, "bbu"}, // log(bool,bool,uint256)
+    {0x2555fa46, "bbs"}, // log(bool,bool,string)
+    {0x50709698, "bbb"}, // log(bool,bool,bool)
+    {0x1078f68d, "bba"}, // log(bool,bool,address)
+    {0x5f7b9afb, "bau"}, // log(bool,address,uint256)
+    {0xde9a9270, "bas"}, // log(bool,address,string)
+    {0x18c9c746, "bab"}, // log(bool,address,bool)
+    {0xd2763667, "baa"}, // log(bool,address,address)
+    {0xb69bcaf6, "auu"}, // log(address,uint256,uint256)
+    {0xa1f2e8aa, "aus"}, // log(address,uint256,string)
+    {0x678209a8, "aub"}, // log(address,uint256,bool)
+    {0x7bc0d848, "aua"}, // log(address,uint256,address)
+    {0x67dd6ff1, "asu"}, // log(address,string,uint256)
+    {0xfb772265, "ass"}, // log(address,string,string)
+    {0xcf020fb1, "asb"}, // log(address,string,bool)
+    {0xf08744e8, "asa"}, // log(address,string,address)
+    {0x9c4f99fb, "abu"}, // log(address,bool,uint256)
+    {0x212255cc, "abs"}, // log(address,bool,string)
+    {0xeb830c92, "abb"}, // log(address,bool,bool)
+    {0xf11699ed, "aba"}, // log(address,bool,address)
+    {0x17fe6185, "aau"}, // log(address,address,uint256)
+    {0x007150be, "aas"}, // log(address,address,string)
+    {0xf2a66286, "aab"}, // log(address,address,bool)
+    {0x018c84c2, "aaa"}, // log(address,address,address)
+};
+
+#define CONSOLE_LOG_SIGNATURES_COUNT                                           \
+  (sizeof(console_log_signatures) / sizeof(console_log_signature))
+
+#define CONSOLE_LOG_LINE_MAX_LENGTH 1024
+static char console_log_line[CONSOLE_LOG_LINE_MAX_LENGTH];
+static uint32_t console_log_line_length = 0;
+
+static void console_log_append(const char *data, uint32_t length) {
+  for (uint32_t i = 0;
+       i < length && console_log_line_length < CONSOLE_LOG_LINE_MAX_LENGTH;
+       i++) {
+    console_log_line[console_log_line_length++] = data[i];
+  }
+}
+
+static void console_log_append_str(const char *str) {
+  uint32_t length = 0;
+  while (str[length] != '\0') {
+    length++;
+  }
+  console_log_append(str, length);
+}
+
+static void console_log_append_hex(const uint8_t *data, uint32_t length) {
+  static const char hex_chars[] = "0123456789abcdef";
+  console_log_append_str("0x");
+  for (uint32_t i = 0; i < length; i++) {
+    char hex[2] = {hex_chars[data[i] >> 4], hex_chars[data[i] & 0x0f]};
+    console_log_append(hex, 2);
+  }
+}
+
+static void console_log_append_u256(uint256_t value) {
+  // 2**256 has 78 decimal digits
+  char digits[78];
+  int count = 0;
+  do {
+    digits[count++] = '0' + (char)(value % 10);
+    value /= 10;
+  } while (value != 0);
+  while (count > 0) {
+    console_log_append(&digits[--count], 1);
+  }
+}
+
+// Reads the 32-byte word at offset of the payload arguments, bytes past the
+// end of the payload are read as zeros
+static void console_log_read_word(const uint8_t *args, uint32_t args_length,
+                                  uint32_t offset, bytes32 *word) {
+  uint8_t *word_bytes = (uint8_t *)word;
+  for (uint32_t i = 0; i < 32; i++) {
+    uint64_t pos = (uint64_t)offset + i;
+    word_bytes[i] = pos < args_length ? args[pos] : 0;
+  }
+}
+
+// Returns 1(true) and the value when the word fits in uint32
+static BOOL console_log_word_to_u32(const bytes32 *word, uint32_t *value) {
+  const uint8_t *word_bytes = (const uint8_t *)word;
+  for (int i = 0; i < 28; i++) {
+    if (word_bytes[i] != 0) {
+      return 0;
+    }
+  }
+  *value = ((uint32_t)word_bytes[28] << 24) | ((uint32_t)word_bytes[29] << 16) |
+           ((uint32_t)word_bytes[30] << 8) | (uint32_t)word_bytes[31];
+  return 1;
+}
+
+static void console_log_append_param(char param_type, const uint8_t *args,
+                                     uint32_t args_length,
+                                     uint32_t head_offset) {
+  bytes32 word;
+  console_log_read_word(args, args_length, head_offset, &word);
+  uint8_t *word_bytes = (uint8_t *)&word;
+  switch (param_type) {
+  case 'u': {
+    uint256_t value;
+    u256_from_big_endian_bytes(word_bytes, &value);
+    console_log_append_u256(value);
+    break;
+  }
+  case 'i': {
+    uint256_t value;
+    u256_from_big_endian_bytes(word_bytes, &value);
+    if (word_bytes[0] & 0x80) {
+      console_log_append_str("-");
+      value = (uint256_t)0 - value;
+    }
+    console_log_append_u256(value);
+    break;
+  }
+  case 'b':
+    console_log_append_str(word_bytes[31] ? "true" : "false");
+    break;
+  case 'a':
+    console_log_append_hex(word_bytes + 12, 20);
+    break;
+  case 'y':
+    console_log_append_hex(word_bytes, 32);
+    break;
+  case 's':
+  case 'B': {
+    // dynamic types: the head is the offset of the length-prefixed data
+    uint32_t data_offset = 0;
+    uint32_t data_length = 0;
+    bytes32 length_word;
+    if (!console_log_word_to_u32(&word, &data_offset)) {
+      console_log_append_str("<invalid offset>");
+      break;
+    }
+    console_log_read_word(args, args_length, data_offset, &length_word);
+    if (!console_log_word_to_u32(&length_word, &data_length) ||
+        (uint64_t)data_offset + 32 + data_length > args_length) {
+      console_log_append_str("<invalid length>");
+      break;
+    }
+    const uint8_t *data = args + data_offset + 32;
+    if (param_type == 's') {
+      console_log_append((const char *)data, data_length);
+    } else {
+      console_log_append_hex(data, data_length);
+    }
+    break;
+  }
+  default:
+    break;
+  }
+}
+
+BOOL wrapper_console_log(int32_t in_evm_offset, int32_t in_length) {
+  console_log_line_length = 0;
+  // the console.log address has no code, its calls return no data
+  precompile_set_empty_return_data();
+  // the staticcall expands the memory like any call, before its address is
+  // taken since expanding it can move it
+  evm_touch_memory(in_evm_offset, in_length);
+  uint8_t *payload = evm_get_memory_addr(in_evm_offset);
+  if (in_length < 4) {
+    console_log_append_str("console.log: invalid payload");
+    debug_bytes((ADDRESS_UINT)console_log_line, console_log_line_length);
+    return 1;
+  }
+  uint32_t selector = ((uint32_t)payload[0] << 24) |
+                      ((uint32_t)payload[1] << 16) |
+                      ((uint32_t)payload[2] << 8) | (uint32_t)payload[3];
+  const uint8_t *args = payload + 4;
+  uint32_t args_length = (uint32_t)in_length - 4;
+
+  const char *param_types = NULL;
+  for (uint32_t i = 0; i < CONSOLE_LOG_SIGNATURES_COUNT; i++) {
+    if (console_log_signatures[i].selector == selector) {
+      param_types = console_log_signatures[i].param_types;
+      break;
+    }
+  }
+  if (param_types == NULL) {
+    console_log_append_str("console.log: unsupported selector ");
+    console_log_append_hex(payload, 4);
+    debug_bytes((ADDRESS_UINT)console_log_line, console_log_line_length);
+    return 1;
+  }
+
+  for (uint32_t i = 0; param_types[i] != '\0'; i++) {
+    if (i > 0) {
+      console_log_append_str(" ");
+    }
+    console_log_append_param(param_types[i], args, args_length, i * 32);
+  }
+  debug_bytes((ADDRESS_UINT)console_log_line, console_log_line_length);
+  return 1;
+}
@@ -0,0 +1,25 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#ifndef __CONSOLE_LOG_H_
+#define __CONSOLE_LOG_H_
+
+#include "hostapi.h"
+#include "stdlib.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Decodes a forge-std/hardhat console.log payload (selector + abi encoded
+// arguments) and prints it through debug_bytes.
+// In debug mode the compiler lowers staticcall to the console address
+// (0x000000000000000000636F6e736F6c652e6c6f67) to this function.
+// Always returns 1(true), like a successful staticcall.
+BOOL wrapper_console_log(int32_t in_evm_offset, int32_t in_length);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif // __CONSOLE_LOG_H_
@@ -3,26 +3,30 @@
 
 #include "evm_memory.h"
 #include "debug.h"
+#include "stdlib.h"
 
 static int32_t inited_before = 0; // 1 is true, 0 is false
 
-// EVM memory start address,
-// Once set, it remains unchanged. This is possible because after the WASM
-// instance starts (before optimization), the newly grown memory area is treated
-// as EVM memory, preventing conflicts with the LLVM constant pool.
+// EVM memory region [evm_memory_begin, evm_memory_end).
+// It grows in place while it is the last region of the wasm memory. When
+// something else grew the wasm memory after it (memory_alloca_bytes32), it is
+// moved to new pages at the end of the wasm memory with its contents.
 static uint8_t *evm_memory_begin = 0;
 static uint8_t *evm_memory_end = 0;
-static uint32_t evm_memory_size = 0; // accessible memory range
+// Highest word touched by an EVM memory access, msize is 32 times it
+static uint64_t evm_memory_words = 0;
 static uint32_t evm_memory_full_size = 0;
 
-// In wasm-ld mode, it is possible to avoid allocating separate pages and
-// instead use the remaining space after the constant data segment on the stack.
-// Another approach is to use static allocation(current choice)
-// Allocate a data segment and point to it, allowing space without needing to
-// grow; in most cases, 16384 (16k) is sufficient.
-// TODO: Implement memory relocation and growth when exceeding limits
-#define EVM_MEMORY_MAX_SIZE 16384 // 16KB
-static uint8_t evm_memory_init_buffer[EVM_MEMORY_MAX_SIZE] = {0};
+// End of the static data, defined by wasm-ld
+extern uint8_t __heap_base;
+
+static uint32_t wasm_memory_end() {
+  return __builtin_wasm_memory_size(0) * WASM_PAGE_SIZE;
+}
+
+static uint32_t pages_for(uint32_t size) {
+  return size / WASM_PAGE_SIZE + (size % WASM_PAGE_SIZE != 0);
+}
 
 void __init_evm_heap(int32_t try_new_wasm_page_as_evm_heap_bool) {
   if (inited_before) {
@@ -31,8 +35,8 @@ void __init_evm_heap(int32_t try_new_wasm_page_as_evm_heap_bool) {
   }
 
   // if current is large module(init memory size > 1 page), we use the
-  // evm_memory_begin in new page else we use the evm_memory_init_buffer in
-  // stack
+  // evm_memory_begin in new page else the EVM memory starts after the static
+  // data and takes the rest of the initial memory without growing it
   uint32_t init_memory_pages = __builtin_wasm_memory_size(0);
   if (init_memory_pages > 1 || try_new_wasm_page_as_evm_heap_bool) {
     evm_memory_begin = (uint8_t *)(init_memory_pages * WASM_PAGE_SIZE);
@@ -43,45 +47,92 @@ void __init_evm_heap(int32_t try_new_wasm_page_as_evm_heap_bool) {
       __abort("wasm memory grow failed");
     }
     evm_memory_end = (uint8_t *)(after_pages * WASM_PAGE_SIZE);
-    evm_memory_size = 0;
-    evm_memory_full_size =
-        (uint32_t)evm_memory_end - (uint32_t)evm_memory_begin;
   } else {
-    // small contract
-    evm_memory_begin = evm_memory_init_buffer;
-    evm_memory_end = evm_memory_begin + EVM_MEMORY_MAX_SIZE;
-    evm_memory_size = 0;
-    evm_memory_full_size = EVM_MEMORY_MAX_SIZE;
+    // small contract, 32 bytes aligned like EVM words
+    evm_memory_begin = (uint8_t *)(((uint32_t)&__heap_base + 31) & ~31u);
+    evm_memory_end = (uint8_t *)wasm_memory_end();
+    if (evm_memory_end < evm_memory_begin) {
+      evm_memory_end = evm_memory_begin;
+    }
   }
+  evm_memory_words = 0;
+  evm_memory_full_size = (uint32_t)evm_memory_end - (uint32_t)evm_memory_begin;
 
   inited_before = 1;
 }
 
-uint8_t *evm_make_sure_memory(uint32_t size) {
-  if (evm_memory_full_size < size) {
-    uint32_t target_evm_pages = (size + WASM_PAGE_SIZE - 1) / WASM_PAGE_SIZE;
-    uint32_t grow_pages =
-        target_evm_pages - evm_memory_full_size / WASM_PAGE_SIZE;
+static void evm_grow_memory(uint32_t size) {
+  uint32_t memory_end = wasm_memory_end();
+  if ((uint32_t)evm_memory_end == memory_end) {
+    // the EVM memory ends the wasm memory, grow in place
+    uint32_t grow_pages = pages_for(size - evm_memory_full_size);
     if (__builtin_wasm_memory_grow(0, grow_pages) == -1) {
       __abort("__malloc: failed");
-    };
-    evm_memory_end += (grow_pages * WASM_PAGE_SIZE);
-    evm_memory_size = size;
-    evm_memory_full_size = target_evm_pages * WASM_PAGE_SIZE;
-    return evm_memory_begin;
+    }
+    evm_memory_end += grow_pages * WASM_PAGE_SIZE;
+    evm_memory_full_size += grow_pages * WASM_PAGE_SIZE;
+    return;
   }
-  if (evm_memory_size < size) {
-    // EVM memory is allocated but not accessible for the requested size
-    evm_memory_size = size;
+  // move to new pages, the old region stays allocated
+  uint32_t target_pages = pages_for(size);
+  if (__builtin_wasm_memory_grow(0, target_pages) == -1) {
+    __abort("__malloc: failed");
   }
+  uint8_t *new_begin = (uint8_t *)memory_end;
+  memcpy(new_begin, evm_memory_begin, evm_memory_full_size);
+  evm_memory_begin = new_begin;
+  evm_memory_end = new_begin + target_pages * WASM_PAGE_SIZE;
+  evm_memory_full_size = target_pages * WASM_PAGE_SIZE;
+}
 
+uint8_t *evm_make_sure_memory(uint32_t size) {
+  if (evm_memory_full_size < size) {
+    evm_grow_memory(size);
+  }
   return evm_memory_begin;
 }
 
+// Total memory gas of an EVM memory of the given words
+static uint64_t memory_cost(uint64_t words) {
+  return 3 * words + words * words / 512;
+}
+
+uint8_t *evm_touch_memory(int32_t offset, uint32_t size) {
+  // Accesses of zero bytes don't expand the memory, whatever their offset
+  if (size == 0) {
+    return evm_memory_begin;
+  }
+  uint64_t end = (uint64_t)(uint32_t)offset + size;
+  uint64_t words = (end + 31) / 32;
+  if (words > evm_memory_words) {
+    evm_charge_memory_expansion(memory_cost(words) -
+                                memory_cost(evm_memory_words));
+    evm_memory_words = words;
+  }
+  // offsets from 2^31 are EVM memory like any other, they only fit while the
+  // end is inside the 4 GiB of the wasm memory
+  if (end > UINT32_MAX - (uint32_t)evm_memory_begin) {
+    __abort("__malloc: failed");
+  }
+  return evm_make_sure_memory((uint32_t)end);
+}
+
+void evm_touch_call_memory(int32_t in_offset, uint32_t in_length,
+                           int32_t out_offset, uint32_t out_length) {
+  evm_touch_memory(in_offset, in_length);
+  evm_touch_memory(out_offset, out_length);
+}
+
+uint64_t evm_memory_msize() { return evm_memory_words * 32; }
+
 uint8_t *evm_get_memory_addr(int32_t offset) {
-  return evm_memory_begin + offset;
+  return evm_memory_begin + (uint32_t)offset;
+}
+
+uint8_t *evm_get_data_addr(int32_t data_offset) {
+  return (uint8_t *)((uint32_t)data_offset - EVM_DATA_OFFSET_BASE);
 }
 
 int32_t is_available_evm_memory(uint8_t *ptr, uint32_t size) {
-  return ptr >= evm_memory_begin && size <= evm_memory_size;
+  return ptr >= evm_memory_begin && size <= evm_memory_msize();
 }
@@ -7,22 +7,53 @@
 
 #define WASM_PAGE_SIZE (65536)
 
+// dataoffset of a data segment is its wasm address minus this (a negative
+// int32_t), so it doesn't depend on where the EVM memory is
+#define EVM_DATA_OFFSET_BASE (0x80000000u)
+
 // TODO: use revert with not malloc for abort
 #define __abort(x) __builtin_unreachable()
 
 void __init_evm_heap(int32_t try_new_wasm_page_as_evm_heap_bool);
 
-// Ensures there is memory space of specified size available for EVM use
-// Memory beyond the specified size requires additional checks to prevent access
+// Ensures there is memory space of specified size available for EVM use,
+// without expanding the EVM memory size (msize)
 // Returns the starting address of the EVM memory region
-// The starting address of the EVM memory region remains unchanged after
-// modifications
+// The EVM memory grows in place when it ends the wasm memory, otherwise it is
+// moved to new pages with its contents, so addresses returned before a call
+// may be stale after it
 uint8_t *evm_make_sure_memory(uint32_t size);
 
-// Returns the memory address at the given offset
-// If offset < 0, copies data from non-EVM memory region
+// Expands the EVM memory to cover an access of [offset, offset + size) like
+// the EVM: msize grows to the highest touched word and the expansion gas is
+// charged. Accesses of zero bytes don't expand it. Makes sure the memory is
+// allocated and returns the starting address like evm_make_sure_memory
+uint8_t *evm_touch_memory(int32_t offset, uint32_t size);
+
+// Expands the EVM memory for the input and output ranges of a call, the
+// output range is expanded before the call whatever the return data size
+void evm_touch_call_memory(int32_t in_offset, uint32_t in_length,
+                           int32_t out_offset, uint32_t out_length);
+
+// msize, the number of touched words of the EVM memory times 32
+uint64_t evm_memory_msize();
+
+// Charges the gas of an EVM memory expansion. Defined by the compiler: it
+// calls the useGas host api with --memory-expansion-gas and does nothing
+// otherwise
+void evm_charge_memory_expansion(uint64_t gas);
+
+// Returns the memory address at the given offset, offsets from 2^31 are in the
+// EVM memory too
+// The address is only valid until the EVM memory is expanded again
+// (evm_touch_memory, evm_make_sure_memory), wrappers expand it for all their
+// ranges first and take the addresses after
 uint8_t *evm_get_memory_addr(int32_t offset);
 
+// Returns the wasm address of a data segment from its dataoffset (a negative
+// int32_t), only for the source of codecopy and datacopy
+uint8_t *evm_get_data_addr(int32_t data_offset);
+
 // Checks if ptr is within the accessible memory range of EVM
 // Returns 1(true) if accessible, 0(false) otherwise
 int32_t is_available_evm_memory(uint8_t *ptr, uint32_t size);
@@ -50,6 +50,9 @@ getBlockTimestamp();
 __attribute__((import_module("env"), import_name("getGasLeft"))) int64_t
 getGasLeft();
 
+__attribute__((import_module("env"), import_name("useGas"))) void
+useGas(int64_t amount);
+
 __attribute__((import_module("env"), import_name("getBlockNumber"))) int64_t
 getBlockNumber();
 
@@ -0,0 +1,170 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+// 128 bits builtins the wasm32 backend calls for i128 operations. Only linked
+// when the contract is compiled with the multi-value wasm feature: LLVM then
+// returns the i128 results of these calls as two i64 results, while the
+// prebuilt compiler-rt returns them through a pointer. Defined here they are
+// compiled with the same features as their callers.
+// Only 64 bits operations and constant shifts are used, any other i128
+// operation would call the builtin itself.
+#include <stdint.h>
+
+typedef unsigned __int128 u128;
+typedef __int128 i128;
+
+static inline uint64_t u128_high(u128 value) { return (uint64_t)(value >> 64); }
+
+static inline uint64_t u128_low(u128 value) { return (uint64_t)value; }
+
+static inline u128 u128_make(uint64_t high, uint64_t low) {
+  return ((u128)high << 64) | low;
+}
+
+// 64x64->128 bits multiplication on 32 bits halves
+static u128 u64_mul_wide(uint64_t a, uint64_t b) {
+  uint64_t a_low = (uint32_t)a, a_high = a >> 32;
+  uint64_t b_low = (uint32_t)b, b_high = b >> 32;
+  uint64_t low_low = a_low * b_low;
+  uint64_t high_low = a_high * b_low;
+  uint64_t low_high = a_low * b_high;
+  uint64_t high_high = a_high * b_high;
+  uint64_t middle = (low_low >> 32) + (uint32_t)high_low + (uint32_t)low_high;
+  uint64_t low = (middle << 32) | (uint32_t)low_low;
+  uint64_t high =
+      high_high + (high_low >> 32) + (low_high >> 32) + (middle >> 32);
+  return u128_make(high, low);
+}
+
+static u128 u128_shl(u128 value, uint32_t shift) {
+  uint64_t high = u128_high(value), low = u128_low(value);
+  shift &= 127;
+  if (shift >= 64) {
+    return u128_make(low << (shift - 64), 0);
+  }
+  if (shift == 0) {
+    return value;
+  }
+  return u128_make((high << shift) | (low >> (64 - shift)), low << shift);
+}
+
+static u128 u128_lshr(u128 value, uint32_t shift) {
+  uint64_t high = u128_high(value), low = u128_low(value);
+  shift &= 127;
+  if (shift >= 64) {
+    return u128_make(0, high >> (shift - 64));
+  }
+  if (shift == 0) {
+    return value;
+  }
+  return u128_make(high >> shift, (low >> shift) | (high << (64 - shift)));
+}
+
+static u128 u128_ashr(u128 value, uint32_t shift) {
+  int64_t high = (int64_t)u128_high(value);
+  uint64_t low = u128_low(value);
+  shift &= 127;
+  if (shift >= 64) {
+    return u128_make((uint64_t)(high >> 63), (uint64_t)(high >> (shift - 64)));
+  }
+  if (shift == 0) {
+    return value;
+  }
+  return u128_make((uint64_t)(high >> shift),
+                   (low >> shift) | ((uint64_t)high << (64 - shift)));
+}
+
+static uint32_t u128_clz(u128 value) {
+  uint64_t high = u128_high(value);
+  if (high != 0) {
+    return __builtin_clzll(high);
+  }
+  return 64 + __builtin_clzll(u128_low(value));
+}
+
+// Shift-subtract division, a zero divisor gives a zero quotient and remainder
+// like the EVM
+static u128 u128_divmod(u128 dividend, u128 divisor, u128 *remainder) {
+  if (divisor == 0) {
+    *remainder = 0;
+    return 0;
+  }
+  if (u128_high(dividend) == 0 && u128_high(divisor) == 0) {
+    *remainder = u128_low(dividend) % u128_low(divisor);
+    return u128_low(dividend) / u128_low(divisor);
+  }
+  if (dividend < divisor) {
+    *remainder = dividend;
+    return 0;
+  }
+  uint32_t shift = u128_clz(divisor) - u128_clz(dividend);
+  divisor = u128_shl(divisor, shift);
+  u128 quotient = 0;
+  for (uint32_t i = 0; i <= shift; i++) {
+    quotient = quotient << 1;
+    if (dividend >= divisor) {
+      dividend -= divisor;
+      quotient |= 1;
+    }
+    divisor = divisor >> 1;
+  }
+  *remainder = dividend;
+  return quotient;
+}
+
+static inline u128 u128_negate_if(u128 value, int negative) {
+  return negative ? 0 - value : value;
+}
+
+i128 __multi3(i128 a, i128 b) {
+  u128 ua = (u128)a, ub = (u128)b;
+  u128 product = u64_mul_wide(u128_low(ua), u128_low(ub));
+  uint64_t cross = u128_low(ua) * u128_high(ub) + u128_high(ua) * u128_low(ub);
+  return (i128)u128_make(u128_high(product) + cross, u128_low(product));
+}
+
+i128 __ashlti3(i128 a, int b) { return (i128)u128_shl((u128)a, (uint32_t)b); }
+
+i128 __lshrti3(i128 a, int b) { return (i128)u128_lshr((u128)a, (uint32_t)b); }
+
+i128 __ashrti3(i128 a, int b) { return (i128)u128_ashr((u128)a, (uint32_t)b); }
+
+u128 __udivti3(u128 a, u128 b) {
+  u128 remainder;
+  return u128_divmod(a, b, &remainder);
+}
+
+u128 __umodti3(u128 a, u128 b) {
+  u128 remainder;
+  u128_divmod(a, b, &remainder);
+  return remainder;
+}
+
+i128 __divti3(i128 a, i128 b) {
+  int a_negative = a < 0, b_negative = b < 0;
+  u128 remainder;
+  u128 quotient = u128_divmod(u128_negate_if((u128)a, a_negative),
+                              u128_negate_if((u128)b, b_negative), &remainder);
+  return (i128)u128_negate_if(quotient, a_negative != b_negative);
+}
+
+i128 __modti3(i128 a, i128 b) {
+  int a_negative = a < 0, b_negative = b < 0;
+  u128 remainder;
+  u128_divmod(u128_negate_if((u128)a, a_negative),
+              u128_negate_if((u128)b, b_negative), &remainder);
+  return (i128)u128_negate_if(remainder, a_negative);
+}
+
+// Wrapping product, *overflow is set when it doesn't fit in i128
+i128 __muloti4(i128 a, i128 b, int *overflow) {
+  int a_negative = a < 0, b_negative = b < 0;
+  u128 abs_a = u128_negate_if((u128)a, a_negative);
+  u128 abs_b = u128_negate_if((u128)b, b_negative);
+  // 2^127 for a negative product, 2^127 - 1 otherwise
+  u128 limit = ((u128)1 << 127) - (a_negative == b_negative);
+  u128 remainder;
+  *overflow =
+      abs_a != 0 && abs_b != 0 && abs_a > u128_divmod(limit, abs_b, &remainder);
+  return __multi3(a, b);
+}
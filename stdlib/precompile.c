@@ -0,0 +1,228 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#include "precompile.h"
+#include "chain.h"
+#include "evm_memory.h"
+
+#define PRECOMPILE_IDENTITY 0x04
+
+static uint8_t precompile_return_data[PRECOMPILE_MAX_RETURN_DATA] = {0};
+static uint32_t precompile_return_data_len = 0;
+static BOOL precompile_return_data_valid = 0; // 1 is true, 0 is false
+
+BOOL precompile_has_return_data() { return precompile_return_data_valid; }
+
+uint32_t precompile_return_data_size() { return precompile_return_data_len; }
+
+void precompile_return_data_copy(uint8_t *dst, uint32_t offset, uint32_t len) {
+  if ((uint64_t)offset + (uint64_t)len > precompile_return_data_len) {
+    // reading past the return data is an exceptional halt in EVM
+    invalid();
+    return;
+  }
+  memcpy(dst, precompile_return_data + offset, len);
+}
+
+void precompile_clear_return_data() {
+  precompile_return_data_valid = 0;
+  precompile_return_data_len = 0;
+}
+
+void precompile_set_empty_return_data() {
+  precompile_return_data_valid = 1;
+  precompile_return_data_len = 0;
+}
+
+// Records the precompile output (already written to precompile_return_data)
+// as the current return data and copies it to the call output area
+static BOOL precompile_finish(BOOL success, uint32_t output_len,
+                              int32_t out_evm_offset, int32_t out_length) {
+  precompile_return_data_valid = 1;
+  if (!success) {
+    precompile_return_data_len = 0;
+    return 0;
+  }
+  precompile_return_data_len = output_len;
+  uint32_t copy_len =
+      (uint32_t)out_length < output_len ? (uint32_t)out_length : output_len;
+  if (copy_len > 0) {
+    evm_make_sure_memory(out_evm_offset + copy_len);
+    memcpy(evm_get_memory_addr(out_evm_offset), precompile_return_data,
+           copy_len);
+  }
+  return 1;
+}
+
+// Charges the EVM gas of a precompile computed here from the gas of the call.
+// Without enough gas the call fails and consumes the gas it was given, at most
+// 63/64 of the gas left like a call in the EVM
+static BOOL precompile_use_gas(uint64_t gas, uint64_t cost) {
+  if (cost <= gas) {
+    wrapper_use_gas(cost);
+    return 1;
+  }
+  uint64_t gas_left = (uint64_t)getGasLeft();
+  uint64_t available = gas_left - gas_left / 64;
+  wrapper_use_gas(gas < available ? gas : available);
+  return 0;
+}
+
+static uint64_t precompile_words(int32_t length) {
+  return ((uint64_t)(uint32_t)length + 31) / 32;
+}
+
+// Inputs that don't fit in the return data buffer are forwarded to the host
+// precompile through the normal staticcall path, with the gas of the call
+static BOOL precompile_call_host(uint64_t gas, uint8_t address,
+                                 int32_t in_evm_offset, int32_t in_length,
+                                 int32_t out_evm_offset, int32_t out_length) {
+  bytes32 callee_addr = {0};
+  callee_addr[31] = address;
+  return wrapper_staticcall(gas, &callee_addr, in_evm_offset, in_length,
+                            out_evm_offset, out_length);
+}
+
+BOOL wrapper_precompile_sha256(uint64_t gas, int32_t in_evm_offset,
+                               int32_t in_length, int32_t out_evm_offset,
+                               int32_t out_length) {
+  evm_touch_call_memory(in_evm_offset, in_length, out_evm_offset, out_length);
+  if (!precompile_use_gas(gas, 60 + 12 * precompile_words(in_length))) {
+    return precompile_finish(0, 0, out_evm_offset, out_length);
+  }
+  uint8_t *input = evm_get_memory_addr(in_evm_offset);
+  sha256((ADDRESS_UINT)input, in_length, (ADDRESS_UINT)precompile_return_data);
+  return precompile_finish(1, 32, out_evm_offset, out_length);
+}
+
+BOOL wrapper_precompile_identity(uint64_t gas, int32_t in_evm_offset,
+                                 int32_t in_length, int32_t out_evm_offset,
+                                 int32_t out_length) {
+  evm_touch_call_memory(in_evm_offset, in_length, out_evm_offset, out_length);
+  if ((uint32_t)in_length > PRECOMPILE_MAX_RETURN_DATA) {
+    return precompile_call_host(gas, PRECOMPILE_IDENTITY, in_evm_offset,
+                                in_length, out_evm_offset, out_length);
+  }
+  if (!precompile_use_gas(gas, 15 + 3 * precompile_words(in_length))) {
+    return precompile_finish(0, 0, out_evm_offset, out_length);
+  }
+  uint8_t *input = evm_get_memory_addr(in_evm_offset);
+  memcpy(precompile_return_data, input, in_length);
+  return precompile_finish(1, in_length, out_evm_offset, out_length);
+}
+
+// BLAKE2b compression function F (EIP-152)
+static const uint64_t blake2b_iv[8] = {
+    0x6a09e667f3bcc908ULL, 0xbb67ae8584caa73bULL, 0x3c6ef372fe94f82bULL,
+    0xa54ff53a5f1d36f1ULL, 0x510e527fade682d1ULL, 0x9b05688c2b3e6c1fULL,
+    0x1f83d9abfb41bd6bULL, 0x5be0cd19137e2179ULL};
+
+static const uint8_t blake2b_sigma[10][16] = {
+    {0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15},
+    {14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3},
+    {11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4},
+    {7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8},
+    {9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13},
+    {2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9},
+    {12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11},
+    {13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10},
+    {6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5},
+    {10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 14, 9, 3, 12, 13, 0}};
+
+static uint64_t load_u64_little_endian(const uint8_t *bytes) {
+  uint64_t value = 0;
+  for (int i = 7; i >= 0; i--) {
+    value = (value << 8) | bytes[i];
+  }
+  return value;
+}
+
+static void store_u64_little_endian(uint64_t value, uint8_t *bytes) {
+  for (int i = 0; i < 8; i++) {
+    bytes[i] = (uint8_t)(value >> (8 * i));
+  }
+}
+
+static uint64_t rotr64(uint64_t x, int n) { return (x >> n) | (x << (64 - n)); }
+
+static void blake2b_g(uint64_t *v, int a, int b, int c, int d, uint64_t x,
+                      uint64_t y) {
+  v[a] = v[a] + v[b] + x;
+  v[d] = rotr64(v[d] ^ v[a], 32);
+  v[c] = v[c] + v[d];
+  v[b] = rotr64(v[b] ^ v[c], 24);
+  v[a] = v[a] + v[b] + y;
+  v[d] = rotr64(v[d] ^ v[a], 16);
+  v[c] = v[c] + v[d];
+  v[b] = rotr64(v[b] ^ v[c], 63);
+}
+
+static void blake2b_compress(uint32_t rounds, uint64_t *h, const uint64_t *m,
+                             const uint64_t *t, BOOL final_block) {
+  uint64_t v[16];
+  for (int i = 0; i < 8; i++) {
+    v[i] = h[i];
+    v[i + 8] = blake2b_iv[i];
+  }
+  v[12] ^= t[0];
+  v[13] ^= t[1];
+  if (final_block) {
+    v[14] = ~v[14];
+  }
+  for (uint32_t r = 0; r < rounds; r++) {
+    const uint8_t *s = blake2b_sigma[r % 10];
+    blake2b_g(v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+    blake2b_g(v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+    blake2b_g(v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+    blake2b_g(v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+    blake2b_g(v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+    blake2b_g(v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+    blake2b_g(v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+    blake2b_g(v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+  }
+  for (int i = 0; i < 8; i++) {
+    h[i] ^= v[i] ^ v[i + 8];
+  }
+}
+
+BOOL wrapper_precompile_blake2f(uint64_t gas, int32_t in_evm_offset,
+                                int32_t in_length, int32_t out_evm_offset,
+                                int32_t out_length) {
+  evm_touch_call_memory(in_evm_offset, in_length, out_evm_offset, out_length);
+  // rounds(4) + h(64) + m(128) + t(16) + f(1), invalid inputs consume the
+  // gas of the call
+  if (in_length != 213) {
+    precompile_use_gas(gas, UINT64_MAX);
+    return precompile_finish(0, 0, out_evm_offset, out_length);
+  }
+  uint8_t *input = evm_get_memory_addr(in_evm_offset);
+  uint8_t final_block = input[212];
+  if (final_block > 1) {
+    precompile_use_gas(gas, UINT64_MAX);
+    return precompile_finish(0, 0, out_evm_offset, out_length);
+  }
+  uint32_t rounds = ((uint32_t)input[0] << 24) | ((uint32_t)input[1] << 16) |
+                    ((uint32_t)input[2] << 8) | (uint32_t)input[3];
+  // one gas per round, charged before the rounds run
+  if (!precompile_use_gas(gas, rounds)) {
+    return precompile_finish(0, 0, out_evm_offset, out_length);
+  }
+  uint64_t h[8];
+  uint64_t m[16];
+  uint64_t t[2];
+  for (int i = 0; i < 8; i++) {
+    h[i] = load_u64_little_endian(input + 4 + i * 8);
+  }
+  for (int i = 0; i < 16; i++) {
+    m[i] = load_u64_little_endian(input + 68 + i * 8);
+  }
+  t[0] = load_u64_little_endian(input + 196);
+  t[1] = load_u64_little_endian(input + 204);
+
+  blake2b_compress(rounds, h, m, t, final_block);
+
+  for (int i = 0; i < 8; i++) {
+    store_u64_little_endian(h[i], precompile_return_data + i * 8);
+  }
+  return precompile_finish(1, 64, out_evm_offset, out_length);
+}
@@ -0,0 +1,55 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#ifndef __PRECOMPILE_H_
+#define __PRECOMPILE_H_
+
+#include "hostapi.h"
+#include "stdlib.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Largest output a precompile computed here can produce. identity is the only
+// one with a variable output size, larger inputs are forwarded to the host.
+#define PRECOMPILE_MAX_RETURN_DATA 1024
+
+// The compiler lowers call/staticcall with a constant address of a precompile
+// computed inside wasm (sha256 through the sha256 host import, identity and
+// blake2f) to these wrappers instead of going through the host call path. The
+// other precompiles go through callStatic like any other call, so they only
+// need the host imports every host provides. Each charges the EVM gas of the
+// precompile through useGas, returns 1(true) on success and 0(false) on
+// failure, like a call, and leaves the precompile output as the current return
+// data. A call with less gas than the precompile costs fails.
+BOOL wrapper_precompile_sha256(uint64_t gas, int32_t in_evm_offset,
+                               int32_t in_length, int32_t out_evm_offset,
+                               int32_t out_length);
+
+BOOL wrapper_precompile_identity(uint64_t gas, int32_t in_evm_offset,
+                                 int32_t in_length, int32_t out_evm_offset,
+                                 int32_t out_length);
+
+BOOL wrapper_precompile_blake2f(uint64_t gas, int32_t in_evm_offset,
+                                int32_t in_length, int32_t out_evm_offset,
+                                int32_t out_length);
+
+// The return data of an in-wasm precompile call shadows the host return data
+// until the next host call or create clears it.
+BOOL precompile_has_return_data();
+
+uint32_t precompile_return_data_size();
+
+void precompile_return_data_copy(uint8_t *dst, uint32_t offset, uint32_t len);
+
+void precompile_clear_return_data();
+
+// Empty return data for a call that fails without reaching the host
+void precompile_set_empty_return_data();
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif // __PRECOMPILE_H_
@@ -0,0 +1,104 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#include "static_context.h"
+#include "chain.h"
+#include "precompile.h"
+
+// EVM limit of nested call frames, the transaction frame is at depth 0
+#define EVM_MAX_CALL_DEPTH 1024
+
+static BOOL in_static_context = 0;
+static uint32_t call_depth = 0;
+
+void wrapper_enter_static_context() { in_static_context = 1; }
+
+void wrapper_set_call_depth(int32_t depth) { call_depth = (uint32_t)depth; }
+
+int32_t wrapper_is_static_context() { return in_static_context; }
+
+// A call or create from the deepest frame fails without running the callee:
+// it returns 0, keeps the gas and leaves no return data
+static BOOL call_depth_exceeded() {
+  if (call_depth < EVM_MAX_CALL_DEPTH) {
+    return 0;
+  }
+  precompile_set_empty_return_data();
+  return 1;
+}
+
+void wrapper_check_static_context() {
+  if (in_static_context) {
+    // exceptional halt, all gas is consumed and no return data is left
+    invalid();
+  }
+}
+
+int wrapper_call_contract_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                     uint256_t *value, int32_t in_evm_offset,
+                                     int32_t in_length, int32_t out_evm_offset,
+                                     int32_t out_length) {
+  if (call_depth_exceeded()) {
+    return 0;
+  }
+  if (!in_static_context) {
+    return wrapper_call_contract(gas, callee_addr_ptr, value, in_evm_offset,
+                                 in_length, out_evm_offset, out_length);
+  }
+  if (*value != 0) {
+    invalid();
+  }
+  return wrapper_staticcall(gas, callee_addr_ptr, in_evm_offset, in_length,
+                            out_evm_offset, out_length);
+}
+
+int wrapper_callcode_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                uint256_t *value, int32_t in_evm_offset,
+                                int32_t in_length, int32_t out_evm_offset,
+                                int32_t out_length) {
+  if (call_depth_exceeded()) {
+    return 0;
+  }
+  return wrapper_callcode(gas, callee_addr_ptr, value, in_evm_offset,
+                          in_length, out_evm_offset, out_length);
+}
+
+int wrapper_delegatecall_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                    int32_t in_evm_offset, int32_t in_length,
+                                    int32_t out_evm_offset,
+                                    int32_t out_length) {
+  if (call_depth_exceeded()) {
+    return 0;
+  }
+  return wrapper_delegatecall(gas, callee_addr_ptr, in_evm_offset, in_length,
+                              out_evm_offset, out_length);
+}
+
+int wrapper_staticcall_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                  int32_t in_evm_offset, int32_t in_length,
+                                  int32_t out_evm_offset, int32_t out_length) {
+  if (call_depth_exceeded()) {
+    return 0;
+  }
+  return wrapper_staticcall(gas, callee_addr_ptr, in_evm_offset, in_length,
+                            out_evm_offset, out_length);
+}
+
+void wrapper_create_in_context(uint256_t *value, int32_t code_evm_mem,
+                               int32_t code_length, bytes32 *result) {
+  if (call_depth_exceeded()) {
+    memset(result, 0, sizeof(bytes32));
+    return;
+  }
+  wrapper_create(value, code_evm_mem, code_length, result);
+}
+
+void wrapper_create2_in_context(uint256_t *value, int32_t code_evm_mem,
+                                int32_t code_length, uint256_t *salt,
+                                bytes32 *result) {
+  if (call_depth_exceeded()) {
+    memset(result, 0, sizeof(bytes32));
+    return;
+  }
+  wrapper_create2(value, code_evm_mem, code_length, salt, result);
+}
@@ -0,0 +1,76 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#ifndef __STATIC_CONTEXT_H_
+#define __STATIC_CONTEXT_H_
+
+#include "hostapi.h"
+#include "stdlib.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Runtime static-context tracking (EIP-214) and call-depth limit for hosts
+// that don't enforce them. Only used when the contract is compiled with
+// --static-context-check: the compiler then exports a `call_static` entry,
+// which the host calls instead of `call` when the contract is entered through
+// a staticcall, and a `set_call_depth(i32)` entry, which the host calls before
+// `deploy`, `call` or `call_static` with the depth of the new frame.
+// A delegatecall or callcode from a static frame must run the callee static
+// too, but the host API has no static variant of them: the compiler also
+// exports `is_static_context() -> i32`, which the host asks on the caller when
+// it handles callDelegate or callCode, and it enters the callee through
+// `call_static` when the answer is 1.
+
+// Marks the current call as static, called by the `call_static` entry
+void wrapper_enter_static_context();
+
+// Depth of the current frame, called by the `set_call_depth` entry
+void wrapper_set_call_depth(int32_t depth);
+
+// 1 in static context, called by the `is_static_context` entry
+int32_t wrapper_is_static_context();
+
+// Halts like the EVM does for a state modifying instruction (sstore, tstore,
+// log, create, create2, selfdestruct) in static context
+void wrapper_check_static_context();
+
+// call that keeps the static context: in static context a call with value
+// halts, and a call without value is forwarded to the host as a staticcall so
+// the callee runs in static context too
+int wrapper_call_contract_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                     uint256_t *value, int32_t in_evm_offset,
+                                     int32_t in_length, int32_t out_evm_offset,
+                                     int32_t out_length);
+
+// calls and creates that fail like the EVM in a frame at the depth limit,
+// otherwise forward to the wrappers in chain.h. In static context the host
+// learns from `is_static_context` that a delegatecall or callcode callee runs
+// static
+int wrapper_callcode_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                uint256_t *value, int32_t in_evm_offset,
+                                int32_t in_length, int32_t out_evm_offset,
+                                int32_t out_length);
+
+int wrapper_delegatecall_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                    int32_t in_evm_offset, int32_t in_length,
+                                    int32_t out_evm_offset,
+                                    int32_t out_length);
+
+int wrapper_staticcall_in_context(uint64_t gas, bytes32 *callee_addr_ptr,
+                                  int32_t in_evm_offset, int32_t in_length,
+                                  int32_t out_evm_offset, int32_t out_length);
+
+void wrapper_create_in_context(uint256_t *value, int32_t code_evm_mem,
+                               int32_t code_length, bytes32 *result);
+
+void wrapper_create2_in_context(uint256_t *value, int32_t code_evm_mem,
+                                int32_t code_length, uint256_t *salt,
+                                bytes32 *result);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif // __STATIC_CONTEXT_H_
@@ -40,6 +40,24 @@ void __memset(void *_dest, uint8_t val, size_t length) {
   } while (--length);
 }
 
+// copy of possibly overlapping ranges, backwards when dest is after src
+void __memmove(void *_dest, const void *_src, uint32_t length) {
+  uint8_t *dest = _dest;
+  const uint8_t *src = _src;
+
+  if (dest <= src) {
+    while (length--) {
+      *dest++ = *src++;
+    }
+  } else {
+    dest += length;
+    src += length;
+    while (length--) {
+      *--dest = *--src;
+    }
+  }
+}
+
 #ifndef CC_LIB_TEST_MOCK
 
 /*
@@ -50,6 +50,7 @@ extern void *memset(void *dest, uint8_t val, size_t length);
 #endif
 
 extern void __memset(void *dest, uint8_t val, size_t length);
+extern void __memmove(void *dest, const void *src, uint32_t length);
 extern bool __memcmp(uint8_t *left, uint32_t left_len, uint8_t *right,
                      uint32_t right_len);
 
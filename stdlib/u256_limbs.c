@@ -0,0 +1,401 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#include "u256_limbs.h"
+
+// A u256 is 8 little endian 32 bits digits (memory of wasm32 is little endian,
+// so a uint256_t can be read as digits directly). Digits are multiplied and
+// divided with native i64 operations, wasm32 has no 64x64->128 bits multiply
+// and 128 bits division goes through compiler-rt.
+#define U256_DIGITS 8
+#define U512_DIGITS 16
+#define DIGIT_BASE ((uint64_t)1 << 32)
+
+static void u256_to_digits(const uint256_t *value, uint32_t *digits) {
+  const uint32_t *src = (const uint32_t *)value;
+  for (int i = 0; i < U256_DIGITS; i++) {
+    digits[i] = src[i];
+  }
+}
+
+static void digits_to_u256(const uint32_t *digits, int len, uint256_t *value) {
+  uint32_t *dst = (uint32_t *)value;
+  for (int i = 0; i < U256_DIGITS; i++) {
+    dst[i] = i < len ? digits[i] : 0;
+  }
+}
+
+// Number of significant digits
+static int digits_len(const uint32_t *digits, int len) {
+  while (len > 0 && digits[len - 1] == 0) {
+    len--;
+  }
+  return len;
+}
+
+static int digits_is_negative(const uint32_t *digits) {
+  return (digits[U256_DIGITS - 1] >> 31) != 0;
+}
+
+// Two's complement negation in place
+static void digits_negate(uint32_t *digits) {
+  uint64_t carry = 1;
+  for (int i = 0; i < U256_DIGITS; i++) {
+    uint64_t sum = (uint64_t)(uint32_t)~digits[i] + carry;
+    digits[i] = (uint32_t)sum;
+    carry = sum >> 32;
+  }
+}
+
+// w = a * b truncated to w_len digits, w must not overlap a or b
+static void digits_mul(const uint32_t *a, int a_len, const uint32_t *b,
+                       int b_len, uint32_t *w, int w_len) {
+  for (int i = 0; i < w_len; i++) {
+    w[i] = 0;
+  }
+  for (int j = 0; j < b_len; j++) {
+    if (b[j] == 0) {
+      continue;
+    }
+    uint64_t carry = 0;
+    int i = 0;
+    for (; i < a_len && i + j < w_len; i++) {
+      // at most (2^32 - 1)^2 + 2 * (2^32 - 1) = 2^64 - 1
+      uint64_t t = (uint64_t)a[i] * b[j] + w[i + j] + carry;
+      w[i + j] = (uint32_t)t;
+      carry = t >> 32;
+    }
+    if (i + j < w_len) {
+      w[i + j] = (uint32_t)carry;
+    }
+  }
+}
+
+// Knuth algorithm D (TAOCP 4.3.1) on 32 bits digits: q = u / v and r = u % v
+// for u of m digits and v of n significant digits with m >= n >= 1. q has
+// m - n + 1 digits and r n digits, either can be NULL.
+static void digits_divmod(const uint32_t *u, int m, const uint32_t *v, int n,
+                          uint32_t *q, uint32_t *r) {
+  if (n == 1) {
+    uint64_t rem = 0;
+    for (int j = m - 1; j >= 0; j--) {
+      uint64_t cur = (rem << 32) | u[j];
+      if (q) {
+        q[j] = (uint32_t)(cur / v[0]);
+      }
+      rem = cur % v[0];
+    }
+    if (r) {
+      r[0] = (uint32_t)rem;
+    }
+    return;
+  }
+
+  // normalize so the top digit of the divisor has its high bit set, the
+  // estimated quotient digit is then at most 2 too large
+  uint32_t un[U512_DIGITS + 1];
+  uint32_t vn[U256_DIGITS];
+  int s = __builtin_clz(v[n - 1]);
+  for (int i = n - 1; i > 0; i--) {
+    vn[i] = (v[i] << s) | (uint32_t)((uint64_t)v[i - 1] >> (32 - s));
+  }
+  vn[0] = v[0] << s;
+  un[m] = (uint32_t)((uint64_t)u[m - 1] >> (32 - s));
+  for (int i = m - 1; i > 0; i--) {
+    un[i] = (u[i] << s) | (uint32_t)((uint64_t)u[i - 1] >> (32 - s));
+  }
+  un[0] = u[0] << s;
+
+  for (int j = m - n; j >= 0; j--) {
+    uint64_t top = ((uint64_t)un[j + n] << 32) | un[j + n - 1];
+    uint64_t qhat = top / vn[n - 1];
+    uint64_t rhat = top % vn[n - 1];
+    while (qhat >= DIGIT_BASE ||
+           qhat * vn[n - 2] > ((rhat << 32) | un[j + n - 2])) {
+      qhat--;
+      rhat += vn[n - 1];
+      if (rhat >= DIGIT_BASE) {
+        break;
+      }
+    }
+
+    // un[j..j+n] -= qhat * vn
+    uint64_t carry = 0;
+    uint64_t borrow = 0;
+    for (int i = 0; i < n; i++) {
+      uint64_t p = qhat * vn[i] + carry;
+      carry = p >> 32;
+      uint64_t sub = (uint64_t)un[i + j] - (uint32_t)p - borrow;
+      un[i + j] = (uint32_t)sub;
+      borrow = sub >> 63;
+    }
+    uint64_t sub = (uint64_t)un[j + n] - carry - borrow;
+    un[j + n] = (uint32_t)sub;
+
+    // qhat was still one too large, add the divisor back
+    if (sub >> 63) {
+      qhat--;
+      carry = 0;
+      for (int i = 0; i < n; i++) {
+        uint64_t sum = (uint64_t)un[i + j] + vn[i] + carry;
+        un[i + j] = (uint32_t)sum;
+        carry = sum >> 32;
+      }
+      un[j + n] += (uint32_t)carry;
+    }
+    if (q) {
+      q[j] = (uint32_t)qhat;
+    }
+  }
+
+  if (r) {
+    for (int i = 0; i < n; i++) {
+      r[i] = (un[i] >> s) | (uint32_t)((uint64_t)un[i + 1] << (32 - s));
+    }
+  }
+}
+
+// u % v for u of u_len digits and a nonzero v of U256_DIGITS digits
+static void digits_mod_to_u256(const uint32_t *u, int u_len, const uint32_t *v,
+                               uint256_t *result) {
+  uint32_t r[U256_DIGITS];
+  int m = digits_len(u, u_len);
+  int n = digits_len(v, U256_DIGITS);
+  if (m < n) {
+    digits_to_u256(u, m, result);
+    return;
+  }
+  digits_divmod(u, m, v, n, NULL, r);
+  digits_to_u256(r, n, result);
+}
+
+// |a| / |b| or |a| % |b| of the digits, the divisor is nonzero
+static void digits_u256_divmod(const uint32_t *a, const uint32_t *b,
+                               uint256_t *quotient, uint256_t *remainder) {
+  int m = digits_len(a, U256_DIGITS);
+  int n = digits_len(b, U256_DIGITS);
+  if (m < n) {
+    if (quotient) {
+      *quotient = 0;
+    }
+    if (remainder) {
+      digits_to_u256(a, m, remainder);
+    }
+    return;
+  }
+  if (m <= 2) {
+    // both fit in 64 bits
+    uint64_t x = ((uint64_t)(m > 1 ? a[1] : 0) << 32) | a[0];
+    uint64_t y = ((uint64_t)(n > 1 ? b[1] : 0) << 32) | b[0];
+    if (quotient) {
+      *quotient = x / y;
+    }
+    if (remainder) {
+      *remainder = x % y;
+    }
+    return;
+  }
+  uint32_t q[U256_DIGITS];
+  uint32_t r[U256_DIGITS];
+  digits_divmod(a, m, b, n, quotient ? q : NULL, remainder ? r : NULL);
+  if (quotient) {
+    digits_to_u256(q, m - n + 1, quotient);
+  }
+  if (remainder) {
+    digits_to_u256(r, n, remainder);
+  }
+}
+
+void wrapper_limb_u256_div(const uint256_t *a, const uint256_t *b,
+                           uint256_t *result) {
+  uint32_t x[U256_DIGITS];
+  uint32_t y[U256_DIGITS];
+  u256_to_digits(a, x);
+  u256_to_digits(b, y);
+  if (digits_len(y, U256_DIGITS) == 0) {
+    *result = 0;
+    return;
+  }
+  digits_u256_divmod(x, y, result, NULL);
+}
+
+void wrapper_limb_u256_mod(const uint256_t *a, const uint256_t *b,
+                           uint256_t *result) {
+  uint32_t x[U256_DIGITS];
+  uint32_t y[U256_DIGITS];
+  u256_to_digits(a, x);
+  u256_to_digits(b, y);
+  if (digits_len(y, U256_DIGITS) == 0) {
+    *result = 0;
+    return;
+  }
+  digits_u256_divmod(x, y, NULL, result);
+}
+
+void wrapper_limb_u256_sdiv(const uint256_t *a, const uint256_t *b,
+                            uint256_t *result) {
+  uint32_t x[U256_DIGITS];
+  uint32_t y[U256_DIGITS];
+  u256_to_digits(a, x);
+  u256_to_digits(b, y);
+  if (digits_len(y, U256_DIGITS) == 0) {
+    *result = 0;
+    return;
+  }
+  int negative = digits_is_negative(x) != digits_is_negative(y);
+  if (digits_is_negative(x)) {
+    // |-2^255| is 2^255 as unsigned, so -2^255 / -1 wraps back to -2^255
+    digits_negate(x);
+  }
+  if (digits_is_negative(y)) {
+    digits_negate(y);
+  }
+  uint256_t quotient;
+  digits_u256_divmod(x, y, &quotient, NULL);
+  if (negative) {
+    uint32_t q[U256_DIGITS];
+    u256_to_digits(&quotient, q);
+    digits_negate(q);
+    digits_to_u256(q, U256_DIGITS, &quotient);
+  }
+  *result = quotient;
+}
+
+void wrapper_limb_u256_smod(const uint256_t *a, const uint256_t *b,
+                            uint256_t *result) {
+  uint32_t x[U256_DIGITS];
+  uint32_t y[U256_DIGITS];
+  u256_to_digits(a, x);
+  u256_to_digits(b, y);
+  if (digits_len(y, U256_DIGITS) == 0) {
+    *result = 0;
+    return;
+  }
+  int negative = digits_is_negative(x);
+  if (negative) {
+    digits_negate(x);
+  }
+  if (digits_is_negative(y)) {
+    digits_negate(y);
+  }
+  uint256_t remainder;
+  digits_u256_divmod(x, y, NULL, &remainder);
+  if (negative) {
+    uint32_t r[U256_DIGITS];
+    u256_to_digits(&remainder, r);
+    digits_negate(r);
+    digits_to_u256(r, U256_DIGITS, &remainder);
+  }
+  *result = remainder;
+}
+
+void wrapper_limb_u256_addmod(const uint256_t *a, const uint256_t *b,
+                              const uint256_t *m, uint256_t *result) {
+  uint32_t x[U256_DIGITS];
+  uint32_t y[U256_DIGITS];
+  uint32_t modulus[U256_DIGITS];
+  u256_to_digits(a, x);
+  u256_to_digits(b, y);
+  u256_to_digits(m, modulus);
+  if (digits_len(modulus, U256_DIGITS) == 0) {
+    *result = 0;
+    return;
+  }
+  uint32_t sum[U256_DIGITS + 1];
+  uint64_t carry = 0;
+  for (int i = 0; i < U256_DIGITS; i++) {
+    uint64_t t = (uint64_t)x[i] + y[i] + carry;
+    sum[i] = (uint32_t)t;
+    carry = t >> 32;
+  }
+  sum[U256_DIGITS] = (uint32_t)carry;
+  digits_mod_to_u256(sum, U256_DIGITS + 1, modulus, result);
+}
+
+void wrapper_limb_u256_mulmod(const uint256_t *a, const uint256_t *b,
+                              const uint256_t *m, uint256_t *result) {
+  uint32_t x[U256_DIGITS];
+  uint32_t y[U256_DIGITS];
+  uint32_t modulus[U256_DIGITS];
+  u256_to_digits(a, x);
+  u256_to_digits(b, y);
+  u256_to_digits(m, modulus);
+  if (digits_len(modulus, U256_DIGITS) == 0) {
+    *result = 0;
+    return;
+  }
+  uint32_t product[U512_DIGITS];
+  digits_mul(x, digits_len(x, U256_DIGITS), y, digits_len(y, U256_DIGITS),
+             product, U512_DIGITS);
+  digits_mod_to_u256(product, U512_DIGITS, modulus, result);
+}
+
+// x = x * y mod 2^256, x and y may be the same
+static void digits_mul_assign(uint32_t *x, const uint32_t *y) {
+  uint32_t product[U256_DIGITS];
+  digits_mul(x, U256_DIGITS, y, digits_len(y, U256_DIGITS), product,
+             U256_DIGITS);
+  for (int i = 0; i < U256_DIGITS; i++) {
+    x[i] = product[i];
+  }
+}
+
+void wrapper_limb_u256_exp(const uint256_t *base, const uint256_t *exponent,
+                           uint256_t *result) {
+  uint32_t b[U256_DIGITS];
+  uint32_t e[U256_DIGITS];
+  u256_to_digits(base, b);
+  u256_to_digits(exponent, e);
+  int e_len = digits_len(e, U256_DIGITS);
+  uint32_t acc[U256_DIGITS] = {1};
+  if (e_len == 0) {
+    *result = 1;
+    return;
+  }
+  if (e_len == 1 && e[0] < 256) {
+    // small exponents by square and multiply, building the window table
+    // costs more than it saves
+    uint32_t exp = e[0];
+    for (;;) {
+      if (exp & 1) {
+        digits_mul_assign(acc, b);
+      }
+      exp >>= 1;
+      if (exp == 0) {
+        break;
+      }
+      digits_mul_assign(b, b);
+    }
+    digits_to_u256(acc, U256_DIGITS, result);
+    return;
+  }
+
+  // table[i] = base^i
+  uint32_t table[16][U256_DIGITS];
+  for (int i = 0; i < U256_DIGITS; i++) {
+    table[0][i] = i == 0;
+    table[1][i] = b[i];
+  }
+  for (int i = 2; i < 16; i++) {
+    for (int k = 0; k < U256_DIGITS; k++) {
+      table[i][k] = table[i - 1][k];
+    }
+    digits_mul_assign(table[i], b);
+  }
+
+  // exponent nibbles from the most significant one
+  int started = 0;
+  for (int i = e_len * 8 - 1; i >= 0; i--) {
+    uint32_t window = (e[i / 8] >> ((i % 8) * 4)) & 0xf;
+    if (started) {
+      for (int k = 0; k < 4; k++) {
+        digits_mul_assign(acc, acc);
+      }
+    }
+    if (window != 0) {
+      digits_mul_assign(acc, table[window]);
+      started = 1;
+    }
+  }
+  digits_to_u256(acc, U256_DIGITS, result);
+}
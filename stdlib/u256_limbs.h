@@ -0,0 +1,51 @@
+// Copyright (c) the DTVM authors Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#ifndef __U256_LIMBS_H_
+#define __U256_LIMBS_H_
+
+#include "stdlib.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// u256 arithmetic on little endian 32/64 bits limbs instead of the generic
+// _BitInt(256) lowering, which the wasm32 backend expands into shift-subtract
+// loops and compiler-rt calls. Only used when the contract is compiled with
+// --limb-u256-math. All functions follow the EVM semantics: division, remainder
+// and modulo by zero are zero.
+
+void wrapper_limb_u256_div(const uint256_t *a, const uint256_t *b,
+                           uint256_t *result);
+
+void wrapper_limb_u256_mod(const uint256_t *a, const uint256_t *b,
+                           uint256_t *result);
+
+// sdiv(-2^255, -1) is -2^255
+void wrapper_limb_u256_sdiv(const uint256_t *a, const uint256_t *b,
+                            uint256_t *result);
+
+// The result has the sign of the dividend
+void wrapper_limb_u256_smod(const uint256_t *a, const uint256_t *b,
+                            uint256_t *result);
+
+// (a + b) % m with the 257 bits intermediate sum, doesn't need the host
+// addmod import
+void wrapper_limb_u256_addmod(const uint256_t *a, const uint256_t *b,
+                              const uint256_t *m, uint256_t *result);
+
+// (a * b) % m with the 512 bits intermediate product, doesn't need the host
+// mulmod import
+void wrapper_limb_u256_mulmod(const uint256_t *a, const uint256_t *b,
+                              const uint256_t *m, uint256_t *result);
+
+// base^exponent mod 2^256 with a 4 bits fixed window
+void wrapper_limb_u256_exp(const uint256_t *base, const uint256_t *exponent,
+                           uint256_t *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // __U256_LIMBS_H_